@@ -0,0 +1,5 @@
+use criterion::{criterion_group, criterion_main};
+
+// Executed with `cargo bench --features bench`
+criterion_group!(benches, isup::bench::strategies, isup::bench::memory);
+criterion_main!(benches);
@@ -3,7 +3,7 @@ use std::time::Duration;
 use tokio::time::sleep;
 
 #[tokio::main]
-async fn main() -> Result<(), Box<dyn std::error::Error>> {
+async fn main() -> Result<(), Box<dyn std::error::Error + Send + Sync>> {
     // > Initialize a default client  (2 second request timeout and 60 second pool idle timeout)
     let client = Client::default();
     // Additionally, you can create a client with custom settings
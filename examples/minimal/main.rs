@@ -1,7 +1,7 @@
 use isup::{Config, Service};
 
 #[tokio::main]
-async fn main() -> Result<(), Box<dyn std::error::Error>> {
+async fn main() -> Result<(), Box<dyn std::error::Error + Send + Sync>> {
     // > Load the configuration from a file
     let config = Config::from_file("examples/minimal/config.yml")?;
 
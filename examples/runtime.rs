@@ -1,7 +1,7 @@
 use isup::{Request, Service};
 
 #[tokio::main]
-async fn main() -> Result<(), Box<dyn std::error::Error>> {
+async fn main() -> Result<(), Box<dyn std::error::Error + Send + Sync>> {
     // > Initialize a Default Service
     let mut service = Service::default();
     // with empty requests
@@ -9,7 +9,7 @@ async fn main() -> Result<(), Box<dyn std::error::Error>> {
 
     // > Insert a new request on runtime, after initialization
     let request = Request::new("GET", "https://www.rust-lang.org");
-    service.insert_request(request);
+    service.insert_request(request)?;
 
     // > Get and print all requests for demonstrational purposes
     let all = service.urls();
@@ -25,20 +25,27 @@ impl Response {
 //
 // Define the route handler
 async fn best_url(service: Arc<Service>) -> Result<impl warp::Reply, warp::Rejection> {
-    // Additionally, the response can be cached in a light data structure (in-memory, ...)
+    // Additionally, the response can be cached in a light data structure (in-memory, ...),
+    // as the embedded `isup::server::Server` does along with rate limiting its clients
     let url = service.best_url().await.unwrap_or(None);
     let updated_at = service.updated_at.load(SeqCst);
 
     Ok(warp::reply::json(&Response::new(url, updated_at)))
 }
 
+// Define the handler reporting on the latest update cycle
+async fn last_cycle(service: Arc<Service>) -> Result<impl warp::Reply, warp::Rejection> {
+    // The outcomes, store errors and skipped endpoints of the cycle, or `null` until one completed
+    Ok(warp::reply::json(&service.last_cycle()))
+}
+
 #[tokio::main]
-async fn main() -> Result<(), Box<dyn std::error::Error>> {
+async fn main() -> Result<(), Box<dyn std::error::Error + Send + Sync>> {
     // > Load the configuration from a file
     let config = Config::from_file("examples/server/config.yml")?;
 
     // > Extract the interval from the configuration
-    let interval = config.interval.expect("interval is required");
+    let interval = config.interval()?;
 
     // > Create a new IsUp instance wrapped in an Arc
     // This allows us to share the instance across threads
@@ -52,8 +59,10 @@ async fn main() -> Result<(), Box<dyn std::error::Error>> {
 
     // > Create a Service instance to pass to the route handler
     let warp_service = warp::any().map(move || service.clone());
-    // > Define the GET / route
-    let route = warp::get().and(warp_service).and_then(best_url);
+    // > Define the GET / and GET /last-cycle routes
+    let best = warp::path::end().and(warp_service.clone()).and_then(best_url);
+    let cycle = warp::path!("last-cycle").and(warp_service).and_then(last_cycle);
+    let route = warp::get().and(best.or(cycle));
 
     // Print the server address
     println!("initialized service @ http://localhost:{PORT}");
@@ -0,0 +1,221 @@
+use crate::config::{deserialize_uri, serialize_uri};
+use crate::{Client, ProbeOutcome, Service};
+use bytes::Bytes;
+use http_body_util::{BodyExt, Full, Limited};
+use hyper::body::Incoming;
+use hyper::header::{HeaderMap, AUTHORIZATION, CONTENT_TYPE};
+use hyper::{Method, Response, StatusCode, Uri};
+use hyper_util::rt::TokioIo;
+use std::convert::Infallible;
+use std::sync::{Arc, Mutex};
+use tokio::net::TcpListener;
+
+/// The path coordinators ingest the samples of agents on.
+pub const SAMPLES_PATH: &str = "/v1/samples";
+
+/// The maximum number of samples an agent keeps while its coordinator is unreachable; the oldest are dropped first.
+const MAX_PENDING: usize = 10_000;
+
+/// The maximum size of a batch accepted by a coordinator.
+const MAX_BATCH_SIZE: usize = 16 * 1024 * 1024;
+
+/// Agent configuration
+///
+/// - `coordinator`: the URL the samples are pushed to, e.g. `https://isup.example.com/v1/samples`
+/// - `token`: the bearer token authenticating the agent to the coordinator
+/// - `name`: identifies the agent to the coordinator (default: `agent`)
+#[derive(serde::Deserialize, serde::Serialize, Clone, Debug)]
+pub struct Config {
+    #[serde(deserialize_with = "deserialize_uri", serialize_with = "serialize_uri")]
+    pub coordinator: Uri,
+    pub token: String,
+    #[serde(default = "default_name")]
+    pub name: String,
+}
+
+fn default_name() -> String {
+    "agent".into()
+}
+
+impl Config {
+    /// Creates a new agent configuration.
+    ///
+    /// # Arguments
+    /// * `coordinator`: The URL the samples are pushed to.
+    /// * `token`: The bearer token authenticating the agent.
+    ///
+    /// # Panics
+    /// Panics if the URL cannot be parsed.
+    pub fn new<I: Into<String>>(coordinator: I, token: I) -> Self {
+        Self {
+            coordinator: coordinator.into().parse().expect("Invalid URL"),
+            token: token.into(),
+            name: default_name(),
+        }
+    }
+
+    /// Sets the name identifying the agent to the coordinator.
+    pub fn set_name<I: Into<String>>(mut self, name: I) -> Self {
+        self.name = name.into();
+        self
+    }
+}
+
+/// A batch of samples, pushed by an agent to its coordinator as JSON.
+#[derive(serde::Deserialize, serde::Serialize, Clone, Debug)]
+pub struct Batch {
+    /// The name of the agent.
+    pub agent: String,
+    /// The outcomes of the probes executed by the agent since its last push.
+    pub samples: Vec<ProbeOutcome>,
+}
+
+/// The response of a coordinator to a batch.
+#[derive(serde::Deserialize, serde::Serialize, Clone, Debug, Default, PartialEq, Eq)]
+pub struct Receipt {
+    /// The number of samples that were scored.
+    pub accepted: usize,
+    /// The reason each of the remaining samples was rejected, e.g. an endpoint unknown to the coordinator.
+    pub rejected: Vec<String>,
+}
+
+/// Pushes the outcomes of the probes of a `Service` to its coordinator.
+pub(crate) struct Agent {
+    config: Config,
+    client: Client,
+    /// The samples a previous push failed to deliver.
+    pending: Mutex<Vec<ProbeOutcome>>,
+}
+
+impl Agent {
+    pub(crate) fn new(config: Config) -> Self {
+        Self { config, client: Client::default(), pending: Mutex::default() }
+    }
+
+    /// Returns the number of samples waiting to be delivered to the coordinator.
+    pub(crate) fn pending(&self) -> usize {
+        self.pending.lock().map_or(0, |pending| pending.len())
+    }
+
+    /// Pushes the samples to the coordinator, along with the ones previous pushes failed to deliver.
+    /// Undelivered samples are kept for the next push.
+    pub(crate) async fn push(&self, samples: impl IntoIterator<Item = ProbeOutcome>) {
+        let batch = {
+            let mut pending = self.pending.lock().expect("failed to lock pending samples");
+            pending.extend(samples);
+            let excess = pending.len().saturating_sub(MAX_PENDING);
+            pending.drain(..excess);
+            Batch { agent: self.config.name.clone(), samples: std::mem::take(&mut *pending) }
+        };
+        if batch.samples.is_empty() {
+            return;
+        }
+
+        let body = serde_json::to_vec(&batch).expect("failed to serialize samples");
+        let request = hyper::Request::post(self.config.coordinator.clone())
+            .header(AUTHORIZATION, format!("Bearer {}", self.config.token))
+            .header(CONTENT_TYPE, "application/json")
+            .body(Full::new(Bytes::from(body)))
+            .expect("failed to build request");
+
+        let delivered = matches!(self.client.request(request).await, Ok(response) if response.status().is_success());
+        if !delivered {
+            let mut pending = self.pending.lock().expect("failed to lock pending samples");
+            let newer = std::mem::replace(&mut *pending, batch.samples);
+            pending.extend(newer);
+        }
+    }
+}
+
+/// Ingests the samples pushed by agents into a `Service`, which scores them as if it probed the endpoints itself.
+///
+/// Agents post their `Batch`es to `SAMPLES_PATH`, authenticated by a bearer token. The service of the coordinator
+/// has to monitor the same endpoints as the agents, although it doesn't need to probe them itself.
+#[derive(Clone)]
+pub struct Coordinator {
+    service: Arc<Service>,
+    tokens: Arc<Vec<String>>,
+}
+
+impl Coordinator {
+    /// Creates a new `Coordinator`.
+    ///
+    /// # Arguments
+    /// * `service`: The service scoring the samples.
+    /// * `tokens`: The bearer tokens agents are allowed to authenticate with.
+    pub fn new(service: Arc<Service>, tokens: Vec<String>) -> Self {
+        Self { service, tokens: Arc::new(tokens) }
+    }
+
+    /// Serves the agents connecting to the listener, until accepting a connection fails.
+    ///
+    /// # Arguments
+    /// * `listener`: The listener agents connect to.
+    pub async fn serve(self, listener: TcpListener) -> std::io::Result<()> {
+        loop {
+            let (stream, _) = listener.accept().await?;
+            let coordinator = self.clone();
+            tokio::spawn(async move {
+                let handler = hyper::service::service_fn(move |request| {
+                    let coordinator = coordinator.clone();
+                    async move { Ok::<_, Infallible>(coordinator.handle(request).await) }
+                });
+                // Connection errors only affect the agent on the other end
+                let _ =
+                    hyper::server::conn::http1::Builder::new().serve_connection(TokioIo::new(stream), handler).await;
+            });
+        }
+    }
+
+    /// Handles a request of an agent.
+    async fn handle(&self, request: hyper::Request<Incoming>) -> Response<Full<Bytes>> {
+        if request.uri().path() != SAMPLES_PATH {
+            return reply(StatusCode::NOT_FOUND, "not found");
+        }
+        if request.method() != Method::POST {
+            return reply(StatusCode::METHOD_NOT_ALLOWED, "method not allowed");
+        }
+        if !self.is_authorized(request.headers()) {
+            return reply(StatusCode::UNAUTHORIZED, "unauthorized");
+        }
+
+        let body = match Limited::new(request.into_body(), MAX_BATCH_SIZE).collect().await {
+            Ok(body) => body.to_bytes(),
+            Err(e) => return reply(StatusCode::BAD_REQUEST, &format!("failed to read batch: {e}")),
+        };
+        let batch = match serde_json::from_slice::<Batch>(&body) {
+            Ok(batch) => batch,
+            Err(e) => return reply(StatusCode::BAD_REQUEST, &format!("invalid batch: {e}")),
+        };
+
+        let mut receipt = Receipt::default();
+        for sample in batch.samples {
+            match self.service.ingest_outcome(sample).await.map_err(|e| e.to_string()) {
+                Ok(()) => receipt.accepted += 1,
+                Err(e) => receipt.rejected.push(e),
+            }
+        }
+        let body = serde_json::to_vec(&receipt).expect("failed to serialize receipt");
+        let mut response = Response::new(Full::new(Bytes::from(body)));
+        response.headers_mut().insert(CONTENT_TYPE, "application/json".parse().expect("invalid content type"));
+        response
+    }
+
+    /// Returns `true` if the request carries one of the bearer tokens of the coordinator.
+    fn is_authorized(&self, headers: &HeaderMap) -> bool {
+        let token = headers.get(AUTHORIZATION).and_then(|v| v.to_str().ok()).and_then(|v| v.strip_prefix("Bearer "));
+        token.is_some_and(|token| self.tokens.iter().any(|t| constant_time_eq(t.as_bytes(), token.as_bytes())))
+    }
+}
+
+/// Builds a plain-text response.
+fn reply(status: StatusCode, message: &str) -> Response<Full<Bytes>> {
+    let mut response = Response::new(Full::new(Bytes::from(message.to_string())));
+    *response.status_mut() = status;
+    response
+}
+
+/// Compares two secrets in constant time, so that a token can't be guessed from the time it takes to be rejected.
+pub(crate) fn constant_time_eq(a: &[u8], b: &[u8]) -> bool {
+    a.len() == b.len() && a.iter().zip(b).fold(0, |acc, (x, y)| acc | (x ^ y)) == 0
+}
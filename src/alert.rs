@@ -0,0 +1,283 @@
+use crate::client::PIN_MISMATCH;
+use crate::config::{deserialize_headers, deserialize_uri, serialize_headers, serialize_uri};
+use crate::forecast::Risk;
+use crate::incident::Incident;
+use crate::Client;
+use bytes::Bytes;
+use http_body_util::Full;
+use hyper::header::CONTENT_TYPE;
+use hyper::{HeaderMap, Uri};
+use std::collections::BTreeMap;
+use std::error::Error;
+use std::time::{Duration, SystemTime};
+
+/// The name of the alerts raised for the endpoints that go down.
+pub const ALERT_NAME: &str = "EndpointDown";
+
+/// The name of the alerts raised for the endpoints responding slower than their latency budget.
+pub const BUDGET_ALERT_NAME: &str = "BudgetExceeded";
+
+/// The name of the alerts raised for the endpoints going down because their certificate doesn't match their pins.
+pub const PIN_ALERT_NAME: &str = "CertificatePinMismatch";
+
+/// The name of the alerts raised for the endpoints forecast to fall under the score threshold.
+pub const RISK_ALERT_NAME: &str = "EndpointAtRisk";
+
+/// The path of the Alertmanager API receiving the alerts.
+const ALERTS_PATH: &str = "/api/v2/alerts";
+
+/// Alertmanager configuration
+///
+/// - `url`: the base URL of Alertmanager, e.g. `http://alertmanager:9093`
+/// - `labels`: labels attached to every alert, e.g. the environment of the monitor
+/// - `headers`: additional headers sent along, e.g. an `authorization` header
+///
+/// Every alert is labeled with `alertname: EndpointDown`, the `url` of the endpoint, the tags of its request and the
+/// `namespace` of the service, if any, so they can be routed, grouped and silenced like the alerts of any other
+/// Prometheus-compatible source. The endpoints responding slower than their latency budget raise a separate
+/// `alertname: BudgetExceeded` alert, labeled the same way, and the incidents opened by a certificate that doesn't
+/// match the pins of its endpoint are raised as `alertname: CertificatePinMismatch`.
+#[derive(serde::Deserialize, serde::Serialize, Clone, Debug)]
+pub struct Config {
+    #[serde(deserialize_with = "deserialize_uri", serialize_with = "serialize_uri")]
+    pub url: Uri,
+    #[serde(default)]
+    pub labels: BTreeMap<String, String>,
+    #[serde(deserialize_with = "deserialize_headers", serialize_with = "serialize_headers", default)]
+    pub headers: HeaderMap,
+}
+
+impl Config {
+    /// Creates a new Alertmanager configuration.
+    ///
+    /// # Arguments
+    /// * `url`: The base URL of Alertmanager.
+    ///
+    /// # Panics
+    /// Panics if the URL cannot be parsed.
+    pub fn new<I: Into<String>>(url: I) -> Self {
+        Self { url: url.into().parse().expect("Invalid URL"), labels: BTreeMap::new(), headers: HeaderMap::new() }
+    }
+
+    /// Sets a label attached to every alert.
+    pub fn set_label<I: Into<String>>(mut self, name: I, value: I) -> Self {
+        self.labels.insert(name.into(), value.into());
+        self
+    }
+
+    /// Sets the additional headers sent along with the alerts.
+    pub fn set_headers(mut self, headers: HeaderMap) -> Self {
+        self.headers = headers;
+        self
+    }
+}
+
+/// An alert in the format of the Alertmanager API, which is also the format of the alerts of its webhooks.
+#[derive(serde::Deserialize, serde::Serialize, Clone, Debug, PartialEq, Eq)]
+#[serde(rename_all = "camelCase")]
+pub struct Alert {
+    /// The labels identifying the alert.
+    pub labels: BTreeMap<String, String>,
+    /// The details of the alert, e.g. its summary and the ID of its incident.
+    pub annotations: BTreeMap<String, String>,
+    /// When the endpoint went down, in RFC 3339 format.
+    pub starts_at: String,
+    /// When the endpoint recovered, in RFC 3339 format; `None` while the alert is firing.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub ends_at: Option<String>,
+}
+
+impl Alert {
+    /// Builds the alert of an incident.
+    ///
+    /// # Arguments
+    /// * `incident`: The incident of the endpoint.
+    /// * `labels`: The labels attached to every alert.
+    /// * `tags`: The tags of the request of the endpoint, overriding the labels of the same name.
+    pub(crate) fn new(incident: &Incident, labels: &BTreeMap<String, String>, tags: &BTreeMap<String, String>) -> Self {
+        let mut alert_labels = labels.clone();
+        alert_labels.extend(tags.clone());
+        alert_labels.insert("url".into(), incident.url.clone());
+
+        let mut annotations = BTreeMap::new();
+        // A certificate that doesn't match its pins hints at an interception, rather than an outage
+        match incident.error.as_deref().is_some_and(|e| e.starts_with(PIN_MISMATCH)) {
+            true => {
+                alert_labels.insert("alertname".into(), PIN_ALERT_NAME.into());
+                annotations.insert("summary".into(), format!("{} presented an unexpected certificate", incident.url));
+            }
+            false => {
+                alert_labels.insert("alertname".into(), ALERT_NAME.into());
+                annotations.insert("summary".into(), format!("{} is down", incident.url));
+            }
+        }
+        annotations.insert("incident".into(), incident.id.clone());
+        annotations.insert("status".into(), incident.status.to_string());
+        if let Some(error) = &incident.error {
+            annotations.insert("description".into(), error.clone());
+        }
+
+        Self {
+            labels: alert_labels,
+            annotations,
+            starts_at: humantime::format_rfc3339_seconds(incident.started_at).to_string(),
+            ends_at: incident.resolved_at.map(|t| humantime::format_rfc3339_seconds(t).to_string()),
+        }
+    }
+}
+
+/// The successful responses of an endpoint exceeding its latency budget, since the first one in a row.
+#[derive(Clone, Debug)]
+pub(crate) struct Violation {
+    /// The latency budget of the endpoint.
+    pub(crate) budget: Duration,
+    /// The latest response time of the endpoint.
+    pub(crate) elapsed: Duration,
+    /// The number of responses exceeding the budget in a row.
+    pub(crate) count: u64,
+    /// When the first response exceeded the budget.
+    pub(crate) started_at: SystemTime,
+    /// When a response fit the budget again, ending the violation.
+    pub(crate) resolved_at: Option<SystemTime>,
+}
+
+impl Violation {
+    pub(crate) fn new(budget: Duration) -> Self {
+        Self { budget, elapsed: Duration::ZERO, count: 0, started_at: SystemTime::now(), resolved_at: None }
+    }
+}
+
+impl Alert {
+    /// Builds the alert of an endpoint exceeding its latency budget.
+    ///
+    /// # Arguments
+    /// * `url`: The URL of the endpoint.
+    /// * `violation`: The responses exceeding the budget.
+    /// * `labels`: The labels attached to every alert.
+    /// * `tags`: The tags of the request of the endpoint, overriding the labels of the same name.
+    pub(crate) fn budget(
+        url: &str,
+        violation: &Violation,
+        labels: &BTreeMap<String, String>,
+        tags: &BTreeMap<String, String>,
+    ) -> Self {
+        let mut alert_labels = labels.clone();
+        alert_labels.extend(tags.clone());
+        alert_labels.insert("alertname".into(), BUDGET_ALERT_NAME.into());
+        alert_labels.insert("url".into(), url.to_string());
+
+        let mut annotations = BTreeMap::new();
+        let budget = violation.budget.as_millis();
+        annotations.insert("summary".into(), format!("{url} exceeds its latency budget of {budget}ms"));
+        annotations.insert("budget".into(), format!("{budget}ms"));
+        annotations.insert("latency".into(), format!("{}ms", violation.elapsed.as_millis()));
+        annotations.insert("violations".into(), violation.count.to_string());
+
+        Self {
+            labels: alert_labels,
+            annotations,
+            starts_at: humantime::format_rfc3339_seconds(violation.started_at).to_string(),
+            ends_at: violation.resolved_at.map(|t| humantime::format_rfc3339_seconds(t).to_string()),
+        }
+    }
+}
+
+impl Alert {
+    /// Builds the alert of an endpoint forecast to fall under the score threshold.
+    ///
+    /// # Arguments
+    /// * `risk`: The risk of the endpoint.
+    /// * `labels`: The labels attached to every alert.
+    /// * `tags`: The tags of the request of the endpoint, overriding the labels of the same name.
+    pub(crate) fn risk(risk: &Risk, labels: &BTreeMap<String, String>, tags: &BTreeMap<String, String>) -> Self {
+        let mut alert_labels = labels.clone();
+        alert_labels.extend(tags.clone());
+        alert_labels.insert("alertname".into(), RISK_ALERT_NAME.into());
+        alert_labels.insert("url".into(), risk.url.clone());
+
+        let mut annotations = BTreeMap::new();
+        let horizon = humantime::format_duration(risk.horizon);
+        let summary = format!("{} is forecast to score under {} within {horizon}", risk.url, risk.threshold);
+        annotations.insert("summary".into(), summary);
+        annotations.insert("score".into(), risk.score.to_string());
+        annotations.insert("forecast".into(), risk.forecast.to_string());
+
+        Self {
+            labels: alert_labels,
+            annotations,
+            starts_at: humantime::format_rfc3339_seconds(risk.started_at).to_string(),
+            ends_at: risk.resolved_at.map(|t| humantime::format_rfc3339_seconds(t).to_string()),
+        }
+    }
+}
+
+/// Raises the alerts of the incidents of a `Service` in Alertmanager.
+///
+/// Firing alerts are sent again on every update of their incident, so Alertmanager doesn't resolve them on its own,
+/// and resolved ones are sent along with their end.
+pub(crate) struct Alertmanager {
+    config: Config,
+    client: Client,
+}
+
+impl Alertmanager {
+    pub(crate) fn new(config: Config) -> Self {
+        Self { config, client: Client::default() }
+    }
+
+    /// Sends the alert of an incident.
+    ///
+    /// # Arguments
+    /// * `incident`: The incident of the endpoint.
+    /// * `tags`: The tags of the request of the endpoint.
+    pub(crate) async fn send(
+        &self,
+        incident: &Incident,
+        tags: &BTreeMap<String, String>,
+    ) -> Result<(), Box<dyn Error + Send + Sync>> {
+        self.post(&[Alert::new(incident, &self.config.labels, tags)]).await
+    }
+
+    /// Sends the alert of an endpoint exceeding its latency budget.
+    ///
+    /// # Arguments
+    /// * `url`: The URL of the endpoint.
+    /// * `violation`: The responses exceeding the budget.
+    /// * `tags`: The tags of the request of the endpoint.
+    pub(crate) async fn send_violation(
+        &self,
+        url: &str,
+        violation: &Violation,
+        tags: &BTreeMap<String, String>,
+    ) -> Result<(), Box<dyn Error + Send + Sync>> {
+        self.post(&[Alert::budget(url, violation, &self.config.labels, tags)]).await
+    }
+
+    /// Sends the alert of an endpoint forecast to fall under the score threshold.
+    ///
+    /// # Arguments
+    /// * `risk`: The risk of the endpoint.
+    /// * `tags`: The tags of the request of the endpoint.
+    pub(crate) async fn send_risk(
+        &self,
+        risk: &Risk,
+        tags: &BTreeMap<String, String>,
+    ) -> Result<(), Box<dyn Error + Send + Sync>> {
+        self.post(&[Alert::risk(risk, &self.config.labels, tags)]).await
+    }
+
+    /// Posts alerts to the Alertmanager API.
+    async fn post(&self, alerts: &[Alert]) -> Result<(), Box<dyn Error + Send + Sync>> {
+        let url = format!("{}{ALERTS_PATH}", self.config.url.to_string().trim_end_matches('/'));
+        let mut request = hyper::Request::post(url)
+            .header(CONTENT_TYPE, "application/json")
+            .body(Full::new(Bytes::from(serde_json::to_vec(alerts)?)))?;
+        request.headers_mut().extend(self.config.headers.clone());
+
+        let response = self.client.request(request).await?;
+        match response.status().is_success() {
+            true => Ok(()),
+            false => Err(format!("alertmanager responded with `{}`", response.status()).into()),
+        }
+    }
+}
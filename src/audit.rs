@@ -0,0 +1,131 @@
+use hyper::HeaderMap;
+
+/// Security-header audit configuration
+///
+/// - `required`: the response headers to be checked (default: HSTS, CSP, X-Content-Type-Options,
+///   X-Frame-Options and Referrer-Policy)
+/// - `penalize`: fails the probe when a required header is missing or invalid, lowering the score (default: `false`)
+#[derive(serde::Deserialize, serde::Serialize, Clone, Debug, PartialEq, Eq)]
+pub struct Config {
+    #[serde(default = "default_required")]
+    pub required: Vec<String>,
+    #[serde(default)]
+    pub penalize: bool,
+}
+
+impl Default for Config {
+    /// Provides a default configuration, which checks the common security headers without penalizing the score.
+    fn default() -> Self {
+        Self { required: default_required(), penalize: false }
+    }
+}
+
+fn default_required() -> Vec<String> {
+    [
+        "strict-transport-security",
+        "content-security-policy",
+        "x-content-type-options",
+        "x-frame-options",
+        "referrer-policy",
+    ]
+    .map(String::from)
+    .to_vec()
+}
+
+impl Config {
+    /// Sets the response headers to be checked.
+    pub fn set_required<I: Into<String>>(mut self, required: Vec<I>) -> Self {
+        self.required = required.into_iter().map(|h| h.into().to_ascii_lowercase()).collect();
+        self
+    }
+
+    /// Sets whether the probe fails when a required header is missing or invalid.
+    pub fn set_penalize(mut self, penalize: bool) -> Self {
+        self.penalize = penalize;
+        self
+    }
+
+    /// Audits the headers of a response.
+    ///
+    /// # Returns
+    /// A `Report` listing the missing and invalid headers.
+    pub(crate) fn audit(&self, headers: &HeaderMap) -> Report {
+        let mut report = Report::default();
+        for name in &self.required {
+            match headers.get(name.as_str()).map(|v| v.to_str().unwrap_or_default()) {
+                None => report.missing.push(name.clone()),
+                Some(value) => match validate(&name.to_ascii_lowercase(), value) {
+                    Ok(()) => report.present.push(name.clone()),
+                    Err(reason) => report.invalid.push(format!("{name}: {reason}")),
+                },
+            }
+        }
+        report
+    }
+}
+
+/// The result of auditing the security headers of the last response received from an endpoint.
+#[derive(serde::Serialize, Clone, Debug, Default, PartialEq, Eq)]
+pub struct Report {
+    /// The required headers that were present and valid.
+    pub present: Vec<String>,
+    /// The required headers that were missing.
+    pub missing: Vec<String>,
+    /// The required headers that were present but invalid, along with the reason.
+    pub invalid: Vec<String>,
+}
+
+impl Report {
+    /// Returns `true` if every required header was present and valid.
+    pub fn is_compliant(&self) -> bool {
+        self.missing.is_empty() && self.invalid.is_empty()
+    }
+}
+
+impl std::fmt::Display for Report {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let issues = self.missing.iter().map(|h| format!("{h}: missing")).chain(self.invalid.iter().cloned());
+        write!(f, "security headers audit failed ({})", issues.collect::<Vec<_>>().join(", "))
+    }
+}
+
+/// Validates the value of the headers with well-known semantics. Other headers only need to be present.
+fn validate(name: &str, value: &str) -> Result<(), String> {
+    match name {
+        "strict-transport-security" => {
+            let max_age =
+                value.split(';').find_map(|d| d.trim().to_ascii_lowercase().strip_prefix("max-age=").map(String::from));
+            match max_age.and_then(|age| age.trim_matches('"').parse::<u64>().ok()) {
+                Some(age) if age > 0 => Ok(()),
+                _ => Err("max-age must be positive".into()),
+            }
+        }
+        "x-content-type-options" if !value.trim().eq_ignore_ascii_case("nosniff") => Err("must be `nosniff`".into()),
+        _ if value.trim().is_empty() => Err("empty value".into()),
+        _ => Ok(()),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use hyper::header::HeaderValue;
+
+    #[test]
+    fn test_audit() {
+        let mut headers = HeaderMap::new();
+        headers.insert("strict-transport-security", HeaderValue::from_static("max-age=0"));
+        headers.insert("x-content-type-options", HeaderValue::from_static("nosniff"));
+
+        let config = Config::default().set_required(vec![
+            "Strict-Transport-Security",
+            "X-Content-Type-Options",
+            "X-Frame-Options",
+        ]);
+        let report = config.audit(&headers);
+        assert_eq!(report.present, vec!["x-content-type-options"]);
+        assert_eq!(report.missing, vec!["x-frame-options"]);
+        assert_eq!(report.invalid, vec!["strict-transport-security: max-age must be positive"]);
+        assert!(!report.is_compliant());
+    }
+}
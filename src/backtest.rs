@@ -0,0 +1,79 @@
+use crate::{outcome::ProbeOutcome, score::Score, strategy::Strategy};
+use std::collections::HashMap;
+
+/// The ranking of the endpoints after a single replayed cycle, ordered from best to worst.
+#[derive(Clone, Debug, serde::Serialize)]
+pub struct Round {
+    /// The URL and score of every endpoint seen so far.
+    pub ranking: Vec<(String, Score)>,
+}
+
+impl Round {
+    /// Returns the URL with the highest score in this round, if any.
+    pub fn best(&self) -> Option<&str> {
+        self.ranking.first().map(|(url, _)| url.as_str())
+    }
+}
+
+/// Describes how the rankings evolved while replaying recorded outcomes through a strategy.
+#[derive(Clone, Debug, Default, serde::Serialize)]
+pub struct Report {
+    /// One round per replayed cycle, in chronological order.
+    pub rounds: Vec<Round>,
+}
+
+impl Report {
+    /// Counts the rounds in which each URL was ranked first.
+    pub fn best_counts(&self) -> HashMap<String, usize> {
+        self.rounds.iter().filter_map(Round::best).fold(HashMap::new(), |mut acc, url| {
+            *acc.entry(url.to_string()).or_default() += 1;
+            acc
+        })
+    }
+
+    /// Counts how many times the best URL changed between consecutive rounds.
+    /// A lower value indicates a more stable strategy.
+    pub fn switches(&self) -> usize {
+        let best = self.rounds.iter().map(Round::best).collect::<Vec<_>>();
+        best.windows(2).filter(|w| w[0] != w[1]).count()
+    }
+
+    /// Returns the ranking after the last replayed cycle.
+    pub fn last(&self) -> Option<&Round> {
+        self.rounds.last()
+    }
+}
+
+/// Replays recorded probe outcomes through a strategy, the same way a `Service` would score them.
+///
+/// # Arguments
+/// * `strategy`: The strategy to be evaluated.
+/// * `cycles`: The recorded outcomes, grouped by update cycle in chronological order.
+///
+/// # Returns
+/// A `Report` containing the ranking of the endpoints after each cycle.
+pub fn run<S, I, C>(strategy: &S, cycles: I) -> Report
+where
+    S: Strategy + ?Sized,
+    I: IntoIterator<Item = C>,
+    C: IntoIterator<Item = ProbeOutcome>,
+{
+    let mut scores: HashMap<String, Score> = HashMap::new();
+    let mut report = Report::default();
+
+    for cycle in cycles {
+        for outcome in cycle {
+            // Endpoints without a score start from the initial one of the strategy, as they do in a `Service`
+            let score = scores.remove(&outcome.url).unwrap_or_else(|| strategy.initial_score());
+            let score = strategy.calculate_outcome(score, &outcome);
+            scores.insert(outcome.url, score);
+        }
+
+        let mut ranking = scores.iter().map(|(url, score)| (url.clone(), score.clone())).collect::<Vec<_>>();
+        // Sort by descending score, breaking ties by URL to keep the ranking deterministic
+        ranking.sort_by(|(a_url, a), (b_url, b)| b.score.total_cmp(&a.score).then_with(|| a_url.cmp(b_url)));
+        report.rounds.push(Round { ranking });
+    }
+
+    report
+}
@@ -0,0 +1,86 @@
+use crate::{
+    score::Score,
+    store::{Memory, Store},
+    strategy::{Strategy, WeightedLog},
+};
+use criterion::{BenchmarkId, Criterion, Throughput};
+use std::time::Duration;
+
+/// The number of endpoints each store benchmark is executed against.
+pub const SIZES: [usize; 3] = [100, 1_000, 10_000];
+
+/// Benchmarks the cost of a single score calculation of the built-in strategies.
+pub fn strategies(c: &mut Criterion) {
+    strategy(c, "weighted_log", &WeightedLog::default());
+}
+
+/// Benchmarks the cost of a single score calculation of the given strategy.
+///
+/// # Arguments
+/// * `c`: The criterion instance.
+/// * `name`: The name the benchmark is reported under.
+/// * `strategy`: The strategy to be benchmarked.
+pub fn strategy<S: Strategy + ?Sized>(c: &mut Criterion, name: &str, strategy: &S) {
+    let score = Score::new(0.5, 0.5, Duration::from_millis(250));
+    c.bench_function(&format!("strategy/{name}"), |b| {
+        b.iter(|| strategy.calculate(std::hint::black_box(score.clone()), Duration::from_millis(200), 200))
+    });
+}
+
+/// Benchmarks the built-in in-memory store.
+pub fn memory(c: &mut Criterion) {
+    store(c, "memory", Memory::new);
+}
+
+/// Benchmarks the `set`, `get` and `best_url` throughput of a store, for every size in `SIZES`.
+///
+/// # Arguments
+/// * `c`: The criterion instance.
+/// * `name`: The name the benchmark group is reported under.
+/// * `store`: Creates an empty instance of the store to be benchmarked, called once per size.
+///
+/// # Panics
+/// Panics if the tokio runtime cannot be created or a store operation fails.
+pub fn store<S, F>(c: &mut Criterion, name: &str, store: F)
+where
+    S: Store,
+    F: Fn() -> S,
+{
+    let runtime = tokio::runtime::Runtime::new().expect("failed to create runtime");
+    let mut group = c.benchmark_group(format!("store/{name}"));
+
+    for size in SIZES {
+        let store = store();
+        let urls = (0..size).map(|i| format!("https://{i}.example.com/")).collect::<Vec<_>>();
+        let score = |i: usize| Score::new(i as f32 / size as f32, 1.0, Duration::from_millis(100));
+
+        // Writes every endpoint once per iteration, populating the store for the read benchmarks.
+        group.throughput(Throughput::Elements(size as u64));
+        group.bench_with_input(BenchmarkId::new("set", size), &urls, |b, urls| {
+            b.iter(|| {
+                runtime.block_on(async {
+                    for (i, url) in urls.iter().enumerate() {
+                        store.set(url.clone(), score(i)).await.expect("failed to set score");
+                    }
+                })
+            })
+        });
+        group.bench_with_input(BenchmarkId::new("get", size), &urls, |b, urls| {
+            b.iter(|| {
+                runtime.block_on(async {
+                    for url in urls {
+                        store.get(url).await.expect("failed to get score");
+                    }
+                })
+            })
+        });
+
+        // Retrieving the best URL is a single operation, regardless of the number of endpoints.
+        group.throughput(Throughput::Elements(1));
+        group.bench_function(BenchmarkId::new("best_url", size), |b| {
+            b.iter(|| runtime.block_on(store.best_url()).expect("failed to get best url"))
+        });
+    }
+
+    group.finish();
+}
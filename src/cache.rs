@@ -0,0 +1,169 @@
+use hyper::header::{HeaderValue, AGE, DATE, ETAG, IF_MODIFIED_SINCE, IF_NONE_MATCH, LAST_MODIFIED};
+use hyper::{HeaderMap, StatusCode};
+use std::time::{Duration, SystemTime};
+
+/// The cache validators of the last full response received from an endpoint.
+///
+/// Used by requests with `cache_validation` enabled, which send conditional requests and
+/// verify that unchanged content is revalidated with a `304 Not Modified` response.
+#[derive(Clone, Debug, Default)]
+pub(crate) struct Validators {
+    etag: Option<HeaderValue>,
+    last_modified: Option<HeaderValue>,
+}
+
+impl Validators {
+    /// Extracts the validators from the headers of a response.
+    pub(crate) fn from_headers(headers: &HeaderMap) -> Self {
+        Self { etag: headers.get(ETAG).cloned(), last_modified: headers.get(LAST_MODIFIED).cloned() }
+    }
+
+    /// Makes a request conditional, by adding the `If-None-Match` and `If-Modified-Since` headers.
+    pub(crate) fn apply(&self, headers: &mut HeaderMap) {
+        if let Some(etag) = &self.etag {
+            headers.insert(IF_NONE_MATCH, etag.clone());
+        }
+        if let Some(last_modified) = &self.last_modified {
+            headers.insert(IF_MODIFIED_SINCE, last_modified.clone());
+        }
+    }
+
+    /// Verifies the response to a request, made conditional by these validators if they were known.
+    ///
+    /// # Arguments
+    /// * `status`: The status code of the response.
+    /// * `headers`: The headers of the response.
+    ///
+    /// # Returns
+    /// The validators to be used by the next probe, and a description of the misbehavior of the cache, if any.
+    pub(crate) fn check(&self, status: StatusCode, headers: &HeaderMap) -> (Self, Result<(), String>) {
+        let received = Self::from_headers(headers);
+        let conditional = self.etag.is_some() || self.last_modified.is_some();
+
+        match status {
+            StatusCode::NOT_MODIFIED if !conditional => {
+                (self.clone(), Err("unconditional request returned 304".into()))
+            }
+            // A 304 must carry the same entity tag as the full response it revalidates.
+            StatusCode::NOT_MODIFIED => match (&self.etag, &received.etag) {
+                (Some(expected), Some(etag)) if expected != etag => {
+                    (self.clone(), Err(format!("304 returned a different ETag ({etag:?} instead of {expected:?})")))
+                }
+                (Some(_), None) => (self.clone(), Err("304 returned without an ETag".into())),
+                _ => (self.clone(), Ok(())),
+            },
+            status if status.is_success() => {
+                let result = match (received.etag.is_some() || received.last_modified.is_some(), conditional) {
+                    (false, _) => Err("response has no ETag or Last-Modified validator".into()),
+                    // Content is only expected to be revalidated as long as it's unchanged.
+                    (true, true) if received.etag.is_some() && received.etag == self.etag => {
+                        Err("conditional request with a matching ETag wasn't answered with 304".into())
+                    }
+                    (true, true) if received.etag.is_none() && received.last_modified == self.last_modified => {
+                        Err("conditional request for unmodified content wasn't answered with 304".into())
+                    }
+                    _ => Ok(()),
+                };
+                (received, result)
+            }
+            // Errors are scored through their status code; the validators are kept for the next probe.
+            _ => (self.clone(), Ok(())),
+        }
+    }
+}
+
+/// Verifies the freshness of a response, from its `Date` and `Age` headers compared against the local clock.
+///
+/// A response older than the maximum staleness was served from a stale cache, and one dated ahead of the local clock
+/// by more than it comes from a clock-skewed origin.
+///
+/// # Arguments
+/// * `headers`: The headers of the response.
+/// * `now`: When the response was received.
+/// * `max_staleness`: The maximum age of the response, and the maximum skew of the clock of the origin.
+///
+/// # Returns
+/// The age of the response, if dated, and a description of the staleness or skew, if any.
+pub(crate) fn check_freshness(
+    headers: &HeaderMap,
+    now: SystemTime,
+    max_staleness: Duration,
+) -> (Option<Duration>, Result<(), String>) {
+    let Some(date) = headers.get(DATE).and_then(|d| d.to_str().ok()).and_then(|d| httpdate::parse_http_date(d).ok())
+    else {
+        return (None, Err("response has no valid Date header".into()));
+    };
+    // The time spent in caches, on top of the time since the origin generated the response
+    let cached = headers.get(AGE).and_then(|a| a.to_str().ok()).and_then(|a| a.trim().parse().ok());
+    let cached = Duration::from_secs(cached.unwrap_or(0));
+
+    match now.duration_since(date) {
+        Ok(elapsed) => {
+            let age = elapsed.max(cached);
+            match age > max_staleness {
+                true => (Some(age), Err(format!("stale response, {}s old", age.as_secs()))),
+                false => (Some(age), Ok(())),
+            }
+        }
+        Err(ahead) if ahead.duration() > max_staleness => {
+            (Some(cached), Err(format!("clock skew, the origin is {}s ahead", ahead.duration().as_secs())))
+        }
+        Err(_) => (Some(cached), Ok(())),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn headers(etag: &'static str) -> HeaderMap {
+        HeaderMap::from_iter([(ETAG, HeaderValue::from_static(etag))])
+    }
+
+    #[test]
+    fn test_validators_check() {
+        let (validators, result) = Validators::default().check(StatusCode::OK, &headers("\"v1\""));
+        assert!(result.is_ok());
+
+        // Unchanged content must be revalidated with a 304
+        assert!(validators.check(StatusCode::NOT_MODIFIED, &headers("\"v1\"")).1.is_ok());
+        assert!(validators.check(StatusCode::OK, &headers("\"v1\"")).1.is_err());
+        assert!(validators.check(StatusCode::NOT_MODIFIED, &headers("\"v2\"")).1.is_err());
+
+        // Changed content replaces the validators
+        let (validators, result) = validators.check(StatusCode::OK, &headers("\"v2\""));
+        assert!(result.is_ok());
+        assert_eq!(validators.etag, Some(HeaderValue::from_static("\"v2\"")));
+
+        // Responses without validators can't be cached
+        assert!(Validators::default().check(StatusCode::OK, &HeaderMap::new()).1.is_err());
+    }
+
+    #[test]
+    fn test_check_freshness() {
+        let now = SystemTime::UNIX_EPOCH + Duration::from_secs(1_700_000_000);
+        let dated = |offset: i64, age: Option<&str>| {
+            let date = match offset >= 0 {
+                true => now + Duration::from_secs(offset as u64),
+                false => now - Duration::from_secs(offset.unsigned_abs()),
+            };
+            let mut headers = HeaderMap::new();
+            headers.insert(DATE, HeaderValue::from_str(&httpdate::fmt_http_date(date)).unwrap());
+            if let Some(age) = age {
+                headers.insert(AGE, HeaderValue::from_str(age).unwrap());
+            }
+            headers
+        };
+        let max = Duration::from_secs(60);
+
+        assert_eq!(check_freshness(&dated(-10, None), now, max), (Some(Duration::from_secs(10)), Ok(())));
+        // The age of a cached response is the longest of its `Age` and the time since its `Date`
+        let (age, result) = check_freshness(&dated(-10, Some("120")), now, max);
+        assert_eq!((age, result.is_err()), (Some(Duration::from_secs(120)), true));
+        assert!(check_freshness(&dated(-120, None), now, max).1.unwrap_err().starts_with("stale response"));
+        // An origin whose clock is ahead is tolerated up to the maximum staleness
+        assert!(check_freshness(&dated(30, None), now, max).1.is_ok());
+        assert!(check_freshness(&dated(120, None), now, max).1.unwrap_err().starts_with("clock skew"));
+        assert!(check_freshness(&HeaderMap::new(), now, max).1.is_err());
+    }
+}
@@ -0,0 +1,85 @@
+use hyper::HeaderMap;
+use std::collections::BTreeMap;
+use std::time::SystemTime;
+
+/// The headers whose values are never captured, as they may carry credentials.
+const REDACTED: &[&str] = &["set-cookie", "authorization", "proxy-authenticate", "www-authenticate"];
+
+/// Failure capture configuration
+///
+/// - `max_body`: the number of bytes of the response body kept, the rest being truncated (default: 4096)
+///
+/// The headers and the beginning of the body of the latest failed response of every endpoint are kept in memory,
+/// so operators can see the actual error page or error payload instead of just its status code. The values of the
+/// headers that may carry credentials, such as `set-cookie`, are redacted.
+#[derive(serde::Deserialize, serde::Serialize, Clone, Debug, PartialEq, Eq)]
+pub struct Config {
+    #[serde(default = "default_max_body")]
+    pub max_body: usize,
+}
+
+impl Default for Config {
+    fn default() -> Self {
+        Self { max_body: default_max_body() }
+    }
+}
+
+fn default_max_body() -> usize {
+    4096
+}
+
+impl Config {
+    /// Sets the number of bytes of the response body kept.
+    pub fn set_max_body(mut self, max_body: usize) -> Self {
+        self.max_body = max_body;
+        self
+    }
+
+    /// Captures a failed probe.
+    ///
+    /// # Arguments
+    /// * `status`: The status code of the response, `0` if none was received.
+    /// * `error`: The reason the probe failed, if any besides its status code.
+    /// * `headers`: The headers of the response, if one was received.
+    /// * `body`: The decoded body of the response, if it was read.
+    pub(crate) fn capture(
+        &self,
+        status: u16,
+        error: Option<String>,
+        headers: Option<&HeaderMap>,
+        body: Option<&[u8]>,
+    ) -> Failure {
+        let headers = headers.into_iter().flatten().map(|(name, value)| {
+            let value = match REDACTED.contains(&name.as_str()) {
+                true => "<redacted>".into(),
+                false => String::from_utf8_lossy(value.as_bytes()).to_string(),
+            };
+            (name.to_string(), value)
+        });
+        Failure {
+            at: SystemTime::now(),
+            status,
+            error,
+            headers: headers.collect(),
+            truncated: body.is_some_and(|b| b.len() > self.max_body),
+            body: body.map(|b| String::from_utf8_lossy(&b[..b.len().min(self.max_body)]).to_string()),
+        }
+    }
+}
+
+/// The latest failed probe of an endpoint, along with the response it received.
+#[derive(serde::Deserialize, serde::Serialize, Clone, Debug, PartialEq, Eq)]
+pub struct Failure {
+    /// When the probe failed.
+    pub at: SystemTime,
+    /// The status code of the response, `0` if none was received.
+    pub status: u16,
+    /// The reason the probe failed, if any besides its status code, e.g. a timeout or an unexpected body.
+    pub error: Option<String>,
+    /// The headers of the response, empty if none was received.
+    pub headers: BTreeMap<String, String>,
+    /// The beginning of the decoded body of the response, `None` if it wasn't read.
+    pub body: Option<String>,
+    /// Whether the body was truncated to the configured size.
+    pub truncated: bool,
+}
@@ -0,0 +1,153 @@
+use crate::config::{deserialize_uri, serialize_uri, ConfigDuration};
+use crate::request::Request;
+use hyper::Uri;
+use std::collections::HashMap;
+use std::sync::atomic::{AtomicU64, AtomicUsize, Ordering::SeqCst};
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
+
+/// Chaos configuration
+///
+/// Lists the endpoints to be simulated instead of requested over the network.
+/// The `seed` field can be set to make the simulated failures reproducible across runs.
+#[derive(serde::Deserialize, serde::Serialize, Debug, Clone)]
+pub struct Config {
+    /// Seed of the random number generator used to decide which probes fail.
+    #[serde(default)]
+    pub seed: Option<u64>,
+    /// The endpoints to be simulated.
+    pub endpoints: Vec<Fault>,
+}
+
+/// The scripted behavior of a single simulated endpoint.
+#[derive(serde::Deserialize, serde::Serialize, Debug, Clone)]
+pub struct Fault {
+    /// The URL of the endpoint to be simulated.
+    #[serde(deserialize_with = "deserialize_uri", serialize_with = "serialize_uri")]
+    pub url: Uri,
+    /// Latencies that are cycled through on each probe. Defaults to an immediate response.
+    #[serde(default)]
+    pub latencies: Vec<ConfigDuration>,
+    /// Probability, between 0.0 and 1.0, that a probe fails.
+    #[serde(default)]
+    pub failure_rate: f32,
+    /// The status code reported by failed probes. Defaults to `0`, simulating no response.
+    #[serde(default)]
+    pub failure_status: u16,
+}
+
+impl Fault {
+    /// Creates a new `Fault` for the given URL, which responds immediately and never fails.
+    ///
+    /// # Panics
+    /// Panics if the URL cannot be parsed.
+    pub fn new<I: Into<String>>(url: I) -> Self {
+        let url = Request::normalize(url.into().parse().expect("Invalid URL"));
+        Self { url, latencies: vec![], failure_rate: 0.0, failure_status: 0 }
+    }
+
+    /// Sets the latencies cycled through on each probe.
+    pub fn set_latencies(mut self, latencies: Vec<Duration>) -> Self {
+        self.latencies = latencies.into_iter().map(ConfigDuration::from).collect();
+        self
+    }
+
+    /// Sets the probability, between 0.0 and 1.0, that a probe fails.
+    pub fn set_failure_rate(mut self, failure_rate: f32) -> Self {
+        self.failure_rate = failure_rate.clamp(0.0, 1.0);
+        self
+    }
+
+    /// Sets the status code reported by failed probes.
+    pub fn set_failure_status(mut self, failure_status: u16) -> Self {
+        self.failure_status = failure_status;
+        self
+    }
+}
+
+/// Simulates endpoints with scripted latencies and failure rates, without touching the network.
+///
+/// Intended for testing the failover logic and alerting of applications built on top of a `Service`.
+/// Endpoints that aren't part of the simulation are requested as usual.
+#[derive(Debug)]
+pub struct Chaos {
+    /// Simulated endpoints, along with the number of times each was probed.
+    faults: HashMap<String, (Fault, AtomicUsize)>,
+    /// State of the xorshift random number generator.
+    rng: AtomicU64,
+}
+
+impl Default for Chaos {
+    /// Creates a new `Chaos` instance without any simulated endpoints, seeded from the current time.
+    fn default() -> Self {
+        let seed = SystemTime::now().duration_since(UNIX_EPOCH).map(|d| d.as_nanos() as u64).unwrap_or_default();
+        Self::new(seed)
+    }
+}
+
+impl Chaos {
+    /// Creates a new `Chaos` instance without any simulated endpoints.
+    ///
+    /// # Arguments
+    /// * `seed`: Seed of the random number generator, used to reproduce the same failures.
+    pub fn new(seed: u64) -> Self {
+        // Xorshift would be stuck at zero forever.
+        Self { faults: HashMap::new(), rng: AtomicU64::new(seed.max(1)) }
+    }
+
+    /// Creates a new `Chaos` instance from the provided configuration.
+    pub fn from_config(config: Config) -> Self {
+        let chaos = config.seed.map(Self::new).unwrap_or_default();
+        config.endpoints.into_iter().fold(chaos, Self::insert)
+    }
+
+    /// Adds a simulated endpoint, replacing any previous one with the same URL.
+    ///
+    /// # Returns
+    /// The updated `Chaos` instance.
+    pub fn insert(mut self, fault: Fault) -> Self {
+        self.faults.insert(fault.url.to_string(), (fault, AtomicUsize::new(0)));
+        self
+    }
+
+    /// Simulates a probe against the given URL.
+    ///
+    /// # Arguments
+    /// * `url`: The URL of the probed endpoint.
+    /// * `timeout`: The request timeout of the client; slower latencies are reported as timeouts.
+    ///
+    /// # Returns
+    /// The elapsed time and status code of the simulated probe, or `None` if the URL isn't simulated.
+    pub(crate) async fn simulate(&self, url: &str, timeout: Option<Duration>) -> Option<(Duration, u16)> {
+        let (fault, probes) = self.faults.get(url)?;
+
+        let n = probes.fetch_add(1, SeqCst);
+        let latency = match fault.latencies.is_empty() {
+            true => Duration::ZERO,
+            false => fault.latencies[n % fault.latencies.len()].get(),
+        };
+
+        // A latency exceeding the timeout behaves like a request that never completed.
+        if let Some(timeout) = timeout.filter(|t| latency > *t) {
+            tokio::time::sleep(timeout).await;
+            return Some((timeout, 0));
+        }
+
+        tokio::time::sleep(latency).await;
+        match self.random() < fault.failure_rate {
+            true => Some((latency, fault.failure_status)),
+            false => Some((latency, 200)),
+        }
+    }
+
+    /// Returns a pseudo-random number in the range `[0.0, 1.0)`.
+    fn random(&self) -> f32 {
+        let xorshift = |mut x: u64| {
+            x ^= x << 13;
+            x ^= x >> 7;
+            x ^= x << 17;
+            x
+        };
+        let previous = self.rng.fetch_update(SeqCst, SeqCst, |x| Some(xorshift(x))).unwrap_or_default();
+        (xorshift(previous) >> 40) as f32 / (1u64 << 24) as f32
+    }
+}
@@ -0,0 +1,66 @@
+use bytes::Bytes;
+use hyper::body::{Body, Frame, SizeHint};
+use std::convert::Infallible;
+use std::pin::Pin;
+use std::sync::{Arc, OnceLock};
+use std::task::{Context, Poll};
+use tokio::time::Instant;
+
+/// The size of the frames a request body is split into.
+const CHUNK_SIZE: usize = 16 * 1024;
+
+/// Records the instant the body of a request was fully handed to its connection.
+///
+/// Attached to the extensions of a request sent through `Client::request`, in order to tell the time spent
+/// uploading the body apart from the time the server took to respond.
+#[derive(Clone, Debug, Default)]
+pub(crate) struct UploadTimer(Arc<OnceLock<Instant>>);
+
+impl UploadTimer {
+    /// Returns the instant the last chunk of the body was sent, if it was.
+    pub(crate) fn sent(&self) -> Option<Instant> {
+        self.0.get().copied()
+    }
+}
+
+/// A request body sent in chunks, so that the connection only pulls the next one once it has room for it
+/// and the end of the upload can be timed.
+#[derive(Debug)]
+pub(crate) struct Chunked {
+    data: Bytes,
+    timer: Option<UploadTimer>,
+}
+
+impl Chunked {
+    pub(crate) fn new(data: Bytes, timer: Option<UploadTimer>) -> Self {
+        Self { data, timer }
+    }
+}
+
+impl Body for Chunked {
+    type Data = Bytes;
+    type Error = Infallible;
+
+    fn poll_frame(
+        mut self: Pin<&mut Self>,
+        _cx: &mut Context<'_>,
+    ) -> Poll<Option<Result<Frame<Self::Data>, Self::Error>>> {
+        if self.data.is_empty() {
+            return Poll::Ready(None);
+        }
+        let len = self.data.len().min(CHUNK_SIZE);
+        let chunk = self.data.split_to(len);
+        if let (true, Some(timer)) = (self.data.is_empty(), &self.timer) {
+            let _ = timer.0.set(Instant::now());
+        }
+        Poll::Ready(Some(Ok(Frame::data(chunk))))
+    }
+
+    fn is_end_stream(&self) -> bool {
+        self.data.is_empty()
+    }
+
+    fn size_hint(&self) -> SizeHint {
+        SizeHint::with_exact(self.data.len() as u64)
+    }
+}
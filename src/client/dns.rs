@@ -0,0 +1,125 @@
+use crate::guard::Guard;
+use hyper_util::client::legacy::connect::dns::{GaiResolver, Name};
+use std::future::Future;
+use std::net::{IpAddr, SocketAddr};
+use std::pin::Pin;
+use std::sync::Arc;
+use std::task::{Context, Poll};
+
+/// The address family used to connect to dual-stack endpoints.
+///
+/// Families are only applied to resolved hostnames; URLs containing an IP address are connected to as-is.
+#[derive(serde::Deserialize, serde::Serialize, Clone, Copy, Debug, Default, PartialEq, Eq, Hash)]
+#[serde(rename_all = "snake_case")]
+pub enum AddressFamily {
+    /// Use the addresses in the order returned by the system resolver.
+    #[default]
+    Any,
+    /// Only connect over IPv4.
+    V4Only,
+    /// Only connect over IPv6.
+    V6Only,
+    /// Try IPv4 first, falling back to IPv6 after the happy eyeballs timeout.
+    PreferV4,
+    /// Try IPv6 first, falling back to IPv4 after the happy eyeballs timeout.
+    PreferV6,
+}
+
+impl AddressFamily {
+    /// Filters and orders the resolved addresses according to the family.
+    ///
+    /// # Returns
+    /// The addresses to connect to, in order of preference.
+    pub(crate) fn apply(self, addrs: impl Iterator<Item = SocketAddr>) -> Vec<SocketAddr> {
+        let mut addrs = addrs.collect::<Vec<_>>();
+        match self {
+            AddressFamily::Any => {}
+            AddressFamily::V4Only => addrs.retain(SocketAddr::is_ipv4),
+            AddressFamily::V6Only => addrs.retain(SocketAddr::is_ipv6),
+            // The sort is stable, preserving the order of the resolver within each family.
+            AddressFamily::PreferV4 => addrs.sort_by_key(SocketAddr::is_ipv6),
+            AddressFamily::PreferV6 => addrs.sort_by_key(SocketAddr::is_ipv4),
+        }
+        addrs
+    }
+}
+
+/// A resolver that applies an `AddressFamily` and the network rules of a `Guard` on top of the system resolver.
+#[derive(Clone, Debug)]
+pub(crate) struct Resolver {
+    inner: GaiResolver,
+    family: AddressFamily,
+    guard: Option<Arc<Guard>>,
+    /// A hostname resolved as another host, e.g. the address of a specific origin behind a load balancer.
+    connect_to: Option<(String, String)>,
+}
+
+impl Resolver {
+    pub(crate) fn new(family: AddressFamily, guard: Option<Arc<Guard>>, connect_to: Option<(String, String)>) -> Self {
+        Self { inner: GaiResolver::new(), family, guard, connect_to }
+    }
+}
+
+impl tower_service::Service<Name> for Resolver {
+    type Response = std::vec::IntoIter<SocketAddr>;
+    type Error = std::io::Error;
+    type Future = Pin<Box<dyn Future<Output = Result<Self::Response, Self::Error>> + Send>>;
+
+    fn poll_ready(&mut self, cx: &mut Context<'_>) -> Poll<Result<(), Self::Error>> {
+        self.inner.poll_ready(cx)
+    }
+
+    fn call(&mut self, name: Name) -> Self::Future {
+        let family = self.family;
+        let guard = self.guard.clone();
+        let host = name.as_str().to_string();
+        // Resolve the pinned hostname as its target, which is connected to as-is if it's an IP address
+        let target = match &self.connect_to {
+            Some((pinned, target)) if pinned.eq_ignore_ascii_case(&host) => {
+                Some(target.trim_matches(['[', ']']).to_string())
+            }
+            _ => None,
+        };
+        let mut inner = self.inner.clone();
+
+        Box::pin(async move {
+            let addrs: Vec<SocketAddr> = match target {
+                Some(target) => match target.parse::<IpAddr>() {
+                    Ok(ip) => vec![SocketAddr::new(ip, 0)],
+                    Err(_) => inner.call(target.parse::<Name>().map_err(std::io::Error::other)?).await?.collect(),
+                },
+                None => inner.call(name).await?.collect(),
+            };
+            let mut addrs = family.apply(addrs.into_iter());
+            // Never connect to addresses denied by the guard, even if the hostname itself is allowed.
+            if let Some(guard) = guard {
+                addrs.retain(|addr| guard.check_ip(addr.ip()).is_ok());
+                if addrs.is_empty() {
+                    return Err(std::io::Error::other(format!("all addresses of {host} are blocked by guard")));
+                }
+            }
+            match addrs.is_empty() {
+                true => Err(std::io::Error::other(format!("no {family:?} addresses found for {host}"))),
+                false => Ok(addrs.into_iter()),
+            }
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_address_family_apply() {
+        let v4: SocketAddr = "127.0.0.1:80".parse().unwrap();
+        let v6: SocketAddr = "[::1]:80".parse().unwrap();
+        let addrs = || vec![v4, v6].into_iter();
+
+        assert_eq!(AddressFamily::Any.apply(addrs()), vec![v4, v6]);
+        assert_eq!(AddressFamily::V4Only.apply(addrs()), vec![v4]);
+        assert_eq!(AddressFamily::V6Only.apply(addrs()), vec![v6]);
+        assert_eq!(AddressFamily::PreferV4.apply(addrs()), vec![v4, v6]);
+        assert_eq!(AddressFamily::PreferV6.apply(addrs()), vec![v6, v4]);
+    }
+}
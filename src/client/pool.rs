@@ -0,0 +1,110 @@
+use hyper::rt::{Read, ReadBufCursor, Write};
+use hyper::Uri;
+use hyper_util::client::legacy::connect::{Connected, Connection};
+use std::future::Future;
+use std::io::IoSlice;
+use std::pin::Pin;
+use std::sync::atomic::{AtomicU64, Ordering::SeqCst};
+use std::sync::Arc;
+use std::task::{Context, Poll};
+
+/// Connection statistics of a single host.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, serde::Serialize)]
+pub struct PoolStats {
+    /// The number of requests that required a new connection to be established.
+    pub new: u64,
+    /// The number of requests that were sent over an existing, pooled connection.
+    pub reused: u64,
+}
+
+/// The number of requests sent over a connection so far.
+/// Attached to the extensions of every response received through a `Tracked` connector.
+#[derive(Clone, Debug)]
+pub(crate) struct Uses(Arc<AtomicU64>);
+
+impl Uses {
+    /// Records a new request sent over the connection.
+    ///
+    /// # Returns
+    /// `true` if the connection was already used by a previous request.
+    pub(crate) fn record(&self) -> bool {
+        self.0.fetch_add(1, SeqCst) > 0
+    }
+}
+
+/// A connector wrapper that tags every established connection with a `Uses` counter,
+/// so that responses received over a reused connection can be told apart from new ones.
+#[derive(Clone, Debug)]
+pub(crate) struct Tracked<C> {
+    inner: C,
+}
+
+impl<C> Tracked<C> {
+    pub(crate) fn new(inner: C) -> Self {
+        Self { inner }
+    }
+}
+
+impl<C> tower_service::Service<Uri> for Tracked<C>
+where
+    C: tower_service::Service<Uri>,
+    C::Future: Send + 'static,
+{
+    type Response = TrackedStream<C::Response>;
+    type Error = C::Error;
+    type Future = Pin<Box<dyn Future<Output = Result<Self::Response, Self::Error>> + Send>>;
+
+    fn poll_ready(&mut self, cx: &mut Context<'_>) -> Poll<Result<(), Self::Error>> {
+        self.inner.poll_ready(cx)
+    }
+
+    fn call(&mut self, uri: Uri) -> Self::Future {
+        let connecting = self.inner.call(uri);
+        Box::pin(async move { Ok(TrackedStream { inner: connecting.await?, uses: Uses(Arc::default()) }) })
+    }
+}
+
+/// A connection established through a `Tracked` connector.
+#[derive(Debug)]
+pub(crate) struct TrackedStream<T> {
+    inner: T,
+    uses: Uses,
+}
+
+impl<T: Connection> Connection for TrackedStream<T> {
+    fn connected(&self) -> Connected {
+        self.inner.connected().extra(self.uses.clone())
+    }
+}
+
+impl<T: Read + Unpin> Read for TrackedStream<T> {
+    fn poll_read(mut self: Pin<&mut Self>, cx: &mut Context<'_>, buf: ReadBufCursor<'_>) -> Poll<std::io::Result<()>> {
+        Pin::new(&mut self.inner).poll_read(cx, buf)
+    }
+}
+
+impl<T: Write + Unpin> Write for TrackedStream<T> {
+    fn poll_write(mut self: Pin<&mut Self>, cx: &mut Context<'_>, buf: &[u8]) -> Poll<std::io::Result<usize>> {
+        Pin::new(&mut self.inner).poll_write(cx, buf)
+    }
+
+    fn poll_flush(mut self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<std::io::Result<()>> {
+        Pin::new(&mut self.inner).poll_flush(cx)
+    }
+
+    fn poll_shutdown(mut self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<std::io::Result<()>> {
+        Pin::new(&mut self.inner).poll_shutdown(cx)
+    }
+
+    fn is_write_vectored(&self) -> bool {
+        self.inner.is_write_vectored()
+    }
+
+    fn poll_write_vectored(
+        mut self: Pin<&mut Self>,
+        cx: &mut Context<'_>,
+        bufs: &[IoSlice<'_>],
+    ) -> Poll<std::io::Result<usize>> {
+        Pin::new(&mut self.inner).poll_write_vectored(cx, bufs)
+    }
+}
@@ -0,0 +1,196 @@
+use base64::{engine::general_purpose::STANDARD, Engine};
+use hyper::rt::{Read, Write};
+use hyper::Uri;
+use hyper_tls::MaybeHttpsStream;
+use sha2::{Digest, Sha256};
+use std::error::Error;
+use std::future::Future;
+use std::pin::Pin;
+use std::sync::Arc;
+use std::task::{Context, Poll};
+
+/// TLS settings of the connections to `https` endpoints.
+///
+/// - `accept_invalid_certs`: accepts invalid, expired or self-signed certificates (default: `false`)
+/// - `accept_invalid_hostnames`: accepts certificates issued for another hostname (default: `false`)
+/// - `min_version`: the minimum TLS version negotiated, either `1.0`, `1.1` or `1.2` (default: the system's)
+///
+/// Accepting invalid certificates exposes the probes to interception, so it should be limited to internal endpoints.
+#[derive(serde::Deserialize, serde::Serialize, Clone, Copy, Debug, Default, PartialEq, Eq, Hash)]
+pub struct Tls {
+    #[serde(default)]
+    pub accept_invalid_certs: bool,
+    #[serde(default)]
+    pub accept_invalid_hostnames: bool,
+    #[serde(default)]
+    pub min_version: Option<TlsVersion>,
+}
+
+/// A version of the TLS protocol.
+#[derive(serde::Deserialize, serde::Serialize, Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub enum TlsVersion {
+    #[serde(rename = "1.0")]
+    Tls10,
+    #[serde(rename = "1.1")]
+    Tls11,
+    #[serde(rename = "1.2")]
+    Tls12,
+}
+
+impl Tls {
+    /// Builds the TLS connector of the connections.
+    ///
+    /// # Panics
+    /// Panics if the TLS context can't be created.
+    pub(crate) fn connector(&self) -> tokio_native_tls::TlsConnector {
+        let min_version = self.min_version.map(|version| match version {
+            TlsVersion::Tls10 => native_tls::Protocol::Tlsv10,
+            TlsVersion::Tls11 => native_tls::Protocol::Tlsv11,
+            TlsVersion::Tls12 => native_tls::Protocol::Tlsv12,
+        });
+        let connector = native_tls::TlsConnector::builder()
+            .danger_accept_invalid_certs(self.accept_invalid_certs)
+            .danger_accept_invalid_hostnames(self.accept_invalid_hostnames)
+            .min_protocol_version(min_version)
+            .build()
+            .expect("failed to create TLS connector");
+        connector.into()
+    }
+}
+
+/// The prefix of the errors of the connections whose certificate doesn't match the pins of their request.
+pub(crate) const PIN_MISMATCH: &str = "certificate pin mismatch";
+
+/// The error of a connection whose certificate doesn't match any of the pins of its request.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub(crate) struct PinMismatch(Option<String>);
+
+impl std::fmt::Display for PinMismatch {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match &self.0 {
+            Some(pin) => write!(f, "{PIN_MISMATCH}: the server presented {pin}"),
+            None => write!(f, "{PIN_MISMATCH}: the server presented no certificate"),
+        }
+    }
+}
+
+impl std::error::Error for PinMismatch {}
+
+/// A connector wrapper that verifies the public key of every connection against the pins of its request,
+/// before any request is sent over it.
+///
+/// Pins are the SHA-256 hashes of the subject public key info of the expected certificates, encoded in base64 and
+/// prefixed with `sha256/`, as printed by
+/// `openssl x509 -pubkey -noout | openssl pkey -pubin -outform der | openssl dgst -sha256 -binary | base64`.
+/// Without pins, connections are passed through unverified.
+#[derive(Clone, Debug)]
+pub(crate) struct Pinned<C> {
+    inner: C,
+    pins: Arc<[String]>,
+}
+
+impl<C> Pinned<C> {
+    pub(crate) fn new(inner: C, pins: &[String]) -> Self {
+        Self { inner, pins: pins.into() }
+    }
+}
+
+impl<C, T> tower_service::Service<Uri> for Pinned<C>
+where
+    C: tower_service::Service<Uri, Response = MaybeHttpsStream<T>>,
+    C::Error: Into<Box<dyn Error + Send + Sync>>,
+    C::Future: Send + 'static,
+    T: Read + Write + Unpin,
+{
+    type Response = MaybeHttpsStream<T>;
+    type Error = Box<dyn Error + Send + Sync>;
+    type Future = Pin<Box<dyn Future<Output = Result<Self::Response, Self::Error>> + Send>>;
+
+    fn poll_ready(&mut self, cx: &mut Context<'_>) -> Poll<Result<(), Self::Error>> {
+        self.inner.poll_ready(cx).map_err(Into::into)
+    }
+
+    fn call(&mut self, uri: Uri) -> Self::Future {
+        let pins = self.pins.clone();
+        let connecting = self.inner.call(uri);
+        Box::pin(async move {
+            let stream = connecting.await.map_err(Into::into)?;
+            if pins.is_empty() {
+                return Ok(stream);
+            }
+            // Plain-text connections have no certificate, so they never match
+            let certificate = match &stream {
+                MaybeHttpsStream::Https(tls) => tls.inner().get_ref().peer_certificate()?,
+                MaybeHttpsStream::Http(_) => None,
+            };
+            let pin = match certificate {
+                Some(certificate) => Some(spki_pin(&certificate.to_der()?).ok_or("invalid peer certificate")?),
+                None => None,
+            };
+            match pin {
+                Some(pin) if pins.contains(&pin) => Ok(stream),
+                pin => Err(PinMismatch(pin).into()),
+            }
+        })
+    }
+}
+
+/// Computes the pin of a DER-encoded certificate, the SHA-256 hash of its subject public key info.
+///
+/// # Returns
+/// The pin, prefixed with `sha256/`, or `None` if the certificate can't be parsed.
+fn spki_pin(der: &[u8]) -> Option<String> {
+    // Certificate ::= SEQUENCE { tbsCertificate, signatureAlgorithm, signatureValue }
+    let (_, certificate, _) = tlv(der)?;
+    let (_, mut fields, _) = tlv(certificate)?;
+    // TBSCertificate ::= SEQUENCE { [0] version OPTIONAL, serialNumber, signature, issuer, validity, subject,
+    // subjectPublicKeyInfo, ... }
+    if fields.first() == Some(&0xa0) {
+        fields = tlv(fields)?.2;
+    }
+    for _ in 0..5 {
+        fields = tlv(fields)?.2;
+    }
+    let (spki, _, _) = tlv(fields)?;
+    Some(format!("sha256/{}", STANDARD.encode(Sha256::digest(spki))))
+}
+
+/// Splits a DER element from its input.
+///
+/// # Returns
+/// The whole element, its content and the rest of the input, or `None` if the element is truncated.
+fn tlv(input: &[u8]) -> Option<(&[u8], &[u8], &[u8])> {
+    let first = *input.get(1)? as usize;
+    let (header, length) = match first {
+        0..=0x7f => (2, first),
+        // Long form lengths, up to 4 bytes
+        0x81..=0x84 => {
+            let bytes = input.get(2..2 + first - 0x80)?;
+            (2 + bytes.len(), bytes.iter().fold(0, |length, byte| length << 8 | *byte as usize))
+        }
+        _ => return None,
+    };
+    let element = input.get(..header.checked_add(length)?)?;
+    Some((element, &element[header..], &input[element.len()..]))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_spki_pin() {
+        let der = STANDARD
+            .decode(
+                "MIIBgTCCASegAwIBAgIUVHxIoWuMCnlnBMxDmgflYoVm6RIwCgYIKoZIzj0EAwIwFjEUMBIGA1UEAwwLcGluLmV4YW1wbGUwHhcNMjYx\
+                 MDE3MDE1MTE1WhcNMzYxMDE0MDE1MTE1WjAWMRQwEgYDVQQDDAtwaW4uZXhhbXBsZTBZMBMGByqGSM49AgEGCCqGSM49AwEHA0IABI2K\
+                 yBpPIze64pZ28ixA/fN+UW0u1J/eQxmsiF59A24SJuIEdfnjs0PnX0l8uRrjCKrrkcOMkMl6Oln/iFxu11mjUzBRMB0GA1UdDgQWBBS4\
+                 5Xb70CNgwsLyj3E6m3AahK1G6jAfBgNVHSMEGDAWgBS45Xb70CNgwsLyj3E6m3AahK1G6jAPBgNVHRMBAf8EBTADAQH/MAoGCCqGSM49\
+                 BAMCA0gAMEUCIQCWpLO1GFHkN61DuDEWxZnQo5Z3Bg83nImtXt9X3mLyuwIgDVFop7q2m7qrxyV/C9GmKVF7PYCCELvZ5EP+fangJWo=",
+            )
+            .unwrap();
+        // As printed by `openssl x509 -pubkey -noout | openssl pkey -pubin -outform der | openssl dgst -sha256 -binary`
+        assert_eq!(spki_pin(&der).unwrap(), "sha256/eJLa95Fj/kjYWGlKZ3rz29nkORxeO8zZha21xPNIJ2Q=");
+        assert_eq!(spki_pin(&der[..100]), None);
+    }
+}
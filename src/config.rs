@@ -1,12 +1,53 @@
-use crate::{client, request::Request, store, strategy};
+use crate::{
+    agent, alert, chaos, client, election, export, forecast, guard, history, incident, notify, report,
+    request::Request, secret, shard, store, strategy,
+};
 use bytes::Bytes;
 use hyper::header::{HeaderName, HeaderValue};
 use hyper::{HeaderMap, Method, Uri};
-use serde::{Deserialize, Deserializer};
+use serde::{Deserialize, Deserializer, Serialize, Serializer};
 use std::{collections::HashMap, error::Error, str::FromStr, time::Duration};
 
+/// The prefix of the environment variables overriding the fields of the configuration file.
+const ENV_PREFIX: &str = "ISUP_";
+
+/// The top-level fields of `Config`, which environment variables can set although they're missing from the file.
+const FIELDS: &[&str] = &[
+    "client",
+    "strategy",
+    "store",
+    "interval",
+    "requests",
+    "guard",
+    "chaos",
+    "request_id_header",
+    "quorum",
+    "backoff",
+    "agent",
+    "election",
+    "sharding",
+    "notifiers",
+    "exporters",
+    "report",
+    "history",
+    "alertmanager",
+    "forecast",
+    "traceroute",
+    "persist_requests",
+    "read_only",
+    "namespace",
+    "grace_period",
+    "relative_scoring",
+    "cycle_deadline",
+    "concurrency",
+    "tracing",
+    "capture",
+    "error_window",
+    "scoring_batch",
+];
+
 /// Main configuration struct containing all other configuration settings for each module.
-#[derive(serde::Deserialize, Debug)]
+#[derive(serde::Deserialize, serde::Serialize, Debug)]
 pub struct Config {
     /// Specifies how the HTTP client behaves, particularly concerning request timeouts and
     /// connection pool behavior.
@@ -20,29 +61,301 @@ pub struct Config {
     #[serde(default)]
     pub store: store::Config,
     /// Can be set, if there's the need to provide an interval for the `run` method from config.
-    #[serde(deserialize_with = "deserialize_opt_duration")]
     #[serde(default)]
-    pub interval: Option<Duration>,
+    pub interval: Option<ConfigDuration>,
     /// List of web service requests to monitor.
     pub requests: Vec<Request>,
+    /// Restricts the endpoints that can be monitored, protecting against SSRF when URLs are user-submitted.
+    #[serde(default)]
+    pub guard: Option<guard::Config>,
+    /// Simulates the listed endpoints instead of requesting them, for testing purposes.
+    #[serde(default)]
+    pub chaos: Option<chaos::Config>,
+    /// The name of a header carrying a unique ID on every probe, e.g. `x-request-id`. Disabled if not set.
+    #[serde(default)]
+    pub request_id_header: Option<String>,
+    /// The number of failed probes required before an endpoint is considered down. A single failure if not set.
+    #[serde(default)]
+    pub quorum: Option<incident::Quorum>,
+    /// Backs off the probing of the endpoints that stay down for several cycles. Disabled if not set.
+    #[serde(default)]
+    pub backoff: Option<incident::Backoff>,
+    /// Pushes the outcome of every probe to a coordinator, instead of only scoring it locally.
+    #[serde(default)]
+    pub agent: Option<agent::Config>,
+    /// Elects a single replica probing the endpoints, among the ones sharing the store.
+    #[serde(default)]
+    pub election: Option<election::Config>,
+    /// Splits the endpoints among the replicas sharing the store.
+    #[serde(default)]
+    pub sharding: Option<shard::Config>,
+    /// The channels notifications, such as summary reports, are delivered through.
+    #[serde(default)]
+    pub notifiers: Vec<notify::Config>,
+    /// The sinks the scored probes of every update cycle are exported to, e.g. a file or InfluxDB.
+    #[serde(default)]
+    pub exporters: Vec<export::Config>,
+    /// Delivers a summary of the endpoints through the notifiers at the end of every period. Disabled if not set.
+    #[serde(default)]
+    pub report: Option<report::Config>,
+    /// Records the history of the endpoints in the store, e.g. to be graphed through the embedded server.
+    #[serde(default)]
+    pub history: Option<history::Config>,
+    /// Raises the incidents of the endpoints as alerts in Alertmanager. Disabled if not set.
+    #[serde(default)]
+    pub alertmanager: Option<alert::Config>,
+    /// Flags the endpoints whose score is forecast to fall under a threshold, before it does. Disabled if not set.
+    #[serde(default)]
+    pub forecast: Option<forecast::Config>,
+    /// Isolates the service from the other ones sharing its store, e.g. the name of a team or tenant.
+    /// Its keys are prefixed with the namespace, and its alerts are labeled with it.
+    #[serde(default)]
+    pub namespace: Option<String>,
+    /// Persists the monitored requests in the store, so the ones inserted at runtime can be restored on startup.
+    #[serde(default)]
+    pub persist_requests: bool,
+    /// Marks the configuration as the one of a consumer, e.g. an API server querying the shared store through a
+    /// `store::ReadOnlyStore`, so no `Service` probing and writing the scores can be built from it.
+    #[serde(default)]
+    pub read_only: bool,
+    /// How long the endpoints inserted at runtime are probed and scored, but excluded from `best_url` and alerting,
+    /// so their fresh score doesn't immediately influence the routing. Disabled if not set.
+    #[serde(default)]
+    pub grace_period: Option<ConfigDuration>,
+    /// Exposes the scores relative to the best endpoint, scoring `1.0`, when ranking and filtering the endpoints.
+    #[serde(default)]
+    pub relative_scoring: bool,
+    /// How long the probes of an update cycle may run, e.g. `50s` for an interval of `1m`. The probes still running
+    /// at the deadline are aborted and scored as timeouts, keeping the update cadence predictable. Disabled if not set.
+    #[serde(default)]
+    pub cycle_deadline: Option<ConfigDuration>,
+    /// The maximum number of endpoints probed at once within a cycle, the ones that are down and the ones of higher
+    /// priority being probed first. All of them at once if not set.
+    #[serde(default)]
+    pub concurrency: Option<usize>,
+    /// The number of latest probes the error rates of the endpoints are computed over (default: 100).
+    #[serde(default)]
+    pub error_window: Option<usize>,
+    /// The maximum number of samples scored and written to the store at once (default: 64).
+    #[serde(default)]
+    pub scoring_batch: Option<usize>,
+    /// Emits a `tracing` span for the sampled probes and propagates their trace context. Disabled if not set.
+    #[serde(default)]
+    pub tracing: Option<crate::trace::Config>,
+    /// Keeps the headers and the beginning of the body of the latest failed response of every endpoint.
+    /// Disabled if not set.
+    #[serde(default)]
+    pub capture: Option<crate::capture::Config>,
+    /// Traces the network path toward the endpoints that go down. Requires the `traceroute` feature.
+    #[cfg(feature = "traceroute")]
+    #[serde(default)]
+    pub traceroute: Option<crate::traceroute::Config>,
 }
 
 impl Config {
-    /// Constructs a `Config` object from a YAML file.
+    /// Constructs a `Config` object from a YAML file, overridden by the `ISUP_*` environment variables.
+    ///
+    /// Every variable sets the field at its path, with `_` separating the levels, e.g. `ISUP_INTERVAL=10s`,
+    /// `ISUP_STORE_CONNECTION=redis://redis:6379` or `ISUP_REQUESTS_0_URL=https://example.com`.
+    /// Values are parsed as YAML, so `ISUP_QUORUM={ failures: 2, probes: 3 }` sets a whole section;
+    /// quoting a value keeps it a string.
+    ///
+    /// Sensitive values can be written as references to secrets, `{ from_env: NAME }` or `{ from_file: path }`,
+    /// resolved once the environment variables are applied.
     ///
     /// # Arguments
     /// * `path` - A string slice that holds the path to the config YAML file.
     ///
     /// # Returns
-    /// `Config` on success or a `Box<dyn Error>` error caused due to parsing or reading the file.
-    pub fn from_file(path: &str) -> Result<Config, Box<dyn Error>> {
+    /// `Config` on success or a `Box<dyn Error + Send + Sync>` error caused due to parsing or reading the file.
+    pub fn from_file(path: &str) -> Result<Config, Box<dyn Error + Send + Sync>> {
+        Self::from_file_with(path, secret::defaults())
+    }
+
+    /// Constructs a `Config` object from a YAML file, like `from_file`, resolving the references to secrets
+    /// through the given resolvers, e.g. a Vault client handling `{ from_vault: path }` references.
+    ///
+    /// # Arguments
+    /// * `path` - A string slice that holds the path to the config YAML file.
+    /// * `resolvers` - The resolvers of the references, e.g. `secret::defaults()` along with a custom one.
+    ///
+    /// # Returns
+    /// `Config` on success or a `Box<dyn Error + Send + Sync>` error caused due to parsing or reading the file,
+    /// or resolving a secret.
+    pub fn from_file_with(
+        path: &str,
+        resolvers: Vec<Box<dyn secret::Resolver>>,
+    ) -> Result<Config, Box<dyn Error + Send + Sync>> {
         // Read the configuration file into a string.
         let config_str = std::fs::read_to_string(path)?;
 
-        // Deserialize the YAML string into a `Config` object.
-        let config = serde_yaml::from_str(&config_str)?;
+        // Override the fields of the file with the environment variables.
+        let mut value = serde_yaml::from_str::<serde_yaml::Value>(&config_str)?;
+        overlay(&mut value, std::env::vars())?;
+        // Replace the references with the secrets they point to.
+        secret::resolve(&mut value, &resolvers)?;
+
+        // Deserialize the YAML value into a `Config` object.
+        let config = serde_yaml::from_value(value)?;
         Ok(config)
     }
+
+    /// Writes the configuration to a YAML file, which `from_file` reads back into an equivalent `Config`.
+    ///
+    /// Secrets are written as resolved, rather than as the references they were loaded from.
+    ///
+    /// # Arguments
+    /// * `path` - A string slice that holds the path to the config YAML file.
+    ///
+    /// # Returns
+    /// An empty `Ok` on success or a `Box<dyn Error + Send + Sync>` error caused due to serializing or writing the file.
+    pub fn to_file(&self, path: &str) -> Result<(), Box<dyn Error + Send + Sync>> {
+        let config_str = serde_yaml::to_string(self)?;
+        std::fs::write(path, config_str)?;
+        Ok(())
+    }
+
+    /// Returns the interval the `run` method of the service is called with.
+    ///
+    /// # Errors
+    /// Returns `ConfigError::MissingInterval` if the configuration has no interval, or `ConfigError::ZeroInterval`
+    /// if it's zero.
+    pub fn interval(&self) -> Result<Duration, ConfigError> {
+        match self.interval {
+            None => Err(ConfigError::MissingInterval),
+            Some(interval) if interval.is_zero() => Err(ConfigError::ZeroInterval),
+            Some(interval) => Ok(interval.get()),
+        }
+    }
+
+    /// Returns the client configuration the service is built with, once the implicit defaults are resolved.
+    ///
+    /// Without a `client` section, the `request_timeout` defaults to the interval, and the remaining fields to the
+    /// defaults of `client::Config`, e.g. the `pool_idle_timeout` of the underlying hyper client (90s).
+    pub fn effective_client(&self) -> client::Config {
+        match &self.client {
+            Some(client) => client.clone(),
+            None => client::Config { request_timeout: self.interval, ..Default::default() },
+        }
+    }
+
+    /// Returns the store configuration the service is built with, isolated under its namespace if any.
+    pub fn effective_store(&self) -> store::Config {
+        match &self.namespace {
+            Some(namespace) => self.store.clone().set_namespace(namespace),
+            None => self.store.clone(),
+        }
+    }
+
+    /// Checks that the values of the configuration are consistent with each other.
+    ///
+    /// # Errors
+    /// Returns `ConfigError::ZeroInterval` if the interval is zero, which would update the scores in a busy loop, or
+    /// `ConfigError::TimeoutExceedsInterval` if the request timeout is longer than the interval, which would let the
+    /// probes of a cycle run into the next one.
+    pub fn validate(&self) -> Result<(), ConfigError> {
+        if self.interval.is_some_and(|interval| interval.is_zero()) {
+            return Err(ConfigError::ZeroInterval);
+        }
+        let (Some(timeout), Some(interval)) = (self.effective_client().request_timeout, self.interval) else {
+            return Ok(());
+        };
+        if timeout > interval {
+            return Err(ConfigError::TimeoutExceedsInterval { timeout: timeout.get(), interval: interval.get() });
+        }
+        Ok(())
+    }
+}
+
+/// The reason a `Config` is inconsistent or incomplete.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum ConfigError {
+    /// The service is run from the configuration, although it has no `interval`.
+    MissingInterval,
+    /// The `interval` is zero.
+    ZeroInterval,
+    /// The `request_timeout` of the client is longer than the `interval`.
+    TimeoutExceedsInterval { timeout: Duration, interval: Duration },
+}
+
+impl std::fmt::Display for ConfigError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Self::MissingInterval => write!(f, "missing interval"),
+            Self::ZeroInterval => write!(f, "the interval must be positive"),
+            Self::TimeoutExceedsInterval { timeout, interval } => {
+                write!(f, "request timeout of {timeout:?} exceeds the interval of {interval:?}")
+            }
+        }
+    }
+}
+
+impl Error for ConfigError {}
+
+/// Overrides the fields of a YAML configuration with the `ISUP_*` variables among the given ones.
+///
+/// # Arguments
+/// * `config` - The configuration, as parsed from the file.
+/// * `vars` - The environment variables, as name and value pairs.
+///
+/// # Returns
+/// An error naming the variable whose path doesn't lead to a field.
+fn overlay(config: &mut serde_yaml::Value, vars: impl IntoIterator<Item = (String, String)>) -> Result<(), String> {
+    // Variables are applied in order, so that sequences are extended one item after the other
+    let mut vars = vars.into_iter().filter(|(name, _)| name.starts_with(ENV_PREFIX)).collect::<Vec<_>>();
+    vars.sort();
+    for (name, value) in vars {
+        let path = name[ENV_PREFIX.len()..].to_ascii_lowercase();
+        let segments = path.split('_').collect::<Vec<_>>();
+        set(config, &segments, FIELDS, &value).map_err(|e| format!("invalid `{name}`: {e}"))?;
+    }
+    Ok(())
+}
+
+/// Sets the field at the path of the given segments.
+///
+/// The segments are matched against the longest key, either present in the mapping or known, as keys contain `_`
+/// themselves. Missing keys are created, taking all the remaining segments.
+fn set(node: &mut serde_yaml::Value, segments: &[&str], known: &[&str], value: &str) -> Result<(), String> {
+    use serde_yaml::Value;
+
+    if node.is_null() {
+        *node = Value::Mapping(Default::default());
+    }
+    let (child, rest) = match node {
+        Value::Sequence(items) => {
+            let index = segments[0].parse::<usize>().map_err(|_| format!("`{}` isn't an index", segments[0]))?;
+            // The index right past the end appends an item
+            if index == items.len() {
+                items.push(Value::Null);
+            }
+            let len = items.len();
+            (items.get_mut(index).ok_or(format!("index {index} is out of bounds ({len} items)"))?, &segments[1..])
+        }
+        Value::Mapping(map) => {
+            let matches = |n: &usize| {
+                let key = segments[..*n].join("_");
+                map.contains_key(key.as_str()) || known.contains(&key.as_str())
+            };
+            let n = (1..=segments.len()).rev().find(matches).unwrap_or(segments.len());
+            (map.entry(Value::String(segments[..n].join("_"))).or_insert(Value::Null), &segments[n..])
+        }
+        _ => return Err("the field isn't a mapping".into()),
+    };
+
+    match rest {
+        [] => {
+            *child = match serde_yaml::from_str::<Value>(value) {
+                // Strings are kept as such, although they look like a number or boolean
+                Ok(Value::Bool(_) | Value::Number(_)) if child.is_string() => Value::String(value.into()),
+                Ok(Value::Null) if !value.is_empty() => Value::String(value.into()),
+                Ok(parsed) => parsed,
+                Err(_) => Value::String(value.into()),
+            };
+            Ok(())
+        }
+        rest => set(child, rest, &[], value),
+    }
 }
 
 /// Deserializes body from a `String` into `Bytes`.
@@ -62,25 +375,225 @@ where
     Ok(Bytes::from(value))
 }
 
-/// Deserializes a string into an `Option<Duration>`.
+/// Serializes a body from `Bytes` into the value it was deserialized from.
+/// Bodies that aren't JSON are serialized as a string.
 ///
 /// # Arguments
-/// * `deserializer` - A deserializer that implements the `Deserializer` trait.
+/// * `body` - The body to be serialized.
+/// * `serializer` - A serializer that implements the `Serializer` trait.
 ///
 /// # Returns
-/// An optional Duration on success or a deserialization error on failure.
-pub(crate) fn deserialize_opt_duration<'de, D>(deserializer: D) -> Result<Option<Duration>, D::Error>
+/// The output of the serializer on success or a serialization error on failure.
+pub(crate) fn serialize_body<S>(body: &Bytes, serializer: S) -> Result<S::Ok, S::Error>
 where
-    D: serde::Deserializer<'de>,
+    S: Serializer,
 {
-    let s = Option::<String>::deserialize(deserializer)?;
-    match s {
-        Some(s) => {
-            let duration = humantime::parse_duration(&s).map_err(serde::de::Error::custom)?;
-            Ok(Some(duration))
+    match serde_json::from_slice::<serde_json::Value>(body) {
+        Ok(value) => value.serialize(serializer),
+        Err(_) => String::from_utf8_lossy(body).serialize(serializer),
+    }
+}
+
+/// A duration as written in the configuration: an integer number of seconds, a `humantime` string, e.g. `1m 30s`,
+/// or an ISO-8601 duration, e.g. `PT1M30S`.
+#[derive(Deserialize)]
+#[serde(untagged)]
+enum RawDuration {
+    Seconds(u64),
+    Text(String),
+}
+
+impl RawDuration {
+    /// Parses the duration, whichever its representation.
+    fn parse<E: serde::de::Error>(self) -> Result<ConfigDuration, E> {
+        match self {
+            Self::Seconds(seconds) => Ok(ConfigDuration::new(Duration::from_secs(seconds), DurationFormat::Seconds)),
+            Self::Text(s) if s.starts_with('P') => {
+                parse_iso8601(&s).map(|d| ConfigDuration::new(d, DurationFormat::Iso8601)).map_err(E::custom)
+            }
+            Self::Text(s) => humantime::parse_duration(&s).map(ConfigDuration::from).map_err(E::custom),
+        }
+    }
+}
+
+/// The representation a duration was written in.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+enum DurationFormat {
+    /// An integer number of seconds, e.g. `90`.
+    Seconds,
+    /// A `humantime` string, e.g. `1m 30s`.
+    #[default]
+    Humantime,
+    /// An ISO-8601 duration, e.g. `PT1M30S`.
+    Iso8601,
+}
+
+/// A duration of the configuration, read from an integer number of seconds, a `humantime` string, e.g. `1m 30s`,
+/// or an ISO-8601 duration, e.g. `PT1M30S`, and written back in the same representation, so that generated
+/// configurations read like their source. The durations set in code are written as `humantime` strings.
+///
+/// It dereferences to the `Duration` it holds, and compares, orders and hashes as it regardless of its representation.
+#[derive(Clone, Copy, Debug, Default)]
+pub struct ConfigDuration {
+    duration: Duration,
+    format: DurationFormat,
+}
+
+impl ConfigDuration {
+    fn new(duration: Duration, format: DurationFormat) -> Self {
+        Self { duration, format }
+    }
+
+    /// Returns the duration.
+    pub fn get(self) -> Duration {
+        self.duration
+    }
+}
+
+impl From<Duration> for ConfigDuration {
+    fn from(duration: Duration) -> Self {
+        Self::new(duration, DurationFormat::Humantime)
+    }
+}
+
+impl From<ConfigDuration> for Duration {
+    fn from(duration: ConfigDuration) -> Self {
+        duration.duration
+    }
+}
+
+impl std::ops::Deref for ConfigDuration {
+    type Target = Duration;
+
+    fn deref(&self) -> &Duration {
+        &self.duration
+    }
+}
+
+impl PartialEq for ConfigDuration {
+    fn eq(&self, other: &Self) -> bool {
+        self.duration == other.duration
+    }
+}
+
+impl Eq for ConfigDuration {}
+
+impl PartialEq<Duration> for ConfigDuration {
+    fn eq(&self, other: &Duration) -> bool {
+        self.duration == *other
+    }
+}
+
+impl PartialOrd for ConfigDuration {
+    fn partial_cmp(&self, other: &Self) -> Option<std::cmp::Ordering> {
+        Some(self.cmp(other))
+    }
+}
+
+impl Ord for ConfigDuration {
+    fn cmp(&self, other: &Self) -> std::cmp::Ordering {
+        self.duration.cmp(&other.duration)
+    }
+}
+
+impl std::hash::Hash for ConfigDuration {
+    fn hash<H: std::hash::Hasher>(&self, state: &mut H) {
+        self.duration.hash(state);
+    }
+}
+
+impl<'de> Deserialize<'de> for ConfigDuration {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        RawDuration::deserialize(deserializer)?.parse()
+    }
+}
+
+impl Serialize for ConfigDuration {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        match self.format {
+            DurationFormat::Seconds if self.duration.subsec_nanos() == 0 => {
+                self.duration.as_secs().serialize(serializer)
+            }
+            DurationFormat::Iso8601 => format_iso8601(self.duration).serialize(serializer),
+            _ => humantime::format_duration(self.duration).to_string().serialize(serializer),
+        }
+    }
+}
+
+/// Parses an ISO-8601 duration, e.g. `PT1M30S` or `P1DT12H`, whose last component may have a fraction.
+///
+/// Years and months are rejected, since their length varies.
+fn parse_iso8601(s: &str) -> Result<Duration, String> {
+    let invalid = || format!("invalid ISO-8601 duration `{s}`");
+    let rest = s.strip_prefix('P').filter(|rest| !rest.is_empty()).ok_or_else(invalid)?;
+    let (date, time) = match rest.split_once('T') {
+        Some((_, "")) => return Err(invalid()),
+        Some((date, time)) => (date, time),
+        None => (rest, ""),
+    };
+
+    let mut nanos = 0u128;
+    let components: [(&str, &[(char, u128)]); 2] =
+        [(date, &[('W', 604_800), ('D', 86_400)]), (time, &[('H', 3_600), ('M', 60), ('S', 1)])];
+    for (i, (mut part, units)) in components.into_iter().enumerate() {
+        let is_last = i == 1 || time.is_empty();
+        // The designators are expected in order, each at most once
+        let mut units = units.iter();
+        while !part.is_empty() {
+            let end = part.find(|c: char| c.is_ascii_alphabetic()).ok_or_else(invalid)?;
+            let designator = part[end..].chars().next().ok_or_else(invalid)?;
+            let (_, seconds) = units.find(|(d, _)| *d == designator).ok_or_else(invalid)?;
+            let unit = seconds * 1_000_000_000;
+
+            let (whole, fraction) = part[..end].split_once(['.', ',']).unwrap_or((&part[..end], ""));
+            if whole.is_empty() || !whole.bytes().chain(fraction.bytes()).all(|b| b.is_ascii_digit()) {
+                return Err(invalid());
+            }
+            let value = whole.parse::<u128>().ok().and_then(|value| value.checked_mul(unit));
+            nanos = value.and_then(|value| nanos.checked_add(value)).ok_or_else(invalid)?;
+            if !fraction.is_empty() {
+                // Only the last component may have a fraction, precise to the nanosecond
+                let digits = &fraction[..fraction.len().min(9)];
+                let scale = 10u128.pow(digits.len() as u32);
+                nanos += digits.parse::<u128>().map_err(|_| invalid())? * unit / scale;
+                if !is_last || end + 1 != part.len() {
+                    return Err(invalid());
+                }
+            }
+            part = &part[end + 1..];
+        }
+    }
+    let secs = u64::try_from(nanos / 1_000_000_000).map_err(|_| invalid())?;
+    Ok(Duration::new(secs, (nanos % 1_000_000_000) as u32))
+}
+
+/// Formats a duration as an ISO-8601 duration, e.g. `PT1M30S` or `P1DT12H`, with a fraction of seconds if any.
+fn format_iso8601(duration: Duration) -> String {
+    let secs = duration.as_secs();
+    let (days, hours, minutes, seconds) = (secs / 86_400, secs / 3_600 % 24, secs / 60 % 60, secs % 60);
+    let mut iso = String::from("P");
+    if days > 0 {
+        iso.push_str(&format!("{days}D"));
+    }
+    let nanos = duration.subsec_nanos();
+    if hours > 0 || minutes > 0 || seconds > 0 || nanos > 0 || days == 0 {
+        iso.push('T');
+        if hours > 0 {
+            iso.push_str(&format!("{hours}H"));
+        }
+        if minutes > 0 {
+            iso.push_str(&format!("{minutes}M"));
+        }
+        match nanos {
+            0 if seconds > 0 || iso == "PT" => iso.push_str(&format!("{seconds}S")),
+            0 => {}
+            nanos => {
+                let fraction = format!("{nanos:09}");
+                iso.push_str(&format!("{seconds}.{}S", fraction.trim_end_matches('0')));
+            }
         }
-        None => Ok(None),
     }
+    iso
 }
 
 /// Deserialize an HTTP method from a string.
@@ -98,8 +611,24 @@ where
     let s = String::deserialize(deserializer)?;
     s.parse::<Method>().map_err(serde::de::Error::custom)
 }
+
+/// Serialize an HTTP method into a string.
+///
+/// ## Arguments
+/// * `method`: &Method - The HTTP method to be serialized.
+/// * `serializer`: S - The serializer used for the HTTP method.
+///
+/// ## Returns
+/// A `Result` that is either the output of the serializer on success or a serialization `Error` on failure.
+pub(crate) fn serialize_method<S>(method: &Method, serializer: S) -> Result<S::Ok, S::Error>
+where
+    S: Serializer,
+{
+    serializer.serialize_str(method.as_str())
+}
+
 /// Deserialize a URI from a string.
-/// Validates the URI and ensures proper formatting.
+/// Validates the URI and normalizes it, so that equivalent URLs share the same key.
 ///
 /// ## Arguments
 /// * `deserializer`: D - The deserializer used to parse the URL.
@@ -111,7 +640,82 @@ where
     D: Deserializer<'de>,
 {
     let s = String::deserialize(deserializer)?;
-    Uri::from_str(&s).map_err(serde::de::Error::custom)
+    Uri::from_str(&s).map(Request::normalize).map_err(serde::de::Error::custom)
+}
+
+/// Serialize a URI into a string.
+///
+/// ## Arguments
+/// * `uri`: &Uri - The URL to be serialized.
+/// * `serializer`: S - The serializer used for the URL.
+///
+/// ## Returns
+/// A `Result` that is either the output of the serializer on success or a serialization `Error` on failure.
+pub(crate) fn serialize_uri<S>(uri: &Uri, serializer: S) -> Result<S::Ok, S::Error>
+where
+    S: Serializer,
+{
+    serializer.collect_str(uri)
+}
+
+/// Deserialize an optional URI from a string, normalized like `deserialize_uri`.
+///
+/// ## Arguments
+/// * `deserializer`: D - The deserializer used to parse the URL.
+///
+/// ## Returns
+/// A `Result` that is either an optional `Uri` on success or a deserialization `Error` on failure.
+pub(crate) fn deserialize_opt_uri<'de, D>(deserializer: D) -> Result<Option<Uri>, D::Error>
+where
+    D: Deserializer<'de>,
+{
+    let s = Option::<String>::deserialize(deserializer)?;
+    s.map(|s| Uri::from_str(&s).map(Request::normalize).map_err(serde::de::Error::custom)).transpose()
+}
+
+/// Serialize an optional URI into a string.
+///
+/// ## Arguments
+/// * `uri`: &Option<Uri> - The URL to be serialized.
+/// * `serializer`: S - The serializer used for the URL.
+///
+/// ## Returns
+/// A `Result` that is either the output of the serializer on success or a serialization `Error` on failure.
+pub(crate) fn serialize_opt_uri<S>(uri: &Option<Uri>, serializer: S) -> Result<S::Ok, S::Error>
+where
+    S: Serializer,
+{
+    uri.as_ref().map(Uri::to_string).serialize(serializer)
+}
+
+/// Deserialize an optional regular expression from a string.
+///
+/// ## Arguments
+/// * `deserializer`: D - The deserializer used for the regular expression.
+///
+/// ## Returns
+/// A `Result` that is either an optional `Regex` on success or a deserialization `Error` on failure.
+pub(crate) fn deserialize_opt_regex<'de, D>(deserializer: D) -> Result<Option<regex::Regex>, D::Error>
+where
+    D: Deserializer<'de>,
+{
+    let s = Option::<String>::deserialize(deserializer)?;
+    s.map(|s| regex::Regex::new(&s).map_err(serde::de::Error::custom)).transpose()
+}
+
+/// Serialize an optional regular expression into its pattern.
+///
+/// ## Arguments
+/// * `regex`: &Option<Regex> - The regular expression to be serialized.
+/// * `serializer`: S - The serializer used for the regular expression.
+///
+/// ## Returns
+/// A `Result` that is either the output of the serializer on success or a serialization `Error` on failure.
+pub(crate) fn serialize_opt_regex<S>(regex: &Option<regex::Regex>, serializer: S) -> Result<S::Ok, S::Error>
+where
+    S: Serializer,
+{
+    regex.as_ref().map(regex::Regex::as_str).serialize(serializer)
 }
 
 /// Deserialize HTTP headers from a HashMap.
@@ -137,3 +741,193 @@ where
     });
     Ok(headers)
 }
+
+/// Serialize HTTP headers into a map of names and values.
+/// Fails on values that aren't visible ASCII, which the deserializer couldn't read back.
+///
+/// ## Arguments
+/// * `headers`: &HeaderMap - The headers to be serialized.
+/// * `serializer`: S - The serializer used for the headers.
+///
+/// ## Returns
+/// A `Result` that is either the output of the serializer on success or a serialization `Error` on failure.
+pub(crate) fn serialize_headers<S>(headers: &HeaderMap, serializer: S) -> Result<S::Ok, S::Error>
+where
+    S: Serializer,
+{
+    let mut map = std::collections::BTreeMap::new();
+    for (name, value) in headers {
+        map.insert(name.as_str(), value.to_str().map_err(serde::ser::Error::custom)?);
+    }
+    map.serialize(serializer)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_overlay() {
+        let mut value = serde_yaml::from_str(
+            "
+            store: { type: redis, connection: redis://localhost:6379 }
+            requests:
+              - url: https://a.example.com
+                method: GET
+            agent: { coordinator: https://isup.example.com, token: '1234' }
+            ",
+        )
+        .unwrap();
+        let vars = [
+            ("ISUP_STORE_CONNECTION", "redis://redis:6379"),
+            ("ISUP_INTERVAL", "10s"),
+            ("ISUP_REQUEST_ID_HEADER", "x-request-id"),
+            ("ISUP_REQUESTS_1_URL", "https://b.example.com"),
+            ("ISUP_REQUESTS_1_METHOD", "POST"),
+            ("ISUP_AGENT_TOKEN", "5678"),
+            ("ISUP_QUORUM", "{ failures: 2, probes: 3 }"),
+            ("HOME", "/root"),
+        ];
+        overlay(&mut value, vars.map(|(k, v)| (k.to_string(), v.to_string()))).unwrap();
+
+        let expected = serde_yaml::from_str::<serde_yaml::Value>(
+            "
+            store: { type: redis, connection: redis://redis:6379 }
+            requests:
+              - url: https://a.example.com
+                method: GET
+              - url: https://b.example.com
+                method: POST
+            agent: { coordinator: https://isup.example.com, token: '5678' }
+            interval: 10s
+            request_id_header: x-request-id
+            quorum: { failures: 2, probes: 3 }
+            ",
+        )
+        .unwrap();
+        assert_eq!(value, expected);
+
+        // Paths that don't lead to a field are rejected
+        let vars = [("ISUP_REQUESTS_5_URL".to_string(), "https://c.example.com".to_string())];
+        assert!(overlay(&mut value, vars).is_err());
+    }
+
+    #[test]
+    fn test_validate() {
+        let config = |yaml: &str| serde_yaml::from_str::<Config>(yaml).unwrap();
+
+        // Without a client section, the request timeout is resolved from the interval
+        let implicit = config("interval: 10s\nrequests: []");
+        assert_eq!(implicit.interval(), Ok(Duration::from_secs(10)));
+        assert_eq!(implicit.effective_client().request_timeout, Some(Duration::from_secs(10).into()));
+        assert_eq!(implicit.validate(), Ok(()));
+
+        // Running without an interval is a typed error
+        let missing = config("requests: []");
+        assert_eq!(missing.interval(), Err(ConfigError::MissingInterval));
+        assert_eq!(missing.validate(), Ok(()));
+
+        // A zero interval is rejected, rather than updating the scores in a busy loop
+        let zero = config("interval: 0s\nrequests: []");
+        assert_eq!(zero.interval(), Err(ConfigError::ZeroInterval));
+        assert_eq!(zero.validate(), Err(ConfigError::ZeroInterval));
+        assert!(crate::Service::from_config(zero).is_err());
+
+        // A request timeout longer than the interval is rejected, along with the services built from it
+        let exceeding = config("interval: 5s\nclient: { request_timeout: 10s, pool_idle_timeout: null }\nrequests: []");
+        let (timeout, interval) = (Duration::from_secs(10), Duration::from_secs(5));
+        assert_eq!(exceeding.validate(), Err(ConfigError::TimeoutExceedsInterval { timeout, interval }));
+        assert!(crate::Service::from_config(exceeding).is_err());
+    }
+
+    #[test]
+    fn test_round_trip() {
+        let config = serde_yaml::from_str::<Config>(
+            "
+            interval: 1m 30s
+            client: { request_timeout: 5s, pool_idle_timeout: null, address_family: prefer_v6 }
+            strategy: { type: weighted_log, weight: 0.8, effort: 5 }
+            requests:
+              - url: HTTPS://Example.com:443
+                method: POST
+                body: { query: ping }
+                headers: { authorization: Bearer 1234 }
+                accept_encoding: [gzip, br]
+                tags: { team: platform }
+              - url: tcp://example.com:22
+                method: GET
+                expect_banner: ^SSH-2\\.0-
+            guard: { allow_networks: [10.0.0.0/8], deny_networks: ['::1'] }
+            chaos: { endpoints: [{ url: https://example.com, latencies: [10ms, 2s], failure_rate: 0.5 }] }
+            quorum: { failures: 2, probes: 3 }
+            report: { period: 1week, format: html }
+            ",
+        )
+        .unwrap();
+
+        let path = std::env::temp_dir().join(format!("isup-config-{}.yml", std::process::id()));
+        let path = path.to_str().unwrap();
+        config.to_file(path).unwrap();
+        let parsed = Config::from_file(path).unwrap();
+        std::fs::remove_file(path).unwrap();
+
+        assert_eq!(serde_yaml::to_value(&parsed).unwrap(), serde_yaml::to_value(&config).unwrap());
+        let value = serde_yaml::to_value(&parsed).unwrap();
+        assert_eq!(value["interval"], "1m 30s");
+        assert_eq!(value["requests"][0]["url"], "https://example.com/");
+        assert_eq!(value["requests"][0]["body"]["query"], "ping");
+        assert_eq!(value["requests"][0]["headers"]["authorization"], "Bearer 1234");
+        assert_eq!(value["requests"][1]["expect_banner"], "^SSH-2\\.0-");
+        assert_eq!(value["guard"]["deny_networks"][0], "::1/128");
+        assert_eq!(value["chaos"]["endpoints"][0]["latencies"][1], "2s");
+    }
+
+    #[test]
+    fn test_duration_formats() {
+        let interval =
+            |yaml: &str| serde_yaml::from_str::<Config>(&format!("{yaml}\nrequests: []")).map(|c| c.interval);
+
+        // Integers are seconds, and strings are either `humantime` or ISO-8601 durations
+        for (yaml, expected) in [
+            ("interval: 90", Duration::from_secs(90)),
+            ("interval: 1m 30s", Duration::from_secs(90)),
+            ("interval: PT1M30S", Duration::from_secs(90)),
+            ("interval: P1DT12H", Duration::from_secs(129_600)),
+            ("interval: P2W", Duration::from_secs(1_209_600)),
+            ("interval: PT0.25S", Duration::from_millis(250)),
+            ("interval: PT1,5M", Duration::from_secs(90)),
+        ] {
+            assert_eq!(interval(yaml).unwrap(), Some(expected.into()), "{yaml}");
+        }
+        for yaml in
+            ["interval: P", "interval: PT", "interval: P1M", "interval: PT1S1M", "interval: PT1.5M1S", "interval: -1"]
+        {
+            assert!(interval(yaml).is_err(), "{yaml}");
+        }
+
+        // Durations are written back in the representation they were read in
+        for yaml in ["interval: 90", "interval: 1m 30s", "interval: PT1M30S", "interval: P1DT12H", "interval: PT0.25S"]
+        {
+            let config = serde_yaml::from_str::<Config>(&format!("{yaml}\nrequests: []")).unwrap();
+            let value = serde_yaml::to_value(&config).unwrap();
+            assert_eq!(value["interval"], serde_yaml::from_str::<serde_yaml::Value>(&yaml[10..]).unwrap(), "{yaml}");
+        }
+
+        // Lists of durations accept the same formats, and keep the representation of every item
+        let yaml = "endpoints: [{ url: http://a.example, latencies: [1, PT2S, 3s] }]";
+        let config = serde_yaml::from_str::<chaos::Config>(yaml).unwrap();
+        let value = serde_yaml::to_value(&config).unwrap();
+        assert_eq!(
+            value["endpoints"][0]["latencies"],
+            serde_yaml::from_str::<serde_yaml::Value>("[1, PT2S, 3s]").unwrap()
+        );
+        assert_eq!(
+            serde_yaml::from_value::<chaos::Config>(value).unwrap().endpoints[0].latencies,
+            config.endpoints[0].latencies
+        );
+
+        // Durations set in code are written as `humantime` strings
+        let value = serde_yaml::to_value(ConfigDuration::from(Duration::from_secs(90))).unwrap();
+        assert_eq!(value, serde_yaml::Value::from("1m 30s"));
+    }
+}
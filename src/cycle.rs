@@ -0,0 +1,73 @@
+use crate::export::Record;
+use crate::ProbeOutcome;
+use std::time::{Duration, SystemTime};
+
+/// The report of an update cycle, returned by `Service::update_report` to be logged or published.
+#[derive(serde::Deserialize, serde::Serialize, Clone, Debug)]
+pub struct CycleReport {
+    /// When the cycle started.
+    pub started_at: SystemTime,
+    /// How long the cycle took.
+    pub duration: Duration,
+    /// The scored outcome of every endpoint probed during the cycle, excluding the vetoed ones.
+    pub outcomes: Vec<Record>,
+    /// The errors encountered while writing the scores or the history of the endpoints to the store.
+    pub store_errors: Vec<String>,
+    /// The endpoints that weren't probed during the cycle, along with the reason.
+    pub skipped: Vec<Skipped>,
+}
+
+impl CycleReport {
+    /// Creates the report of a cycle started at the given time, before any endpoint is probed.
+    pub(crate) fn new(started_at: SystemTime) -> Self {
+        Self { started_at, duration: Duration::ZERO, outcomes: vec![], store_errors: vec![], skipped: vec![] }
+    }
+}
+
+/// An endpoint that wasn't probed during an update cycle.
+#[derive(serde::Deserialize, serde::Serialize, Clone, Debug, PartialEq, Eq)]
+pub struct Skipped {
+    /// The URL of the endpoint.
+    pub url: String,
+    /// Why the endpoint wasn't probed.
+    pub reason: SkipReason,
+}
+
+/// The reason an endpoint wasn't probed during an update cycle.
+#[derive(serde::Deserialize, serde::Serialize, Clone, Copy, Debug, PartialEq, Eq)]
+#[serde(rename_all = "snake_case")]
+pub enum SkipReason {
+    /// Another replica holds the election, and probes every endpoint.
+    NotElected,
+    /// The endpoint is paused, e.g. during a planned maintenance.
+    Paused,
+    /// The endpoint is assigned to another replica.
+    Unassigned,
+    /// The probes of the endpoint are backed off, since it's been down for several cycles.
+    BackedOff,
+}
+
+/// The report of a dry run, returned by `Service::dry_run` to validate a configuration before it's deployed.
+#[derive(serde::Deserialize, serde::Serialize, Clone, Debug)]
+pub struct DryRunReport {
+    /// The outcome of the single probe of every endpoint.
+    pub outcomes: Vec<ProbeOutcome>,
+    /// The endpoints whose probe failed, e.g. on a DNS, TLS or authentication error, along with the reason.
+    pub misconfigured: Vec<Misconfigured>,
+}
+
+impl DryRunReport {
+    /// Returns `true` if every endpoint was probed successfully.
+    pub fn is_valid(&self) -> bool {
+        self.misconfigured.is_empty()
+    }
+}
+
+/// An endpoint whose probe failed during a dry run.
+#[derive(serde::Deserialize, serde::Serialize, Clone, Debug, PartialEq, Eq)]
+pub struct Misconfigured {
+    /// The URL of the endpoint.
+    pub url: String,
+    /// Why its probe failed, e.g. `responded with status 401`.
+    pub reason: String,
+}
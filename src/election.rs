@@ -0,0 +1,156 @@
+use crate::config::ConfigDuration;
+use std::error::Error;
+use std::sync::atomic::{AtomicBool, Ordering::SeqCst};
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, Instant};
+
+/// Leader election configuration
+///
+/// - `id`: identifies the replica among the others (default: a random ID)
+/// - `ttl`: how long the leadership lasts without being renewed, which should exceed the interval (default: 30s)
+///
+/// The lease is held in the configured store, so replicas sharing a Redis store elect a single leader among them.
+#[derive(serde::Deserialize, serde::Serialize, Clone, Debug, Default, PartialEq, Eq)]
+pub struct Config {
+    #[serde(default)]
+    pub id: Option<String>,
+    #[serde(default)]
+    pub ttl: Option<ConfigDuration>,
+}
+
+impl Config {
+    /// Sets the ID of the replica.
+    pub fn set_id<I: Into<String>>(mut self, id: I) -> Self {
+        self.id = Some(id.into());
+        self
+    }
+
+    /// Sets how long the leadership lasts without being renewed.
+    pub fn set_ttl(mut self, ttl: Duration) -> Self {
+        self.ttl = Some(ttl.into());
+        self
+    }
+}
+
+/// A lease granting the leadership to a single replica at a time.
+#[async_trait::async_trait]
+pub trait Lease {
+    /// Acquires the lease for a replica, or renews it if the replica already holds it.
+    ///
+    /// # Arguments
+    /// * `holder`: The ID of the replica.
+    /// * `ttl`: How long the lease lasts without being renewed.
+    ///
+    /// # Returns
+    /// `true` if the replica holds the lease, or `false` if another replica does.
+    ///
+    /// # Errors
+    /// Returns an error if the lease can't be reached, or its expiration can't be represented.
+    async fn acquire(&self, holder: &str, ttl: Duration) -> Result<bool, Box<dyn Error + Send + Sync>>;
+}
+
+#[async_trait::async_trait]
+impl<T: Lease + Sync + Send + ?Sized> Lease for Arc<T> {
+    async fn acquire(&self, holder: &str, ttl: Duration) -> Result<bool, Box<dyn Error + Send + Sync>> {
+        (**self).acquire(holder, ttl).await
+    }
+}
+
+/// An in-process lease, shared by the replicas running within the same process.
+#[derive(Debug, Default)]
+pub struct Memory {
+    /// The current holder of the lease and its expiration.
+    inner: Mutex<Option<(String, Instant)>>,
+}
+
+impl Memory {
+    /// Creates a new in-process lease.
+    pub fn new() -> Self {
+        Self::default()
+    }
+}
+
+#[async_trait::async_trait]
+impl Lease for Memory {
+    async fn acquire(&self, holder: &str, ttl: Duration) -> Result<bool, Box<dyn Error + Send + Sync>> {
+        let mut inner = self.inner.lock().map_err(|e| e.to_string())?;
+        match &*inner {
+            Some((current, expires_at)) if current != holder && *expires_at > Instant::now() => Ok(false),
+            _ => {
+                let expires_at = Instant::now().checked_add(ttl).ok_or("the TTL of the lease overflows the clock")?;
+                *inner = Some((holder.to_string(), expires_at));
+                Ok(true)
+            }
+        }
+    }
+}
+
+/// Elects the replica of a `Service` probing the endpoints.
+pub(crate) struct Election {
+    lease: Box<dyn Lease + Sync + Send + 'static>,
+    id: String,
+    ttl: Duration,
+    leader: AtomicBool,
+}
+
+impl Election {
+    pub(crate) fn new(lease: Box<dyn Lease + Sync + Send + 'static>, config: Config) -> Self {
+        Self {
+            lease,
+            id: config.id.unwrap_or_else(crate::request::generate_id),
+            ttl: config.ttl.map_or(Duration::from_secs(30), ConfigDuration::get),
+            leader: AtomicBool::new(false),
+        }
+    }
+
+    /// Acquires or renews the leadership of the replica.
+    ///
+    /// # Returns
+    /// `true` if the replica is the leader; replicas that fail to reach the lease aren't.
+    pub(crate) async fn campaign(&self) -> Result<bool, Box<dyn Error + Send + Sync>> {
+        let leader = self.lease.acquire(&self.id, self.ttl).await;
+        self.leader.store(*leader.as_ref().unwrap_or(&false), SeqCst);
+        leader
+    }
+
+    /// Returns `true` if the replica was the leader as of its latest campaign.
+    pub(crate) fn is_leader(&self) -> bool {
+        self.leader.load(SeqCst)
+    }
+}
+
+/// Creates the lease held in the store of the given configuration.
+///
+/// # Arguments
+/// * `config` - Storage configuration.
+///
+/// # Returns
+/// A boxed lease, shared by the replicas using the same store.
+pub fn from_config(config: &crate::store::Config) -> Box<dyn Lease + Sync + Send + 'static> {
+    match config {
+        #[cfg(feature = "redis")]
+        crate::store::Config::Redis(config) => Box::new(crate::store::Redis::from_config(config)),
+        crate::store::Config::Memory => Box::new(Memory::new()),
+        // The lease is held in the primary store only, as the replicas coordinate through it
+        crate::store::Config::Fallback(fallback) => from_config(&fallback.primary),
+        crate::store::Config::Buffered(buffered) => from_config(&buffered.store),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[tokio::test]
+    async fn test_memory_lease() {
+        let lease = Memory::new();
+        assert!(lease.acquire("a", Duration::from_millis(50)).await.unwrap());
+        // The lease is renewed by its holder, but can't be taken over until it expires
+        assert!(lease.acquire("a", Duration::from_millis(50)).await.unwrap());
+        assert!(!lease.acquire("b", Duration::from_millis(50)).await.unwrap());
+
+        tokio::time::sleep(Duration::from_millis(60)).await;
+        assert!(lease.acquire("b", Duration::from_millis(50)).await.unwrap());
+        assert!(!lease.acquire("a", Duration::from_millis(50)).await.unwrap());
+    }
+}
@@ -0,0 +1,66 @@
+use std::io::Read;
+
+/// A content encoding that can be requested through the `Accept-Encoding` header, and decoded once received.
+#[derive(serde::Deserialize, serde::Serialize, Clone, Copy, Debug, PartialEq, Eq, Hash)]
+#[serde(rename_all = "snake_case")]
+pub enum Encoding {
+    Gzip,
+    Deflate,
+    Br,
+}
+
+impl Encoding {
+    /// Returns the token of the encoding, as used in the `Accept-Encoding` and `Content-Encoding` headers.
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            Encoding::Gzip => "gzip",
+            Encoding::Deflate => "deflate",
+            Encoding::Br => "br",
+        }
+    }
+
+    /// Decodes a body according to the `Content-Encoding` header of its response.
+    ///
+    /// # Arguments
+    /// * `content_encoding`: The value of the `Content-Encoding` header, if any.
+    ///   Multiple encodings are decoded in the reverse order they were applied.
+    /// * `body`: The body, as received.
+    ///
+    /// # Returns
+    /// The decoded body.
+    ///
+    /// # Errors
+    /// Returns an error if an encoding is unsupported, or the body is corrupted.
+    pub(crate) fn decode(content_encoding: Option<&str>, body: Vec<u8>) -> std::io::Result<Vec<u8>> {
+        let encodings = content_encoding.unwrap_or_default().split(',').map(str::trim).filter(|e| !e.is_empty());
+        encodings.rev().try_fold(body, |body, encoding| {
+            let mut decoded = Vec::new();
+            match encoding.to_ascii_lowercase().as_str() {
+                "identity" => return Ok(body),
+                "gzip" | "x-gzip" => flate2::read::GzDecoder::new(body.as_slice()).read_to_end(&mut decoded)?,
+                "deflate" => flate2::read::ZlibDecoder::new(body.as_slice()).read_to_end(&mut decoded)?,
+                "br" => brotli_decompressor::Decompressor::new(body.as_slice(), 4096).read_to_end(&mut decoded)?,
+                encoding => return Err(std::io::Error::other(format!("unsupported content encoding `{encoding}`"))),
+            };
+            Ok(decoded)
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::io::Write;
+
+    #[test]
+    fn test_decode() {
+        let mut encoder = flate2::write::GzEncoder::new(Vec::new(), flate2::Compression::default());
+        encoder.write_all(b"isup").unwrap();
+        let gzip = encoder.finish().unwrap();
+
+        assert_eq!(Encoding::decode(Some("gzip"), gzip.clone()).unwrap(), b"isup");
+        assert_eq!(Encoding::decode(Some("identity, gzip"), gzip).unwrap(), b"isup");
+        assert_eq!(Encoding::decode(None, b"isup".to_vec()).unwrap(), b"isup");
+        assert!(Encoding::decode(Some("zstd"), b"isup".to_vec()).is_err());
+    }
+}
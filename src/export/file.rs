@@ -0,0 +1,43 @@
+use super::{Exporter, Record};
+use std::error::Error;
+use std::io::Write;
+use std::path::PathBuf;
+
+/// File configuration
+///
+/// - `path`: the file the records are appended to, created if it doesn't exist
+#[derive(serde::Deserialize, serde::Serialize, Clone, Debug)]
+pub struct Config {
+    pub path: PathBuf,
+}
+
+/// Appends every record to a file, one JSON document per line, e.g. to be shipped by a log collector.
+pub struct File {
+    config: Config,
+}
+
+impl File {
+    /// Creates a new `File` exporter, appending to the given path.
+    pub fn new<P: Into<PathBuf>>(path: P) -> Self {
+        Self::from_config(Config { path: path.into() })
+    }
+
+    /// Creates a new `File` exporter from its configuration.
+    pub fn from_config(config: Config) -> Self {
+        Self { config }
+    }
+}
+
+#[async_trait::async_trait]
+impl Exporter for File {
+    async fn export(&self, records: &[Record]) -> Result<(), Box<dyn Error + Send + Sync>> {
+        let mut lines = Vec::new();
+        for record in records {
+            serde_json::to_writer(&mut lines, record)?;
+            lines.push(b'\n');
+        }
+        // The lines of a cycle are appended at once, so concurrent writers don't interleave them
+        let mut file = std::fs::OpenOptions::new().create(true).append(true).open(&self.config.path)?;
+        Ok(file.write_all(&lines)?)
+    }
+}
@@ -0,0 +1,94 @@
+use super::{Exporter, Record};
+use crate::config::{deserialize_headers, deserialize_uri, serialize_headers, serialize_uri};
+use crate::Client;
+use bytes::Bytes;
+use http_body_util::Full;
+use hyper::header::CONTENT_TYPE;
+use hyper::{HeaderMap, Uri};
+use std::error::Error;
+use std::fmt::Write;
+use std::time::UNIX_EPOCH;
+
+/// The measurement the records are written to, unless configured otherwise.
+const DEFAULT_MEASUREMENT: &str = "isup";
+
+/// InfluxDB configuration
+///
+/// - `url`: the write endpoint, along with its parameters, e.g. `http://influx:8086/api/v2/write?bucket=isup&org=ops`
+/// - `headers`: additional headers sent along, e.g. an `authorization` header carrying the token
+/// - `measurement`: the measurement the records are written to (default: `isup`)
+#[derive(serde::Deserialize, serde::Serialize, Clone, Debug)]
+pub struct Config {
+    #[serde(deserialize_with = "deserialize_uri", serialize_with = "serialize_uri")]
+    pub url: Uri,
+    #[serde(deserialize_with = "deserialize_headers", serialize_with = "serialize_headers", default)]
+    pub headers: HeaderMap,
+    #[serde(default)]
+    pub measurement: Option<String>,
+}
+
+/// Writes every record to InfluxDB, in its line protocol, tagged with the URL of its endpoint.
+/// The latency is written in milliseconds, along with the status, score and reliability.
+pub struct Influx {
+    config: Config,
+    client: Client,
+}
+
+impl Influx {
+    /// Creates a new `Influx` exporter, writing to the given endpoint.
+    ///
+    /// # Panics
+    /// Panics if the URL cannot be parsed.
+    pub fn new<I: Into<String>>(url: I) -> Self {
+        let url = url.into().parse().expect("Invalid URL");
+        Self::from_config(Config { url, headers: HeaderMap::new(), measurement: None })
+    }
+
+    /// Creates a new `Influx` exporter from its configuration.
+    pub fn from_config(config: Config) -> Self {
+        Self { config, client: Client::default() }
+    }
+
+    /// Renders the records in the line protocol, timestamped in nanoseconds.
+    fn lines(&self, records: &[Record]) -> String {
+        let measurement = escape(self.config.measurement.as_deref().unwrap_or(DEFAULT_MEASUREMENT));
+        let mut lines = String::new();
+        for record in records {
+            let at = record.at.duration_since(UNIX_EPOCH).unwrap_or_default().as_nanos();
+            let _ = writeln!(
+                lines,
+                "{measurement},url={} latency_ms={},status={}i,score={},reliability={} {at}",
+                escape(&record.outcome.url),
+                record.outcome.elapsed.as_secs_f64() * 1000.0,
+                record.outcome.status,
+                record.score.score,
+                record.score.reliability,
+            );
+        }
+        lines
+    }
+}
+
+/// Escapes the commas, spaces and equal signs of a measurement or tag, as specified by the line protocol.
+fn escape(value: &str) -> String {
+    value.replace(',', "\\,").replace(' ', "\\ ").replace('=', "\\=")
+}
+
+#[async_trait::async_trait]
+impl Exporter for Influx {
+    async fn export(&self, records: &[Record]) -> Result<(), Box<dyn Error + Send + Sync>> {
+        if records.is_empty() {
+            return Ok(());
+        }
+        let mut request = hyper::Request::post(self.config.url.clone())
+            .header(CONTENT_TYPE, "text/plain; charset=utf-8")
+            .body(Full::new(Bytes::from(self.lines(records))))?;
+        request.headers_mut().extend(self.config.headers.clone());
+
+        let response = self.client.request(request).await?;
+        match response.status().is_success() {
+            true => Ok(()),
+            false => Err(format!("influx responded with `{}`", response.status()).into()),
+        }
+    }
+}
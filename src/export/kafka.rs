@@ -0,0 +1,80 @@
+use super::{Exporter, Record, Transition};
+use futures::future::try_join_all;
+use rdkafka::producer::{FutureProducer, FutureRecord};
+use rdkafka::ClientConfig;
+use std::error::Error;
+use std::time::Duration;
+
+/// How long a message may wait for room in the queue of the producer before its publication fails.
+const QUEUE_TIMEOUT: Duration = Duration::from_secs(5);
+
+/// Kafka configuration
+///
+/// - `brokers`: the bootstrap servers, e.g. `kafka-1:9092,kafka-2:9092`
+/// - `topic`: the topic every scored probe is published to
+/// - `transitions_topic`: the topic the state transitions are published to, not published if not set
+#[derive(serde::Deserialize, serde::Serialize, Clone, Debug)]
+pub struct Config {
+    pub brokers: String,
+    pub topic: String,
+    #[serde(default)]
+    pub transitions_topic: Option<String>,
+}
+
+/// Publishes every scored probe and state transition to Kafka as a JSON message, keyed by the URL of its
+/// endpoint so the messages of an endpoint stay ordered within their partition.
+pub struct Kafka {
+    config: Config,
+    producer: FutureProducer,
+}
+
+impl Kafka {
+    /// Creates a new `Kafka` exporter, publishing the scored probes to the given topic.
+    ///
+    /// # Panics
+    /// Panics if the producer cannot be created.
+    pub fn new<I: Into<String>>(brokers: I, topic: I) -> Self {
+        Self::from_config(Config { brokers: brokers.into(), topic: topic.into(), transitions_topic: None })
+    }
+
+    /// Creates a new `Kafka` exporter from its configuration.
+    ///
+    /// # Panics
+    /// Panics if the producer cannot be created.
+    pub fn from_config(config: Config) -> Self {
+        let producer =
+            ClientConfig::new().set("bootstrap.servers", &config.brokers).create().expect("failed to create producer");
+        Self { config, producer }
+    }
+
+    /// Publishes the transitions of the states of the endpoints to the given topic as well.
+    pub fn set_transitions_topic<I: Into<String>>(mut self, topic: I) -> Self {
+        self.config.transitions_topic = Some(topic.into());
+        self
+    }
+
+    /// Publishes a message to a topic, waiting for its acknowledgement.
+    async fn publish(&self, topic: &str, key: &str, payload: &[u8]) -> Result<(), Box<dyn Error + Send + Sync>> {
+        let record = FutureRecord::to(topic).key(key).payload(payload);
+        self.producer.send(record, QUEUE_TIMEOUT).await.map_err(|(e, _)| e)?;
+        Ok(())
+    }
+}
+
+#[async_trait::async_trait]
+impl Exporter for Kafka {
+    async fn export(&self, records: &[Record]) -> Result<(), Box<dyn Error + Send + Sync>> {
+        let payloads = records.iter().map(serde_json::to_vec).collect::<Result<Vec<_>, _>>()?;
+        let publications =
+            records.iter().zip(&payloads).map(|(r, p)| self.publish(&self.config.topic, &r.outcome.url, p));
+        try_join_all(publications).await?;
+        Ok(())
+    }
+
+    async fn transition(&self, transition: &Transition) -> Result<(), Box<dyn Error + Send + Sync>> {
+        match &self.config.transitions_topic {
+            Some(topic) => self.publish(topic, &transition.url, &serde_json::to_vec(transition)?).await,
+            None => Ok(()),
+        }
+    }
+}
@@ -0,0 +1,135 @@
+use crate::incident::State;
+use crate::{ProbeOutcome, Score};
+use std::error::Error;
+use std::sync::Arc;
+use std::time::SystemTime;
+
+mod file;
+mod influx;
+pub use file::File;
+pub use influx::Influx;
+
+// Feature-gated Kafka module. Included only if the "kafka" feature is enabled.
+#[cfg(feature = "kafka")]
+mod kafka;
+#[cfg(feature = "kafka")]
+pub use kafka::Kafka;
+
+// Feature-gated NATS module. Included only if the "nats" feature is enabled.
+#[cfg(feature = "nats")]
+mod nats;
+#[cfg(feature = "nats")]
+pub use nats::Nats;
+
+/// Configuration options for the different exporters.
+///
+/// Like the notifiers, exporters are selected by their `type`, e.g.
+/// `{ type: file, path: /var/log/isup/samples.jsonl }`.
+#[derive(serde::Deserialize, serde::Serialize, Clone, Debug)]
+#[serde(tag = "type")]
+#[serde(rename_all = "snake_case")]
+pub enum Config {
+    /// Appends every record to a file, as JSON lines.
+    File(file::Config),
+    /// Writes every record to InfluxDB, in its line protocol.
+    Influx(influx::Config),
+    /// Publishes every record and state transition to Kafka topics.
+    // The Kafka configuration is only included if the "kafka" feature is enabled.
+    #[cfg(feature = "kafka")]
+    Kafka(kafka::Config),
+    /// Publishes every record and state transition to NATS subjects.
+    // The NATS configuration is only included if the "nats" feature is enabled.
+    #[cfg(feature = "nats")]
+    Nats(nats::Config),
+}
+
+/// Constructs an exporter from the provided configuration.
+///
+/// # Arguments
+/// * `config` - Exporter configuration.
+///
+/// # Returns
+/// A boxed exporter implementing the `Exporter` trait.
+pub fn from_config(config: Config) -> Box<dyn Exporter + Sync + Send + 'static> {
+    match config {
+        Config::File(config) => Box::new(File::from_config(config)),
+        Config::Influx(config) => Box::new(Influx::from_config(config)),
+        #[cfg(feature = "kafka")]
+        Config::Kafka(config) => Box::new(Kafka::from_config(config)),
+        #[cfg(feature = "nats")]
+        Config::Nats(config) => Box::new(Nats::from_config(config)),
+    }
+}
+
+/// A scored probe of an endpoint, as exported at the end of its update cycle.
+#[derive(serde::Deserialize, serde::Serialize, Clone, Debug)]
+pub struct Record {
+    /// When the probe was scored.
+    pub at: SystemTime,
+    /// The outcome of the probe.
+    pub outcome: ProbeOutcome,
+    /// The score of the endpoint once the probe was scored.
+    pub score: Score,
+}
+
+impl Record {
+    /// Creates a record from a scored probe, dated now.
+    pub(crate) fn new(outcome: ProbeOutcome, score: Score) -> Self {
+        Self { at: SystemTime::now(), outcome, score }
+    }
+}
+
+/// A change of the state of an endpoint, as exported as soon as it happens.
+#[derive(serde::Deserialize, serde::Serialize, Clone, Debug, PartialEq)]
+pub struct Transition {
+    /// When the state changed.
+    pub at: SystemTime,
+    /// The URL of the endpoint.
+    pub url: String,
+    /// The state of the endpoint before the change, `None` if it wasn't probed yet.
+    pub previous: Option<State>,
+    /// The state of the endpoint after the change.
+    pub state: State,
+}
+
+impl Transition {
+    /// Creates a transition of the state of an endpoint, dated now.
+    pub(crate) fn new(url: &str, previous: Option<State>, state: State) -> Self {
+        Self { at: SystemTime::now(), url: url.to_string(), previous, state }
+    }
+}
+
+/// Exports the scored probes of every update cycle to an external sink, e.g. a file or a time series database.
+#[async_trait::async_trait]
+pub trait Exporter {
+    /// Exports the records of an update cycle.
+    ///
+    /// # Arguments
+    /// * `records`: The scored probes of the cycle, in the order they were scored.
+    ///
+    /// # Returns
+    /// A result indicating whether the records were exported.
+    async fn export(&self, records: &[Record]) -> Result<(), Box<dyn Error + Send + Sync>>;
+
+    /// Exports a change of the state of an endpoint, as soon as it happens.
+    ///
+    /// # Arguments
+    /// * `transition`: The change of the state of the endpoint.
+    ///
+    /// # Returns
+    /// A result indicating whether the transition was exported. Sinks of records only ignore it by default.
+    async fn transition(&self, _transition: &Transition) -> Result<(), Box<dyn Error + Send + Sync>> {
+        Ok(())
+    }
+}
+
+#[async_trait::async_trait]
+impl<T: Exporter + Sync + Send + ?Sized> Exporter for Arc<T> {
+    async fn export(&self, records: &[Record]) -> Result<(), Box<dyn Error + Send + Sync>> {
+        (**self).export(records).await
+    }
+
+    async fn transition(&self, transition: &Transition) -> Result<(), Box<dyn Error + Send + Sync>> {
+        (**self).transition(transition).await
+    }
+}
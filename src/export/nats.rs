@@ -0,0 +1,66 @@
+use super::{Exporter, Record, Transition};
+use bytes::Bytes;
+use std::error::Error;
+use tokio::sync::OnceCell;
+
+/// NATS configuration
+///
+/// - `url`: the URL of the server, e.g. `nats://nats:4222`
+/// - `subject`: the subject every scored probe is published to
+/// - `transitions_subject`: the subject the state transitions are published to, not published if not set
+#[derive(serde::Deserialize, serde::Serialize, Clone, Debug)]
+pub struct Config {
+    pub url: String,
+    pub subject: String,
+    #[serde(default)]
+    pub transitions_subject: Option<String>,
+}
+
+/// Publishes every scored probe and state transition to NATS as a JSON message.
+/// The connection is opened along with the first publication, and reopened by the client whenever it's lost.
+pub struct Nats {
+    config: Config,
+    client: OnceCell<async_nats::Client>,
+}
+
+impl Nats {
+    /// Creates a new `Nats` exporter, publishing the scored probes to the given subject.
+    pub fn new<I: Into<String>>(url: I, subject: I) -> Self {
+        Self::from_config(Config { url: url.into(), subject: subject.into(), transitions_subject: None })
+    }
+
+    /// Creates a new `Nats` exporter from its configuration.
+    pub fn from_config(config: Config) -> Self {
+        Self { config, client: OnceCell::new() }
+    }
+
+    /// Publishes the transitions of the states of the endpoints to the given subject as well.
+    pub fn set_transitions_subject<I: Into<String>>(mut self, subject: I) -> Self {
+        self.config.transitions_subject = Some(subject.into());
+        self
+    }
+
+    /// Publishes messages to a subject, flushing them to the server.
+    async fn publish(&self, subject: &str, payloads: Vec<Vec<u8>>) -> Result<(), Box<dyn Error + Send + Sync>> {
+        let client = self.client.get_or_try_init(|| async_nats::connect(&self.config.url)).await?;
+        for payload in payloads {
+            client.publish(subject.to_string(), Bytes::from(payload)).await?;
+        }
+        Ok(client.flush().await?)
+    }
+}
+
+#[async_trait::async_trait]
+impl Exporter for Nats {
+    async fn export(&self, records: &[Record]) -> Result<(), Box<dyn Error + Send + Sync>> {
+        let payloads = records.iter().map(serde_json::to_vec).collect::<Result<Vec<_>, _>>()?;
+        self.publish(&self.config.subject, payloads).await
+    }
+
+    async fn transition(&self, transition: &Transition) -> Result<(), Box<dyn Error + Send + Sync>> {
+        match &self.config.transitions_subject {
+            Some(subject) => self.publish(subject, vec![serde_json::to_vec(transition)?]).await,
+            None => Ok(()),
+        }
+    }
+}
@@ -0,0 +1,92 @@
+use crate::config::ConfigDuration;
+use crate::ranking::{Direction, Trend};
+use std::time::{Duration, SystemTime};
+
+/// Forecast configuration
+///
+/// - `threshold`: the score under which an endpoint is considered down by its consumers (default: 0.5)
+/// - `horizon`: how far ahead the score is extrapolated (default: 15m)
+///
+/// The score of every endpoint is extrapolated along its trend, fitted over its latest scores, so an endpoint whose
+/// latency or errors keep rising is flagged as at risk before its score actually crosses the threshold. The risks are
+/// raised as `alertname: EndpointAtRisk` alerts when Alertmanager is configured, and resolved once the forecast
+/// recovers or the score crosses the threshold.
+#[derive(serde::Deserialize, serde::Serialize, Clone, Debug, PartialEq)]
+pub struct Config {
+    #[serde(default = "default_threshold")]
+    pub threshold: f32,
+    #[serde(default = "default_horizon")]
+    pub horizon: ConfigDuration,
+}
+
+impl Default for Config {
+    fn default() -> Self {
+        Self { threshold: default_threshold(), horizon: default_horizon() }
+    }
+}
+
+fn default_threshold() -> f32 {
+    0.5
+}
+
+fn default_horizon() -> ConfigDuration {
+    Duration::from_secs(15 * 60).into()
+}
+
+impl Config {
+    /// Sets the score under which an endpoint is considered down.
+    pub fn set_threshold(mut self, threshold: f32) -> Self {
+        self.threshold = threshold;
+        self
+    }
+
+    /// Sets how far ahead the score is extrapolated.
+    pub fn set_horizon(mut self, horizon: Duration) -> Self {
+        self.horizon = horizon.into();
+        self
+    }
+
+    /// Extrapolates a score along its trend, up to the horizon.
+    ///
+    /// # Arguments
+    /// * `score`: The current score of the endpoint.
+    /// * `trend`: The trend of the score.
+    ///
+    /// # Returns
+    /// The score forecast at the horizon, clamped between 0.0 and 1.0.
+    pub fn extrapolate(&self, score: f32, trend: &Trend) -> f32 {
+        let change = trend.slope * self.horizon.as_secs_f64() / 3600.0;
+        (score as f64 + change).clamp(0.0, 1.0) as f32
+    }
+
+    /// Checks whether an endpoint is at risk: its score is still above the threshold, but degrading fast enough to
+    /// fall under it within the horizon.
+    ///
+    /// # Returns
+    /// The score forecast at the horizon if the endpoint is at risk, `None` otherwise.
+    pub(crate) fn at_risk(&self, score: f32, trend: &Trend) -> Option<f32> {
+        let forecast = self.extrapolate(score, trend);
+        (trend.direction == Direction::Degrading && score >= self.threshold && forecast < self.threshold)
+            .then_some(forecast)
+    }
+}
+
+/// An endpoint forecast to fall under the score threshold, since it was first flagged as at risk.
+#[derive(serde::Deserialize, serde::Serialize, Clone, Debug, PartialEq)]
+pub struct Risk {
+    /// The URL of the endpoint.
+    pub url: String,
+    /// The latest score of the endpoint.
+    pub score: f32,
+    /// The score forecast at the horizon.
+    pub forecast: f32,
+    /// The score threshold the endpoint is forecast to fall under.
+    pub threshold: f32,
+    /// How far ahead the score was forecast.
+    pub horizon: Duration,
+    /// When the endpoint was first flagged as at risk.
+    pub started_at: SystemTime,
+    /// When the endpoint stopped being at risk; `None` while it still is.
+    #[serde(default)]
+    pub resolved_at: Option<SystemTime>,
+}
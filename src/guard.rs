@@ -0,0 +1,263 @@
+use hyper::Uri;
+use serde::{Deserialize, Deserializer, Serialize, Serializer};
+use std::net::{IpAddr, Ipv4Addr, Ipv6Addr};
+use std::str::FromStr;
+
+/// Guard configuration
+///
+/// Restricts the endpoints that can be monitored, protecting services that accept user-submitted URLs
+/// from being abused to probe internal networks (SSRF).
+///
+/// - `schemes`: the allowed URL schemes (default: `http` and `https`)
+/// - `allow_hosts` / `deny_hosts`: hostname patterns, either exact (`api.example.com`) or wildcards (`*.example.com`)
+/// - `allow_networks` / `deny_networks`: CIDR ranges (`10.0.0.0/8`, `2001:db8::/32`) or single addresses
+/// - `deny_private`: denies loopback, private, link-local and other non-public addresses (default: `true`)
+///
+/// When an allow list is not empty, only the matching endpoints are allowed. Deny rules always take precedence.
+#[derive(Deserialize, Serialize, Clone, Debug)]
+pub struct Config {
+    #[serde(default = "default_schemes")]
+    pub schemes: Vec<String>,
+    #[serde(default)]
+    pub allow_hosts: Vec<String>,
+    #[serde(default)]
+    pub deny_hosts: Vec<String>,
+    #[serde(default)]
+    pub allow_networks: Vec<Network>,
+    #[serde(default)]
+    pub deny_networks: Vec<Network>,
+    #[serde(default = "default_deny_private")]
+    pub deny_private: bool,
+}
+
+impl Default for Config {
+    /// Provides a default configuration, which only allows public `http` and `https` endpoints.
+    fn default() -> Self {
+        Self {
+            schemes: default_schemes(),
+            allow_hosts: vec![],
+            deny_hosts: vec![],
+            allow_networks: vec![],
+            deny_networks: vec![],
+            deny_private: default_deny_private(),
+        }
+    }
+}
+
+fn default_schemes() -> Vec<String> {
+    vec!["http".into(), "https".into()]
+}
+
+fn default_deny_private() -> bool {
+    true
+}
+
+/// An IP network in CIDR notation.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct Network {
+    addr: IpAddr,
+    prefix: u8,
+}
+
+impl Network {
+    /// Returns `true` if the address is part of the network.
+    pub fn contains(&self, ip: IpAddr) -> bool {
+        match (self.addr, ip) {
+            (IpAddr::V4(net), IpAddr::V4(ip)) => {
+                let mask = u32::MAX.checked_shl(32 - self.prefix as u32).unwrap_or(0);
+                u32::from(net) & mask == u32::from(ip) & mask
+            }
+            (IpAddr::V6(net), IpAddr::V6(ip)) => {
+                let mask = u128::MAX.checked_shl(128 - self.prefix as u32).unwrap_or(0);
+                u128::from(net) & mask == u128::from(ip) & mask
+            }
+            _ => false,
+        }
+    }
+}
+
+impl FromStr for Network {
+    type Err = String;
+
+    /// Parses a network from CIDR notation, or a single address.
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let (addr, prefix) = match s.split_once('/') {
+            Some((addr, prefix)) => (addr, Some(prefix)),
+            None => (s, None),
+        };
+        let addr = IpAddr::from_str(addr).map_err(|e| format!("invalid network `{s}`: {e}"))?;
+        let max = if addr.is_ipv4() { 32 } else { 128 };
+        let prefix = match prefix {
+            Some(prefix) => {
+                prefix.parse::<u8>().ok().filter(|p| *p <= max).ok_or(format!("invalid prefix in `{s}`"))?
+            }
+            None => max,
+        };
+        Ok(Self { addr, prefix })
+    }
+}
+
+impl<'de> Deserialize<'de> for Network {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        let s = String::deserialize(deserializer)?;
+        s.parse().map_err(serde::de::Error::custom)
+    }
+}
+
+impl std::fmt::Display for Network {
+    /// Formats the network in CIDR notation.
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{}/{}", self.addr, self.prefix)
+    }
+}
+
+impl Serialize for Network {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        serializer.collect_str(self)
+    }
+}
+
+/// The reason an endpoint was rejected by a `Guard`.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Violation(pub String);
+
+impl std::fmt::Display for Violation {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "blocked by guard: {}", self.0)
+    }
+}
+
+impl std::error::Error for Violation {}
+
+/// Validates endpoints against the allow and deny rules of its configuration.
+///
+/// The rules are checked when requests are inserted into a `Service`, and again by the `Client`
+/// for every resolved address, so that a hostname later resolving to a denied address is blocked as well.
+#[derive(Clone, Debug, Default)]
+pub struct Guard {
+    config: Config,
+}
+
+impl Guard {
+    /// Creates a new `Guard` from the provided configuration.
+    pub fn new(config: Config) -> Self {
+        Self { config }
+    }
+
+    /// Checks the scheme and host of a URL. Addresses of hostnames are checked once they're resolved.
+    ///
+    /// # Returns
+    /// A `Violation` describing the broken rule, if any.
+    pub fn check_url(&self, url: &Uri) -> Result<(), Violation> {
+        let scheme = url.scheme_str().unwrap_or_default();
+        if !self.config.schemes.iter().any(|s| s.eq_ignore_ascii_case(scheme)) {
+            return Err(Violation(format!("scheme `{scheme}` is not allowed")));
+        }
+
+        let host = url.host().ok_or(Violation(format!("`{url}` has no host")))?;
+        // IPv6 hosts are enclosed in brackets within URLs.
+        match IpAddr::from_str(host.trim_start_matches('[').trim_end_matches(']')) {
+            Ok(ip) => self.check_ip(ip),
+            Err(_) => self.check_host(host),
+        }
+    }
+
+    /// Checks a hostname against the host patterns.
+    pub fn check_host(&self, host: &str) -> Result<(), Violation> {
+        if self.config.deny_hosts.iter().any(|p| matches(p, host)) {
+            return Err(Violation(format!("host `{host}` is denied")));
+        }
+        if !self.config.allow_hosts.is_empty() && !self.config.allow_hosts.iter().any(|p| matches(p, host)) {
+            return Err(Violation(format!("host `{host}` is not allowed")));
+        }
+        Ok(())
+    }
+
+    /// Checks an address against the network rules.
+    pub fn check_ip(&self, ip: IpAddr) -> Result<(), Violation> {
+        // Treat IPv4-mapped IPv6 addresses as the IPv4 addresses they represent.
+        let ip = match ip {
+            IpAddr::V6(v6) => v6.to_ipv4_mapped().map(IpAddr::V4).unwrap_or(ip),
+            ip => ip,
+        };
+
+        if self.config.deny_private && !is_public(ip) {
+            return Err(Violation(format!("address `{ip}` is not public")));
+        }
+        if self.config.deny_networks.iter().any(|n| n.contains(ip)) {
+            return Err(Violation(format!("address `{ip}` is denied")));
+        }
+        if !self.config.allow_networks.is_empty() && !self.config.allow_networks.iter().any(|n| n.contains(ip)) {
+            return Err(Violation(format!("address `{ip}` is not allowed")));
+        }
+        Ok(())
+    }
+}
+
+/// Matches a hostname against an exact or wildcard (`*.example.com`) pattern, ignoring case.
+fn matches(pattern: &str, host: &str) -> bool {
+    let (pattern, host) = (pattern.to_ascii_lowercase(), host.to_ascii_lowercase());
+    match pattern.strip_prefix("*.") {
+        Some(domain) => host.ends_with(&format!(".{domain}")),
+        None => pattern == host,
+    }
+}
+
+/// Returns `true` if the address is publicly routable.
+fn is_public(ip: IpAddr) -> bool {
+    match ip {
+        IpAddr::V4(ip) => !is_private_v4(ip),
+        IpAddr::V6(ip) => !is_private_v6(ip),
+    }
+}
+
+fn is_private_v4(ip: Ipv4Addr) -> bool {
+    let [a, b, ..] = ip.octets();
+    ip.is_private()
+        || ip.is_loopback()
+        || ip.is_link_local()
+        || ip.is_unspecified()
+        || ip.is_broadcast()
+        || ip.is_multicast()
+        || ip.is_documentation()
+        // Shared address space (100.64.0.0/10), used for carrier-grade NAT.
+        || (a == 100 && (64..128).contains(&b))
+        // The "this network" block (0.0.0.0/8).
+        || a == 0
+}
+
+fn is_private_v6(ip: Ipv6Addr) -> bool {
+    let first = ip.segments()[0];
+    ip.is_loopback()
+        || ip.is_unspecified()
+        || ip.is_multicast()
+        // Unique local addresses (fc00::/7).
+        || (first & 0xfe00) == 0xfc00
+        // Link-local addresses (fe80::/10).
+        || (first & 0xffc0) == 0xfe80
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_network_contains() {
+        let network: Network = "10.0.0.0/8".parse().unwrap();
+        assert!(network.contains("10.1.2.3".parse().unwrap()));
+        assert!(!network.contains("11.0.0.1".parse().unwrap()));
+
+        let network: Network = "2001:db8::/32".parse().unwrap();
+        assert!(network.contains("2001:db8::1".parse().unwrap()));
+        assert!(!network.contains("2001:db9::1".parse().unwrap()));
+
+        assert!("10.0.0.0/33".parse::<Network>().is_err());
+    }
+
+    #[test]
+    fn test_host_patterns() {
+        assert!(matches("*.example.com", "api.EXAMPLE.com"));
+        assert!(!matches("*.example.com", "example.com"));
+        assert!(matches("example.com", "example.com"));
+    }
+}
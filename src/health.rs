@@ -0,0 +1,26 @@
+use std::time::{Duration, SystemTime};
+
+/// The health of the monitor itself, as opposed to the health of the monitored endpoints.
+///
+/// A `Service` is unhealthy when its store can't be reached, or its update loop hasn't completed a cycle
+/// within three intervals (plus a second), e.g. because it's stuck on a probe.
+#[derive(serde::Deserialize, serde::Serialize, Clone, Debug, PartialEq, Eq)]
+pub struct Health {
+    /// Whether the store is reachable and the update loop is making progress.
+    pub healthy: bool,
+    /// When the last update completed, or `None` if none did yet.
+    pub last_update: Option<SystemTime>,
+    /// The time elapsed since the last update completed, or since the update loop started if none did yet.
+    /// `None` if the service isn't running and was never updated.
+    pub since_last_update: Option<Duration>,
+    /// The time without a completed update after which the service is unhealthy, three times the interval of
+    /// `Service::run` plus a second. `None` if the service is updated manually, in which case its progress isn't checked.
+    pub stale_after: Option<Duration>,
+    /// The error encountered while reaching the store, if any.
+    pub store_error: Option<String>,
+    /// How long the store took to answer the health check, or to fail.
+    #[serde(default)]
+    pub store_latency: Duration,
+    /// The number of samples waiting to be delivered to the coordinator, in agent mode.
+    pub queue_depth: usize,
+}
@@ -0,0 +1,495 @@
+use crate::config::ConfigDuration;
+use crate::{ProbeOutcome, Score};
+use dashmap::DashMap;
+use std::collections::{BTreeMap, VecDeque};
+use std::error::Error;
+use std::sync::Arc;
+use std::time::{Duration, Instant, SystemTime, UNIX_EPOCH};
+
+/// The `remote_write` module ships the history to a Prometheus compatible TSDB.
+pub mod remote_write;
+pub use remote_write::RemoteWrite;
+
+/// The default number of samples kept per endpoint; the oldest are dropped first.
+pub const DEFAULT_CAPACITY: usize = 10_000;
+
+/// The number of hourly slots of the baselines, covering a week.
+const SLOTS: usize = 7 * 24;
+
+/// How long the baseline of an endpoint is cached before being computed again from its history.
+const BASELINE_REFRESH: Duration = Duration::from_secs(3600);
+
+/// History configuration
+///
+/// - `capacity`: the number of samples kept per endpoint, the oldest being dropped first (default: 10000)
+/// - `baseline_window`: the period the baselines of the endpoints are computed over, e.g. `4 weeks`.
+///   Each probe is then given the usual latency of its endpoint at the same hour of the week, which strategies
+///   can score against. Disabled if not set.
+/// - `retention`: rolls up the older samples and drops the expired ones, see `Retention`. Disabled if not set.
+/// - `remote_write`: ships the history to a Prometheus compatible TSDB instead, see `remote_write::Config`
+///
+/// The history is kept in the configured store, so it's shared by the replicas using the same Redis store, unless
+/// it's shipped through `remote_write`, in which case the `capacity` is left to the retention of the TSDB.
+#[derive(serde::Deserialize, serde::Serialize, Clone, Debug, Default, PartialEq, Eq)]
+pub struct Config {
+    #[serde(default)]
+    pub capacity: Option<usize>,
+    #[serde(default)]
+    pub baseline_window: Option<ConfigDuration>,
+    #[serde(default)]
+    pub retention: Option<Retention>,
+    #[serde(default)]
+    pub remote_write: Option<remote_write::Config>,
+}
+
+impl Config {
+    /// Sets the number of samples kept per endpoint.
+    pub fn set_capacity(mut self, capacity: usize) -> Self {
+        self.capacity = Some(capacity);
+        self
+    }
+
+    /// Sets the period the baselines of the endpoints are computed over.
+    pub fn set_baseline_window(mut self, window: Duration) -> Self {
+        self.baseline_window = Some(window.into());
+        self
+    }
+
+    /// Sets the retention policy of the history.
+    pub fn set_retention(mut self, retention: Retention) -> Self {
+        self.retention = Some(retention);
+        self
+    }
+}
+
+/// History retention policy
+///
+/// - `raw`: how long the samples are kept as recorded, before being rolled up (default: 24h)
+/// - `rollup_interval`: the interval the older samples are rolled up over, one rollup per interval (default: 5m)
+/// - `rollups`: how long the rollups are kept, before being dropped (default: 30d)
+///
+/// The history is compacted in the background, at most once per `rollup_interval` for every endpoint, so it doesn't
+/// grow unbounded while keeping enough of it for the baselines and dashboards.
+#[derive(serde::Deserialize, serde::Serialize, Clone, Debug, PartialEq, Eq)]
+pub struct Retention {
+    #[serde(default = "default_raw")]
+    pub raw: ConfigDuration,
+    #[serde(default = "default_rollup_interval")]
+    pub rollup_interval: ConfigDuration,
+    #[serde(default = "default_rollups")]
+    pub rollups: ConfigDuration,
+}
+
+impl Default for Retention {
+    fn default() -> Self {
+        Self { raw: default_raw(), rollup_interval: default_rollup_interval(), rollups: default_rollups() }
+    }
+}
+
+fn default_raw() -> ConfigDuration {
+    Duration::from_secs(24 * 3600).into()
+}
+
+fn default_rollup_interval() -> ConfigDuration {
+    Duration::from_secs(5 * 60).into()
+}
+
+fn default_rollups() -> ConfigDuration {
+    Duration::from_secs(30 * 24 * 3600).into()
+}
+
+impl Retention {
+    /// Sets how long the samples are kept as recorded.
+    pub fn set_raw(mut self, raw: Duration) -> Self {
+        self.raw = raw.into();
+        self
+    }
+
+    /// Sets the interval the older samples are rolled up over.
+    pub fn set_rollup_interval(mut self, interval: Duration) -> Self {
+        self.rollup_interval = interval.into();
+        self
+    }
+
+    /// Sets how long the rollups are kept.
+    pub fn set_rollups(mut self, rollups: Duration) -> Self {
+        self.rollups = rollups.into();
+        self
+    }
+
+    /// Rolls up samples into one per interval, dated at its start, with the mean latency of their successful probes
+    /// (of all of them if none succeeded), their most common status and their mean score and reliability.
+    ///
+    /// # Arguments
+    /// * `samples`: The samples to be rolled up, in any order.
+    ///
+    /// # Returns
+    /// The rollups, ordered by their date.
+    pub fn rollup(&self, samples: &[Sample]) -> Vec<Sample> {
+        let mut intervals = BTreeMap::<SystemTime, Vec<&Sample>>::new();
+        for sample in samples {
+            intervals.entry(self.interval_start(sample.at)).or_default().push(sample);
+        }
+        intervals
+            .into_iter()
+            .map(|(at, samples)| {
+                let succeeded = samples.iter().filter(|s| (100..400).contains(&s.status)).copied().collect::<Vec<_>>();
+                let latencies = if succeeded.is_empty() { &samples } else { &succeeded };
+                let mut statuses = BTreeMap::<u16, usize>::new();
+                samples.iter().for_each(|s| *statuses.entry(s.status).or_default() += 1);
+                let count = samples.len() as f32;
+                Sample {
+                    at,
+                    elapsed: latencies.iter().map(|s| s.elapsed).sum::<Duration>() / latencies.len() as u32,
+                    status: statuses.into_iter().max_by_key(|(_, count)| *count).map_or(0, |(status, _)| status),
+                    score: samples.iter().map(|s| s.score).sum::<f32>() / count,
+                    reliability: samples.iter().map(|s| s.reliability).sum::<f32>() / count,
+                }
+            })
+            .collect()
+    }
+
+    /// Returns the date before which the samples are rolled up, aligned on the start of an interval so that every
+    /// interval is rolled up whole, and once.
+    pub(crate) fn raw_cutoff(&self, now: SystemTime) -> SystemTime {
+        self.interval_start(now.checked_sub(*self.raw).unwrap_or(UNIX_EPOCH))
+    }
+
+    /// Returns the date before which the rollups are dropped.
+    pub(crate) fn rollup_cutoff(&self, now: SystemTime) -> SystemTime {
+        now.checked_sub(*self.rollups).unwrap_or(UNIX_EPOCH)
+    }
+
+    /// Returns the start of the interval a date falls in.
+    fn interval_start(&self, at: SystemTime) -> SystemTime {
+        let interval = self.rollup_interval.as_millis().max(1);
+        let millis = at.duration_since(UNIX_EPOCH).unwrap_or_default().as_millis();
+        UNIX_EPOCH + Duration::from_millis((millis - millis % interval) as u64)
+    }
+}
+
+/// Tracks when the history of every endpoint was last compacted, so it's compacted once per rollup interval.
+#[derive(Debug, Default)]
+pub(crate) struct Compactions {
+    compacted: DashMap<String, Instant>,
+}
+
+impl Compactions {
+    /// Returns `true` if the history of an endpoint is due for compaction, i.e. it wasn't compacted within the last
+    /// rollup interval, marking it as compacted if so. The first compaction is due one interval after the first
+    /// sample is recorded, so the samples copied in bulk, e.g. by a migration, are rolled up together.
+    pub(crate) fn due(&self, url: &str, retention: &Retention) -> bool {
+        let mut compacted = self.compacted.entry(url.to_string()).or_insert_with(Instant::now);
+        if compacted.elapsed() < *retention.rollup_interval {
+            return false;
+        }
+        *compacted = Instant::now();
+        true
+    }
+}
+
+/// A scored probe of an endpoint, as recorded in its history.
+#[derive(serde::Deserialize, serde::Serialize, Clone, Debug, PartialEq)]
+pub struct Sample {
+    /// When the probe was scored.
+    pub at: SystemTime,
+    /// The time it took for the response to be received.
+    pub elapsed: Duration,
+    /// The status code of the probe, `0` if it failed without a response.
+    pub status: u16,
+    /// The score of the endpoint once the probe was scored.
+    pub score: f32,
+    /// The reliability of the endpoint once the probe was scored.
+    pub reliability: f32,
+}
+
+impl Sample {
+    /// Creates a sample from a scored probe, dated now.
+    pub(crate) fn new(outcome: &ProbeOutcome, score: &Score) -> Self {
+        Self {
+            at: SystemTime::now(),
+            elapsed: outcome.elapsed,
+            status: if outcome.error.is_some() { 0 } else { outcome.status },
+            score: score.score,
+            reliability: score.reliability,
+        }
+    }
+}
+
+/// A time series of the samples of every endpoint, so their latency and score can be graphed and analyzed over time.
+#[async_trait::async_trait]
+pub trait History {
+    /// Records a sample of an endpoint.
+    ///
+    /// # Arguments
+    /// * `url`: The URL of the endpoint.
+    /// * `sample`: The sample to be recorded.
+    async fn record(&self, url: &str, sample: Sample) -> Result<(), Box<dyn Error + Send + Sync>>;
+
+    /// Retrieves the samples of an endpoint recorded within a time range.
+    ///
+    /// # Arguments
+    /// * `url`: The URL of the endpoint.
+    /// * `from`: The start of the range, inclusive.
+    /// * `to`: The end of the range, inclusive.
+    ///
+    /// # Returns
+    /// The samples, ordered by their date.
+    async fn query(
+        &self,
+        url: &str,
+        from: SystemTime,
+        to: SystemTime,
+    ) -> Result<Vec<Sample>, Box<dyn Error + Send + Sync>>;
+
+    /// Computes the baseline of an endpoint, from the samples recorded over a period until now.
+    ///
+    /// # Arguments
+    /// * `url`: The URL of the endpoint.
+    /// * `window`: The period the baseline is computed over, e.g. the last 4 weeks.
+    ///
+    /// # Returns
+    /// The usual latency of the endpoint at every hour of the week.
+    async fn baseline(&self, url: &str, window: Duration) -> Result<Baseline, Box<dyn Error + Send + Sync>> {
+        let now = SystemTime::now();
+        let samples = self.query(url, now.checked_sub(window).unwrap_or(UNIX_EPOCH), now).await?;
+        Ok(Baseline::new(&samples))
+    }
+
+    /// Compacts the history of an endpoint according to its retention policy, rolling up the samples older than
+    /// the raw retention and dropping the expired rollups. Histories without a retention policy are left as is.
+    ///
+    /// The histories with a retention policy compact themselves in the background as samples are recorded.
+    ///
+    /// # Arguments
+    /// * `url`: The URL of the endpoint.
+    async fn compact(&self, _url: &str) -> Result<(), Box<dyn Error + Send + Sync>> {
+        Ok(())
+    }
+}
+
+/// The usual latency of an endpoint at every hour of the week (in UTC), so the predictable variations of its load,
+/// e.g. nightly jobs or weekday peaks, can be told apart from actual degradations.
+#[derive(Clone, Debug, PartialEq)]
+pub struct Baseline {
+    slots: Vec<Option<Duration>>,
+}
+
+impl Baseline {
+    /// Computes a baseline from the samples of an endpoint, as the median latency of its successful probes
+    /// at every hour of the week.
+    ///
+    /// # Arguments
+    /// * `samples`: The samples of the endpoint.
+    pub fn new(samples: &[Sample]) -> Self {
+        let mut latencies = vec![Vec::new(); SLOTS];
+        for sample in samples.iter().filter(|s| (100..400).contains(&s.status)) {
+            latencies[Self::slot(sample.at)].push(sample.elapsed);
+        }
+        let slots = latencies
+            .into_iter()
+            .map(|mut latencies| {
+                latencies.sort();
+                latencies.get(latencies.len() / 2).copied()
+            })
+            .collect();
+        Self { slots }
+    }
+
+    /// Retrieves the usual latency of the endpoint at a given time.
+    ///
+    /// # Arguments
+    /// * `time`: The time, whose hour of the week is looked up.
+    ///
+    /// # Returns
+    /// The median latency of the endpoint at the same hour of the week, or `None` if it wasn't sampled then.
+    pub fn at(&self, time: SystemTime) -> Option<Duration> {
+        self.slots[Self::slot(time)]
+    }
+
+    /// Returns the hour of the week of a time, in UTC, from `0` on Monday at midnight.
+    fn slot(time: SystemTime) -> usize {
+        let secs = time.duration_since(UNIX_EPOCH).unwrap_or_default().as_secs() as usize;
+        // The Unix epoch was a Thursday
+        let weekday = (secs / 86400 + 3) % 7;
+        weekday * 24 + secs / 3600 % 24
+    }
+}
+
+/// Caches the baselines of the endpoints, computed again from their history every hour.
+pub(crate) struct Baselines {
+    window: Duration,
+    cache: DashMap<String, (Instant, Baseline)>,
+}
+
+impl Baselines {
+    pub(crate) fn new(window: Duration) -> Self {
+        Self { window, cache: DashMap::new() }
+    }
+
+    /// Retrieves the usual latency of an endpoint at the current hour of the week.
+    ///
+    /// # Arguments
+    /// * `history`: The history the baseline is computed from.
+    /// * `url`: The URL of the endpoint.
+    ///
+    /// # Returns
+    /// The usual latency of the endpoint, or `None` if it wasn't sampled at this hour or its history failed.
+    pub(crate) async fn latency(&self, history: &(dyn History + Sync + Send), url: &str) -> Option<Duration> {
+        let cached = self.cache.get(url).filter(|c| c.0.elapsed() < BASELINE_REFRESH).map(|c| c.1.clone());
+        let baseline = match cached {
+            Some(baseline) => baseline,
+            None => {
+                let baseline = history.baseline(url, self.window).await.ok()?;
+                self.cache.insert(url.to_string(), (Instant::now(), baseline.clone()));
+                baseline
+            }
+        };
+        baseline.at(SystemTime::now())
+    }
+}
+
+#[async_trait::async_trait]
+impl<T: History + Sync + Send + ?Sized> History for Arc<T> {
+    async fn record(&self, url: &str, sample: Sample) -> Result<(), Box<dyn Error + Send + Sync>> {
+        (**self).record(url, sample).await
+    }
+
+    async fn query(
+        &self,
+        url: &str,
+        from: SystemTime,
+        to: SystemTime,
+    ) -> Result<Vec<Sample>, Box<dyn Error + Send + Sync>> {
+        (**self).query(url, from, to).await
+    }
+
+    async fn compact(&self, url: &str) -> Result<(), Box<dyn Error + Send + Sync>> {
+        (**self).compact(url).await
+    }
+}
+
+/// An in-memory history, keeping the latest samples of every endpoint.
+#[derive(Debug)]
+pub struct Memory {
+    inner: DashMap<String, VecDeque<Sample>>,
+    capacity: usize,
+    /// The rollups of the samples past the raw retention, ordered by their date.
+    rollups: DashMap<String, VecDeque<Sample>>,
+    retention: Option<Retention>,
+    compactions: Compactions,
+}
+
+impl Default for Memory {
+    fn default() -> Self {
+        Self::new(DEFAULT_CAPACITY)
+    }
+}
+
+impl Memory {
+    /// Creates a new in-memory history.
+    ///
+    /// # Arguments
+    /// * `capacity`: The number of samples kept per endpoint.
+    pub fn new(capacity: usize) -> Self {
+        Self {
+            inner: DashMap::new(),
+            capacity,
+            rollups: DashMap::new(),
+            retention: None,
+            compactions: Compactions::default(),
+        }
+    }
+
+    /// Sets the retention policy of the history, compacting it as samples are recorded.
+    pub fn set_retention(mut self, retention: Retention) -> Self {
+        self.retention = Some(retention);
+        self
+    }
+}
+
+#[async_trait::async_trait]
+impl History for Memory {
+    async fn record(&self, url: &str, sample: Sample) -> Result<(), Box<dyn Error + Send + Sync>> {
+        let mut samples = self.inner.entry(url.to_string()).or_default();
+        samples.push_back(sample);
+        let excess = samples.len().saturating_sub(self.capacity);
+        samples.drain(..excess);
+        drop(samples);
+
+        if self.retention.as_ref().is_some_and(|retention| self.compactions.due(url, retention)) {
+            self.compact(url).await?;
+        }
+        Ok(())
+    }
+
+    /// Retrieves the samples of an endpoint recorded within a time range, the rollups of the older ones first.
+    async fn query(
+        &self,
+        url: &str,
+        from: SystemTime,
+        to: SystemTime,
+    ) -> Result<Vec<Sample>, Box<dyn Error + Send + Sync>> {
+        let (rollups, samples) = (self.rollups.get(url), self.inner.get(url));
+        let samples = rollups.iter().flat_map(|r| r.iter()).chain(samples.iter().flat_map(|s| s.iter()));
+        Ok(samples.filter(|s| s.at >= from && s.at <= to).cloned().collect())
+    }
+
+    async fn compact(&self, url: &str) -> Result<(), Box<dyn Error + Send + Sync>> {
+        let Some(retention) = &self.retention else {
+            return Ok(());
+        };
+        let now = SystemTime::now();
+        let cutoff = retention.raw_cutoff(now);
+        let expired = match self.inner.get_mut(url) {
+            Some(mut samples) => {
+                let (expired, raw) = samples.drain(..).partition::<Vec<_>, _>(|s| s.at < cutoff);
+                samples.extend(raw);
+                expired
+            }
+            None => return Ok(()),
+        };
+
+        let mut rollups = self.rollups.entry(url.to_string()).or_default();
+        rollups.extend(retention.rollup(&expired));
+        rollups.make_contiguous().sort_by_key(|s| s.at);
+        let cutoff = retention.rollup_cutoff(now);
+        rollups.retain(|s| s.at >= cutoff);
+        Ok(())
+    }
+}
+
+/// Creates the history kept in the store of the given configuration, or shipped through remote write if configured.
+///
+/// # Arguments
+/// * `store` - Storage configuration.
+/// * `config` - History configuration.
+///
+/// # Returns
+/// A boxed history, shared by the replicas using the same store.
+pub fn from_config(store: &crate::store::Config, config: Config) -> Box<dyn History + Sync + Send + 'static> {
+    if let Some(remote_write) = config.remote_write {
+        return Box::new(RemoteWrite::from_config(remote_write));
+    }
+    let capacity = config.capacity.unwrap_or(DEFAULT_CAPACITY);
+    match store {
+        #[cfg(feature = "redis")]
+        crate::store::Config::Redis(store) => {
+            let mut history = crate::store::Redis::from_config(store).set_history_capacity(capacity);
+            if let Some(retention) = config.retention {
+                history = history.set_history_retention(retention);
+            }
+            Box::new(history)
+        }
+        crate::store::Config::Memory => {
+            let mut history = Memory::new(capacity);
+            if let Some(retention) = config.retention {
+                history = history.set_retention(retention);
+            }
+            Box::new(history)
+        }
+        // The history is kept in the primary store only
+        crate::store::Config::Fallback(fallback) => from_config(&fallback.primary, config),
+        crate::store::Config::Buffered(buffered) => from_config(&buffered.store, config),
+    }
+}
@@ -0,0 +1,316 @@
+use super::{History, Sample};
+use crate::config::{
+    deserialize_headers, deserialize_opt_uri, deserialize_uri, serialize_headers, serialize_opt_uri, serialize_uri,
+};
+use crate::Client;
+use bytes::Bytes;
+use http_body_util::{BodyExt, Full, Limited};
+use hyper::header::{CONTENT_ENCODING, CONTENT_TYPE};
+use hyper::{HeaderMap, Uri};
+use std::collections::{BTreeMap, HashMap};
+use std::error::Error;
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
+
+/// The version of the remote write protocol the samples are sent with.
+const PROTOCOL_VERSION: &str = "0.1.0";
+
+/// The maximum size of the responses to the history queries.
+const MAX_RESPONSE_SIZE: usize = 64 * 1024 * 1024;
+
+/// The series every sample is written to, along with the field of the sample they hold.
+const LATENCY: &str = "isup_probe_duration_seconds";
+const STATUS: &str = "isup_probe_status";
+const SCORE: &str = "isup_score";
+const RELIABILITY: &str = "isup_reliability";
+
+/// Prometheus remote write configuration
+///
+/// - `url`: the remote write endpoint, e.g. `http://prometheus:9090/api/v1/write`
+/// - `query_url`: the Prometheus HTTP API the history is queried from, e.g. `http://prometheus:9090`.
+///   Without it, the history is only written, and can't be queried by isup nor its baselines computed.
+/// - `headers`: additional headers sent along, e.g. an `authorization` header
+/// - `labels`: additional labels of every series, e.g. the `job` or `region` of this deployment
+#[derive(serde::Deserialize, serde::Serialize, Clone, Debug, PartialEq, Eq)]
+pub struct Config {
+    #[serde(deserialize_with = "deserialize_uri", serialize_with = "serialize_uri")]
+    pub url: Uri,
+    #[serde(deserialize_with = "deserialize_opt_uri", serialize_with = "serialize_opt_uri", default)]
+    pub query_url: Option<Uri>,
+    #[serde(deserialize_with = "deserialize_headers", serialize_with = "serialize_headers", default)]
+    pub headers: HeaderMap,
+    #[serde(default)]
+    pub labels: BTreeMap<String, String>,
+}
+
+/// A history shipping its samples to a Prometheus compatible TSDB through the remote write protocol, e.g. Prometheus,
+/// Mimir or VictoriaMetrics, so that isup stays stateless while the history is kept along with the other metrics.
+///
+/// Every sample is written to the `isup_probe_duration_seconds`, `isup_probe_status`, `isup_score` and
+/// `isup_reliability` series, labelled with the URL of its endpoint, and read back through the HTTP API.
+pub struct RemoteWrite {
+    config: Config,
+    client: Client,
+}
+
+impl RemoteWrite {
+    /// Creates a new `RemoteWrite` history, writing to the given endpoint.
+    ///
+    /// # Panics
+    /// Panics if the URL cannot be parsed.
+    pub fn new<I: Into<String>>(url: I) -> Self {
+        let url = url.into().parse().expect("Invalid URL");
+        Self::from_config(Config { url, query_url: None, headers: HeaderMap::new(), labels: BTreeMap::new() })
+    }
+
+    /// Creates a new `RemoteWrite` history from its configuration.
+    pub fn from_config(config: Config) -> Self {
+        Self { config, client: Client::default() }
+    }
+
+    /// Sets the Prometheus HTTP API the history is queried from.
+    ///
+    /// # Panics
+    /// Panics if the URL cannot be parsed.
+    pub fn set_query_url<I: Into<String>>(mut self, url: I) -> Self {
+        self.config.query_url = Some(url.into().parse().expect("Invalid URL"));
+        self
+    }
+
+    /// Sets an additional label of every series.
+    pub fn set_label<K: Into<String>, V: Into<String>>(mut self, name: K, value: V) -> Self {
+        self.config.labels.insert(name.into(), value.into());
+        self
+    }
+
+    /// Encodes a sample as a `WriteRequest` protobuf message, one time series per field of the sample.
+    fn write_request(&self, url: &str, sample: &Sample) -> Vec<u8> {
+        let at = sample.at.duration_since(UNIX_EPOCH).unwrap_or_default().as_millis() as u64;
+        let fields = [
+            (LATENCY, sample.elapsed.as_secs_f64()),
+            (STATUS, sample.status as f64),
+            (SCORE, sample.score as f64),
+            (RELIABILITY, sample.reliability as f64),
+        ];
+
+        let mut request = vec![];
+        for (name, value) in fields {
+            let mut labels = self.config.labels.clone();
+            labels.insert("__name__".to_string(), name.to_string());
+            labels.insert("url".to_string(), url.to_string());
+
+            let mut series = vec![];
+            // The labels are sorted by their name, as expected by the receivers
+            for (name, value) in &labels {
+                let mut label = vec![];
+                length_delimited(&mut label, 1, name.as_bytes());
+                length_delimited(&mut label, 2, value.as_bytes());
+                length_delimited(&mut series, 1, &label);
+            }
+            let mut point = vec![0x09];
+            point.extend_from_slice(&value.to_le_bytes());
+            point.push(0x10);
+            varint(&mut point, at);
+            length_delimited(&mut series, 2, &point);
+            length_delimited(&mut request, 1, &series);
+        }
+        request
+    }
+
+    /// Builds the PromQL selector of the series of an endpoint.
+    fn selector(&self, url: &str, range: Duration) -> String {
+        let mut matchers = vec![format!("__name__=~\"{LATENCY}|{STATUS}|{SCORE}|{RELIABILITY}\"")];
+        let labels = self.config.labels.iter().map(|(name, value)| (name.as_str(), value.as_str()));
+        matchers.extend(labels.chain([("url", url)]).map(|(name, value)| format!("{name}=\"{}\"", escape(value))));
+        // Range selectors are limited to a whole number of milliseconds
+        format!("{{{}}}[{}ms]", matchers.join(","), range.as_millis().max(1))
+    }
+}
+
+/// Writes a varint, as encoded by protobuf and snappy.
+fn varint(buf: &mut Vec<u8>, mut value: u64) {
+    while value >= 0x80 {
+        buf.push(value as u8 | 0x80);
+        value >>= 7;
+    }
+    buf.push(value as u8);
+}
+
+/// Writes a length-delimited protobuf field, e.g. a string or an embedded message.
+fn length_delimited(buf: &mut Vec<u8>, field: u8, value: &[u8]) {
+    buf.push(field << 3 | 2);
+    varint(buf, value.len() as u64);
+    buf.extend_from_slice(value);
+}
+
+/// Frames the data as a snappy block made of a single literal, as the remote write protocol expects the messages to
+/// be compressed with snappy. The samples are small, so they're sent uncompressed rather than pulling a compressor.
+fn snappy(data: &[u8]) -> Vec<u8> {
+    let mut block = Vec::with_capacity(data.len() + 10);
+    varint(&mut block, data.len() as u64);
+    if !data.is_empty() {
+        let len = data.len() - 1;
+        match len {
+            0..=59 => block.push((len as u8) << 2),
+            _ => {
+                // The length follows the tag in as many little-endian bytes as needed
+                let bytes = (len as u32).to_le_bytes();
+                let count = 4 - (len as u32).leading_zeros() as usize / 8;
+                block.push(((59 + count) as u8) << 2);
+                block.extend_from_slice(&bytes[..count]);
+            }
+        }
+        block.extend_from_slice(data);
+    }
+    block
+}
+
+/// Escapes the backslashes and double quotes of a PromQL string.
+fn escape(value: &str) -> String {
+    value.replace('\\', "\\\\").replace('"', "\\\"")
+}
+
+/// Percent-encodes a query parameter, leaving the unreserved characters as is.
+fn encode(value: &str) -> String {
+    value
+        .bytes()
+        .map(|b| match b {
+            b'A'..=b'Z' | b'a'..=b'z' | b'0'..=b'9' | b'-' | b'.' | b'_' | b'~' => (b as char).to_string(),
+            _ => format!("%{b:02X}"),
+        })
+        .collect()
+}
+
+/// Parses the matrix of a Prometheus query into the samples, joined by their timestamp.
+fn parse_matrix(body: &[u8]) -> Result<Vec<Sample>, Box<dyn Error + Send + Sync>> {
+    let body: serde_json::Value = serde_json::from_slice(body)?;
+    if body["status"] != "success" {
+        return Err(format!("prometheus query failed: {}", body["error"].as_str().unwrap_or("unknown error")).into());
+    }
+    let mut samples = BTreeMap::<u64, HashMap<String, f64>>::new();
+    for series in body["data"]["result"].as_array().into_iter().flatten() {
+        let name = series["metric"]["__name__"].as_str().unwrap_or_default();
+        for point in series["values"].as_array().into_iter().flatten() {
+            let (Some(at), Some(value)) = (point[0].as_f64(), point[1].as_str()) else {
+                return Err("invalid prometheus sample".into());
+            };
+            let at = (at * 1000.0).round() as u64;
+            samples.entry(at).or_default().insert(name.to_string(), value.parse()?);
+        }
+    }
+
+    let samples = samples.into_iter().filter(|(_, fields)| fields.contains_key(LATENCY)).map(|(at, fields)| {
+        let field = |name| fields.get(name).copied().unwrap_or_default();
+        Sample {
+            at: UNIX_EPOCH + Duration::from_millis(at),
+            elapsed: Duration::from_secs_f64(field(LATENCY).max(0.0)),
+            status: field(STATUS) as u16,
+            score: field(SCORE) as f32,
+            reliability: field(RELIABILITY) as f32,
+        }
+    });
+    Ok(samples.collect())
+}
+
+#[async_trait::async_trait]
+impl History for RemoteWrite {
+    async fn record(&self, url: &str, sample: Sample) -> Result<(), Box<dyn Error + Send + Sync>> {
+        let mut request = hyper::Request::post(self.config.url.clone())
+            .header(CONTENT_TYPE, "application/x-protobuf")
+            .header(CONTENT_ENCODING, "snappy")
+            .header("x-prometheus-remote-write-version", PROTOCOL_VERSION)
+            .body(Full::new(Bytes::from(snappy(&self.write_request(url, &sample)))))?;
+        request.headers_mut().extend(self.config.headers.clone());
+
+        let response = self.client.request(request).await?;
+        match response.status().is_success() {
+            true => Ok(()),
+            false => Err(format!("remote write responded with `{}`", response.status()).into()),
+        }
+    }
+
+    /// Retrieves the samples of an endpoint from the Prometheus HTTP API, with a range query ending at `to`.
+    ///
+    /// # Errors
+    /// Returns an error if no `query_url` is configured, or the query fails.
+    async fn query(
+        &self,
+        url: &str,
+        from: SystemTime,
+        to: SystemTime,
+    ) -> Result<Vec<Sample>, Box<dyn Error + Send + Sync>> {
+        let Some(query_url) = &self.config.query_url else {
+            return Err("the remote write history has no `query_url` to be queried".into());
+        };
+        let range = to.duration_since(from).unwrap_or_default();
+        let time = to.duration_since(UNIX_EPOCH).unwrap_or_default().as_secs_f64();
+        let uri = format!(
+            "{}/api/v1/query?query={}&time={time:.3}",
+            query_url.to_string().trim_end_matches('/'),
+            encode(&self.selector(url, range)),
+        );
+        let mut request = hyper::Request::get(uri).body(Full::new(Bytes::new()))?;
+        request.headers_mut().extend(self.config.headers.clone());
+
+        let response = self.client.request(request).await?;
+        if !response.status().is_success() {
+            return Err(format!("prometheus responded with `{}`", response.status()).into());
+        }
+        let body = Limited::new(response.into_body(), MAX_RESPONSE_SIZE).collect().await?.to_bytes();
+        let samples = parse_matrix(&body)?;
+        Ok(samples.into_iter().filter(|s| s.at >= from && s.at <= to).collect())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_frames_the_snappy_literals() {
+        assert_eq!(snappy(b""), vec![0]);
+        assert_eq!(snappy(b"abc"), [&[3, 2 << 2][..], b"abc"].concat());
+        // Literals longer than 60 bytes carry their length after the tag
+        let data = vec![7; 300];
+        assert_eq!(snappy(&data)[..5], [0xac, 0x02, 61 << 2, 0x2b, 0x01]);
+        assert_eq!(snappy(&data).len(), 305);
+    }
+
+    #[test]
+    fn test_encodes_the_write_request() {
+        let history = RemoteWrite::new("http://prometheus.example/api/v1/write");
+        let sample = Sample {
+            at: UNIX_EPOCH + Duration::from_millis(1500),
+            elapsed: Duration::from_millis(250),
+            status: 200,
+            score: 0.5,
+            reliability: 1.0,
+        };
+        let request = history.write_request("http://a.example/", &sample);
+
+        // The first series holds the latency, labelled with its name first
+        let label = [&[0x0a, 8][..], b"__name__", &[0x12, 27], LATENCY.as_bytes()].concat();
+        let url = [&[0x0a, 3][..], b"url", &[0x12, 17], b"http://a.example/"].concat();
+        let point = [&[0x09][..], &0.25f64.to_le_bytes(), &[0x10, 0xdc, 0x0b]].concat();
+        let mut series = vec![];
+        length_delimited(&mut series, 1, &label);
+        length_delimited(&mut series, 1, &url);
+        length_delimited(&mut series, 2, &point);
+        let mut expected = vec![];
+        length_delimited(&mut expected, 1, &series);
+        assert!(request.starts_with(&expected));
+    }
+
+    #[test]
+    fn test_joins_the_queried_series() {
+        let body = br#"{"status":"success","data":{"resultType":"matrix","result":[
+            {"metric":{"__name__":"isup_probe_duration_seconds","url":"u"},"values":[[1.5,"0.25"],[2,"0.5"]]},
+            {"metric":{"__name__":"isup_probe_status","url":"u"},"values":[[1.5,"200"],[2,"0"]]},
+            {"metric":{"__name__":"isup_score","url":"u"},"values":[[1.5,"0.75"]]}
+        ]}}"#;
+        let samples = parse_matrix(body).unwrap();
+        assert_eq!(samples.len(), 2);
+        assert_eq!(samples[0].at, UNIX_EPOCH + Duration::from_millis(1500));
+        assert_eq!((samples[0].elapsed, samples[0].status, samples[0].score), (Duration::from_millis(250), 200, 0.75));
+        assert_eq!((samples[1].status, samples[1].score), (0, 0.0));
+    }
+}
@@ -0,0 +1,226 @@
+use crate::ProbeOutcome;
+use std::time::{Duration, SystemTime};
+
+/// The state of an endpoint, as determined by the outcome of its latest probes.
+#[derive(serde::Deserialize, serde::Serialize, Clone, Copy, Debug, PartialEq, Eq, Hash)]
+#[serde(rename_all = "snake_case")]
+pub enum State {
+    Up,
+    Down,
+}
+
+/// The number of failed probes required before an endpoint is considered down, reducing the false positives
+/// caused by a single dropped packet or timed-out request.
+///
+/// - `failures`: the number of failed probes (K) required among the `probes`
+/// - `probes`: the number of probes (N) the failures are counted over
+/// - `concurrent`: when `false` (default), the failures are counted over the latest N probes of the endpoint,
+///   so K = N requires consecutive failures. When `true`, a failed probe is confirmed by N - 1 additional probes
+///   sent concurrently, which aren't scored.
+#[derive(serde::Deserialize, serde::Serialize, Clone, Copy, Debug, PartialEq, Eq)]
+pub struct Quorum {
+    pub failures: usize,
+    pub probes: usize,
+    #[serde(default)]
+    pub concurrent: bool,
+}
+
+impl Quorum {
+    /// Creates a new `Quorum`, counting the failures over the latest probes of the endpoints.
+    ///
+    /// # Arguments
+    /// * `failures`: The number of failed probes required.
+    /// * `probes`: The number of probes the failures are counted over.
+    ///
+    /// # Panics
+    /// Panics if no failure is required, or more failures than probes are.
+    pub fn new(failures: usize, probes: usize) -> Self {
+        let quorum = Self { failures, probes, concurrent: false };
+        quorum.validate().expect("invalid quorum");
+        quorum
+    }
+
+    /// Sets whether a failed probe is confirmed by additional probes sent concurrently.
+    pub fn set_concurrent(mut self, concurrent: bool) -> Self {
+        self.concurrent = concurrent;
+        self
+    }
+
+    /// Verifies that the quorum can be reached.
+    pub(crate) fn validate(&self) -> Result<(), String> {
+        match self.failures {
+            0 => Err("a quorum requires at least one failure".into()),
+            failures if failures > self.probes => {
+                Err(format!("a quorum of {failures} failures can't be reached in {} probes", self.probes))
+            }
+            _ => Ok(()),
+        }
+    }
+}
+
+/// Backs off the probing of the endpoints that stay down, so dead hosts aren't hammered and the probe capacity
+/// goes to the endpoints that can be routed to.
+///
+/// - `after`: the number of cycles in a row an endpoint must be down before its probes back off (default: 3)
+/// - `max_cycles`: the maximum number of cycles between two probes of an endpoint (default: 32)
+///
+/// Once an endpoint is backed off, the number of cycles between its probes doubles on every failed probe, up to
+/// `max_cycles`. The normal interval is restored as soon as it's found up again.
+#[derive(serde::Deserialize, serde::Serialize, Clone, Copy, Debug, PartialEq, Eq)]
+pub struct Backoff {
+    #[serde(default = "Backoff::default_after")]
+    pub after: u32,
+    #[serde(default = "Backoff::default_max_cycles")]
+    pub max_cycles: u32,
+}
+
+impl Default for Backoff {
+    fn default() -> Self {
+        Self { after: Self::default_after(), max_cycles: Self::default_max_cycles() }
+    }
+}
+
+impl Backoff {
+    /// Creates a new `Backoff`.
+    ///
+    /// # Arguments
+    /// * `after`: The number of cycles in a row an endpoint must be down before its probes back off.
+    /// * `max_cycles`: The maximum number of cycles between two probes of an endpoint.
+    ///
+    /// # Panics
+    /// Panics if either of them is zero.
+    pub fn new(after: u32, max_cycles: u32) -> Self {
+        let backoff = Self { after, max_cycles };
+        backoff.validate().expect("invalid backoff");
+        backoff
+    }
+
+    fn default_after() -> u32 {
+        3
+    }
+
+    fn default_max_cycles() -> u32 {
+        32
+    }
+
+    /// Returns the number of cycles skipped after a probe, given how many cycles in a row the endpoint was down.
+    ///
+    /// # Arguments
+    /// * `down_cycles`: The number of cycles in a row the endpoint was down, including the latest one.
+    pub fn skipped_cycles(&self, down_cycles: u32) -> u32 {
+        match down_cycles.checked_sub(self.after) {
+            Some(backoffs) => 2u32.saturating_pow(backoffs + 1).min(self.max_cycles) - 1,
+            None => 0,
+        }
+    }
+
+    /// Verifies that the probes are eventually backed off, and still sent.
+    pub(crate) fn validate(&self) -> Result<(), String> {
+        match (self.after, self.max_cycles) {
+            (0, _) => Err("a backoff requires at least one cycle down".into()),
+            (_, 0) => Err("a backoff requires at least one cycle between probes".into()),
+            _ => Ok(()),
+        }
+    }
+}
+
+/// The number of cycles in a row an endpoint was down, and the number of cycles left before it's probed again.
+#[derive(Clone, Copy, Debug, Default)]
+pub(crate) struct BackedOff {
+    pub(crate) down_cycles: u32,
+    pub(crate) skipped_cycles: u32,
+}
+
+/// The maximum number of probe outcomes kept as the evidence of an incident; the oldest are dropped first.
+const MAX_EVIDENCE: usize = 10;
+
+/// A period during which an endpoint was down, opened when it transitions to `Down`
+/// and resolved once it's back `Up`.
+///
+/// Incidents are recorded in the store, so they can be listed and annotated for post-mortems.
+#[derive(serde::Deserialize, serde::Serialize, Clone, Debug)]
+pub struct Incident {
+    /// The unique ID of the incident.
+    pub id: String,
+    /// The URL of the endpoint.
+    pub url: String,
+    /// When the endpoint went down.
+    pub started_at: SystemTime,
+    /// When the endpoint recovered, or `None` while the incident is ongoing.
+    pub resolved_at: Option<SystemTime>,
+    /// How long the endpoint was down, once the incident is resolved.
+    pub duration: Option<Duration>,
+    /// The status code of the probe that opened the incident, `0` if no response was received.
+    pub status: u16,
+    /// The error of the probe that opened the incident, if any.
+    pub error: Option<String>,
+    /// The latest outcomes probed during the incident, including the one that resolved it.
+    #[serde(default)]
+    pub evidence: Vec<ProbeOutcome>,
+    /// The notes attached by operators.
+    #[serde(default)]
+    pub annotations: Vec<Annotation>,
+    /// The network path toward the endpoint, traced when the incident was opened.
+    /// `None` until the traceroute completes, or if it's not enabled.
+    #[cfg(feature = "traceroute")]
+    #[serde(default)]
+    pub trace: Option<crate::traceroute::Trace>,
+}
+
+impl Incident {
+    /// Opens an incident from the outcome of the probe that found the endpoint down.
+    pub(crate) fn open(outcome: &ProbeOutcome) -> Self {
+        Self {
+            id: crate::request::generate_id(),
+            url: outcome.url.clone(),
+            started_at: SystemTime::now(),
+            resolved_at: None,
+            duration: None,
+            status: outcome.status,
+            error: outcome.error.clone(),
+            evidence: vec![outcome.clone()],
+            annotations: Vec::new(),
+            #[cfg(feature = "traceroute")]
+            trace: None,
+        }
+    }
+
+    /// Records the outcome of a probe executed during the incident.
+    pub(crate) fn record(&mut self, outcome: &ProbeOutcome) {
+        if self.evidence.len() >= MAX_EVIDENCE {
+            self.evidence.remove(0);
+        }
+        self.evidence.push(outcome.clone());
+    }
+
+    /// Resolves the incident, with the outcome of the probe that found the endpoint back up.
+    pub(crate) fn resolve(&mut self, outcome: &ProbeOutcome) {
+        let now = SystemTime::now();
+        self.record(outcome);
+        self.resolved_at = Some(now);
+        self.duration = Some(now.duration_since(self.started_at).unwrap_or_default());
+    }
+
+    /// Returns `true` while the endpoint hasn't recovered.
+    pub fn is_ongoing(&self) -> bool {
+        self.resolved_at.is_none()
+    }
+}
+
+/// A note attached to an incident by an operator, e.g. its root cause or the actions taken.
+#[derive(serde::Deserialize, serde::Serialize, Clone, Debug, PartialEq, Eq)]
+pub struct Annotation {
+    /// Who wrote the note.
+    pub author: String,
+    /// The note itself.
+    pub text: String,
+    /// When the note was attached.
+    pub created_at: SystemTime,
+}
+
+impl Annotation {
+    /// Creates a new `Annotation`, dated now.
+    pub fn new<I: Into<String>>(author: I, text: I) -> Self {
+        Self { author: author.into(), text: text.into(), created_at: SystemTime::now() }
+    }
+}
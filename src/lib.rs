@@ -3,17 +3,43 @@
 #![deny(unused_must_use, rust_2018_idioms)]
 
 mod score;
-pub use score::Score;
+pub use score::{Score, ScoreView};
 
 mod config;
-pub use config::Config;
+pub use config::{Config, ConfigDuration, ConfigError};
+
+/// The `secret` module resolves the references to secrets written in the configuration, such as
+/// `{ from_env: NAME }` or `{ from_file: path }`, and lets custom resolvers fetch them from a secret manager.
+pub mod secret;
 
 mod client;
-pub use client::Client;
+pub use client::{AddressFamily, Client, PoolStats, RequestOptions, Tls, TlsVersion};
 
 mod request;
 pub use request::Request;
 
+mod outcome;
+pub use outcome::{ProbeOutcome, Sample};
+
+mod health;
+pub use health::Health;
+
+mod cycle;
+pub use cycle::{CycleReport, DryRunReport, Misconfigured, SkipReason, Skipped};
+
+mod ranking;
+pub use ranking::{Direction, RankedEndpoint, Trend};
+
+mod encoding;
+pub use encoding::Encoding;
+
+mod cache;
+use cache::{check_freshness, Validators};
+
+mod probe;
+use probe::Probe;
+pub use probe::{Exec, GraphQl};
+
 /// The `store` module provides the necessary implementations for data storage and retrieval within the application.
 /// It defines the `Store` trait and various implementations of this trait to handle the storage of monitoring data,
 /// such as scores and metrics, potentially using different backend technologies (in-memory storage, redis, ...).
@@ -26,14 +52,141 @@ use store::Store;
 pub mod strategy;
 use strategy::Strategy;
 
+/// The `middleware` module defines hooks that run around every probe of a `Service`.
+/// Middlewares can modify outgoing requests and inspect, mutate or veto probe outcomes before they're scored,
+/// e.g. to log every result or to ignore failures during a deployment.
+pub mod middleware;
+use middleware::{Action, Middleware};
+
+/// The `chaos` module provides a fault-injection mode for testing applications built on top of a `Service`.
+/// Configured endpoints are simulated with scripted latencies and failure rates instead of being requested,
+/// so failover logic and alerting can be verified without breaking real services.
+pub mod chaos;
+use chaos::Chaos;
+
+/// The `guard` module provides allow and deny rules (schemes, hostname patterns and CIDR ranges) for the monitored
+/// endpoints, so services accepting user-submitted URLs can't be abused to probe internal networks.
+pub mod guard;
+use guard::Guard;
+
+/// The `audit` module checks the security headers (HSTS, CSP, X-Content-Type-Options, ...) of the responses
+/// received from the monitored endpoints, reporting the missing and invalid ones and optionally penalizing the score.
+pub mod audit;
+
+/// The `capture` module keeps the headers and the beginning of the body of the latest failed response of every
+/// endpoint, so operators can see the actual error page instead of just its status code.
+pub mod capture;
+
+/// The `backtest` module replays recorded probe outcomes through any `Strategy` and reports how the
+/// rankings would have evolved, so strategy parameters can be tuned and compared against real data.
+pub mod backtest;
+
+/// The `bench` module provides criterion benchmarks for strategies and stores. It's exposed behind the `bench`
+/// feature, so the same benchmarks can be executed against custom `Strategy` and `Store` implementations.
+#[cfg(feature = "bench")]
+pub mod bench;
+
+/// The `incident` module tracks the state of the monitored endpoints, opening an incident whenever one goes down
+/// and resolving it once it recovers.
+pub mod incident;
+use incident::{Annotation, Backoff, Incident, Quorum, State};
+
+/// The `agent` module distributes the probing across hosts: agents probe the endpoints locally and push the
+/// outcomes over HTTP to a coordinator, which scores them without agents needing credentials to the store.
+pub mod agent;
+use agent::Agent;
+
+/// The `election` module elects a single replica probing the endpoints, among the ones sharing a store,
+/// so multi-replica deployments don't probe every endpoint once per replica.
+pub mod election;
+use election::{Election, Lease};
+
+/// The `shard` module splits the endpoints among the replicas sharing a store through consistent hashing,
+/// rebalancing them whenever a replica joins or leaves.
+pub mod shard;
+use shard::{Registry, Sharding};
+
+/// The `traceroute` module traces the network path toward an endpoint once it goes down, attaching the hops to its
+/// incident so network failures can be told apart from application ones. It's exposed behind the `traceroute`
+/// feature, as it requires raw sockets.
+#[cfg(feature = "traceroute")]
+pub mod traceroute;
+
+/// The `notify` module delivers notifications to the operators through external channels, such as webhooks.
+pub mod notify;
+use notify::Notifier;
+
+/// The `export` module exports the scored probes of every update cycle to external sinks, such as files or InfluxDB,
+/// so the probe data can be consumed by other systems.
+pub mod export;
+use export::Exporter;
+
+/// The `report` module summarizes the uptime, latency and incidents of the endpoints over periods of time,
+/// delivering the summaries through the notifiers of the service.
+pub mod report;
+use report::Reporter;
+
+/// The `alert` module raises the incidents of the endpoints as alerts in Prometheus Alertmanager,
+/// labeled with the tags of their requests, so they go through the existing routing and silencing rules.
+pub mod alert;
+use alert::Alertmanager;
+
+/// The `forecast` module extrapolates the trend of the scores, flagging the endpoints at risk of falling under a
+/// score threshold before they do.
+pub mod forecast;
+
+/// The `history` module records a time series of the scored probes of every endpoint, in memory or in the store,
+/// so their latency and score can be graphed and analyzed over time.
+pub mod history;
+use history::History;
+
+/// The `systemd` module implements the `sd_notify` protocol, so a `Service` running under systemd reports its
+/// readiness and pings the watchdog from its update loop, getting restarted once the loop hangs.
+pub mod systemd;
+
+/// The `metrics` module measures the operations of a `Service`, such as the latency and errors of its store,
+/// exposing them in the Prometheus text format.
+pub mod metrics;
+use metrics::{Instrumented, Metrics};
+
+/// The `trace` module emits a `tracing` span for the sampled probes, and propagates the W3C trace context
+/// to the endpoints through the `traceparent` header.
+pub mod trace;
+use tracing::Instrument;
+
+/// The `server` module provides an HTTP server to be embedded in the monitoring process, exposing the state of a
+/// `Service`, including a Grafana JSON datasource over its history.
+pub mod server;
+
+/// The `registry` module manages several named services, each with its own requests, strategy, store and interval,
+/// starting and stopping them together and routing the queries to them by name.
+pub mod registry;
+
+/// The `lint` module checks configuration files before they're deployed, reporting machine-readable findings
+/// such as duplicate URLs or missing schemes, and is run by the `isup lint` command.
+pub mod lint;
+
 use bytes::Bytes;
+use dashmap::{DashMap, DashSet};
 use futures::future::join_all;
-use http_body_util::Full;
+use futures::StreamExt;
+use http_body_util::{BodyExt, Full};
+use hyper::body::Incoming;
+use hyper::header::{HeaderName, HeaderValue, CONTENT_ENCODING};
 use hyper::Uri;
+use std::collections::{HashMap, VecDeque};
 use std::error::Error;
-use std::sync::atomic::{AtomicU64, Ordering::SeqCst};
-use std::time::{SystemTime, UNIX_EPOCH};
+use std::sync::atomic::{AtomicBool, AtomicU64, Ordering::SeqCst};
+use std::sync::{Arc, OnceLock, RwLock};
+use std::time::{Instant, SystemTime, UNIX_EPOCH};
 use std::{str::FromStr, time::Duration};
+use tokio::sync::mpsc;
+
+/// The default maximum number of samples scored and written to the store at once.
+const DEFAULT_SCORING_BATCH: usize = 64;
+
+/// A callback invoked with the URL of an endpoint, its previous state and its new one.
+type TransitionHook = dyn Fn(&str, Option<State>, State) + Sync + Send + 'static;
 
 /// The `Service` struct is the main component of the application, responsible for
 /// orchestrattion, monitoring and performance calculation.
@@ -44,16 +197,117 @@ pub struct Service {
     /// communication and ensures requests are properly sent and responses received.
     client: Client,
     /// The strategy used for calculating the scores of the endpoints. It takes into
-    /// account various metrics and updates the evaluation of the endpoints.
-    strategy: Box<dyn Strategy + Sync + Send + 'static>,
+    /// account various metrics and updates the evaluation of the endpoints. It can be swapped at runtime.
+    strategy: RwLock<Arc<dyn Strategy + Sync + Send + 'static>>,
+    /// The strategies overriding the one of the service for the endpoints of a URL or tag.
+    strategies: HashMap<strategy::Key, Arc<dyn Strategy + Sync + Send + 'static>>,
     /// The store mechanism for the scores. It allows for storing, updating,
     /// and retrieving the scores of monitored endpoints.
-    pub store: Box<dyn Store + Sync + Send + 'static>,
+    pub store: Arc<dyn Store + Sync + Send + 'static>,
+    /// The latency and errors of the operations of the service, such as the ones of its store.
+    metrics: Arc<Metrics>,
+    /// The chain of middlewares executed around every probe, in order of registration.
+    middleware: Vec<Box<dyn Middleware + Sync + Send + 'static>>,
+    /// The callbacks invoked whenever the state of an endpoint changes.
+    transition_hooks: Vec<Box<TransitionHook>>,
+    /// Simulated endpoints, probed without touching the network.
+    chaos: Option<Chaos>,
+    /// The header carrying a unique ID on every probe, to correlate it with the logs of the endpoint.
+    request_id_header: Option<HeaderName>,
+    /// The cache validators last received from each endpoint with `cache_validation` enabled.
+    validators: DashMap<String, Validators>,
+    /// The security-header audit of the last response received from each endpoint with an audit configured.
+    audits: DashMap<String, audit::Report>,
+    /// Captures the failed responses of the endpoints, if set.
+    capture: Option<capture::Config>,
+    /// The latest failed probe of each endpoint, along with its response, if captured.
+    last_failures: DashMap<String, capture::Failure>,
+    /// The state of each endpoint, according to its latest scored probe.
+    states: DashMap<String, State>,
+    /// The number of failed probes required before an endpoint is considered down, if more than one.
+    quorum: Option<Quorum>,
+    /// Whether each of the latest probes of an endpoint failed, counted toward the quorum.
+    failures: DashMap<String, VecDeque<bool>>,
+    /// The number of latest probes the error rates and jitter of the endpoints are computed over.
+    error_window: usize,
+    /// The latency of each of the probes of an endpoint within the error window, `None` if it failed.
+    recent_probes: DashMap<String, VecDeque<Option<Duration>>>,
+    /// Backs off the probing of the endpoints that stay down, if set.
+    backoff: Option<Backoff>,
+    /// How long each endpoint was down, and how many cycles its probes are skipped for.
+    backed_off: DashMap<String, incident::BackedOff>,
+    /// Restricts the probing to the elected replica, if set.
+    election: Option<Election>,
+    /// Restricts the probing to the endpoints assigned to this replica, if set.
+    sharding: Option<Sharding>,
+    /// Pushes the outcomes to a coordinator, in agent mode.
+    agent: Option<Agent>,
+    /// The ongoing incident of each endpoint, shared with the diagnostics running in the background.
+    incidents: Arc<DashMap<String, Incident>>,
+    /// Traces the network path toward the endpoints that go down, if set.
+    #[cfg(feature = "traceroute")]
+    traceroute: Option<traceroute::Config>,
+    /// Traces the probes and propagates their trace context, if set.
+    tracing: Option<trace::Config>,
+    /// The channels notifications are delivered through.
+    notifiers: Vec<Box<dyn Notifier + Sync + Send + 'static>>,
+    /// The sinks the scored probes of every update cycle are exported to.
+    exporters: Vec<Box<dyn Exporter + Sync + Send + 'static>>,
+    /// Summarizes the probes over periods of time, if set.
+    reporter: Option<Reporter>,
+    /// Raises the incidents as alerts in Alertmanager, if set.
+    alertmanager: Option<Alertmanager>,
+    /// Records the scored probes of every endpoint, if set.
+    history: Option<Box<dyn History + Sync + Send + 'static>>,
+    /// Looks up the baselines of the endpoints in their history, if set.
+    baselines: Option<history::Baselines>,
+    /// When the update loop started, once the service is running.
+    running: OnceLock<SystemTime>,
+    /// The interval of the update loop in nanoseconds, which can be changed while it's running.
+    interval: AtomicU64,
+    /// Whether an update cycle is running, so the next one is skipped instead of overlapping it.
+    updating: AtomicBool,
+    /// Persists the monitored requests in the store, so they can be restored on startup.
+    persist_requests: bool,
+    /// The namespace isolating the service from the other ones sharing its store, if any.
+    namespace: Option<String>,
+    /// Whether the monitored requests changed since they were last persisted.
+    requests_changed: AtomicBool,
+    /// How long the endpoints inserted at runtime are excluded from `best_url` and alerting, if set.
+    grace_period: Option<Duration>,
+    /// When each endpoint was inserted at runtime, to tell whether it's within its grace period.
+    inserted_at: DashMap<String, Instant>,
+    /// The endpoints that are paused, which are neither probed nor routed to until they're resumed.
+    paused: DashSet<String>,
+    /// Until when the failures of each endpoint are suppressed, e.g. while it's deployed.
+    suppressed: DashMap<String, Instant>,
+    /// When each endpoint was last scored.
+    checked_at: DashMap<String, SystemTime>,
+    /// The responses of each endpoint exceeding its latency budget in a row, if they currently do.
+    violations: DashMap<String, alert::Violation>,
+    /// Extrapolates the trend of the scores, flagging the endpoints at risk, if set.
+    forecast: Option<forecast::Config>,
+    /// The endpoints currently at risk of falling under the score threshold.
+    risks: DashMap<String, forecast::Risk>,
+    /// Whether the scores are exposed relative to the best candidate, by `ranking` and `best_url_where`.
+    relative_scoring: bool,
+    /// How long the probes of an update cycle may run before being aborted, if set.
+    cycle_deadline: Option<Duration>,
+    /// The maximum number of endpoints probed at once within a cycle, if set.
+    concurrency: Option<usize>,
+    /// The maximum number of samples scored and written to the store at once, which is also the number of samples
+    /// the probes can push onto the scoring channel before waiting for the scorer.
+    scoring_batch: usize,
+    /// The report of the latest update cycle, if one completed.
+    last_cycle: RwLock<Option<CycleReport>>,
     /// List of HTTP requests to be monitored. Each request corresponds to a
     /// web endpoint whose availability and performance is to be ranked.
-    pub requests: Vec<hyper::Request<Full<Bytes>>>,
+    pub requests: Vec<Request>,
     /// Unix timestamp of last time the scores were updated.
     pub updated_at: AtomicU64,
+    /// The revision of the state of the endpoints, bumped whenever it's altered between two updates, e.g. when an
+    /// endpoint is paused, so the queries of the state are tagged apart.
+    revision: AtomicU64,
 }
 
 impl Default for Service {
@@ -84,13 +338,63 @@ impl Service {
         client: Client,
         requests: Vec<Request>,
     ) -> Self {
+        let metrics = Arc::<Metrics>::default();
         Self {
-            // Convert each `Request` into a `hyper::Request` for the HTTP client.
-            requests: requests.into_iter().map(|request| request.into()).collect(),
+            requests,
             client,
-            store: Box::new(store),
-            strategy: Box::new(strategy),
+            store: Arc::new(Instrumented::new(Arc::new(store), metrics.clone())),
+            metrics,
+            strategy: RwLock::new(Arc::new(strategy)),
+            strategies: HashMap::new(),
+            middleware: Vec::new(),
+            transition_hooks: Vec::new(),
+            chaos: None,
+            request_id_header: None,
+            validators: DashMap::new(),
+            audits: DashMap::new(),
+            capture: None,
+            last_failures: DashMap::new(),
+            states: DashMap::new(),
+            quorum: None,
+            backoff: None,
+            failures: DashMap::new(),
+            error_window: Score::DEFAULT_ERROR_WINDOW,
+            recent_probes: DashMap::new(),
+            backed_off: DashMap::new(),
+            agent: None,
+            election: None,
+            sharding: None,
+            incidents: Arc::default(),
+            #[cfg(feature = "traceroute")]
+            traceroute: None,
+            tracing: None,
+            notifiers: Vec::new(),
+            exporters: Vec::new(),
+            reporter: None,
+            alertmanager: None,
+            history: None,
+            baselines: None,
+            running: OnceLock::new(),
+            interval: AtomicU64::new(0),
+            updating: AtomicBool::new(false),
+            persist_requests: false,
+            namespace: None,
+            requests_changed: AtomicBool::new(false),
+            grace_period: None,
+            inserted_at: DashMap::new(),
+            paused: DashSet::new(),
+            suppressed: DashMap::new(),
+            checked_at: DashMap::new(),
+            violations: DashMap::new(),
+            forecast: None,
+            risks: DashMap::new(),
+            relative_scoring: false,
+            cycle_deadline: None,
+            concurrency: None,
+            scoring_batch: DEFAULT_SCORING_BATCH,
+            last_cycle: RwLock::default(),
             updated_at: AtomicU64::new(0),
+            revision: AtomicU64::new(0),
         }
     }
 
@@ -103,36 +407,421 @@ impl Service {
     /// A result that, on success, contains an initialized `Service` instance.
     ///
     /// # Errors
-    /// Returns an error if the configuration is invalid or incomplete.
-    pub fn from_config(config: Config) -> Result<Self, Box<dyn std::error::Error>> {
+    /// Returns an error if the configuration is invalid or incomplete, e.g. its request timeout exceeds its interval.
+    pub fn from_config(config: Config) -> Result<Self, Box<dyn std::error::Error + Send + Sync>> {
+        // Reject a request timeout longer than the interval, before anything is built
+        config.validate()?;
+        // Consumers of the store are left without a write path
+        if config.read_only {
+            return Err("the configuration is read-only, its store can only be read through a `ReadOnlyStore`".into());
+        }
+        // Resolve the client configuration, whose request timeout defaults to the interval
+        let client_config = config.effective_client();
+        // Isolate the keys of the service in the store, if a namespace is configured
+        let store_config = config.effective_store();
+        // Elect the probing replica through the store, if configured
+        let election = config.election.map(|c| Election::new(election::from_config(&store_config), c));
+        // Split the endpoints among the replicas registered in the store, if configured
+        let sharding = config.sharding.map(|c| Sharding::new(shard::from_config(&store_config), c));
+        // Record the history of the endpoints in the store, if configured
+        let baselines =
+            config.history.as_ref().and_then(|c| c.baseline_window).map(|w| history::Baselines::new(w.get()));
+        let history = config.history.map(|c| history::from_config(&store_config, c));
         //  Create store from the configuration
-        let store = store::from_config(config.store);
-        // Create strategy from the configuration
+        let metrics = Arc::<Metrics>::default();
+        metrics.set_namespace(config.namespace.clone());
+        let store = Arc::new(Instrumented::new(store::from_config(store_config).into(), metrics.clone()));
+        // Create strategy from the configuration, rejecting the parameters its scores would be undefined with
+        config.strategy.validate()?;
+        for config in config.requests.iter().filter_map(|r| r.strategy.as_ref()) {
+            config.validate()?;
+        }
         let strategy = strategy::from_config(config.strategy);
-        // Initialize a new HTTP client; without timeout set from the configuration
-        // and `pool_idle_timeout` set to 60 seconds. That determines how long an idle
-        // connection is kept open before being closed.
+        // Initialize a new HTTP client from the resolved configuration
+        let mut client = Client::from_config(client_config);
 
-        let client = match config.client {
-            Some(config) => Client::new(config.request_timeout, config.pool_idle_timeout),
-            None => Client::new(config.interval, None),
-        };
+        // Reject duplicate endpoints, which would otherwise silently share one score, and invalid host overrides
+        for (i, request) in config.requests.iter().enumerate() {
+            if config.requests[..i].iter().any(|r| r.key() == request.key()) {
+                return Err(format!("duplicate request for `{}`", request.key()).into());
+            }
+            request.check_host()?;
+        }
+
+        // Restrict the endpoints to the ones allowed by the guard, if configured
+        if let Some(guard) = config.guard.map(Guard::new) {
+            for request in &config.requests {
+                guard.check_url(&request.url)?;
+            }
+            client = client.set_guard(guard);
+        }
+
+        let requests = config.requests;
+        // Score the requests naming their own strategy with it
+        let strategies = requests
+            .iter()
+            .filter_map(|r| Some((strategy::Key::Url(r.key()), strategy::from_config(r.strategy.clone()?).into())))
+            .collect();
+
+        // Simulate the endpoints listed in the chaos configuration, if any
+        let chaos = config.chaos.map(Chaos::from_config);
+
+        if let Some(quorum) = &config.quorum {
+            quorum.validate()?;
+        }
+        if let Some(backoff) = &config.backoff {
+            backoff.validate()?;
+        }
+        if config.error_window == Some(0) {
+            return Err("the error window must be positive".into());
+        }
+        if config.scoring_batch == Some(0) {
+            return Err("the scoring batch must be positive".into());
+        }
 
-        // Create `HyperRequest` instances from the configuration's `Request` instances
-        let requests = config.requests.into_iter().map(|request| request.into()).collect();
+        // Tag every probe with a unique ID, if a header name is configured
+        let request_id_header = config.request_id_header.as_deref().map(HeaderName::from_str).transpose()?;
 
-        Ok(Self { requests, client, store, strategy, updated_at: AtomicU64::new(0) })
+        Ok(Self {
+            requests,
+            client,
+            store,
+            metrics,
+            strategy: RwLock::new(strategy.into()),
+            strategies,
+            middleware: Vec::new(),
+            transition_hooks: Vec::new(),
+            chaos,
+            request_id_header,
+            validators: DashMap::new(),
+            audits: DashMap::new(),
+            capture: config.capture,
+            last_failures: DashMap::new(),
+            states: DashMap::new(),
+            quorum: config.quorum,
+            backoff: config.backoff,
+            failures: DashMap::new(),
+            error_window: config.error_window.unwrap_or(Score::DEFAULT_ERROR_WINDOW),
+            recent_probes: DashMap::new(),
+            backed_off: DashMap::new(),
+            agent: config.agent.map(Agent::new),
+            election,
+            sharding,
+            incidents: Arc::default(),
+            #[cfg(feature = "traceroute")]
+            traceroute: config.traceroute,
+            tracing: config.tracing,
+            notifiers: config.notifiers.into_iter().map(notify::from_config).collect(),
+            exporters: config.exporters.into_iter().map(export::from_config).collect(),
+            reporter: config.report.map(Reporter::new),
+            alertmanager: config.alertmanager.map(Alertmanager::new),
+            history,
+            baselines,
+            running: OnceLock::new(),
+            interval: AtomicU64::new(0),
+            updating: AtomicBool::new(false),
+            persist_requests: config.persist_requests,
+            namespace: config.namespace,
+            requests_changed: AtomicBool::new(config.persist_requests),
+            grace_period: config.grace_period.map(ConfigDuration::get),
+            inserted_at: DashMap::new(),
+            paused: DashSet::new(),
+            suppressed: DashMap::new(),
+            checked_at: DashMap::new(),
+            violations: DashMap::new(),
+            forecast: config.forecast,
+            risks: DashMap::new(),
+            relative_scoring: config.relative_scoring,
+            cycle_deadline: config.cycle_deadline.map(ConfigDuration::get),
+            concurrency: config.concurrency,
+            scoring_batch: config.scoring_batch.unwrap_or(DEFAULT_SCORING_BATCH),
+            last_cycle: RwLock::default(),
+            updated_at: AtomicU64::new(0),
+            revision: AtomicU64::new(0),
+        })
     }
 
     /// Retrieves the URL with the best score asynchronously.
     ///
+    /// Endpoints within their grace period or paused are skipped, unless none of the other endpoints is scored yet.
+    ///
     /// # Returns
-    /// A future resolving to an `Option<String>` containing the best URL or an error.
+    /// A future resolving to an `Option<String>` containing the best URL or an error. The URL of a named request is
+    /// returned rather than its name, so it can be routed to.
     ///
     /// # Errors
     /// Returns an error if the process of retrieving the best URL fails.
-    pub async fn best_url(&self) -> Result<Option<String>, Box<dyn std::error::Error>> {
-        self.store.best_url().await
+    pub async fn best_url(&self) -> Result<Option<String>, Box<dyn std::error::Error + Send + Sync>> {
+        if self.paused.is_empty() && !self.inserted_at.iter().any(|e| self.is_warming_up(e.key())) {
+            return Ok(self.store.best_url().await?.map(|key| self.url_of(key)));
+        }
+        let best = self.best_candidate().await?;
+        match best {
+            Some((key, _)) => Ok(Some(self.url_of(key))),
+            None => Ok(self.store.best_url().await?.map(|key| self.url_of(key))),
+        }
+    }
+
+    /// Maps the key an endpoint is scored under back to its URL, which differ once its request is named.
+    ///
+    /// # Returns
+    /// The URL of the request scored under the key, or the key itself if it isn't monitored.
+    fn url_of(&self, key: String) -> String {
+        match self.requests.iter().find(|r| r.key() == key) {
+            Some(request) => request.url.to_string(),
+            None => key,
+        }
+    }
+
+    /// Retrieves the best scoring endpoint among the candidates to be routed to, i.e. the monitored endpoints
+    /// out of their grace period and not paused.
+    ///
+    /// # Returns
+    /// The URL and score of the best candidate, or `None` if none was scored.
+    async fn best_candidate(&self) -> Result<Option<(String, f32)>, Box<dyn Error + Send + Sync>> {
+        let mut best: Option<(String, f32)> = None;
+        for url in self.urls().into_iter().filter(|url| self.is_candidate(url)) {
+            let score = self.store.get(&url).await?;
+            if let Some(score) = score.filter(|s| best.as_ref().is_none_or(|(_, best)| s.score > *best)) {
+                best = Some((url, score.score));
+            }
+        }
+        Ok(best)
+    }
+
+    /// Checks whether an endpoint is a candidate to be routed to, i.e. out of its grace period and not paused.
+    fn is_candidate(&self, url: &str) -> bool {
+        !self.is_warming_up(url) && !self.is_paused(url)
+    }
+
+    /// Ranks the monitored endpoints from the best score down, along with their state and latency,
+    /// e.g. to be listed in a dashboard.
+    ///
+    /// With relative scoring, the scores are relative to the best candidate to be routed to.
+    ///
+    /// # Returns
+    /// The monitored endpoints that were scored, ordered by their score.
+    ///
+    /// # Errors
+    /// Returns an error if the store can't be queried, or can't rank its scores.
+    pub async fn ranking(&self) -> Result<Vec<RankedEndpoint>, Box<dyn Error + Send + Sync>> {
+        let ranked = self.store.ranking().await?;
+        let urls = self.urls();
+        let ranked: Vec<_> = ranked.into_iter().filter(|(url, _)| urls.contains(url)).collect();
+        let best = ranked.iter().find(|(url, _)| self.is_candidate(url)).map(|(_, s)| s.score);
+        Ok(ranked
+            .into_iter()
+            .map(|(url, score)| RankedEndpoint {
+                state: self.states.get(&url).map(|s| *s),
+                latency: score.response_avg,
+                last_checked: self.checked_at.get(&url).map(|t| *t),
+                trend: self.metrics.trend(&url),
+                score: match best.filter(|_| self.relative_scoring) {
+                    Some(best) => score.relative_to(best),
+                    None => score,
+                },
+                url,
+            })
+            .collect())
+    }
+
+    /// Retrieves the URL with the best score, among the endpoints meeting a constraint,
+    /// e.g. `|_, score| score.reliability > 0.9`.
+    ///
+    /// Endpoints within their grace period or paused are skipped. With relative scoring, the predicate is given the
+    /// scores relative to the best candidate, e.g. `|_, score| score.score > 0.8` selects the endpoints within 80%
+    /// of the best one, however the network conditions shift all the latencies together.
+    ///
+    /// # Arguments
+    /// * `predicate`: The constraint on the URL and score of the endpoints.
+    ///
+    /// # Returns
+    /// The best URL meeting the constraint, or `None` if there's none. The URL of a named request is returned rather
+    /// than its name.
+    ///
+    /// # Errors
+    /// Returns an error if the store can't be queried, or can't filter its scores.
+    pub async fn best_url_where<F>(
+        &self,
+        predicate: F,
+    ) -> Result<Option<String>, Box<dyn std::error::Error + Send + Sync>>
+    where
+        F: Fn(&str, &Score) -> bool + Send + Sync,
+    {
+        let best = match self.relative_scoring {
+            true => self.best_candidate().await?.map(|(_, best)| best),
+            false => None,
+        };
+        let predicate = |key: &str, score: &Score| {
+            let url = self.url_of(key.to_string());
+            match best {
+                Some(best) => self.is_candidate(key) && predicate(&url, &score.relative_to(best)),
+                None => self.is_candidate(key) && predicate(&url, score),
+            }
+        };
+        Ok(self.store.best_url_where(&predicate).await?.map(|key| self.url_of(key)))
+    }
+
+    /// Pauses an endpoint, e.g. during a planned maintenance. It's no longer probed nor routed to, and its score is
+    /// frozen, marked as `paused`, until it's resumed.
+    ///
+    /// # Arguments
+    /// * `url`: The URL of the endpoint, matched after normalization.
+    ///
+    /// # Errors
+    /// Returns an error if the endpoint isn't monitored, or its score can't be marked as paused in the store.
+    pub async fn pause(&self, url: &str) -> Result<(), Box<dyn Error + Send + Sync>> {
+        let url = self.monitored(url)?;
+        self.paused.insert(url.clone());
+        self.mark_paused(url, true).await
+    }
+
+    /// Resumes a paused endpoint, which is probed and scored again from its frozen score.
+    ///
+    /// # Arguments
+    /// * `url`: The URL of the endpoint, matched after normalization.
+    ///
+    /// # Errors
+    /// Returns an error if the endpoint isn't monitored, or its score can't be unmarked in the store.
+    pub async fn resume(&self, url: &str) -> Result<(), Box<dyn Error + Send + Sync>> {
+        let url = self.monitored(url)?;
+        self.paused.remove(&url);
+        self.mark_paused(url, false).await
+    }
+
+    /// Suppresses the failures of an endpoint for a while, e.g. called by a CI/CD pipeline while it's deployed,
+    /// so the expected blips neither affect its score nor raise an incident. Its successful probes are still scored.
+    ///
+    /// # Arguments
+    /// * `url`: The URL of the endpoint, matched after normalization.
+    /// * `duration`: How long the failures are suppressed, replacing any ongoing suppression.
+    ///
+    /// # Errors
+    /// Returns an error if the endpoint isn't monitored, or the duration is too long to be represented.
+    pub fn suppress(&self, url: &str, duration: Duration) -> Result<(), Box<dyn Error + Send + Sync>> {
+        let url = self.monitored(url)?;
+        let until =
+            Instant::now().checked_add(duration).ok_or_else(|| format!("duration out of range: {duration:?}"))?;
+        self.suppressed.insert(url, until);
+        self.revision.fetch_add(1, SeqCst);
+        Ok(())
+    }
+
+    /// Checks whether the failures of an endpoint are suppressed.
+    ///
+    /// # Arguments
+    /// * `url`: The normalized URL of the endpoint.
+    pub fn is_suppressed(&self, url: &str) -> bool {
+        self.suppressed.get(url).is_some_and(|until| Instant::now() < *until)
+    }
+
+    /// Checks whether an endpoint is paused.
+    ///
+    /// # Arguments
+    /// * `url`: The normalized URL of the endpoint.
+    pub fn is_paused(&self, url: &str) -> bool {
+        self.paused.contains(url)
+    }
+
+    /// Resolves the key of a monitored endpoint.
+    ///
+    /// # Errors
+    /// Returns an error if the URL is invalid, or the endpoint isn't monitored.
+    fn monitored(&self, url: &str) -> Result<String, Box<dyn Error + Send + Sync>> {
+        let key = self.key(url)?;
+        match self.requests.iter().any(|r| r.key() == key) {
+            true => Ok(key),
+            false => Err(format!("unknown endpoint `{key}`").into()),
+        }
+    }
+
+    /// Resolves the key an endpoint is scored under, from the name of its request or its URL, matched after
+    /// normalization. A URL shared by several named requests doesn't resolve to any of them, only their names do.
+    ///
+    /// # Errors
+    /// Returns an error if it's neither the name of a request nor a valid URL.
+    fn key(&self, url: &str) -> Result<String, Box<dyn Error + Send + Sync>> {
+        if self.requests.iter().any(|r| r.name.as_deref() == Some(url)) {
+            return Ok(url.to_string());
+        }
+        let url = Request::normalize(Uri::from_str(url)?);
+        let mut requests = self.requests.iter().filter(|r| r.url == url);
+        match (requests.next(), requests.next()) {
+            (Some(request), None) => Ok(request.key()),
+            _ => Ok(url.to_string()),
+        }
+    }
+
+    /// Marks the stored score of an endpoint as paused or not, if it was scored.
+    async fn mark_paused(&self, url: String, paused: bool) -> Result<(), Box<dyn Error + Send + Sync>> {
+        let score = self.store.get(&url).await;
+        let marked = match score {
+            Ok(Some(score)) => self.store.set(url, Score { paused, ..score }).await,
+            Ok(None) => Ok(()),
+            Err(e) => Err(e),
+        };
+        // The endpoint is paused or resumed whether its score could be marked or not
+        self.revision.fetch_add(1, SeqCst);
+        marked
+    }
+
+    /// Retrieves the revision of the state of the endpoints, bumped whenever it's altered between two updates, i.e.
+    /// when an endpoint is paused, resumed or suppressed, or the scores are flushed.
+    pub(crate) fn revision(&self) -> u64 {
+        self.revision.load(SeqCst)
+    }
+
+    /// Checks whether an endpoint inserted at runtime is within its grace period, during which it's probed and
+    /// scored, but excluded from `best_url` and alerting.
+    ///
+    /// # Arguments
+    /// * `url`: The normalized URL of the endpoint.
+    pub fn is_warming_up(&self, url: &str) -> bool {
+        let inserted_at = self.inserted_at.get(url).map(|i| *i);
+        self.grace_period.zip(inserted_at).is_some_and(|(grace_period, at)| at.elapsed() < grace_period)
+    }
+
+    /// Retrieves the interval of the update loop, zero until the service is running.
+    pub fn interval(&self) -> Duration {
+        Duration::from_nanos(self.interval.load(SeqCst))
+    }
+
+    /// Changes the interval of the running update loop, e.g. to probe more often during an incident.
+    /// The update in progress, if any, is followed by the next one on the new interval.
+    ///
+    /// # Arguments
+    /// * `interval`: Duration between each scoring update.
+    ///
+    /// # Errors
+    /// Returns an error if the interval is too long to be represented in nanoseconds, about 584 years.
+    pub fn set_interval(&self, interval: Duration) -> Result<(), Box<dyn Error + Send + Sync>> {
+        let nanos = u64::try_from(interval.as_nanos()).map_err(|_| format!("interval out of range: {interval:?}"))?;
+        self.interval.store(nanos, SeqCst);
+        Ok(())
+    }
+
+    /// Swaps the strategy of the service at runtime, e.g. to tune it without restarting the service.
+    /// The scores are calculated from the current ones, unless the store is flushed. The strategies of the URLs
+    /// and tags keep taking precedence over it.
+    ///
+    /// # Arguments
+    /// * `config`: The configuration of the new strategy.
+    pub fn set_strategy(&self, config: strategy::Config) {
+        *self.strategy.write().expect("failed to write the strategy") = strategy::from_config(config).into();
+    }
+
+    /// Flushes the scores of every endpoint from the store, along with their error window, so they're scored from
+    /// scratch by the next update. The incidents and states of the endpoints are kept.
+    ///
+    /// # Errors
+    /// Returns an error if the store can't be flushed.
+    pub async fn flush(&self) -> Result<(), Box<dyn Error + Send + Sync>> {
+        // The store may be partially cleared on failure, so the state is revised either way
+        let cleared = self.store.clear().await;
+        self.revision.fetch_add(1, SeqCst);
+        cleared?;
+        self.recent_probes.clear();
+        Ok(())
     }
 
     /// Spawns a background task to periodically update scores of endpoints.
@@ -141,123 +830,1843 @@ impl Service {
     /// * `interval`: Duration between each scoring update.
     ///
     /// This function runs indefinitely, updating endpoint scores based on the specified interval.
-    pub async fn run(self: std::sync::Arc<Self>, interval: Duration) {
+    /// The updates start on a fixed schedule: a cycle outlasting the interval doesn't stretch it, instead the ticks
+    /// it missed are skipped and counted in the metrics of the service.
+    ///
+    /// When supervised by systemd, the service manager is notified once the first update completes (`READY=1`),
+    /// and the watchdog is pinged after every update (`WATCHDOG=1`), as well as while waiting for the next one.
+    /// A hung update stops the pings, so `WatchdogSec` should exceed the time an update takes.
+    ///
+    /// # Returns
+    /// The handle of the background task, which stops updating the scores once aborted.
+    ///
+    /// # Panics
+    /// Panics if the interval is zero, which would update the scores in a busy loop.
+    pub async fn run(self: std::sync::Arc<Self>, interval: Duration) -> tokio::task::JoinHandle<()> {
+        assert!(!interval.is_zero(), "the interval must be positive");
+        let watchdog = systemd::watchdog();
+        // An interval too long to be represented is as good as never updating again
+        self.interval.store(u64::try_from(interval.as_nanos()).unwrap_or(u64::MAX), SeqCst);
+        let _ = self.running.set(SystemTime::now());
         tokio::spawn(async move {
+            let mut state = "READY=1\nWATCHDOG=1";
+            let mut next = tokio::time::Instant::now();
             loop {
-                // Update scores for all services
-                self.update().await.expect("failed to update scores");
-                // Wait for the specified interval before the next update
-                tokio::time::sleep(interval).await;
+                // Update scores for all services. A failed update, e.g. while the store is unreachable, is counted in
+                // the metrics and eventually reported by the health, and the next one is attempted on schedule
+                let _ = self.update().await;
+                // Notifications are best-effort, the service runs whether it's supervised or not
+                let _ = systemd::notify(state);
+                state = "WATCHDOG=1";
+                // Schedule the next update on the interval, skipping the ticks missed by a cycle longer than it.
+                // The interval is read on every update, so changing it takes effect from the next one
+                let interval = self.interval();
+                next += interval;
+                while !interval.is_zero() && next < tokio::time::Instant::now() {
+                    next += interval;
+                    self.metrics.record_skipped_cycle();
+                }
+                // Wait for the next update
+                match watchdog {
+                    // Keep pinging at half the watchdog timeout while waiting
+                    Some(timeout) => {
+                        while tokio::time::Instant::now() + timeout / 2 < next {
+                            tokio::time::sleep(timeout / 2).await;
+                            let _ = systemd::notify(state);
+                        }
+                        tokio::time::sleep_until(next).await;
+                    }
+                    None => tokio::time::sleep_until(next).await,
+                }
             }
-        });
+        })
+    }
+
+    /// Checks the health of the service itself: whether its store is reachable, and its update loop isn't stuck.
+    ///
+    /// # Returns
+    /// The health of the service.
+    pub async fn self_health(&self) -> Health {
+        let store = self.store.health().await;
+        let last_update = match self.updated_at.load(SeqCst) {
+            0 => None,
+            secs => Some(UNIX_EPOCH + Duration::from_secs(secs)),
+        };
+        let running = self.running.get();
+        let since_last_update =
+            last_update.or(running.copied()).map(|since| SystemTime::now().duration_since(since).unwrap_or_default());
+        // Updates are timestamped to the second, which is tolerated on top of the intervals
+        let stale_after = running.map(|_| self.interval() * 3 + Duration::from_secs(1));
+
+        Health {
+            healthy: store.healthy
+                && stale_after.is_none_or(|stale_after| since_last_update.is_none_or(|since| since <= stale_after)),
+            last_update,
+            since_last_update,
+            stale_after,
+            store_error: store.error,
+            store_latency: store.latency,
+            queue_depth: self.agent.as_ref().map_or(0, Agent::pending),
+        }
+    }
+
+    /// Retrieves the namespace of the service, if any.
+    pub fn namespace(&self) -> Option<&str> {
+        self.namespace.as_deref()
     }
 
     /// Retrieves a list of all monitored URLs.
     ///
     /// # Returns
-    /// A vector of strings, each representing a monitored URL.
+    /// A vector of strings, each representing a monitored URL, or the name of its request if it has one.
     pub fn urls(&self) -> Vec<String> {
-        self.requests.iter().map(|r| r.uri().to_string()).collect()
+        self.requests.iter().map(Request::key).collect()
     }
 
-    /// Adds a new request to the list of monitored endpoints.
+    /// Scores a measurement gathered elsewhere, e.g. by the real-user monitoring of a client or another monitoring
+    /// system, through the same middlewares, strategy and store as the probes of this service.
+    /// The samples of paused endpoints are discarded, so their score remains frozen.
     ///
     /// # Arguments
-    /// * `request`: The request to be added for monitoring.
-    pub fn insert_request(&mut self, request: Request) {
-        self.requests.push(request.into());
+    /// * `url`: The URL of the endpoint, normalized the same way as the URL of a `Request`.
+    /// * `sample`: The measurement of the endpoint.
+    ///
+    /// # Errors
+    /// Returns an error if the URL cannot be parsed, or its endpoint isn't monitored by this service.
+    pub async fn ingest(&self, url: &str, sample: Sample) -> Result<(), Box<dyn Error + Send + Sync>> {
+        let url = self.key(url)?;
+        self.ingest_outcome(sample.into_outcome(url)).await
     }
 
-    /// Removes a request from the list of monitored endpoints.
+    /// Scores the outcome of a probe executed elsewhere, e.g. by an agent, as if it was probed by this service.
+    /// The outcomes of paused endpoints are discarded, so their score remains frozen.
     ///
     /// # Arguments
-    /// * `url`: The URL of the request to be removed.
-    ///
-    /// # Returns
-    /// A result indicating the success of the operation.
+    /// * `outcome`: The outcome of the probe.
     ///
     /// # Errors
-    /// Returns an error if the URL is invalid or cannot be parsed.
-    pub fn remove_request(&mut self, url: &str) -> Result<(), Box<dyn Error>> {
-        let url = Uri::from_str(url)?.to_string();
-        self.requests.retain(|r| r.uri().to_string() != url);
+    /// Returns an error if the endpoint of the outcome isn't monitored by this service, or its score couldn't be
+    /// written to the store.
+    pub async fn ingest_outcome(&self, outcome: ProbeOutcome) -> Result<(), Box<dyn Error + Send + Sync>> {
+        if !self.requests.iter().any(|r| r.key() == outcome.url) {
+            return Err(format!("unknown endpoint `{}`", outcome.url).into());
+        }
+        if self.is_paused(&outcome.url) {
+            return Ok(());
+        }
+        if let Some(outcome) = self.evaluate(None, outcome).await {
+            // The history is best-effort, as within an update cycle
+            let sample = Evaluated { probe: None, outcome, span: tracing::Span::none() };
+            self.score_batch(vec![sample], &mut vec![]).await?;
+        }
         Ok(())
     }
 
-    /// Sets a new store for storing and retrieving scores.
+    /// Returns `true` if this replica probes the endpoints, i.e. leader election is disabled or it was elected.
+    pub fn is_leader(&self) -> bool {
+        self.election.as_ref().is_none_or(Election::is_leader)
+    }
+
+    /// Returns the store of the service, shared with the rest of the application, e.g. to read the scores from a
+    /// web handler.
+    pub fn store(&self) -> Arc<dyn Store + Sync + Send + 'static> {
+        self.store.clone()
+    }
+
+    /// Returns the metrics of the service, e.g. the latency and errors of its store operations.
+    pub fn metrics(&self) -> &Metrics {
+        &self.metrics
+    }
+
+    /// Retrieves the score of an endpoint, as stored.
     ///
     /// # Arguments
-    /// * `store`: The new store to be used.
+    /// * `url`: The URL of the endpoint.
     ///
     /// # Returns
-    /// The updated `Service` instance with the new store.
-    pub fn use_store<T: Store + Sync + Send + 'static>(mut self, store: T) -> Self {
-        self.store = Box::new(store);
-        self
+    /// The score of the endpoint, or `None` if it hasn't been scored yet.
+    ///
+    /// # Errors
+    /// Returns an error if the URL is invalid, or the store can't be queried.
+    pub async fn score(&self, url: &str) -> Result<Option<Score>, Box<dyn Error + Send + Sync>> {
+        let url = self.key(url)?;
+        self.store.get(&url).await
     }
 
-    /// Sets a new strategy for score calculation.
+    /// Retrieves the number of probes of an endpoint by class of status code, e.g. to spot an intermittent rate of
+    /// server errors that the score of the endpoint smooths out.
     ///
     /// # Arguments
-    /// * `strategy`: The new strategy to be used for score calculation.
+    /// * `url`: The URL of the endpoint, matched after normalization.
     ///
     /// # Returns
-    /// The updated `Service` instance with the new strategy.
-    pub fn use_strategy<T: Strategy + Sync + Send + 'static>(mut self, strategy: T) -> Self {
-        self.strategy = Box::new(strategy);
-        self
+    /// The counts of the probes scored since the service was created, or `None` if the endpoint wasn't probed.
+    pub fn statuses(&self, url: &str) -> Option<metrics::StatusCounts> {
+        let url = self.key(url).ok()?;
+        self.metrics.statuses(&url)
     }
 
-    /// Updates the scores for all tracked services.
+    /// Retrieves the state of an endpoint, according to its latest probe.
     ///
-    /// This function performs HTTP requests concurrently for each service, updating their
-    /// scores based on the response time and HTTP status code. It leverages the provided
-    /// strategy for score calculation and updates the store with new scores.
-    pub async fn update(&self) -> Result<(), Box<dyn Error>> {
-        // Concurrently send requests to all endpoints and handle their responses
-        join_all(self.requests.iter().map(|r| self.process_request(r))).await;
+    /// # Returns
+    /// The state of the endpoint, or `None` if it's unknown or hasn't been probed yet.
+    pub fn state(&self, url: &str) -> Option<State> {
+        let url = self.key(url).ok()?;
+        self.states.get(&url).map(|s| *s)
+    }
 
-        // Update the timestamp of the last update
-        let unix = SystemTime::now().duration_since(UNIX_EPOCH)?;
-        self.updated_at.store(unix.as_secs(), SeqCst);
-        Ok(())
+    /// Lists the incidents recorded in the store, along with the ongoing ones, ordered by their start.
+    pub async fn incidents(&self) -> Result<Vec<Incident>, Box<dyn Error + Send + Sync>> {
+        let mut incidents = self.store.incidents().await?;
+        // Prefer the ongoing incidents kept in memory, which may be more recent than their recorded copy
+        for ongoing in self.incidents.iter() {
+            incidents.retain(|i| i.id != ongoing.id);
+            incidents.push(ongoing.value().clone());
+        }
+        incidents.sort_by_key(|i| i.started_at);
+        Ok(incidents)
+    }
+
+    /// Summarizes the probes scored since the start of the current report period.
+    ///
+    /// # Returns
+    /// The report so far, or `None` if reports aren't enabled.
+    ///
+    /// # Errors
+    /// Returns an error if the incidents can't be retrieved from the store.
+    pub async fn report(&self) -> Result<Option<report::Report>, Box<dyn Error + Send + Sync>> {
+        match &self.reporter {
+            Some(reporter) => Ok(Some(reporter.report(&self.incidents().await?))),
+            None => Ok(None),
+        }
     }
 
-    /// Handles a single request, updating the score for its corresponding service.
+    /// Computes the latency and availability of an endpoint over a window until now, from its history.
+    ///
+    /// This isn't the summary report of the current period, see `Service::report` for that.
     ///
     /// # Arguments
-    /// * `request` - A reference to the hyper::Request object to be sent.
+    /// * `url`: The URL of the endpoint.
+    /// * `window`: The period the report covers, e.g. the last 7 days.
+    ///
+    /// # Returns
+    /// The report of the endpoint, or `None` if the history isn't enabled.
     ///
-    /// This function sends the HTTP request, measures the response time, calculates the
-    /// new score based on the strategy, and updates the score in store.
-    async fn process_request(&self, request: &hyper::Request<Full<Bytes>>) {
-        let url = request.uri().to_string();
+    /// # Errors
+    /// Returns an error if the samples can't be retrieved from the history.
+    pub async fn latency_report(
+        &self,
+        url: &str,
+        window: Duration,
+    ) -> Result<Option<report::LatencyReport>, Box<dyn Error + Send + Sync>> {
+        if self.history.is_none() {
+            return Ok(None);
+        }
+        let to = SystemTime::now();
+        let from = to.checked_sub(window).unwrap_or(UNIX_EPOCH);
+        let samples = self.history(url, from, to).await?;
+        Ok(Some(report::LatencyReport::new(self.key(url)?, from, to, &samples)))
+    }
 
-        let start = tokio::time::Instant::now();
-        let response = self.client.request(request.clone()).await;
-        let elapsed = start.elapsed();
+    /// Compares endpoints side by side over a window until now, from their history: their latency percentiles,
+    /// availability and score trajectory, along with the one with the best mean score.
+    ///
+    /// # Arguments
+    /// * `urls`: The URLs of the endpoints, e.g. the same asset served by several CDNs.
+    /// * `window`: The period the comparison covers, e.g. the last 7 days.
+    ///
+    /// # Returns
+    /// The comparison of the endpoints, or `None` if the history isn't enabled.
+    ///
+    /// # Errors
+    /// Returns an error if a URL is invalid, or the samples can't be retrieved from the history.
+    pub async fn compare<U: AsRef<str>>(
+        &self,
+        urls: &[U],
+        window: Duration,
+    ) -> Result<Option<report::Comparison>, Box<dyn Error + Send + Sync>> {
+        if self.history.is_none() {
+            return Ok(None);
+        }
+        let to = SystemTime::now();
+        let from = to.checked_sub(window).unwrap_or(UNIX_EPOCH);
+        let queries = urls.iter().map(|url| async move {
+            let samples = self.history(url.as_ref(), from, to).await?;
+            Ok::<_, Box<dyn Error + Send + Sync>>((self.key(url.as_ref())?, samples))
+        });
+        let endpoints = join_all(queries).await.into_iter().collect::<Result<Vec<_>, _>>()?;
+        Ok(Some(report::Comparison::new(from, to, endpoints)))
+    }
+
+    /// Retrieves the samples of an endpoint recorded within a time range.
+    ///
+    /// # Arguments
+    /// * `url`: The URL of the endpoint.
+    /// * `from`: The start of the range, inclusive.
+    /// * `to`: The end of the range, inclusive.
+    ///
+    /// # Returns
+    /// The samples ordered by their date, or none if the history isn't enabled.
+    ///
+    /// # Errors
+    /// Returns an error if the samples can't be retrieved from the history.
+    pub async fn history(
+        &self,
+        url: &str,
+        from: SystemTime,
+        to: SystemTime,
+    ) -> Result<Vec<history::Sample>, Box<dyn Error + Send + Sync>> {
+        match &self.history {
+            Some(history) => history.query(&self.key(url)?, from, to).await,
+            None => Ok(Vec::new()),
+        }
+    }
 
-        let status = response.map(|r| r.status().as_u16()).unwrap_or(0);
+    /// Copies the scores, incidents and persisted requests of a store to another one, e.g. to move from the
+    /// in-memory store to Redis without losing the reliability accumulated by the endpoints.
+    ///
+    /// The data of the target store is overwritten, key by key, and the source store is left untouched.
+    ///
+    /// # Arguments
+    /// * `from`: The store the data is copied from.
+    /// * `to`: The store the data is copied to.
+    ///
+    /// # Returns
+    /// The number of scores, incidents and requests copied.
+    ///
+    /// # Errors
+    /// Returns an error if the source store can't rank its scores, or if the target store fails to record them.
+    pub async fn migrate(
+        from: &(dyn Store + Sync + Send),
+        to: &(dyn Store + Sync + Send),
+    ) -> Result<store::Migration, Box<dyn Error + Send + Sync>> {
+        let scores = from.ranking().await?;
+        let mut migration = store::Migration { scores: scores.len(), ..Default::default() };
+        to.set_many(scores).await?;
 
-        // Calculate and update score based on response
-        self.update_score(url, elapsed, status).await;
+        // Stores without an incident log nor persisted requests have none to copy
+        for incident in from.incidents().await? {
+            to.set_incident(incident).await?;
+            migration.incidents += 1;
+        }
+        let requests = from.requests().await?;
+        if !requests.is_empty() {
+            to.set_requests(&requests).await?;
+            migration.requests = requests.len();
+        }
+        Ok(migration)
     }
 
-    /// Calculates and updates the score for a given URL.
+    /// Copies the whole history of the given endpoints to another history, in the order it was recorded.
     ///
     /// # Arguments
-    /// * `url` - The URL of the service.
-    /// * `elapsed` - The elapsed time of the request.
-    /// * `status` - The HTTP status code received in the response.
+    /// * `from`: The history the samples are copied from.
+    /// * `to`: The history the samples are copied to.
+    /// * `urls`: The URLs of the endpoints, e.g. the ones scored in the source store.
     ///
-    /// This function calculates the new score based on the elapsed time and status code,
-    /// then updates it in the store.
-    async fn update_score(&self, url: String, elapsed: Duration, status: u16) {
-        let score = match self.store.get(&url).await {
-            Ok(Some(score)) => self.strategy.calculate(score, elapsed, status),
-            _ => self.strategy.calculate(Score::default(), elapsed, status),
-        };
+    /// # Returns
+    /// The number of samples copied.
+    ///
+    /// # Errors
+    /// Returns an error if the samples can't be retrieved or recorded.
+    pub async fn migrate_history(
+        from: &(dyn History + Sync + Send),
+        to: &(dyn History + Sync + Send),
+        urls: &[String],
+    ) -> Result<usize, Box<dyn Error + Send + Sync>> {
+        let mut copied = 0;
+        for url in urls {
+            for sample in from.query(url, UNIX_EPOCH, SystemTime::now()).await? {
+                to.record(url, sample).await?;
+                copied += 1;
+            }
+        }
+        Ok(copied)
+    }
+
+    /// Attaches an operator annotation to an incident, whether ongoing or resolved.
+    ///
+    /// # Arguments
+    /// * `id`: The ID of the incident.
+    /// * `annotation`: The note to attach.
+    ///
+    /// # Returns
+    /// The annotated incident.
+    ///
+    /// # Errors
+    /// Returns an error if the incident can't be found, or the store fails to record it.
+    pub async fn annotate(&self, id: &str, annotation: Annotation) -> Result<Incident, Box<dyn Error + Send + Sync>> {
+        let ongoing = self.incidents.iter_mut().find(|i| i.id == id).map(|mut incident| {
+            incident.annotations.push(annotation.clone());
+            incident.clone()
+        });
+        let incident = match ongoing {
+            Some(incident) => incident,
+            None => {
+                let mut incident = self.store.get_incident(id).await?.ok_or(format!("unknown incident `{id}`"))?;
+                incident.annotations.push(annotation);
+                incident
+            }
+        };
+        self.store.set_incident(incident.clone()).await?;
+        Ok(incident)
+    }
+
+    /// Retrieves the security-header audit of the last response received from each audited endpoint.
+    ///
+    /// # Returns
+    /// A vector of URLs along with their audit report.
+    pub fn audit_reports(&self) -> Vec<(String, audit::Report)> {
+        self.audits.iter().map(|r| (r.key().clone(), r.value().clone())).collect()
+    }
+
+    /// Retrieves the latest failed probe of an endpoint, along with the headers and the beginning of the body of
+    /// its response, e.g. the error page behind a `500` status.
+    ///
+    /// # Arguments
+    /// * `url`: The URL of the endpoint, matched after normalization.
+    ///
+    /// # Returns
+    /// The latest failure, or `None` if the endpoint didn't fail since it's monitored, or failures aren't captured.
+    pub fn last_failure(&self, url: &str) -> Option<capture::Failure> {
+        let url = self.key(url).ok()?;
+        self.last_failures.get(&url).map(|f| f.clone())
+    }
+
+    /// Adds a new request to the list of monitored endpoints.
+    ///
+    /// # Arguments
+    /// * `request`: The request to be added for monitoring.
+    ///
+    /// # Returns
+    /// A result indicating the success of the operation.
+    ///
+    /// # Errors
+    /// Returns an error if the URL is already monitored or its strategy is invalid,
+    /// or a `guard::Violation` if it's not allowed by the guard of the service.
+    pub fn insert_request(&mut self, request: Request) -> Result<(), Box<dyn Error + Send + Sync>> {
+        if self.requests.iter().any(|r| r.key() == request.key()) {
+            return Err(format!("duplicate request for `{}`", request.key()).into());
+        }
+        request.check_host()?;
+        if let Some(guard) = self.client.guard() {
+            guard.check_url(&request.url)?;
+        }
+        if let Some(config) = request.strategy.clone() {
+            config.validate()?;
+            self.strategies.insert(strategy::Key::Url(request.key()), strategy::from_config(config).into());
+        }
+        if self.grace_period.is_some() {
+            self.inserted_at.insert(request.key(), Instant::now());
+        }
+        self.requests.push(request);
+        self.requests_changed.store(true, SeqCst);
+        Ok(())
+    }
+
+    /// Removes a request from the list of monitored endpoints.
+    ///
+    /// # Arguments
+    /// * `url`: The URL of the request to be removed, matched after normalization, or its name.
+    ///
+    /// # Returns
+    /// A result indicating the success of the operation.
+    ///
+    /// # Errors
+    /// Returns an error if the URL is invalid or cannot be parsed.
+    pub fn remove_request(&mut self, url: &str) -> Result<(), Box<dyn Error + Send + Sync>> {
+        let url = self.key(url)?;
+        self.requests.retain(|r| r.key() != url);
+        self.validators.remove(&url);
+        self.audits.remove(&url);
+        self.last_failures.remove(&url);
+        self.metrics.forget(&url);
+        self.states.remove(&url);
+        self.failures.remove(&url);
+        self.recent_probes.remove(&url);
+        self.backed_off.remove(&url);
+        self.incidents.remove(&url);
+        self.strategies.remove(&strategy::Key::Url(url.clone()));
+        self.inserted_at.remove(&url);
+        self.paused.remove(&url);
+        self.suppressed.remove(&url);
+        self.checked_at.remove(&url);
+        self.violations.remove(&url);
+        self.risks.remove(&url);
+        self.requests_changed.store(true, SeqCst);
+        Ok(())
+    }
+
+    /// Adds the requests persisted in the store to the monitored endpoints, e.g. the ones inserted at runtime
+    /// before a restart. Meant to be called on startup, before the service runs.
+    ///
+    /// # Returns
+    /// The number of requests that were restored, skipping the URLs already monitored.
+    ///
+    /// # Errors
+    /// Returns an error if the requests can't be retrieved from the store,
+    /// or a `guard::Violation` if one of them is not allowed by the guard of the service.
+    pub async fn restore_requests(&mut self) -> Result<usize, Box<dyn Error + Send + Sync>> {
+        let persisted = self.store.requests().await?;
+        let mut restored = 0;
+        for request in persisted {
+            if !self.requests.iter().any(|r| r.key() == request.key()) {
+                // Restored endpoints were already monitored before the restart, they aren't granted a grace period
+                let url = request.key();
+                self.insert_request(request)?;
+                self.inserted_at.remove(&url);
+                restored += 1;
+            }
+        }
+        Ok(restored)
+    }
+
+    /// Sets a new store for storing and retrieving scores.
+    ///
+    /// # Arguments
+    /// * `store`: The new store to be used, or an `Arc` of it, to share it with the rest of the application.
+    ///
+    /// # Returns
+    /// The updated `Service` instance with the new store.
+    pub fn use_store<T: Store + Sync + Send + 'static>(mut self, store: T) -> Self {
+        self.store = Arc::new(Instrumented::new(Arc::new(store), self.metrics.clone()));
+        self
+    }
+
+    /// Sets a new strategy for score calculation.
+    ///
+    /// # Arguments
+    /// * `strategy`: The new strategy to be used for score calculation.
+    ///
+    /// # Returns
+    /// The updated `Service` instance with the new strategy.
+    pub fn use_strategy<T: Strategy + Sync + Send + 'static>(mut self, strategy: T) -> Self {
+        self.strategy = RwLock::new(Arc::new(strategy));
+        self
+    }
+
+    /// Sets the strategy scoring the endpoints of a URL or tag, instead of the one of the service.
+    ///
+    /// # Arguments
+    /// * `key`: The URL or tag of the endpoints, taking precedence over the tags if it's a URL.
+    /// * `strategy`: The strategy to be used for their score calculation.
+    ///
+    /// # Returns
+    /// The updated `Service` instance with the new strategy.
+    pub fn use_strategy_for<T: Strategy + Sync + Send + 'static>(mut self, key: strategy::Key, strategy: T) -> Self {
+        self.strategies.insert(key, Arc::new(strategy));
+        self
+    }
+
+    /// Appends a middleware to the chain executed around every probe.
+    ///
+    /// # Arguments
+    /// * `middleware`: The middleware to be added.
+    ///
+    /// # Returns
+    /// The updated `Service` instance with the new middleware.
+    pub fn use_middleware<T: Middleware + Sync + Send + 'static>(mut self, middleware: T) -> Self {
+        self.middleware.push(Box::new(middleware));
+        self
+    }
+
+    /// Registers a callback invoked with the outcome of every probe, before it's scored.
+    ///
+    /// # Arguments
+    /// * `callback`: The function to be called with each `ProbeOutcome`.
+    ///
+    /// # Returns
+    /// The updated `Service` instance with the new callback.
+    pub fn on_result<F: Fn(&ProbeOutcome) + Sync + Send + 'static>(self, callback: F) -> Self {
+        self.use_middleware(middleware::OnResult(callback, |_| true))
+    }
+
+    /// Registers a callback invoked with the outcome of every successful probe, before it's scored.
+    ///
+    /// # Arguments
+    /// * `callback`: The function to be called with each successful `ProbeOutcome`.
+    ///
+    /// # Returns
+    /// The updated `Service` instance with the new callback.
+    pub fn on_success<F: Fn(&ProbeOutcome) + Sync + Send + 'static>(self, callback: F) -> Self {
+        self.use_middleware(middleware::OnResult(callback, ProbeOutcome::is_success))
+    }
+
+    /// Registers a callback invoked with the outcome of every failed probe, before it's scored.
+    ///
+    /// # Arguments
+    /// * `callback`: The function to be called with each failed `ProbeOutcome`.
+    ///
+    /// # Returns
+    /// The updated `Service` instance with the new callback.
+    pub fn on_failure<F: Fn(&ProbeOutcome) + Sync + Send + 'static>(self, callback: F) -> Self {
+        self.use_middleware(middleware::OnResult(callback, |outcome| !outcome.is_success()))
+    }
+
+    /// Registers a callback invoked whenever the state of an endpoint changes, including when its first state
+    /// is known, e.g. to page someone without implementing a `Notifier`.
+    ///
+    /// # Arguments
+    /// * `callback`: The function to be called with the URL of the endpoint, its previous state and its new one.
+    ///
+    /// # Returns
+    /// The updated `Service` instance with the new callback.
+    pub fn on_transition<F: Fn(&str, Option<State>, State) + Sync + Send + 'static>(mut self, callback: F) -> Self {
+        self.transition_hooks.push(Box::new(callback));
+        self
+    }
+
+    /// Restricts the monitored endpoints to the ones allowed by the guard.
+    ///
+    /// The guard applies to requests inserted afterwards, and to every address the client connects to.
+    ///
+    /// # Arguments
+    /// * `guard`: The guard validating the endpoints.
+    ///
+    /// # Returns
+    /// The updated `Service` instance with the guard.
+    pub fn use_guard(mut self, guard: Guard) -> Self {
+        self.client = self.client.set_guard(guard);
+        self
+    }
+
+    /// Enables the fault-injection mode, simulating the endpoints of the given `Chaos` instance.
+    ///
+    /// # Arguments
+    /// * `chaos`: The simulated endpoints.
+    ///
+    /// # Returns
+    /// The updated `Service` instance with the simulated endpoints.
+    pub fn use_chaos(mut self, chaos: Chaos) -> Self {
+        self.chaos = Some(chaos);
+        self
+    }
+
+    /// Tags every probe with a unique request ID, sent in the given header and recorded in its `ProbeOutcome`,
+    /// so slow or failed probes can be correlated with the logs of the endpoint.
+    ///
+    /// # Arguments
+    /// * `header`: The name of the header carrying the ID, e.g. `x-request-id`.
+    ///
+    /// # Returns
+    /// The updated `Service` instance with the request ID header.
+    pub fn use_request_id(mut self, header: HeaderName) -> Self {
+        self.request_id_header = Some(header);
+        self
+    }
+
+    /// Requires a quorum of failed probes before an endpoint is considered down.
+    ///
+    /// # Arguments
+    /// * `quorum`: The number of failed probes required, and how they're counted.
+    ///
+    /// # Returns
+    /// The updated `Service` instance with the quorum applied.
+    pub fn use_quorum(mut self, quorum: Quorum) -> Self {
+        self.quorum = Some(quorum);
+        self
+    }
+
+    /// Backs off the probing of the endpoints that stay down, restoring it once they recover.
+    ///
+    /// # Arguments
+    /// * `backoff`: The number of cycles down before backing off, and the maximum number of cycles between probes.
+    ///
+    /// # Returns
+    /// The updated `Service` instance with the backoff applied.
+    pub fn use_backoff(mut self, backoff: Backoff) -> Self {
+        self.backoff = Some(backoff);
+        self
+    }
+
+    /// Enables the agent mode, pushing the outcome of every probe to a coordinator after each update.
+    ///
+    /// # Arguments
+    /// * `config`: The URL of the coordinator and the credentials of the agent.
+    ///
+    /// # Returns
+    /// The updated `Service` instance in agent mode.
+    pub fn use_agent(mut self, config: agent::Config) -> Self {
+        self.agent = Some(Agent::new(config));
+        self
+    }
+
+    /// Enables leader election, so that only one of the replicas sharing the lease probes the endpoints.
+    /// The others skip their updates, taking over once the leadership expires.
+    ///
+    /// # Arguments
+    /// * `lease`: The lease shared by the replicas, e.g. a `store::Redis` instance.
+    /// * `config`: The ID of the replica and the time-to-live of its leadership.
+    ///
+    /// # Returns
+    /// The updated `Service` instance with leader election enabled.
+    pub fn use_election(mut self, lease: impl Lease + Sync + Send + 'static, config: election::Config) -> Self {
+        self.election = Some(Election::new(Box::new(lease), config));
+        self
+    }
+
+    /// Enables sharding, so that the replicas sharing the registry split the endpoints among themselves.
+    /// The endpoints are rebalanced whenever a replica joins, or stops renewing its registration.
+    ///
+    /// # Arguments
+    /// * `registry`: The registry shared by the replicas, e.g. a `store::Redis` instance.
+    /// * `config`: The ID of the replica and the time-to-live of its registration.
+    ///
+    /// # Returns
+    /// The updated `Service` instance with sharding enabled.
+    pub fn use_sharding(mut self, registry: impl Registry + Sync + Send + 'static, config: shard::Config) -> Self {
+        self.sharding = Some(Sharding::new(Box::new(registry), config));
+        self
+    }
+
+    /// Traces the network path toward every endpoint that goes down, attaching the hops to its incident.
+    ///
+    /// # Arguments
+    /// * `config`: The number of hops and the timeout of the traces.
+    ///
+    /// # Returns
+    /// The updated `Service` instance with traceroute diagnostics enabled.
+    #[cfg(feature = "traceroute")]
+    pub fn use_traceroute(mut self, config: traceroute::Config) -> Self {
+        self.traceroute = Some(config);
+        self
+    }
+
+    /// Traces the probes, emitting a span for the sampled ones and propagating their W3C trace context.
+    ///
+    /// # Arguments
+    /// * `config`: The sample rate of the spans, and whether the `traceparent` header is sent.
+    ///
+    /// # Returns
+    /// The updated `Service` instance with tracing enabled.
+    pub fn use_tracing(mut self, config: trace::Config) -> Self {
+        self.tracing = Some(config);
+        self
+    }
+
+    /// Captures the headers and the beginning of the body of the failed responses, retrieved by `last_failure`.
+    ///
+    /// # Arguments
+    /// * `config`: The number of bytes of the bodies kept.
+    ///
+    /// # Returns
+    /// The updated `Service` instance with failure capture enabled.
+    pub fn use_capture(mut self, config: capture::Config) -> Self {
+        self.capture = Some(config);
+        self
+    }
+
+    /// Delivers notifications through the given notifier, along with the previously registered ones.
+    ///
+    /// # Arguments
+    /// * `notifier`: The channel notifications are delivered through, e.g. a `notify::Webhook`.
+    ///
+    /// # Returns
+    /// The updated `Service` instance with the new notifier.
+    pub fn use_notifier(mut self, notifier: impl Notifier + Sync + Send + 'static) -> Self {
+        self.notifiers.push(Box::new(notifier));
+        self
+    }
+
+    /// Exports the scored probes of every update cycle to the given sink, along with the previously registered ones.
+    ///
+    /// # Arguments
+    /// * `exporter`: The sink the scored probes are exported to, e.g. an `export::File`.
+    ///
+    /// # Returns
+    /// The updated `Service` instance with the new exporter.
+    pub fn use_exporter(mut self, exporter: impl Exporter + Sync + Send + 'static) -> Self {
+        self.exporters.push(Box::new(exporter));
+        self
+    }
+
+    /// Enables summary reports, delivered through the notifiers at the end of every period.
+    ///
+    /// # Arguments
+    /// * `config`: The period and format of the reports.
+    ///
+    /// # Returns
+    /// The updated `Service` instance with summary reports enabled.
+    pub fn use_report(mut self, config: report::Config) -> Self {
+        self.reporter = Some(Reporter::new(config));
+        self
+    }
+
+    /// Raises the incidents of the endpoints as alerts in Alertmanager.
+    ///
+    /// # Arguments
+    /// * `config`: The URL of Alertmanager and the labels attached to every alert.
+    ///
+    /// # Returns
+    /// The updated `Service` instance with alerting enabled.
+    pub fn use_alertmanager(mut self, config: alert::Config) -> Self {
+        self.alertmanager = Some(Alertmanager::new(config));
+        self
+    }
+
+    /// Extrapolates the trend of the score of every endpoint, flagging the ones forecast to fall under a threshold
+    /// before they do, and raising them as `EndpointAtRisk` alerts if Alertmanager is configured.
+    ///
+    /// # Arguments
+    /// * `config`: The score threshold, and how far ahead the scores are forecast.
+    ///
+    /// # Returns
+    /// The updated `Service` instance with forecasts enabled.
+    pub fn use_forecast(mut self, config: forecast::Config) -> Self {
+        self.forecast = Some(config);
+        self
+    }
+
+    /// Records the scored probes of every endpoint in the given history.
+    ///
+    /// # Arguments
+    /// * `history`: The history the samples are recorded in, e.g. a `history::Memory` instance.
+    ///
+    /// # Returns
+    /// The updated `Service` instance with the history enabled.
+    pub fn use_history(mut self, history: impl History + Sync + Send + 'static) -> Self {
+        self.history = Some(Box::new(history));
+        self
+    }
+
+    /// Looks up the baseline of every endpoint in its history, i.e. its usual latency at the same hour of the week,
+    /// so strategies can score the deviations from it, e.g. `WeightedLog::set_relative_to_baseline`.
+    /// Requires a history, set through `use_history`.
+    ///
+    /// # Arguments
+    /// * `window`: The period the baselines are computed over, e.g. the last 4 weeks.
+    ///
+    /// # Returns
+    /// The updated `Service` instance with the baselines enabled.
+    pub fn use_baselines(mut self, window: Duration) -> Self {
+        self.baselines = Some(history::Baselines::new(window));
+        self
+    }
+
+    /// Sets the namespace of the service, labeling its alerts and metrics and routing its state through
+    /// `/namespaces/{namespace}` on an embedded server hosting several services.
+    ///
+    /// The store isn't affected, so a store shared with other services should be namespaced as well,
+    /// e.g. with `Redis::set_namespace`, as `from_config` does.
+    ///
+    /// # Arguments
+    /// * `namespace`: The namespace of the service, e.g. the name of a team or tenant.
+    ///
+    /// # Returns
+    /// The updated `Service` instance with the namespace set.
+    pub fn use_namespace<I: Into<String>>(mut self, namespace: I) -> Self {
+        self.namespace = Some(namespace.into());
+        self.metrics.set_namespace(self.namespace.clone());
+        self
+    }
+
+    /// Persists the monitored requests in the store on every update where they changed,
+    /// so that `restore_requests` brings the ones inserted at runtime back after a restart.
+    ///
+    /// # Returns
+    /// The updated `Service` instance with the persistence of the requests enabled.
+    pub fn use_request_persistence(mut self) -> Self {
+        self.persist_requests = true;
+        self.requests_changed.store(true, SeqCst);
+        self
+    }
+
+    /// Sets the grace period of the endpoints inserted at runtime, during which they're probed and scored,
+    /// but excluded from `best_url` and alerting, so their fresh score doesn't immediately influence the routing.
+    ///
+    /// # Arguments
+    /// * `grace_period`: How long the endpoints are excluded after being inserted.
+    ///
+    /// # Returns
+    /// The updated `Service` instance with the grace period set.
+    pub fn use_grace_period(mut self, grace_period: Duration) -> Self {
+        self.grace_period = Some(grace_period);
+        self
+    }
+
+    /// Exposes the scores relative to the best candidate through `ranking` and `best_url_where`, the best one
+    /// scoring `1.0`, so the constraints on the scores hold when the network conditions shift all the latencies
+    /// together. The scores are kept absolute in the store, where the strategy keeps calculating them.
+    ///
+    /// # Returns
+    /// The updated `Service` instance with relative scoring enabled.
+    pub fn use_relative_scoring(mut self) -> Self {
+        self.relative_scoring = true;
+        self
+    }
+
+    /// Bounds the probes of every update cycle, aborting the ones still running at the deadline and scoring them
+    /// as timeouts, so misbehaving endpoints don't stretch the update cadence.
+    ///
+    /// # Arguments
+    /// * `deadline`: How long the probes of a cycle may run, which should leave room for the scoring within the interval.
+    ///   A deadline too far away to be represented by the clock is ignored.
+    ///
+    /// # Returns
+    /// The updated `Service` instance with the deadline set.
+    pub fn use_cycle_deadline(mut self, deadline: Duration) -> Self {
+        self.cycle_deadline = Some(deadline);
+        self
+    }
+
+    /// Bounds the number of endpoints probed at once within a cycle. The endpoints that are down are probed first,
+    /// so their recovery is detected quickly, followed by the ones of higher priority.
+    ///
+    /// # Arguments
+    /// * `concurrency`: The maximum number of endpoints probed at once.
+    ///
+    /// # Returns
+    /// The updated `Service` instance with the concurrency bounded.
+    ///
+    /// # Panics
+    /// Panics if the concurrency is zero.
+    pub fn use_concurrency(mut self, concurrency: usize) -> Self {
+        assert!(concurrency > 0, "the concurrency must be positive");
+        self.concurrency = Some(concurrency);
+        self
+    }
+
+    /// Sets the number of latest probes the error rates and jitter of the endpoints are computed over (default: 100).
+    ///
+    /// # Arguments
+    /// * `window`: The number of probes.
+    ///
+    /// # Returns
+    /// The updated `Service` instance with the new error window.
+    ///
+    /// # Panics
+    /// Panics if the window is zero.
+    pub fn use_error_window(mut self, window: usize) -> Self {
+        assert!(window > 0, "the error window must be positive");
+        self.error_window = window;
+        self
+    }
+
+    /// Sets the maximum number of samples scored and written to the store at once (default: 64).
+    ///
+    /// The probes push their samples onto a channel of the same capacity, so they wait for the scorer once it's
+    /// that far behind.
+    ///
+    /// # Arguments
+    /// * `batch`: The number of samples.
+    ///
+    /// # Returns
+    /// The updated `Service` instance with the new scoring batch.
+    ///
+    /// # Panics
+    /// Panics if the batch is zero.
+    pub fn use_scoring_batch(mut self, batch: usize) -> Self {
+        assert!(batch > 0, "the scoring batch must be positive");
+        self.scoring_batch = batch;
+        self
+    }
+
+    /// Updates the scores for all tracked services.
+    ///
+    /// This function performs HTTP requests concurrently for each service, updating their
+    /// scores based on the response time and HTTP status code. It leverages the provided
+    /// strategy for score calculation and updates the store with new scores.
+    ///
+    /// An update called while the previous one is still running, e.g. a manual one overlapping the update loop,
+    /// is skipped. The duration of every cycle is recorded in the metrics of the service, and its report can be
+    /// retrieved through `Service::last_cycle`.
+    pub async fn update(&self) -> Result<(), Box<dyn Error + Send + Sync>> {
+        self.update_report().await.map(|_| ())
+    }
+
+    /// Updates the scores for all tracked services, like `update`, and reports on the update cycle.
+    ///
+    /// The report is kept until the next cycle completes, and can be retrieved through `Service::last_cycle`.
+    ///
+    /// # Returns
+    /// The report of the cycle, or `None` if it was skipped because the previous one is still running.
+    ///
+    /// # Errors
+    /// Returns an error if the cycle failed, e.g. because the scores couldn't be written to the store.
+    pub async fn update_report(&self) -> Result<Option<CycleReport>, Box<dyn Error + Send + Sync>> {
+        let Some(_cycle) = Cycle::start(&self.updating) else {
+            self.metrics.record_skipped_cycle();
+            return Ok(None);
+        };
+        let started_at = Instant::now();
+        let mut report = CycleReport::new(SystemTime::now());
+        let result = self.update_cycle(&mut report).await;
+        report.duration = started_at.elapsed();
+        self.metrics.record_cycle(report.duration, result.is_err());
+        result?;
+        *self.last_cycle.write().expect("failed to lock last cycle") = Some(report.clone());
+        Ok(Some(report))
+    }
+
+    /// Probes every endpoint once, without scoring the outcomes nor writing anything to the store, and reports the
+    /// ones that failed, e.g. on a DNS, TLS or authentication error.
+    ///
+    /// Useful in CI, to validate a configuration before it's deployed. The requests are passed through the
+    /// middlewares, so the ones adding credentials are validated as well, but their outcomes aren't.
+    ///
+    /// # Returns
+    /// The outcome of every endpoint, along with the misconfigured ones.
+    pub async fn dry_run(&self) -> DryRunReport {
+        let probes = self.requests.iter().map(|probe| {
+            let mut request = hyper::Request::from(probe.clone());
+            self.middleware.iter().for_each(|m| m.before(&mut request));
+            self.execute(probe, request, probe.key(), None)
+        });
+        let outcomes = join_all(probes).await;
+        let misconfigured = outcomes
+            .iter()
+            .filter_map(|outcome| Some(Misconfigured { url: outcome.url.clone(), reason: outcome.failure()? }))
+            .collect();
+        DryRunReport { outcomes, misconfigured }
+    }
+
+    /// Returns the report of the latest update cycle that completed, or `None` if none did yet.
+    pub fn last_cycle(&self) -> Option<CycleReport> {
+        self.last_cycle.read().expect("failed to lock last cycle").clone()
+    }
+
+    /// Runs an update cycle, probing and scoring the endpoints, and records it in the report.
+    async fn update_cycle(&self, report: &mut CycleReport) -> Result<(), Box<dyn Error + Send + Sync>> {
+        // Persist the monitored requests, if they changed since the previous update
+        if self.persist_requests && self.requests_changed.swap(false, SeqCst) {
+            if let Err(e) = self.store.set_requests(&self.requests).await {
+                self.requests_changed.store(true, SeqCst);
+                return Err(e);
+            }
+        }
+
+        // Only the elected replica probes the endpoints
+        if let Some(election) = &self.election {
+            if !election.campaign().await? {
+                let skipped = self.requests.iter().map(|r| Skipped { url: r.key(), reason: SkipReason::NotElected });
+                report.skipped.extend(skipped);
+                return Ok(());
+            }
+        }
+
+        // Only probe the endpoints assigned to this replica
+        let ring = match &self.sharding {
+            Some(sharding) => Some((sharding, sharding.ring().await?)),
+            None => None,
+        };
+        let mut requests: Vec<&Request> = Vec::with_capacity(self.requests.len());
+        for request in &self.requests {
+            let url = request.key();
+            // Paused endpoints aren't probed, freezing their score
+            let reason = if self.is_paused(&url) {
+                Some(SkipReason::Paused)
+            } else if !ring.as_ref().is_none_or(|(s, ring)| s.owns(ring, &url)) {
+                Some(SkipReason::Unassigned)
+            } else if !self.is_due(&url) {
+                Some(SkipReason::BackedOff)
+            } else {
+                None
+            };
+            match reason {
+                Some(reason) => report.skipped.push(Skipped { url, reason }),
+                None => requests.push(request),
+            }
+        }
+        // Probe the endpoints that are down first, so their recovery is detected quickly, then by priority
+        requests.sort_by_key(|r| {
+            let down = self.states.get(&r.key()).is_some_and(|s| *s == State::Down);
+            (!down, std::cmp::Reverse(r.priority))
+        });
+
+        // Concurrently send requests to all endpoints, pushing their samples onto the scoring channel, which is
+        // drained in batches meanwhile; the probes wait whenever the scorer falls a whole batch behind
+        // A deadline too far away to be represented never aborts the probes, as if there was none
+        let deadline = self.cycle_deadline.and_then(|deadline| tokio::time::Instant::now().checked_add(deadline));
+        let (sender, receiver) = mpsc::channel(self.scoring_batch);
+        let probes: Vec<_> = requests.into_iter().map(|r| self.process_request(r, deadline, sender.clone())).collect();
+        // The channel is closed once every probe is done with its sender
+        drop(sender);
+        let probing = async {
+            match self.concurrency {
+                Some(concurrency) => futures::stream::iter(probes).buffer_unordered(concurrency).collect().await,
+                None => join_all(probes).await,
+            }
+        };
+        let (_, scored): (Vec<()>, _) = tokio::join!(probing, self.score_samples(receiver, &mut report.store_errors));
+        let records = scored?;
+
+        // Push the scored outcomes to the coordinator, in agent mode
+        if let Some(agent) = &self.agent {
+            agent.push(records.iter().map(|record| record.outcome.clone())).await;
+        }
+
+        // Export the scored outcomes to every sink; the exports are best-effort, they don't affect the scoring
+        join_all(self.exporters.iter().map(|exporter| exporter.export(&records))).await;
+
+        // Deliver the summary report once its period is over
+        if let Some(reporter) = &self.reporter {
+            let incidents = self.incidents().await?;
+            if let Some(report) = reporter.close(&incidents) {
+                self.notify(&report.notification(reporter.format())).await;
+            }
+        }
+
+        // Update the timestamp of the last update
+        let unix = SystemTime::now().duration_since(UNIX_EPOCH)?;
+        self.updated_at.store(unix.as_secs(), SeqCst);
+        report.outcomes = records;
+        Ok(())
+    }
+
+    /// Delivers a notification through every notifier.
+    /// A notifier failing to deliver it doesn't prevent the others from doing so.
+    async fn notify(&self, notification: &notify::Notification) {
+        for notifier in &self.notifiers {
+            let _ = notifier.notify(notification).await;
+        }
+    }
+
+    /// Handles a single request, pushing its sample onto the scoring channel.
+    ///
+    /// # Arguments
+    /// * `probe` - A reference to the monitored request to be sent.
+    /// * `deadline` - When the probe is aborted and scored as a timeout, if it's still running.
+    /// * `sender` - The scoring channel of the update cycle.
+    ///
+    /// This function sends the HTTP request, measures the response time and updates the state of the endpoint,
+    /// then pushes the outcome onto the scoring channel, unless it was vetoed by a middleware.
+    async fn process_request<'a>(
+        &self,
+        probe: &'a Request,
+        deadline: Option<tokio::time::Instant>,
+        sender: mpsc::Sender<Evaluated<'a>>,
+    ) {
+        let url = probe.key();
+
+        let mut request = hyper::Request::from(probe.clone());
+        // Tag the request with a unique ID, before the middlewares get to see it
+        let request_id = self.request_id_header.as_ref().map(|header| {
+            let id = request::generate_id();
+            request.headers_mut().insert(header, HeaderValue::from_str(&id).expect("invalid request id"));
+            id
+        });
+        // Start a trace, propagated to the endpoint along with the request
+        let trace = self.tracing.as_ref().map(|config| {
+            let trace = trace::Context::new(config);
+            if config.propagate {
+                let traceparent = HeaderValue::from_str(&trace.traceparent()).expect("invalid traceparent");
+                request.headers_mut().insert(trace::TRACEPARENT, traceparent);
+            }
+            trace
+        });
+        let span = trace.map_or_else(tracing::Span::none, |trace| trace.span(&url));
+        // Make the request conditional on the validators of the previous response
+        let validators = match probe.cache_validation {
+            true => Some(self.validators.get(&url).map(|v| v.clone()).unwrap_or_default()),
+            false => None,
+        };
+        if let Some(validators) = &validators {
+            validators.apply(request.headers_mut());
+        }
+        // Allow the middlewares to modify the request before it's sent
+        self.middleware.iter().for_each(|m| m.before(&mut request));
+
+        let start = tokio::time::Instant::now();
+        let execution = self.execute(probe, request, url.clone(), validators);
+        let mut outcome = match deadline {
+            Some(deadline) => tokio::time::timeout_at(deadline, execution).await.unwrap_or_else(|_| {
+                let mut outcome = ProbeOutcome::new(url, start.elapsed(), 0);
+                outcome.error = Some("timed out at the deadline of the update cycle".into());
+                outcome
+            }),
+            None => execution.await,
+        };
+        outcome.request_id = request_id;
+        span.record("status", outcome.status);
+        span.record("latency_ms", outcome.elapsed.as_millis() as u64);
+
+        if let Some(outcome) = self.evaluate(Some(probe), outcome).instrument(span.clone()).await {
+            // The scorer only goes away along with the update cycle
+            let _ = sender.send(Evaluated { probe: Some(probe), outcome, span }).await;
+        }
+    }
+
+    /// Passes an outcome through the middlewares and, unless vetoed, updates the state of its endpoint.
+    ///
+    /// # Arguments
+    /// * `probe`: The request the outcome was received for, or `None` if it was probed elsewhere.
+    /// * `outcome`: The outcome of the probe.
+    ///
+    /// # Returns
+    /// The outcome to be scored, or `None` if it was vetoed.
+    async fn evaluate(&self, probe: Option<&Request>, mut outcome: ProbeOutcome) -> Option<ProbeOutcome> {
+        let request = probe.or_else(|| self.requests.iter().find(|r| r.key() == outcome.url));
+        outcome.budget = request.and_then(|r| r.budget).map(ConfigDuration::get);
+        // Pass the outcome through the middlewares, any of which can veto it from being scored
+        for middleware in &self.middleware {
+            if middleware.after(&mut outcome) == Action::Veto {
+                return None;
+            }
+        }
+        // Failures expected during a deployment are discarded
+        if !outcome.is_success() && self.is_suppressed(&outcome.url) {
+            return None;
+        }
+        // Look up the usual latency of the endpoint at this hour, for the strategies scoring deviations from it
+        if let (Some(baselines), Some(history)) = (&self.baselines, &self.history) {
+            outcome.baseline = baselines.latency(history.as_ref(), &outcome.url).await;
+        }
+
+        self.metrics.record_status(&outcome);
+        let down = self.is_down(probe, &outcome).await;
+        self.transition(&outcome, down).await;
+        self.back_off(&outcome.url, down);
+        self.check_budget(&outcome).await;
+        if let Some(reporter) = &self.reporter {
+            reporter.record(&outcome);
+        }
+        Some(outcome)
+    }
+
+    /// Scores the samples pushed onto the scoring channel in batches, until every probe is done with it.
+    ///
+    /// # Arguments
+    /// * `receiver` - The scoring channel of the update cycle.
+    /// * `errors` - The best-effort writes to the store that failed, such as the history of the endpoints.
+    ///
+    /// # Returns
+    /// The scored outcomes, along with their score.
+    ///
+    /// # Errors
+    /// Returns the first error encountered while writing the scores, once the channel is drained.
+    async fn score_samples(
+        &self,
+        mut receiver: mpsc::Receiver<Evaluated<'_>>,
+        errors: &mut Vec<String>,
+    ) -> Result<Vec<export::Record>, Box<dyn Error + Send + Sync>> {
+        let mut records = Vec::new();
+        let mut failure = None;
+        while let Some(sample) = receiver.recv().await {
+            // Batch the samples already waiting along with the first one
+            let mut batch = vec![sample];
+            while batch.len() < self.scoring_batch {
+                match receiver.try_recv() {
+                    Ok(sample) => batch.push(sample),
+                    Err(_) => break,
+                }
+            }
+            // The channel is drained although a batch failed, so the probes waiting on it complete
+            match self.score_batch(batch, errors).await {
+                Ok(batch) => records.extend(batch),
+                Err(e) => {
+                    failure.get_or_insert(e);
+                }
+            }
+        }
+        match failure {
+            Some(e) => Err(e),
+            None => Ok(records),
+        }
+    }
+
+    /// Applies the strategies to a batch of samples, writing their scores to the store at once.
+    ///
+    /// # Returns
+    /// The scored outcomes, along with their score.
+    ///
+    /// # Errors
+    /// Returns an error if the scores couldn't be written to the store.
+    async fn score_batch(
+        &self,
+        batch: Vec<Evaluated<'_>>,
+        errors: &mut Vec<String>,
+    ) -> Result<Vec<export::Record>, Box<dyn Error + Send + Sync>> {
+        let mut scores: Vec<(String, Score)> = Vec::with_capacity(batch.len());
+        for sample in &batch {
+            // An endpoint sampled more than once is scored from its latest score within the batch
+            let previous = match scores.iter().rev().find(|(url, _)| *url == sample.outcome.url) {
+                Some((_, score)) => Some(score.clone()),
+                None => self.store.get(&sample.outcome.url).await.ok().flatten(),
+            };
+            scores.push((sample.outcome.url.clone(), self.calculate_score(sample, previous)));
+        }
+        self.store.set_many(scores.clone()).await.map_err(|e| format!("failed to set scores: {e}"))?;
+
+        for (sample, (url, score)) in batch.iter().zip(&scores) {
+            self.checked_at.insert(url.clone(), SystemTime::now());
+            self.metrics.record_score(url, score);
+            self.check_risk(url, score).await;
+            if let Some(history) = &self.history {
+                // The history is best-effort, it doesn't affect the scoring
+                if let Err(e) = history.record(url, history::Sample::new(&sample.outcome, score)).await {
+                    errors.push(format!("failed to record the history of `{url}`: {e}"));
+                }
+            }
+        }
+        Ok(batch
+            .into_iter()
+            .zip(scores)
+            .map(|(sample, (_, score))| export::Record::new(sample.outcome, score))
+            .collect())
+    }
+
+    /// Probes an endpoint, either simulated, through its service-specific probe or over HTTP.
+    async fn execute(
+        &self,
+        probe: &Request,
+        request: hyper::Request<Full<Bytes>>,
+        url: String,
+        validators: Option<Validators>,
+    ) -> ProbeOutcome {
+        let simulated = match &self.chaos {
+            Some(chaos) => chaos.simulate(&probe.url.to_string(), self.timeout(probe)).await,
+            None => None,
+        };
+
+        match (simulated, Probe::from_url(&probe.url)) {
+            (Some((elapsed, status)), _) => ProbeOutcome::new(url, elapsed, status),
+            (None, Some(service)) => self.ping(service, probe, url).await,
+            (None, None) => self.send(probe, request, url, validators).await,
+        }
+    }
+
+    /// The request timeout of a probe, either its own or the one of the client.
+    fn timeout(&self, probe: &Request) -> Option<Duration> {
+        probe.timeout.map(ConfigDuration::get).or(self.client.timeout())
+    }
+
+    /// Determines whether an endpoint is down, according to the outcome of its latest probe and the quorum, if any.
+    ///
+    /// Without a quorum, a single failure is enough. Otherwise, the failures are counted either over the latest
+    /// probes of the endpoint, or over confirmation probes sent concurrently once a probe fails.
+    /// Outcomes probed elsewhere can't be confirmed, so they're always counted over the latest ones.
+    async fn is_down(&self, probe: Option<&Request>, outcome: &ProbeOutcome) -> bool {
+        let failed = !outcome.is_success();
+        let Some(quorum) = &self.quorum else {
+            return failed;
+        };
+        let is_down = self.states.get(&outcome.url).is_some_and(|s| *s == State::Down);
+
+        match (quorum.concurrent, probe) {
+            // Only a healthy endpoint has to be confirmed down
+            (true, Some(_)) if !failed || is_down => failed,
+            (true, Some(probe)) => {
+                let confirmations = join_all((1..quorum.probes).map(|_| {
+                    let mut request = hyper::Request::from(probe.clone());
+                    self.middleware.iter().for_each(|m| m.before(&mut request));
+                    self.execute(probe, request, outcome.url.clone(), None)
+                }));
+                let failures = 1 + confirmations.await.iter().filter(|o| !o.is_success()).count();
+                failures >= quorum.failures
+            }
+            _ => {
+                let mut window = self.failures.entry(outcome.url.clone()).or_default();
+                // A recovered endpoint starts over with a clean window
+                if is_down && !failed {
+                    window.clear();
+                    return false;
+                }
+                window.push_back(failed);
+                if window.len() > quorum.probes {
+                    window.pop_front();
+                }
+                failed && (is_down || window.iter().filter(|f| **f).count() >= quorum.failures)
+            }
+        }
+    }
+
+    /// Updates the state of an endpoint from the outcome of its latest probe,
+    /// opening an incident when it goes down and resolving it once it's back up.
+    ///
+    /// Incidents are recorded in the store whenever they're updated. Stores without an incident log
+    /// only keep track of the ongoing incidents, in memory.
+    async fn transition(&self, outcome: &ProbeOutcome, down: bool) {
+        let state = if down { State::Down } else { State::Up };
+        let previous = self.states.insert(outcome.url.clone(), state);
+        if previous != Some(state) {
+            self.transition_hooks.iter().for_each(|hook| hook(&outcome.url, previous, state));
+            // The exports are best-effort, like the ones of the records
+            let transition = export::Transition::new(&outcome.url, previous, state);
+            join_all(self.exporters.iter().map(|exporter| exporter.transition(&transition))).await;
+        }
+        let incident = match (previous, state) {
+            (Some(State::Down), State::Up) => self.incidents.remove(&outcome.url).map(|(_, mut incident)| {
+                incident.resolve(outcome);
+                incident
+            }),
+            (Some(State::Down), State::Down) => self.incidents.get_mut(&outcome.url).map(|mut incident| {
+                incident.record(outcome);
+                incident.clone()
+            }),
+            (None | Some(State::Up), State::Down) => {
+                let incident = Incident::open(outcome);
+                self.incidents.insert(outcome.url.clone(), incident.clone());
+                #[cfg(feature = "traceroute")]
+                if let Some(config) = &self.traceroute {
+                    self.diagnose(outcome.url.clone(), config.clone());
+                }
+                Some(incident)
+            }
+            _ => None,
+        };
+
+        if let Some(incident) = incident {
+            // Alerting is best-effort; a firing alert is sent again along with the next update of its incident,
+            // so an endpoint still down at the end of its grace period is alerted on then
+            if let Some(alertmanager) = self.alertmanager.as_ref().filter(|_| !self.is_warming_up(&incident.url)) {
+                let _ = alertmanager.send(&incident, &self.alert_tags(&incident.url)).await;
+            }
+            // The ongoing incidents remain available from memory, should the store fail to record them
+            let _ = self.store.set_incident(incident).await;
+        }
+    }
+
+    /// Checks whether an endpoint is due to be probed this cycle, consuming one of its skipped cycles if it's backed off.
+    fn is_due(&self, url: &str) -> bool {
+        match self.backed_off.get_mut(url) {
+            Some(mut backed_off) if backed_off.skipped_cycles > 0 => {
+                backed_off.skipped_cycles -= 1;
+                false
+            }
+            _ => true,
+        }
+    }
+
+    /// Counts the cycles an endpoint is down in a row, backing off its probes once there are enough of them,
+    /// and restores its normal interval once it's up.
+    ///
+    /// # Arguments
+    /// * `url` - The URL of the endpoint.
+    /// * `down` - Whether the endpoint is down, according to its latest probe.
+    fn back_off(&self, url: &str, down: bool) {
+        let Some(backoff) = &self.backoff else {
+            return;
+        };
+        match down {
+            true => {
+                let mut backed_off = self.backed_off.entry(url.to_string()).or_default();
+                backed_off.down_cycles += 1;
+                backed_off.skipped_cycles = backoff.skipped_cycles(backed_off.down_cycles);
+            }
+            false => {
+                self.backed_off.remove(url);
+            }
+        }
+    }
+
+    /// Tracks the successful responses of an endpoint exceeding its latency budget, raising a `BudgetExceeded` alert
+    /// while they do, which is resolved once a response fits the budget again.
+    ///
+    /// # Arguments
+    /// * `outcome` - The outcome of the latest probe of the endpoint.
+    async fn check_budget(&self, outcome: &ProbeOutcome) {
+        let Some(budget) = outcome.budget.filter(|_| outcome.is_success()) else {
+            return;
+        };
+        let violation = match outcome.exceeds_budget() {
+            true => {
+                let mut violation =
+                    self.violations.entry(outcome.url.clone()).or_insert_with(|| alert::Violation::new(budget));
+                violation.elapsed = outcome.elapsed;
+                violation.count += 1;
+                violation.clone()
+            }
+            false => match self.violations.remove(&outcome.url) {
+                Some((_, violation)) => alert::Violation { resolved_at: Some(SystemTime::now()), ..violation },
+                None => return,
+            },
+        };
+        // Alerting is best-effort; a firing alert is sent again along with every response exceeding the budget
+        if let Some(alertmanager) = self.alertmanager.as_ref().filter(|_| !self.is_warming_up(&outcome.url)) {
+            let _ = alertmanager.send_violation(&outcome.url, &violation, &self.alert_tags(&outcome.url)).await;
+        }
+    }
+
+    /// Forecasts the score of an endpoint along its trend, flagging it as at risk while it's forecast to fall under
+    /// the threshold, and raising an `EndpointAtRisk` alert meanwhile, which is resolved once it no longer is.
+    ///
+    /// # Arguments
+    /// * `url` - The URL of the endpoint.
+    /// * `score` - The latest score of the endpoint.
+    async fn check_risk(&self, url: &str, score: &Score) {
+        let Some(config) = &self.forecast else {
+            return;
+        };
+        let forecast = self.metrics.trend(url).and_then(|trend| config.at_risk(score.score, &trend));
+        let risk = match forecast {
+            Some(forecast) => {
+                let mut risk = self.risks.entry(url.to_string()).or_insert_with(|| forecast::Risk {
+                    url: url.to_string(),
+                    score: score.score,
+                    forecast,
+                    threshold: config.threshold,
+                    horizon: config.horizon.get(),
+                    started_at: SystemTime::now(),
+                    resolved_at: None,
+                });
+                (risk.score, risk.forecast) = (score.score, forecast);
+                risk.clone()
+            }
+            None => match self.risks.remove(url) {
+                Some((_, risk)) => forecast::Risk { resolved_at: Some(SystemTime::now()), score: score.score, ..risk },
+                None => return,
+            },
+        };
+        // Alerting is best-effort; a firing alert is sent again along with every forecast of the risk
+        if let Some(alertmanager) = self.alertmanager.as_ref().filter(|_| !self.is_warming_up(url)) {
+            let _ = alertmanager.send_risk(&risk, &self.alert_tags(url)).await;
+        }
+    }
+
+    /// Lists the endpoints currently forecast to fall under the score threshold, ordered by URL.
+    ///
+    /// # Returns
+    /// The risks of the endpoints, or none if forecasts aren't enabled.
+    pub fn risks(&self) -> Vec<forecast::Risk> {
+        let mut risks = self.risks.iter().map(|r| r.value().clone()).collect::<Vec<_>>();
+        risks.sort_by(|a, b| a.url.cmp(&b.url));
+        risks
+    }
+
+    /// Builds the labels attached to the alerts of an endpoint: the tags of its request, and the namespace of the
+    /// service, if any.
+    fn alert_tags(&self, url: &str) -> std::collections::BTreeMap<String, String> {
+        let request = self.requests.iter().find(|r| r.key() == url);
+        let mut tags = request.map(|r| r.tags.clone()).unwrap_or_default();
+        if let Some(namespace) = &self.namespace {
+            tags.insert("namespace".into(), namespace.clone());
+        }
+        tags
+    }
+
+    /// Traces the network path toward an endpoint in the background, attaching the hops to its ongoing incident.
+    /// The trace is recorded in the store along with the next update of the incident.
+    #[cfg(feature = "traceroute")]
+    fn diagnose(&self, url: String, config: traceroute::Config) {
+        let guard = self.client.guard().cloned();
+        let incidents = self.incidents.clone();
+        // The incident is keyed by the name of the request, if it has one, rather than the URL traced
+        let uri = match self.requests.iter().find(|r| r.key() == url) {
+            Some(request) => Ok(request.url.clone()),
+            None => Uri::from_str(&url),
+        };
+        tokio::spawn(async move {
+            let trace = match uri {
+                Ok(uri) => traceroute::trace(&uri, &config, guard.as_ref()).await,
+                Err(e) => traceroute::Trace { error: Some(e.to_string()), ..Default::default() },
+            };
+            if let Some(mut incident) = incidents.get_mut(&url).filter(|i| i.is_ongoing()) {
+                incident.trace = Some(trace);
+            }
+        });
+    }
+
+    /// Sends an HTTP request and inspects its response.
+    ///
+    /// # Arguments
+    /// * `probe` - The monitored request.
+    /// * `request` - The request to be sent, built from the monitored one.
+    /// * `url` - The URL of the probed endpoint.
+    /// * `validators` - The cache validators the request was made conditional on, if cache validation is enabled.
+    async fn send(
+        &self,
+        probe: &Request,
+        mut request: hyper::Request<Full<Bytes>>,
+        url: String,
+        validators: Option<Validators>,
+    ) -> ProbeOutcome {
+        let timer = probe.upload_size.map(|_| client::UploadTimer::default());
+        if let Some(timer) = &timer {
+            request.extensions_mut().insert(timer.clone());
+        }
+
+        let start = tokio::time::Instant::now();
+        // The error isn't `Send`, so it's turned into a string before awaiting the body
+        let response = self.client.request(request).await.map_err(|e| e.to_string());
+        let elapsed = start.elapsed();
+        match response {
+            Ok(response) => {
+                let mut outcome = ProbeOutcome::new(url, elapsed, response.status().as_u16());
+                outcome.connection_reused = Client::is_reused(&response);
+                outcome.remote_addr = Client::remote_addr(&response);
+                // The upload ends once its last chunk was sent; the server processes it until the response
+                if let (Some(size), Some(sent)) = (probe.upload_size, timer.and_then(|t| t.sent())) {
+                    let upload_time = sent.duration_since(start).as_secs_f64();
+                    outcome.upload_throughput = Some(size as f64 / upload_time.max(f64::EPSILON));
+                    outcome.processing_time = Some(elapsed.saturating_sub(sent.duration_since(start)));
+                }
+                if let Some(validators) = validators {
+                    let (validators, result) = validators.check(response.status(), response.headers());
+                    outcome.revalidated = Some(response.status() == hyper::StatusCode::NOT_MODIFIED);
+                    outcome.error = result.err();
+                    self.validators.insert(outcome.url.clone(), validators);
+                }
+                if let Some(max_staleness) = probe.max_staleness {
+                    let (staleness, result) =
+                        check_freshness(response.headers(), SystemTime::now(), max_staleness.get());
+                    outcome.staleness = staleness;
+                    if let Err(e) = result {
+                        outcome.error.get_or_insert(e);
+                    }
+                }
+                if let Some(audit) = &probe.audit {
+                    let report = audit.audit(response.headers());
+                    if audit.penalize && !report.is_compliant() {
+                        outcome.error.get_or_insert(report.to_string());
+                    }
+                    self.audits.insert(outcome.url.clone(), report);
+                }
+                let headers = self.capture.as_ref().map(|_| response.headers().clone());
+                let mut body = None;
+                if let Some(size) = probe.download_size {
+                    self.download(probe, response, size, start, &mut outcome).await;
+                } else if probe.reads_body() || (self.capture.is_some() && !outcome.is_success()) {
+                    // The body of a failed response is read to be captured, even if it isn't checked
+                    body = self.inspect_body(probe, response, elapsed, &mut outcome).await;
+                }
+                self.capture_failure(&outcome, headers.as_ref(), body.as_deref());
+                outcome
+            }
+            Err(e) => {
+                let mut outcome = ProbeOutcome::new(url, elapsed, 0);
+                outcome.error = Some(e);
+                self.capture_failure(&outcome, None, None);
+                outcome
+            }
+        }
+    }
+
+    /// Keeps a failed probe along with its response, if failures are captured.
+    ///
+    /// # Arguments
+    /// * `outcome` - The outcome of the probe, ignored if it succeeded.
+    /// * `headers` - The headers of the response, if one was received.
+    /// * `body` - The decoded body of the response, if it was read.
+    fn capture_failure(&self, outcome: &ProbeOutcome, headers: Option<&hyper::HeaderMap>, body: Option<&[u8]>) {
+        if let Some(capture) = self.capture.as_ref().filter(|_| !outcome.is_success()) {
+            let failure = capture.capture(outcome.status, outcome.error.clone(), headers, body);
+            self.last_failures.insert(outcome.url.clone(), failure);
+        }
+    }
+
+    /// Probes a non-HTTP service, bound by the request timeout of the client.
+    ///
+    /// # Arguments
+    /// * `service` - The probe matching the scheme of the URL.
+    /// * `probe` - The monitored request.
+    /// * `url` - The URL of the probed service.
+    ///
+    /// Non-HTTP probes report a `200` status on success, and `0` along with an error on failure.
+    async fn ping(&self, service: Probe, probe: &Request, url: String) -> ProbeOutcome {
+        let start = tokio::time::Instant::now();
+        let result = match self.timeout(probe) {
+            Some(timeout) => match tokio::time::timeout(timeout, service.run(probe, self.client.guard())).await {
+                Ok(result) => result,
+                Err(e) => Err(e.to_string()),
+            },
+            None => service.run(probe, self.client.guard()).await,
+        };
+        let elapsed = start.elapsed();
+
+        match result {
+            Ok(()) => ProbeOutcome::new(url, elapsed, 200),
+            Err(e) => {
+                let mut outcome = ProbeOutcome::new(url, elapsed, 0);
+                outcome.error = Some(e);
+                outcome
+            }
+        }
+    }
+
+    /// Downloads the given number of bytes from the body of a response, measuring the achieved throughput.
+    ///
+    /// # Arguments
+    /// * `probe` - The monitored request the response was received for.
+    /// * `response` - The response, whose body hasn't been read yet.
+    /// * `size` - The number of bytes to be downloaded.
+    /// * `start` - The time the request was sent at, counted against the timeout.
+    /// * `outcome` - The outcome of the probe, whose elapsed time is extended to the whole download.
+    async fn download(
+        &self,
+        probe: &Request,
+        response: hyper::Response<Incoming>,
+        size: u64,
+        start: tokio::time::Instant,
+        outcome: &mut ProbeOutcome,
+    ) {
+        let mut body = response.into_body();
+        let mut received = 0u64;
+        let download = async {
+            while received < size {
+                match body.frame().await {
+                    Some(Ok(frame)) => received += frame.data_ref().map_or(0, |data| data.len() as u64),
+                    Some(Err(e)) => return Err(e.to_string()),
+                    None => return Err(format!("body ended after {received} of {size} bytes")),
+                }
+            }
+            Ok(())
+        };
+        // The download is bound by the time left from the request timeout
+        let result = match self.timeout(probe) {
+            Some(timeout) => match tokio::time::timeout(timeout.saturating_sub(start.elapsed()), download).await {
+                Ok(result) => result,
+                Err(e) => Err(e.to_string()),
+            },
+            None => download.await,
+        };
+
+        outcome.elapsed = start.elapsed();
+        outcome.body_size = Some(received as usize);
+        match result {
+            Ok(()) => outcome.throughput = Some(received as f64 / outcome.elapsed.as_secs_f64()),
+            Err(e) => outcome.error = Some(format!("failed to download body: {e}")),
+        }
+    }
+
+    /// Reads and decodes the body of a response, recording its sizes and checking its content.
+    ///
+    /// # Arguments
+    /// * `probe` - The monitored request the response was received for.
+    /// * `response` - The response, whose body hasn't been read yet.
+    /// * `elapsed` - The time it took for the response headers to be received, counted against the timeout.
+    /// * `outcome` - The outcome of the probe, updated with the measurements and any error.
+    ///
+    /// # Returns
+    /// The decoded body, or `None` if it couldn't be read or decoded.
+    async fn inspect_body(
+        &self,
+        probe: &Request,
+        response: hyper::Response<Incoming>,
+        elapsed: Duration,
+        outcome: &mut ProbeOutcome,
+    ) -> Option<Vec<u8>> {
+        let content_encoding = response.headers().get(CONTENT_ENCODING).and_then(|v| v.to_str().ok()).map(String::from);
+
+        // The body is bound by the time left from the request timeout
+        let body = match self.timeout(probe) {
+            Some(timeout) => {
+                match tokio::time::timeout(timeout.saturating_sub(elapsed), response.into_body().collect()).await {
+                    Ok(body) => body.map_err(|e| e.to_string()),
+                    Err(e) => Err(e.to_string()),
+                }
+            }
+            None => response.into_body().collect().await.map_err(|e| e.to_string()),
+        };
+        let body = match body {
+            Ok(body) => body.to_bytes().to_vec(),
+            Err(e) => {
+                outcome.error = Some(format!("failed to read body: {e}"));
+                return None;
+            }
+        };
+        outcome.body_size = Some(body.len());
+
+        let start = std::time::Instant::now();
+        let decoded = Encoding::decode(content_encoding.as_deref(), body);
+        outcome.decode_time = Some(start.elapsed());
+        let decoded = match decoded {
+            Ok(decoded) => decoded,
+            Err(e) => {
+                outcome.error = Some(format!("failed to decode body: {e}"));
+                return None;
+            }
+        };
+        outcome.decoded_size = Some(decoded.len());
+
+        if let (Some(_), Err(e)) = (&probe.graphql, GraphQl::check(&decoded)) {
+            outcome.error = Some(e);
+        } else if let Some(expected) = &probe.expect_body {
+            if !String::from_utf8_lossy(&decoded).contains(expected.as_str()) {
+                outcome.error = Some(format!("body doesn't contain `{expected}`"));
+            }
+        }
+        Some(decoded)
+    }
+
+    /// Returns the strategy scoring an endpoint: the one of its URL, else the one of its first matching tag,
+    /// else the one of the service.
+    ///
+    /// # Arguments
+    /// * `probe` - The request of the endpoint, if it's monitored.
+    /// * `url` - The URL of the endpoint.
+    fn strategy_for(&self, probe: Option<&Request>, url: &str) -> Arc<dyn Strategy + Sync + Send + 'static> {
+        let by_url = || self.strategies.get(&strategy::Key::Url(url.to_string()));
+        let by_tag = || {
+            let mut tags = probe.into_iter().flat_map(|p| &p.tags);
+            tags.find_map(|(name, value)| self.strategies.get(&strategy::Key::Tag(name.clone(), value.clone())))
+        };
+        match by_url().or_else(by_tag) {
+            Some(strategy) => strategy.clone(),
+            None => self.strategy.read().expect("failed to read the strategy").clone(),
+        }
+    }
+
+    /// Records the latency of a probe, or its failure, within the error window of its endpoint.
+    ///
+    /// # Returns
+    /// The rate of failed probes within the window, this one included, and the jitter of the endpoint, i.e. the
+    /// standard deviation of the latency of the successful ones.
+    fn recent_stats(&self, outcome: &ProbeOutcome) -> (f32, Duration) {
+        let mut recent = self.recent_probes.entry(outcome.url.clone()).or_default();
+        recent.push_back(outcome.is_success().then_some(outcome.elapsed));
+        let excess = recent.len().saturating_sub(self.error_window);
+        recent.drain(..excess);
+
+        let latencies: Vec<f64> = recent.iter().flatten().map(Duration::as_secs_f64).collect();
+        let error_rate = (recent.len() - latencies.len()) as f32 / recent.len() as f32;
+        if latencies.is_empty() {
+            return (error_rate, Duration::ZERO);
+        }
+        let mean = latencies.iter().sum::<f64>() / latencies.len() as f64;
+        let variance = latencies.iter().map(|latency| (latency - mean).powi(2)).sum::<f64>() / latencies.len() as f64;
+        (error_rate, Duration::from_secs_f64(variance.sqrt()))
+    }
+
+    /// Calculates the score of an endpoint from a sample.
+    ///
+    /// # Arguments
+    /// * `sample` - The sample, whose request selects the strategy of its URL or tags.
+    /// * `previous` - The current score of the endpoint, `None` if it wasn't scored yet.
+    ///
+    /// This function calculates the new score based on the elapsed time and status code,
+    /// along with the reason the probe failed, if it did.
+    ///
+    /// # Returns
+    /// The updated score.
+    fn calculate_score(&self, sample: &Evaluated<'_>, previous: Option<Score>) -> Score {
+        let (probe, outcome) = (sample.probe, &sample.outcome);
+        let strategy = self.strategy_for(probe, &outcome.url);
+        // New endpoints start from the initial score of their strategy
+        let mut previous = previous.unwrap_or_else(|| strategy.initial_score());
+        // The strategy is given the error rate and jitter including this probe
+        let (error_rate, jitter) = self.recent_stats(outcome);
+        (previous.error_rate, previous.jitter) = (error_rate, jitter);
+        let mut score = strategy.calculate_outcome(previous.clone(), outcome);
+        // Keep why the endpoint is failing along with its score, for the dashboards
+        score.last_error = outcome.failure();
+        (score.error_rate, score.jitter) = (error_rate, jitter);
+        sample.span.record("score_delta", score.score - previous.score);
+        score
+    }
+}
+
+/// An evaluated outcome, pushed onto the scoring channel of an update cycle.
+struct Evaluated<'a> {
+    /// The request the outcome was received for, or `None` if it was probed elsewhere.
+    probe: Option<&'a Request>,
+    /// The outcome of the probe.
+    outcome: ProbeOutcome,
+    /// The span of the probe, recording its score delta if it's sampled.
+    span: tracing::Span,
+}
+
+/// Marks an update cycle as running, until it's dropped.
+struct Cycle<'a>(&'a AtomicBool);
+
+impl<'a> Cycle<'a> {
+    /// Starts an update cycle, unless one is already running.
+    fn start(updating: &'a AtomicBool) -> Option<Self> {
+        (!updating.swap(true, SeqCst)).then_some(Self(updating))
+    }
+}
 
-        self.store.set(url, score).await.expect("failed to set score");
+impl Drop for Cycle<'_> {
+    fn drop(&mut self) {
+        self.0.store(false, SeqCst);
     }
 }
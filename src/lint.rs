@@ -0,0 +1,156 @@
+use crate::{guard::Guard, Config, ConfigError};
+use std::time::Duration;
+use tokio::net::TcpStream;
+
+/// The time allowed to connect to an endpoint while checking whether it's reachable.
+const CONNECT_TIMEOUT: Duration = Duration::from_secs(5);
+
+/// A problem found in a configuration, reported by `lint` as one item of its machine-readable output.
+#[derive(serde::Deserialize, serde::Serialize, Clone, Debug, PartialEq, Eq)]
+pub struct Finding {
+    /// Whether the configuration is rejected, or only suspicious.
+    pub severity: Severity,
+    /// The rule that was broken.
+    pub rule: Rule,
+    /// The path of the offending field, e.g. `requests[1].url`.
+    pub path: String,
+    /// A description of the problem.
+    pub message: String,
+}
+
+impl Finding {
+    fn new(severity: Severity, rule: Rule, path: impl Into<String>, message: impl Into<String>) -> Self {
+        Self { severity, rule, path: path.into(), message: message.into() }
+    }
+}
+
+/// How serious a `Finding` is.
+#[derive(serde::Deserialize, serde::Serialize, Clone, Copy, Debug, PartialEq, Eq)]
+#[serde(rename_all = "snake_case")]
+pub enum Severity {
+    /// The service can't be built from the configuration, or its endpoints can't be probed.
+    Error,
+    /// The configuration is valid, but likely not what was intended.
+    Warning,
+}
+
+/// The rules a configuration is checked against.
+#[derive(serde::Deserialize, serde::Serialize, Clone, Copy, Debug, PartialEq, Eq)]
+#[serde(rename_all = "snake_case")]
+pub enum Rule {
+    /// The file can't be read or parsed into a `Config`.
+    Unparsable,
+    /// The configuration has no `interval`, so it can't be run on its own.
+    MissingInterval,
+    /// The `interval` is zero, so the scores would be updated in a busy loop.
+    ZeroInterval,
+    /// The `request_timeout` of the client is longer than the `interval`.
+    TimeoutExceedsInterval,
+    /// Several requests share a URL without distinct names, and would share one score.
+    DuplicateUrl,
+    /// The URL of a request has no scheme, e.g. `example.com` instead of `https://example.com`.
+    MissingScheme,
+    /// The host override of a request isn't a bare hostname.
+    InvalidHost,
+    /// The URL of a request is rejected by the guard.
+    Guarded,
+    /// The host of a request can't be resolved or connected to, only checked by `check_hosts`.
+    UnreachableHost,
+}
+
+/// Returns `true` if any of the findings rejects the configuration.
+pub fn has_errors(findings: &[Finding]) -> bool {
+    findings.iter().any(|f| f.severity == Severity::Error)
+}
+
+/// Checks a configuration file, running the validation of the `Config` along with heuristics on its requests.
+///
+/// # Arguments
+/// * `path`: The path of the YAML file, overridden by the `ISUP_*` environment variables like `Config::from_file`.
+/// * `check_hosts`: Whether the hosts of the requests are checked to be reachable as well, see `check_hosts`.
+///
+/// # Returns
+/// The findings, with a single `Rule::Unparsable` one if the file can't be parsed.
+pub async fn lint_file(path: &str, check_hosts: bool) -> Vec<Finding> {
+    let config = match Config::from_file(path) {
+        Ok(config) => config,
+        Err(e) => return vec![Finding::new(Severity::Error, Rule::Unparsable, "", e.to_string())],
+    };
+    let mut findings = lint(&config);
+    if check_hosts {
+        findings.extend(self::check_hosts(&config).await);
+    }
+    findings
+}
+
+/// Checks a configuration, running its validation along with heuristics on its requests.
+///
+/// Nothing is resolved nor connected to; see `check_hosts` for that.
+///
+/// # Returns
+/// The findings, in the order of the fields they point to.
+pub fn lint(config: &Config) -> Vec<Finding> {
+    let mut findings = vec![];
+    if config.interval.is_none() {
+        findings.push(Finding::new(Severity::Warning, Rule::MissingInterval, "interval", "missing interval"));
+    }
+    if let Err(e) = config.validate() {
+        let (rule, path) = match e {
+            ConfigError::ZeroInterval => (Rule::ZeroInterval, "interval"),
+            _ => (Rule::TimeoutExceedsInterval, "client.request_timeout"),
+        };
+        findings.push(Finding::new(Severity::Error, rule, path, e.to_string()));
+    }
+
+    let guard = config.guard.clone().map(Guard::new);
+    for (i, request) in config.requests.iter().enumerate() {
+        let path = format!("requests[{i}]");
+        if let Some(first) = config.requests[..i].iter().position(|r| r.key() == request.key()) {
+            let message = format!("duplicate request for `{}`, first defined at requests[{first}]", request.key());
+            findings.push(Finding::new(Severity::Error, Rule::DuplicateUrl, format!("{path}.url"), message));
+        }
+        if request.url.scheme().is_none() {
+            let message = format!("`{}` has no scheme", request.url);
+            findings.push(Finding::new(Severity::Error, Rule::MissingScheme, format!("{path}.url"), message));
+        }
+        if let Err(e) = request.check_host() {
+            findings.push(Finding::new(Severity::Error, Rule::InvalidHost, format!("{path}.host"), e));
+        }
+        if let Some(Err(violation)) = guard.as_ref().map(|g| g.check_url(&request.url)) {
+            findings.push(Finding::new(Severity::Error, Rule::Guarded, format!("{path}.url"), violation.to_string()));
+        }
+    }
+    findings
+}
+
+/// Checks that the host of every request resolves, and accepts connections on the port of its URL.
+///
+/// Hosts without a scheme or a known port are only resolved.
+///
+/// # Returns
+/// A `Rule::UnreachableHost` finding for every unreachable host.
+pub async fn check_hosts(config: &Config) -> Vec<Finding> {
+    let checks = config.requests.iter().enumerate().map(|(i, request)| async move {
+        let url = &request.url;
+        let host = url.host()?.trim_start_matches('[').trim_end_matches(']');
+        let port = url.port_u16().or(match url.scheme_str() {
+            Some("http") => Some(80),
+            Some("https") => Some(443),
+            _ => None,
+        });
+        let error = match tokio::net::lookup_host((host, port.unwrap_or_default())).await {
+            Ok(mut addrs) => match (addrs.next(), port) {
+                (None, _) => format!("no addresses found for {host}"),
+                (Some(addr), Some(_)) => match tokio::time::timeout(CONNECT_TIMEOUT, TcpStream::connect(addr)).await {
+                    Ok(Ok(_)) => return None,
+                    Ok(Err(e)) => format!("failed to connect to {addr}: {e}"),
+                    Err(_) => format!("timed out connecting to {addr}"),
+                },
+                (Some(_), None) => return None,
+            },
+            Err(e) => format!("failed to resolve {host}: {e}"),
+        };
+        Some(Finding::new(Severity::Error, Rule::UnreachableHost, format!("requests[{i}].url"), error))
+    });
+    futures::future::join_all(checks).await.into_iter().flatten().collect()
+}
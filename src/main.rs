@@ -0,0 +1,64 @@
+use isup::{history, lint, store, Config, Service};
+use std::error::Error;
+use std::process::ExitCode;
+
+const USAGE: &str = "usage: isup lint <config.yml> [--check-hosts]
+       isup migrate <from.yml> <to.yml>";
+
+/// Runs the `isup` command line.
+///
+/// `isup lint` prints the findings of the configuration as a JSON array, exiting with `1` if any of them is an
+/// error. `isup migrate` copies the scores and history from the store of a configuration to the store of another,
+/// printing the number of items copied as JSON. Both exit with `2` on invalid arguments.
+#[tokio::main]
+async fn main() -> ExitCode {
+    let args = std::env::args().skip(1).collect::<Vec<_>>();
+    match args.iter().map(String::as_str).collect::<Vec<_>>()[..] {
+        ["lint", path] => run_lint(path, false).await,
+        ["lint", path, "--check-hosts"] | ["lint", "--check-hosts", path] => run_lint(path, true).await,
+        ["migrate", from, to] => match run_migrate(from, to).await {
+            Ok(()) => ExitCode::SUCCESS,
+            Err(e) => {
+                eprintln!("migration failed: {e}");
+                ExitCode::FAILURE
+            }
+        },
+        _ => {
+            eprintln!("{USAGE}");
+            ExitCode::from(2)
+        }
+    }
+}
+
+/// Lints a configuration file, failing if any of its findings is an error.
+async fn run_lint(path: &str, check_hosts: bool) -> ExitCode {
+    let findings = lint::lint_file(path, check_hosts).await;
+    println!("{}", serde_json::to_string_pretty(&findings).expect("findings are serializable"));
+    match lint::has_errors(&findings) {
+        true => ExitCode::FAILURE,
+        false => ExitCode::SUCCESS,
+    }
+}
+
+/// Copies the scores, incidents and requests between the stores of two configuration files, along with the history
+/// if both of them record one.
+async fn run_migrate(from: &str, to: &str) -> Result<(), Box<dyn Error + Send + Sync>> {
+    let (from, to) = (Config::from_file(from)?, Config::from_file(to)?);
+    // An in-memory store only lives as long as its process, so there's nothing to copy from or to
+    if matches!(from.store, store::Config::Memory) || matches!(to.store, store::Config::Memory) {
+        return Err("the in-memory store can't be migrated from the command line".into());
+    }
+
+    let from_store = store::from_config(from.effective_store());
+    let mut migration = Service::migrate(&*from_store, &*store::from_config(to.effective_store())).await?;
+    if let (Some(from_history), Some(to_history)) = (from.history.clone(), to.history.clone()) {
+        // The history is kept for every scored endpoint, including the ones inserted at runtime
+        let urls = from_store.ranking().await?.into_iter().map(|(url, _)| url).collect::<Vec<_>>();
+        let from_history = history::from_config(&from.effective_store(), from_history);
+        let to_history = history::from_config(&to.effective_store(), to_history);
+        migration.samples = Service::migrate_history(&*from_history, &*to_history, &urls).await?;
+    }
+
+    println!("{}", serde_json::to_string_pretty(&migration)?);
+    Ok(())
+}
@@ -0,0 +1,390 @@
+use crate::incident::Incident;
+use crate::ranking::{Trend, TREND_SCORES};
+use crate::request::Request;
+use crate::score::Score;
+use crate::store::{Predicate, Store, StoreHealth};
+use crate::ProbeOutcome;
+use dashmap::DashMap;
+use std::collections::{BTreeMap, VecDeque};
+use std::error::Error;
+use std::fmt::Write;
+use std::future::Future;
+use std::sync::atomic::{AtomicU64, Ordering::SeqCst};
+use std::sync::{Arc, Mutex, RwLock};
+use std::time::{Duration, Instant, SystemTime};
+
+/// The timing and errors of an operation, accumulated since the service was created.
+#[derive(serde::Deserialize, serde::Serialize, Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub struct Operation {
+    /// The number of times the operation was executed.
+    pub count: u64,
+    /// The number of times the operation failed.
+    pub errors: u64,
+    /// The time spent executing the operation, in total.
+    pub total: Duration,
+    /// The longest execution of the operation.
+    pub max: Duration,
+}
+
+impl Operation {
+    /// Returns the average time it took to execute the operation, or `None` if it never was.
+    pub fn mean(&self) -> Option<Duration> {
+        u32::try_from(self.count).ok().filter(|count| *count > 0).map(|count| self.total / count)
+    }
+
+    /// Accumulates an execution of the operation.
+    fn record(&mut self, elapsed: Duration, failed: bool) {
+        self.count += 1;
+        self.errors += u64::from(failed);
+        self.total += elapsed;
+        self.max = self.max.max(elapsed);
+    }
+}
+
+/// The number of probes of an endpoint by class of status code, so an intermittent rate of server errors is visible
+/// even when the score of the endpoint looks fine.
+#[derive(serde::Deserialize, serde::Serialize, Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub struct StatusCounts {
+    /// The `1xx` responses.
+    pub informational: u64,
+    /// The `2xx` responses.
+    pub success: u64,
+    /// The `3xx` responses.
+    pub redirection: u64,
+    /// The `4xx` responses.
+    pub client_error: u64,
+    /// The `5xx` responses.
+    pub server_error: u64,
+    /// The probes that timed out before a response was received.
+    pub timeout: u64,
+    /// The probes that failed otherwise before a response was received, e.g. on a DNS or TLS failure.
+    pub no_response: u64,
+    /// The probes whose connection presented a certificate that doesn't match the pins of their request.
+    #[serde(default)]
+    pub pin_mismatch: u64,
+}
+
+impl StatusCounts {
+    /// The number of probes, of any class.
+    pub fn total(&self) -> u64 {
+        self.classes().iter().map(|(_, count)| count).sum()
+    }
+
+    /// Returns the count of every class, labeled as exported.
+    pub fn classes(&self) -> [(&'static str, u64); 8] {
+        [
+            ("1xx", self.informational),
+            ("2xx", self.success),
+            ("3xx", self.redirection),
+            ("4xx", self.client_error),
+            ("5xx", self.server_error),
+            ("timeout", self.timeout),
+            ("no_response", self.no_response),
+            ("pin_mismatch", self.pin_mismatch),
+        ]
+    }
+
+    /// Counts a probe in the class of its status code.
+    fn record(&mut self, outcome: &ProbeOutcome) {
+        let count = match outcome.status / 100 {
+            1 => &mut self.informational,
+            2 => &mut self.success,
+            3 => &mut self.redirection,
+            4 => &mut self.client_error,
+            5 => &mut self.server_error,
+            _ if outcome.is_timeout() => &mut self.timeout,
+            _ if outcome.is_pin_mismatch() => &mut self.pin_mismatch,
+            _ => &mut self.no_response,
+        };
+        *count += 1;
+    }
+}
+
+/// The metrics of a `Service`: the duration of its update cycles, the latency and errors of its store,
+/// distinguishing a slow store from slow probes when diagnosing long cycles, the status codes of its probes and the
+/// trend of the scores of its endpoints.
+///
+/// They can be read through `Service::metrics`, or scraped by Prometheus from the `/metrics` route of the
+/// embedded server, in its text exposition format. The series of a namespaced service are labeled with its
+/// `namespace`, so several services monitoring the same URLs in one process export distinct series.
+#[derive(Debug, Default)]
+pub struct Metrics {
+    store: DashMap<&'static str, Operation>,
+    cycles: Mutex<Operation>,
+    last_cycle: Mutex<Option<Duration>>,
+    skipped_cycles: AtomicU64,
+    statuses: DashMap<String, StatusCounts>,
+    /// The latest scores of every endpoint, along with when they were computed.
+    scores: DashMap<String, VecDeque<(SystemTime, f32)>>,
+    /// The namespace of the service, labeling every series.
+    namespace: RwLock<Option<String>>,
+}
+
+impl Metrics {
+    /// Sets the namespace of the service, labeling every series.
+    pub(crate) fn set_namespace(&self, namespace: Option<String>) {
+        *self.namespace.write().expect("poisoned metrics namespace") = namespace;
+    }
+
+    /// Formats the labels of a series, prefixed with the namespace of the service, if any.
+    fn labels(&self, labels: &[(&str, &str)]) -> String {
+        let namespace = self.namespace.read().expect("poisoned metrics namespace");
+        let labels =
+            namespace.as_deref().map(|namespace| ("namespace", namespace)).into_iter().chain(labels.iter().copied());
+        let labels = labels.map(|(name, value)| format!("{name}=\"{}\"", escape(value))).collect::<Vec<_>>();
+        match labels.is_empty() {
+            true => String::new(),
+            false => format!("{{{}}}", labels.join(",")),
+        }
+    }
+
+    /// Returns the duration and errors of the update cycles.
+    pub fn cycles(&self) -> Operation {
+        *self.cycles.lock().expect("poisoned cycle metrics")
+    }
+
+    /// Returns how long the latest update cycle took, or `None` if none completed yet.
+    pub fn last_cycle(&self) -> Option<Duration> {
+        *self.last_cycle.lock().expect("poisoned cycle metrics")
+    }
+
+    /// Returns the number of update cycles skipped, because the previous one was still running.
+    pub fn skipped_cycles(&self) -> u64 {
+        self.skipped_cycles.load(SeqCst)
+    }
+
+    /// Records a completed update cycle.
+    pub(crate) fn record_cycle(&self, elapsed: Duration, failed: bool) {
+        self.cycles.lock().expect("poisoned cycle metrics").record(elapsed, failed);
+        *self.last_cycle.lock().expect("poisoned cycle metrics") = Some(elapsed);
+    }
+
+    /// Records an update cycle skipped, because the previous one was still running.
+    pub(crate) fn record_skipped_cycle(&self) {
+        self.skipped_cycles.fetch_add(1, SeqCst);
+    }
+
+    /// Returns the number of probes of an endpoint by class of status code, or `None` if it wasn't probed.
+    ///
+    /// # Arguments
+    /// * `url`: The normalized URL of the endpoint.
+    pub fn statuses(&self, url: &str) -> Option<StatusCounts> {
+        self.statuses.get(url).map(|s| *s)
+    }
+
+    /// Counts a scored probe in the class of its status code.
+    pub(crate) fn record_status(&self, outcome: &ProbeOutcome) {
+        self.statuses.entry(outcome.url.clone()).or_default().record(outcome);
+    }
+
+    /// Returns the short-term trend of the score of an endpoint, or `None` until it was scored enough times.
+    ///
+    /// # Arguments
+    /// * `url`: The normalized URL of the endpoint.
+    pub fn trend(&self, url: &str) -> Option<Trend> {
+        let scores = self.scores.get(url)?.iter().copied().collect::<Vec<_>>();
+        Trend::from_scores(&scores)
+    }
+
+    /// Records the score of an endpoint, keeping its latest ones to compute its trend.
+    pub(crate) fn record_score(&self, url: &str, score: &Score) {
+        let mut scores = self.scores.entry(url.to_string()).or_default();
+        scores.push_back((SystemTime::now(), score.score));
+        let excess = scores.len().saturating_sub(TREND_SCORES);
+        scores.drain(..excess);
+    }
+
+    /// Forgets the probes of an endpoint, once it's no longer monitored.
+    pub(crate) fn forget(&self, url: &str) {
+        self.statuses.remove(url);
+        self.scores.remove(url);
+    }
+
+    /// Returns the timing and errors of the store operations, by name, e.g. `get`.
+    pub fn store_operations(&self) -> BTreeMap<String, Operation> {
+        self.store.iter().map(|o| (o.key().to_string(), *o.value())).collect()
+    }
+
+    /// Renders the metrics in the Prometheus text exposition format.
+    pub fn render(&self) -> String {
+        let cycles = self.cycles();
+        let mut text = String::new();
+        let last = self.last_cycle().unwrap_or_default();
+        let metrics = [
+            ("isup_update_cycles_total", "counter", "The number of update cycles.", cycles.count.to_string()),
+            (
+                "isup_update_cycle_errors_total",
+                "counter",
+                "The number of failed update cycles.",
+                cycles.errors.to_string(),
+            ),
+            (
+                "isup_update_cycles_skipped_total",
+                "counter",
+                "The number of update cycles skipped.",
+                self.skipped_cycles().to_string(),
+            ),
+            (
+                "isup_update_cycle_seconds_total",
+                "counter",
+                "The time spent in update cycles.",
+                cycles.total.as_secs_f64().to_string(),
+            ),
+            (
+                "isup_update_cycle_seconds_max",
+                "gauge",
+                "The longest update cycle.",
+                cycles.max.as_secs_f64().to_string(),
+            ),
+            (
+                "isup_update_cycle_seconds_last",
+                "gauge",
+                "The duration of the latest update cycle.",
+                last.as_secs_f64().to_string(),
+            ),
+        ];
+        let labels = self.labels(&[]);
+        for (name, kind, help, value) in metrics {
+            let _ = writeln!(text, "# HELP {name} {help}\n# TYPE {name} {kind}\n{name}{labels} {value}");
+        }
+
+        let operations = self.store_operations();
+        let mut family = |name: &str, kind: &str, help: &str, value: &dyn Fn(&Operation) -> String| {
+            let _ = writeln!(text, "# HELP {name} {help}\n# TYPE {name} {kind}");
+            for (operation, stats) in &operations {
+                let _ = writeln!(text, "{name}{} {}", self.labels(&[("operation", operation)]), value(stats));
+            }
+        };
+        family("isup_store_operations_total", "counter", "The number of store operations.", &|o| o.count.to_string());
+        family("isup_store_errors_total", "counter", "The number of failed store operations.", &|o| {
+            o.errors.to_string()
+        });
+        family("isup_store_operation_seconds_total", "counter", "The time spent in store operations.", &|o| {
+            o.total.as_secs_f64().to_string()
+        });
+        family("isup_store_operation_seconds_max", "gauge", "The longest store operation.", &|o| {
+            o.max.as_secs_f64().to_string()
+        });
+
+        let name = "isup_probe_responses_total";
+        let _ = writeln!(text, "# HELP {name} The number of probes by class of status code.\n# TYPE {name} counter");
+        let statuses: BTreeMap<_, _> = self.statuses.iter().map(|s| (s.key().clone(), *s.value())).collect();
+        for (url, counts) in statuses {
+            for (class, count) in counts.classes() {
+                let _ = writeln!(text, "{name}{} {count}", self.labels(&[("url", &url), ("class", class)]));
+            }
+        }
+
+        let trends: BTreeMap<_, _> = self
+            .scores
+            .iter()
+            .filter_map(|s| Some((s.key().clone(), Trend::from_scores(&s.iter().copied().collect::<Vec<_>>())?)))
+            .collect();
+        let name = "isup_score_trend_slope";
+        let _ = writeln!(
+            text,
+            "# HELP {name} The change of the score per hour, over its latest values.\n# TYPE {name} gauge"
+        );
+        for (url, trend) in &trends {
+            let _ = writeln!(text, "{name}{} {}", self.labels(&[("url", url)]), trend.slope);
+        }
+        let name = "isup_score_trend";
+        let _ = writeln!(
+            text,
+            "# HELP {name} The direction of the score: 1 improving, 0 stable, -1 degrading.\n# TYPE {name} gauge"
+        );
+        for (url, trend) in &trends {
+            let _ = writeln!(text, "{name}{} {}", self.labels(&[("url", url)]), trend.direction.value());
+        }
+        text
+    }
+
+    /// Times a store operation, counting it as an error if it fails.
+    async fn time_store<T, E>(
+        &self,
+        operation: &'static str,
+        future: impl Future<Output = Result<T, E>>,
+    ) -> Result<T, E> {
+        let start = Instant::now();
+        let result = future.await;
+        self.store.entry(operation).or_default().record(start.elapsed(), result.is_err());
+        result
+    }
+}
+
+/// Escapes the value of a label, as specified by the Prometheus text exposition format.
+fn escape(value: &str) -> String {
+    value.replace('\\', "\\\\").replace('"', "\\\"").replace('\n', "\\n")
+}
+
+/// A store whose `get`, `set` and `best_url` operations are timed, the others being passed through.
+/// Batched writes are timed as `set` operations.
+pub(crate) struct Instrumented {
+    inner: Arc<dyn Store + Sync + Send + 'static>,
+    metrics: Arc<Metrics>,
+}
+
+impl Instrumented {
+    pub(crate) fn new(inner: Arc<dyn Store + Sync + Send + 'static>, metrics: Arc<Metrics>) -> Self {
+        Self { inner, metrics }
+    }
+}
+
+#[async_trait::async_trait]
+impl Store for Instrumented {
+    async fn set(&self, key: String, value: Score) -> Result<(), Box<dyn Error + Send + Sync>> {
+        self.metrics.time_store("set", self.inner.set(key, value)).await
+    }
+
+    async fn set_many(&self, scores: Vec<(String, Score)>) -> Result<(), Box<dyn Error + Send + Sync>> {
+        self.metrics.time_store("set", self.inner.set_many(scores)).await
+    }
+
+    async fn get(&self, key: &str) -> Result<Option<Score>, Box<dyn Error + Send + Sync>> {
+        self.metrics.time_store("get", self.inner.get(key)).await
+    }
+
+    async fn best_url(&self) -> Result<Option<String>, Box<dyn Error + Send + Sync>> {
+        self.metrics.time_store("best_url", self.inner.best_url()).await
+    }
+
+    async fn best_url_where(&self, predicate: &Predicate<'_>) -> Result<Option<String>, Box<dyn Error + Send + Sync>> {
+        self.inner.best_url_where(predicate).await
+    }
+
+    async fn ranking(&self) -> Result<Vec<(String, Score)>, Box<dyn Error + Send + Sync>> {
+        self.inner.ranking().await
+    }
+
+    async fn set_incident(&self, incident: Incident) -> Result<(), Box<dyn Error + Send + Sync>> {
+        self.inner.set_incident(incident).await
+    }
+
+    async fn get_incident(&self, id: &str) -> Result<Option<Incident>, Box<dyn Error + Send + Sync>> {
+        self.inner.get_incident(id).await
+    }
+
+    async fn incidents(&self) -> Result<Vec<Incident>, Box<dyn Error + Send + Sync>> {
+        self.inner.incidents().await
+    }
+
+    async fn clear(&self) -> Result<(), Box<dyn Error + Send + Sync>> {
+        self.inner.clear().await
+    }
+
+    async fn ping(&self) -> Result<(), Box<dyn Error + Send + Sync>> {
+        self.inner.ping().await
+    }
+
+    async fn health(&self) -> StoreHealth {
+        self.inner.health().await
+    }
+
+    async fn set_requests(&self, requests: &[Request]) -> Result<(), Box<dyn Error + Send + Sync>> {
+        self.inner.set_requests(requests).await
+    }
+
+    async fn requests(&self) -> Result<Vec<Request>, Box<dyn Error + Send + Sync>> {
+        self.inner.requests().await
+    }
+}
@@ -0,0 +1,48 @@
+use crate::outcome::ProbeOutcome;
+use bytes::Bytes;
+use http_body_util::Full;
+
+/// Determines what happens to a `ProbeOutcome` after it passed through a `Middleware`.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Action {
+    /// Pass the outcome to the next middleware, and finally to the strategy.
+    Continue,
+    /// Discard the outcome; the score of the endpoint is left untouched.
+    Veto,
+}
+
+/// Trait defining hooks that run around every probe of a `Service`.
+///
+/// Middlewares are executed in the order they were registered. Each hook has a default
+/// implementation, so only the relevant ones need to be implemented.
+pub trait Middleware {
+    /// Invoked before the request is sent, allowing it to be modified (e.g. adding headers).
+    ///
+    /// # Arguments
+    /// * `request`: The request that is about to be sent.
+    fn before(&self, _request: &mut hyper::Request<Full<Bytes>>) {}
+
+    /// Invoked after the endpoint was probed and before the outcome is scored.
+    ///
+    /// # Arguments
+    /// * `outcome`: The outcome of the probe, which can be modified in place.
+    ///
+    /// # Returns
+    /// `Action::Veto` to discard the outcome, otherwise `Action::Continue`.
+    fn after(&self, _outcome: &mut ProbeOutcome) -> Action {
+        Action::Continue
+    }
+}
+
+/// A `Middleware` that observes the outcomes matching a filter without modifying them.
+/// Created through `Service::on_result`, `Service::on_success` and `Service::on_failure`.
+pub(crate) struct OnResult<F>(pub(crate) F, pub(crate) fn(&ProbeOutcome) -> bool);
+
+impl<F: Fn(&ProbeOutcome)> Middleware for OnResult<F> {
+    fn after(&self, outcome: &mut ProbeOutcome) -> Action {
+        if (self.1)(outcome) {
+            (self.0)(outcome);
+        }
+        Action::Continue
+    }
+}
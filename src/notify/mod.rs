@@ -0,0 +1,61 @@
+use std::error::Error;
+use std::sync::Arc;
+
+mod webhook;
+pub use webhook::Webhook;
+
+/// Configuration options for the different notifiers.
+///
+/// Like the stores, notifiers are selected by their `type`, e.g.
+/// `{ type: webhook, url: https://hooks.example.com/isup }`.
+#[derive(serde::Deserialize, serde::Serialize, Clone, Debug)]
+#[serde(tag = "type")]
+#[serde(rename_all = "snake_case")]
+pub enum Config {
+    /// Posts every notification to a URL.
+    Webhook(webhook::Config),
+}
+
+/// Constructs a notifier from the provided configuration.
+///
+/// # Arguments
+/// * `config` - Notifier configuration.
+///
+/// # Returns
+/// A boxed notifier implementing the `Notifier` trait.
+pub fn from_config(config: Config) -> Box<dyn Notifier + Sync + Send + 'static> {
+    match config {
+        Config::Webhook(config) => Box::new(Webhook::from_config(config)),
+    }
+}
+
+/// A message delivered to the operators, e.g. a summary report.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Notification {
+    /// A short title, e.g. the subject of an email.
+    pub subject: String,
+    /// The media type of the body, e.g. `text/markdown`.
+    pub content_type: String,
+    /// The content of the notification, rendered in its media type.
+    pub body: String,
+}
+
+/// Delivers notifications to the operators, through an external channel.
+#[async_trait::async_trait]
+pub trait Notifier {
+    /// Delivers a notification.
+    ///
+    /// # Arguments
+    /// * `notification`: The notification to be delivered.
+    ///
+    /// # Returns
+    /// A result indicating whether the notification was delivered.
+    async fn notify(&self, notification: &Notification) -> Result<(), Box<dyn Error + Send + Sync>>;
+}
+
+#[async_trait::async_trait]
+impl<T: Notifier + Sync + Send + ?Sized> Notifier for Arc<T> {
+    async fn notify(&self, notification: &Notification) -> Result<(), Box<dyn Error + Send + Sync>> {
+        (**self).notify(notification).await
+    }
+}
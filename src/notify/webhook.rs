@@ -0,0 +1,63 @@
+use super::{Notification, Notifier};
+use crate::config::{deserialize_headers, deserialize_uri, serialize_headers, serialize_uri};
+use crate::Client;
+use bytes::Bytes;
+use http_body_util::Full;
+use hyper::header::CONTENT_TYPE;
+use hyper::{HeaderMap, Uri};
+use std::error::Error;
+
+/// The header carrying the subject of a notification.
+const SUBJECT_HEADER: &str = "x-isup-subject";
+
+/// Webhook configuration
+///
+/// - `url`: the URL the notifications are posted to
+/// - `headers`: additional headers sent along, e.g. an `authorization` header
+#[derive(serde::Deserialize, serde::Serialize, Clone, Debug)]
+pub struct Config {
+    #[serde(deserialize_with = "deserialize_uri", serialize_with = "serialize_uri")]
+    pub url: Uri,
+    #[serde(deserialize_with = "deserialize_headers", serialize_with = "serialize_headers", default)]
+    pub headers: HeaderMap,
+}
+
+/// Posts every notification to a URL, with its body as the payload of the request.
+/// The subject is sent in the `x-isup-subject` header.
+pub struct Webhook {
+    config: Config,
+    client: Client,
+}
+
+impl Webhook {
+    /// Creates a new `Webhook`, posting to the given URL.
+    ///
+    /// # Panics
+    /// Panics if the URL cannot be parsed.
+    pub fn new<I: Into<String>>(url: I) -> Self {
+        let url = url.into().parse().expect("Invalid URL");
+        Self::from_config(Config { url, headers: HeaderMap::new() })
+    }
+
+    /// Creates a new `Webhook` from its configuration.
+    pub fn from_config(config: Config) -> Self {
+        Self { config, client: Client::default() }
+    }
+}
+
+#[async_trait::async_trait]
+impl Notifier for Webhook {
+    async fn notify(&self, notification: &Notification) -> Result<(), Box<dyn Error + Send + Sync>> {
+        let mut request = hyper::Request::post(self.config.url.clone())
+            .header(CONTENT_TYPE, &notification.content_type)
+            .header(SUBJECT_HEADER, &notification.subject)
+            .body(Full::new(Bytes::from(notification.body.clone())))?;
+        request.headers_mut().extend(self.config.headers.clone());
+
+        let response = self.client.request(request).await?;
+        match response.status().is_success() {
+            true => Ok(()),
+            false => Err(format!("webhook responded with `{}`", response.status()).into()),
+        }
+    }
+}
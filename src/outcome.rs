@@ -0,0 +1,166 @@
+use crate::client::PIN_MISMATCH;
+use std::{net::SocketAddr, time::Duration};
+
+/// The result of probing a single endpoint, before it's handed to the strategy.
+///
+/// An outcome is passed through the `Middleware` chain of a `Service`, where it can be
+/// inspected, modified or vetoed before it affects the score of the endpoint.
+/// Outcomes can be serialized, in order to be recorded and later replayed by the `backtest` module.
+#[derive(Clone, Debug, serde::Deserialize, serde::Serialize)]
+pub struct ProbeOutcome {
+    /// The URL of the probed endpoint, used as the key of its score.
+    pub url: String,
+    /// The time it took for the response to be received.
+    pub elapsed: Duration,
+    /// The HTTP status code of the response, or `0` if no response was received.
+    pub status: u16,
+    /// Whether the request was sent over a reused, pooled connection; `None` if unknown.
+    /// A reused connection skips the DNS, TCP and TLS setup, which lowers the measured latency.
+    #[serde(default)]
+    pub connection_reused: Option<bool>,
+    /// The address the response was received from; `None` if unknown.
+    /// Its IP version tells which address family was used to reach a dual-stack endpoint.
+    #[serde(default)]
+    pub remote_addr: Option<SocketAddr>,
+    /// The unique ID sent along with the probe, if the `Service` is configured with a request ID header.
+    #[serde(default)]
+    pub request_id: Option<String>,
+    /// The size of the response body as received, in bytes; `None` if the body wasn't read.
+    #[serde(default)]
+    pub body_size: Option<usize>,
+    /// The size of the response body once decoded, in bytes; equal to `body_size` for uncompressed responses.
+    #[serde(default)]
+    pub decoded_size: Option<usize>,
+    /// The time it took to decode the response body; `None` if the body wasn't read.
+    #[serde(default)]
+    pub decode_time: Option<Duration>,
+    /// The throughput achieved while downloading the body, in bytes per second; `None` unless a download size is set.
+    #[serde(default)]
+    pub throughput: Option<f64>,
+    /// The throughput achieved while uploading the body, in bytes per second; `None` unless an upload size is set.
+    #[serde(default)]
+    pub upload_throughput: Option<f64>,
+    /// The time between the end of the upload and the response headers, spent by the server processing the payload;
+    /// `None` unless an upload size is set.
+    #[serde(default)]
+    pub processing_time: Option<Duration>,
+    /// Whether a conditional request was answered with `304 Not Modified`; `None` without cache validation.
+    #[serde(default)]
+    pub revalidated: Option<bool>,
+    /// The age of the response, from its `Date` and `Age` headers; `None` unless a maximum staleness is set.
+    #[serde(default)]
+    pub staleness: Option<Duration>,
+    /// The reason the probe failed although a response was received, e.g. an unexpected body.
+    /// A probe with an error is scored as if no response was received.
+    #[serde(default)]
+    pub error: Option<String>,
+    /// The usual latency of the endpoint at the same hour of the week, from its history; `None` unless the
+    /// baselines are enabled, or if the endpoint wasn't sampled at this hour yet.
+    #[serde(default)]
+    pub baseline: Option<Duration>,
+    /// The latency budget of the endpoint, if its request declares one.
+    #[serde(default)]
+    pub budget: Option<Duration>,
+}
+
+impl ProbeOutcome {
+    /// Creates a new `ProbeOutcome` instance.
+    ///
+    /// # Arguments
+    /// * `url`: The URL of the probed endpoint.
+    /// * `elapsed`: The time it took for the response to be received.
+    /// * `status`: The HTTP status code of the response, `0` if the request failed.
+    pub fn new<I: Into<String>>(url: I, elapsed: Duration, status: u16) -> Self {
+        Self {
+            url: url.into(),
+            elapsed,
+            status,
+            connection_reused: None,
+            remote_addr: None,
+            request_id: None,
+            body_size: None,
+            decoded_size: None,
+            decode_time: None,
+            throughput: None,
+            upload_throughput: None,
+            processing_time: None,
+            revalidated: None,
+            staleness: None,
+            error: None,
+            baseline: None,
+            budget: None,
+        }
+    }
+
+    /// Returns `true` if a response was received, its status code doesn't indicate an error
+    /// and no other error was recorded.
+    pub fn is_success(&self) -> bool {
+        (100..400).contains(&self.status) && self.error.is_none()
+    }
+
+    /// Describes why the probe failed, in a human-readable form.
+    ///
+    /// # Returns
+    /// The recorded error, else the unexpected status code, or `None` if the probe succeeded.
+    pub fn failure(&self) -> Option<String> {
+        match (&self.error, self.status) {
+            _ if self.is_success() => None,
+            (Some(error), _) => Some(error.clone()),
+            (None, 0) => Some("no response received".into()),
+            (None, status) => Some(format!("responded with status {status}")),
+        }
+    }
+
+    /// Returns `true` if the probe timed out before a response was received, either on the request timeout or at
+    /// the deadline of its update cycle.
+    pub fn is_timeout(&self) -> bool {
+        let timed_out = |e: &str| e.contains("deadline has elapsed") || e.contains("timed out");
+        self.status == 0 && self.error.as_deref().is_some_and(timed_out)
+    }
+
+    /// Returns `true` if the probe failed because the certificate of the endpoint doesn't match the pins of its
+    /// request, hinting at an interception or a misdeployed certificate rather than an outage.
+    pub fn is_pin_mismatch(&self) -> bool {
+        self.status == 0 && self.error.as_deref().is_some_and(|e| e.starts_with(PIN_MISMATCH))
+    }
+
+    /// Returns `true` if the probe succeeded, but its response took longer than the latency budget of its endpoint.
+    pub fn exceeds_budget(&self) -> bool {
+        self.is_success() && self.budget.is_some_and(|budget| self.elapsed > budget)
+    }
+}
+
+/// A measurement of an endpoint gathered outside of `isup`, e.g. by the real-user monitoring of a client or by
+/// another monitoring system, scored through `Service::ingest` alongside the probes of the service.
+#[derive(Clone, Debug, serde::Deserialize, serde::Serialize)]
+pub struct Sample {
+    /// The time it took for the response to be received.
+    pub elapsed: Duration,
+    /// The HTTP status code of the response, or `0` if no response was received.
+    pub status: u16,
+    /// The reason the request failed although a response was received, e.g. an unexpected body.
+    #[serde(default)]
+    pub error: Option<String>,
+}
+
+impl Sample {
+    /// Creates a new `Sample` instance.
+    ///
+    /// # Arguments
+    /// * `elapsed`: The time it took for the response to be received.
+    /// * `status`: The HTTP status code of the response, `0` if the request failed.
+    pub fn new(elapsed: Duration, status: u16) -> Self {
+        Self { elapsed, status, error: None }
+    }
+
+    /// Records why the request failed, scoring the sample as if no response was received.
+    pub fn set_error<I: Into<String>>(mut self, error: I) -> Self {
+        self.error = Some(error.into());
+        self
+    }
+
+    /// Converts the sample into the outcome of a probe of the given endpoint.
+    pub(crate) fn into_outcome(self, url: String) -> ProbeOutcome {
+        ProbeOutcome { error: self.error, ..ProbeOutcome::new(url, self.elapsed, self.status) }
+    }
+}
@@ -0,0 +1,62 @@
+use crate::config::{deserialize_opt_regex, serialize_opt_regex};
+use regex::Regex;
+
+/// A command run by an `exec://` probe, for checks the built-in probes can't express.
+///
+/// The probe succeeds if the command exits with a zero status and, if expected, its standard output matches.
+/// Commands are killed once the request timeout of the client elapses.
+///
+/// Commands are never run through a shell, and `exec://` URLs are rejected by any `Guard` unless explicitly allowed.
+#[derive(serde::Deserialize, serde::Serialize, Debug, Clone)]
+pub struct Exec {
+    /// The program to be run, either a path or a name looked up in the `PATH`.
+    pub command: String,
+    /// The arguments passed to the program.
+    #[serde(default)]
+    pub args: Vec<String>,
+    /// A regular expression the standard output of the command must match.
+    #[serde(deserialize_with = "deserialize_opt_regex", serialize_with = "serialize_opt_regex", default)]
+    pub expect_stdout: Option<Regex>,
+}
+
+impl Exec {
+    /// Creates a new `Exec` running the given program without arguments.
+    pub fn new<I: Into<String>>(command: I) -> Self {
+        Self { command: command.into(), args: vec![], expect_stdout: None }
+    }
+
+    /// Sets the arguments passed to the program.
+    pub fn set_args<I: Into<String>>(mut self, args: Vec<I>) -> Self {
+        self.args = args.into_iter().map(Into::into).collect();
+        self
+    }
+
+    /// Sets the regular expression the standard output of the command must match.
+    pub fn set_expect_stdout(mut self, expect_stdout: Regex) -> Self {
+        self.expect_stdout = Some(expect_stdout);
+        self
+    }
+
+    /// Runs the command, waiting for it to exit.
+    pub(crate) async fn run(&self) -> Result<(), String> {
+        let output = tokio::process::Command::new(&self.command)
+            .args(&self.args)
+            .stdin(std::process::Stdio::null())
+            // Kill the command if the probe times out and its future is dropped.
+            .kill_on_drop(true)
+            .output()
+            .await
+            .map_err(|e| format!("failed to run `{}`: {e}", self.command))?;
+
+        if !output.status.success() {
+            let stderr = String::from_utf8_lossy(&output.stderr);
+            return Err(format!("`{}` exited with {}: {}", self.command, output.status, stderr.trim_end()));
+        }
+        match &self.expect_stdout {
+            Some(expected) if !expected.is_match(&String::from_utf8_lossy(&output.stdout)) => {
+                Err(format!("output of `{}` doesn't match `{expected}`", self.command))
+            }
+            _ => Ok(()),
+        }
+    }
+}
@@ -0,0 +1,71 @@
+use bytes::Bytes;
+
+/// A GraphQL query posted by the probe of a request.
+///
+/// GraphQL services usually report errors within a `200` response, so the probe reads the response and
+/// fails if it contains a non-empty `errors` array, instead of scoring the service as healthy.
+#[derive(serde::Deserialize, serde::Serialize, Debug, Clone, PartialEq)]
+#[serde(rename_all = "camelCase")]
+pub struct GraphQl {
+    /// The query document, e.g. `{ __typename }`.
+    pub query: String,
+    /// The variables of the query.
+    #[serde(default, skip_serializing_if = "serde_json::Value::is_null")]
+    pub variables: serde_json::Value,
+    /// The operation to be executed, if the document contains several ones.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub operation_name: Option<String>,
+}
+
+impl GraphQl {
+    /// Creates a new `GraphQl` query without variables.
+    pub fn new<I: Into<String>>(query: I) -> Self {
+        Self { query: query.into(), variables: serde_json::Value::Null, operation_name: None }
+    }
+
+    /// Sets the variables of the query.
+    pub fn set_variables(mut self, variables: serde_json::Value) -> Self {
+        self.variables = variables;
+        self
+    }
+
+    /// Sets the operation to be executed.
+    pub fn set_operation_name<I: Into<String>>(mut self, operation_name: I) -> Self {
+        self.operation_name = Some(operation_name.into());
+        self
+    }
+
+    /// Returns the JSON body posted to the service.
+    pub(crate) fn body(&self) -> Bytes {
+        Bytes::from(serde_json::to_vec(self).expect("failed to serialize query"))
+    }
+
+    /// Checks a decoded response body for errors.
+    ///
+    /// # Returns
+    /// The message of the first error, if the response isn't JSON or contains any error.
+    pub(crate) fn check(body: &[u8]) -> Result<(), String> {
+        let response: serde_json::Value = serde_json::from_slice(body).map_err(|e| format!("invalid response: {e}"))?;
+        match response.get("errors").and_then(|e| e.as_array()) {
+            Some(errors) if !errors.is_empty() => {
+                let message = errors[0].get("message").and_then(|m| m.as_str()).unwrap_or("unknown error");
+                Err(format!("GraphQL returned {} error(s): {message}", errors.len()))
+            }
+            _ => Ok(()),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_graphql_check() {
+        assert!(GraphQl::check(br#"{"data":{"__typename":"Query"}}"#).is_ok());
+        assert!(GraphQl::check(br#"{"data":null,"errors":[]}"#).is_ok());
+        let error = GraphQl::check(br#"{"data":null,"errors":[{"message":"unauthorized"}]}"#).unwrap_err();
+        assert!(error.contains("unauthorized"));
+        assert!(GraphQl::check(b"<html>").is_err());
+    }
+}
@@ -0,0 +1,111 @@
+use super::{connect, Security, Stream};
+use crate::guard::Guard;
+use hyper::Uri;
+use tokio::io::{AsyncBufReadExt, AsyncWriteExt, BufReader};
+
+/// Checks an SMTP server: reads its banner, introduces itself with `EHLO` and, if required, upgrades
+/// the connection with `STARTTLS` before introducing itself again.
+pub(crate) async fn smtp(url: &Uri, security: Security, guard: Option<&Guard>) -> Result<(), String> {
+    let default_port = if security == Security::Implicit { 465 } else { 25 };
+    let mut stream = connect(url, default_port, security == Security::Implicit, guard).await?;
+
+    smtp_reply(&mut stream, 220).await?;
+    let extensions = smtp_command(&mut stream, "EHLO isup", 250).await?;
+
+    if security == Security::StartTls {
+        if !extensions.iter().any(|e| e.eq_ignore_ascii_case("STARTTLS")) {
+            return Err("server doesn't support STARTTLS".into());
+        }
+        smtp_command(&mut stream, "STARTTLS", 220).await?;
+        stream = super::upgrade(stream.into_inner(), url).await?;
+        smtp_command(&mut stream, "EHLO isup", 250).await?;
+    }
+
+    // The connection is closed right away, so the reply to `QUIT` isn't awaited.
+    stream.write_all(b"QUIT\r\n").await.map_err(|e| e.to_string())
+}
+
+/// Checks an IMAP server without logging in: reads its greeting, lists its capabilities and, if required,
+/// upgrades the connection with `STARTTLS` before listing them again.
+pub(crate) async fn imap(url: &Uri, security: Security, guard: Option<&Guard>) -> Result<(), String> {
+    let default_port = if security == Security::Implicit { 993 } else { 143 };
+    let mut stream = connect(url, default_port, security == Security::Implicit, guard).await?;
+
+    let greeting = read_line(&mut stream).await?;
+    if !greeting.starts_with("* OK") && !greeting.starts_with("* PREAUTH") {
+        return Err(format!("unexpected greeting: {greeting}"));
+    }
+    let capabilities = imap_command(&mut stream, "a1", "CAPABILITY").await?;
+
+    if security == Security::StartTls {
+        if !capabilities.iter().any(|c| c.to_ascii_uppercase().split(' ').any(|c| c == "STARTTLS")) {
+            return Err("server doesn't support STARTTLS".into());
+        }
+        imap_command(&mut stream, "a2", "STARTTLS").await?;
+        stream = super::upgrade(stream.into_inner(), url).await?;
+        imap_command(&mut stream, "a3", "CAPABILITY").await?;
+    }
+
+    imap_command(&mut stream, "a4", "LOGOUT").await.map(|_| ())
+}
+
+/// Reads a line, without its line ending.
+async fn read_line(stream: &mut BufReader<Box<dyn Stream>>) -> Result<String, String> {
+    let mut line = String::new();
+    match stream.read_line(&mut line).await {
+        Ok(0) => Err("connection closed by server".into()),
+        Ok(_) => Ok(line.trim_end().to_string()),
+        Err(e) => Err(e.to_string()),
+    }
+}
+
+/// Reads a possibly multi-line SMTP reply, expecting the given code.
+///
+/// # Returns
+/// The text of every line of the reply.
+async fn smtp_reply(stream: &mut BufReader<Box<dyn Stream>>, expected: u16) -> Result<Vec<String>, String> {
+    let mut lines = Vec::new();
+    loop {
+        let line = read_line(stream).await?;
+        let code = line.get(..3).and_then(|c| c.parse::<u16>().ok()).ok_or(format!("invalid reply: {line}"))?;
+        if code != expected {
+            return Err(format!("expected {expected}, received: {line}"));
+        }
+        lines.push(line.get(4..).unwrap_or_default().to_string());
+        // The last line of a reply separates the code from the text with a space instead of a dash.
+        if line.as_bytes().get(3) != Some(&b'-') {
+            return Ok(lines);
+        }
+    }
+}
+
+/// Sends an SMTP command, expecting a reply with the given code.
+async fn smtp_command(
+    stream: &mut BufReader<Box<dyn Stream>>,
+    command: &str,
+    expected: u16,
+) -> Result<Vec<String>, String> {
+    stream.write_all(format!("{command}\r\n").as_bytes()).await.map_err(|e| e.to_string())?;
+    smtp_reply(stream, expected).await
+}
+
+/// Sends a tagged IMAP command, expecting it to complete with `OK`.
+///
+/// # Returns
+/// The untagged responses received before completion.
+async fn imap_command(
+    stream: &mut BufReader<Box<dyn Stream>>,
+    tag: &str,
+    command: &str,
+) -> Result<Vec<String>, String> {
+    stream.write_all(format!("{tag} {command}\r\n").as_bytes()).await.map_err(|e| e.to_string())?;
+    let mut untagged = Vec::new();
+    loop {
+        let line = read_line(stream).await?;
+        match line.strip_prefix(tag).map(str::trim_start) {
+            Some(status) if status.starts_with("OK") => return Ok(untagged),
+            Some(_) => return Err(format!("{command} failed: {line}")),
+            None => untagged.push(line),
+        }
+    }
+}
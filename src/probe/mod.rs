@@ -0,0 +1,188 @@
+use crate::guard::Guard;
+use crate::request::Request;
+use hyper::Uri;
+use tokio::io::{AsyncRead, AsyncWrite, BufReader};
+use tokio::net::TcpStream;
+
+mod exec;
+pub use exec::Exec;
+
+mod graphql;
+pub use graphql::GraphQl;
+
+mod mail;
+mod tcp;
+
+// Feature-gated driver modules. Each one is included only if its feature is enabled.
+#[cfg(feature = "mysql")]
+mod mysql;
+#[cfg(feature = "postgres")]
+mod postgres;
+#[cfg(feature = "redis")]
+mod redis;
+
+/// A probe checking the connectivity of a non-HTTP service, selected by the scheme of the URL.
+///
+/// Database probes measure the time it takes to connect, authenticate and ping the server,
+/// so stateful dependencies can be ranked with the same machinery as HTTP services.
+/// Mail probes measure the time it takes to connect and complete the handshake, without logging in.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub(crate) enum Probe {
+    /// `redis://` and `rediss://` URLs, pinged with the `PING` command. Requires the `redis` feature.
+    Redis,
+    /// `postgres://` and `postgresql://` URLs, pinged with an empty query. Requires the `postgres` feature.
+    Postgres,
+    /// `mysql://` URLs, pinged with the `COM_PING` command. Requires the `mysql` feature.
+    Mysql,
+    /// `smtp://` and `smtps://` URLs, checked through their banner and the `EHLO` command.
+    Smtp(Security),
+    /// `imap://` and `imaps://` URLs, checked through their greeting and the `CAPABILITY` command.
+    Imap(Security),
+    /// `tcp://` URLs, checked by connecting to the port and matching the banner of the service, if expected.
+    Tcp,
+    /// `exec://` URLs, checked by running the `Exec` command of the request.
+    Exec,
+}
+
+/// How a mail probe secures its connection.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub(crate) enum Security {
+    /// The connection is never secured.
+    Plain,
+    /// The connection is upgraded with the `STARTTLS` command, required by a `starttls` query parameter.
+    StartTls,
+    /// The connection is secured from the start, as implied by the `smtps` and `imaps` schemes.
+    Implicit,
+}
+
+impl Probe {
+    /// Selects the probe of a URL.
+    ///
+    /// # Returns
+    /// The probe matching the scheme of the URL, or `None` for HTTP(S) URLs.
+    pub(crate) fn from_url(url: &Uri) -> Option<Self> {
+        let starttls = url.query().unwrap_or_default().split('&').any(|p| p == "starttls" || p == "starttls=true");
+        let security = if starttls { Security::StartTls } else { Security::Plain };
+        match url.scheme_str()? {
+            "smtp" => Some(Probe::Smtp(security)),
+            "smtps" => Some(Probe::Smtp(Security::Implicit)),
+            "imap" => Some(Probe::Imap(security)),
+            "imaps" => Some(Probe::Imap(Security::Implicit)),
+            "tcp" => Some(Probe::Tcp),
+            "exec" => Some(Probe::Exec),
+            "redis" | "rediss" => Some(Probe::Redis),
+            "postgres" | "postgresql" => Some(Probe::Postgres),
+            "mysql" => Some(Probe::Mysql),
+            _ => None,
+        }
+    }
+
+    /// Runs the probe against the service of the given request.
+    ///
+    /// # Arguments
+    /// * `request`: The monitored request, holding the URL of the service and the expectations of the probe.
+    /// * `guard`: The guard applied to every resolved address, if any. Database drivers resolve addresses on their own,
+    ///   so only their URL is checked by the guard.
+    ///
+    /// # Returns
+    /// An error describing why the service couldn't be reached.
+    pub(crate) async fn run(self, request: &Request, guard: Option<&Guard>) -> Result<(), String> {
+        let url = &request.url;
+        match self {
+            Probe::Tcp => tcp::check(url, request.expect_banner.as_ref(), guard).await,
+            Probe::Exec => match &request.exec {
+                Some(exec) => exec.run().await,
+                None => Err(format!("no command configured for `{url}`")),
+            },
+            Probe::Smtp(security) => mail::smtp(url, security, guard).await,
+            Probe::Imap(security) => mail::imap(url, security, guard).await,
+            #[cfg(feature = "redis")]
+            Probe::Redis => redis::ping(&url.to_string()).await,
+            #[cfg(feature = "postgres")]
+            Probe::Postgres => postgres::ping(&url.to_string()).await,
+            #[cfg(feature = "mysql")]
+            Probe::Mysql => mysql::ping(&url.to_string()).await,
+            #[allow(unreachable_patterns)]
+            probe => Err(format!("{probe:?} probes require the `{}` feature", probe.feature())),
+        }
+    }
+
+    /// Returns the name of the feature enabling the probe.
+    fn feature(self) -> &'static str {
+        match self {
+            Probe::Redis => "redis",
+            Probe::Postgres => "postgres",
+            Probe::Mysql => "mysql",
+            // Mail, TCP and command probes are always available.
+            Probe::Smtp(_) | Probe::Imap(_) | Probe::Tcp | Probe::Exec => "default",
+        }
+    }
+}
+
+/// A plain or TLS stream to a service.
+pub(crate) trait Stream: AsyncRead + AsyncWrite + Unpin + Send {}
+
+impl<T: AsyncRead + AsyncWrite + Unpin + Send> Stream for T {}
+
+/// Connects to the host of a URL, trying each of its addresses allowed by the guard in turn.
+///
+/// # Arguments
+/// * `url`: The URL of the service.
+/// * `default_port`: The port used if the URL doesn't specify one.
+/// * `tls`: Whether the connection is secured right away.
+/// * `guard`: The guard applied to every resolved address, if any.
+pub(crate) async fn connect(
+    url: &Uri,
+    default_port: u16,
+    tls: bool,
+    guard: Option<&Guard>,
+) -> Result<BufReader<Box<dyn Stream>>, String> {
+    let host = url.host().ok_or(format!("`{url}` has no host"))?;
+    // IPv6 hosts are enclosed in brackets within URLs.
+    let host = host.trim_start_matches('[').trim_end_matches(']');
+    let addrs =
+        tokio::net::lookup_host((host, url.port_u16().unwrap_or(default_port))).await.map_err(|e| e.to_string())?;
+
+    let mut error = format!("no addresses found for {host}");
+    for addr in addrs {
+        if let Some(Err(violation)) = guard.map(|g| g.check_ip(addr.ip())) {
+            error = violation.to_string();
+            continue;
+        }
+        match TcpStream::connect(addr).await {
+            Ok(stream) if tls => return upgrade(Box::new(stream), url).await,
+            Ok(stream) => return Ok(BufReader::new(Box::new(stream))),
+            Err(e) => error = e.to_string(),
+        }
+    }
+    Err(error)
+}
+
+/// Secures a connection with TLS, verifying the certificate against the host of the URL.
+pub(crate) async fn upgrade(stream: Box<dyn Stream>, url: &Uri) -> Result<BufReader<Box<dyn Stream>>, String> {
+    let host = url.host().unwrap_or_default().trim_start_matches('[').trim_end_matches(']');
+    let connector = native_tls::TlsConnector::new().map_err(|e| e.to_string())?;
+    let stream =
+        tokio_native_tls::TlsConnector::from(connector).connect(host, stream).await.map_err(|e| e.to_string())?;
+    Ok(BufReader::new(Box::new(stream)))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_probe_from_url() {
+        assert_eq!(Probe::from_url(&"redis://localhost:6379".parse().unwrap()), Some(Probe::Redis));
+        assert_eq!(Probe::from_url(&"postgresql://user@localhost/db".parse().unwrap()), Some(Probe::Postgres));
+        assert_eq!(Probe::from_url(&"mysql://localhost".parse().unwrap()), Some(Probe::Mysql));
+        assert_eq!(
+            Probe::from_url(&"smtp://localhost:587?starttls".parse().unwrap()),
+            Some(Probe::Smtp(Security::StartTls))
+        );
+        assert_eq!(Probe::from_url(&"imaps://localhost".parse().unwrap()), Some(Probe::Imap(Security::Implicit)));
+        assert_eq!(Probe::from_url(&"tcp://localhost:22".parse().unwrap()), Some(Probe::Tcp));
+        assert_eq!(Probe::from_url(&"exec://backup-check".parse().unwrap()), Some(Probe::Exec));
+        assert_eq!(Probe::from_url(&"https://localhost".parse().unwrap()), None);
+    }
+}
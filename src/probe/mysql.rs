@@ -0,0 +1,10 @@
+use mysql_async::prelude::Queryable;
+use mysql_async::{Conn, Opts};
+
+/// Connects to a MySQL server, authenticating with the credentials of the URL, and sends a `COM_PING` command.
+pub(crate) async fn ping(url: &str) -> Result<(), String> {
+    let opts = Opts::from_url(url).map_err(|e| e.to_string())?;
+    let mut conn = Conn::new(opts).await.map_err(|e| e.to_string())?;
+    conn.ping().await.map_err(|e| e.to_string())?;
+    conn.disconnect().await.map_err(|e| e.to_string())
+}
@@ -0,0 +1,10 @@
+use tokio_postgres::NoTls;
+
+/// Connects to a PostgreSQL server, authenticating with the credentials of the URL, and sends an empty query.
+pub(crate) async fn ping(url: &str) -> Result<(), String> {
+    let (client, connection) = tokio_postgres::connect(url, NoTls).await.map_err(|e| e.to_string())?;
+    // The connection performs the actual communication with the server, until the client is dropped.
+    tokio::spawn(connection);
+    client.simple_query("").await.map_err(|e| e.to_string())?;
+    Ok(())
+}
@@ -0,0 +1,10 @@
+/// Connects to a Redis server and sends a `PING` command.
+pub(crate) async fn ping(url: &str) -> Result<(), String> {
+    let client = redis::Client::open(url).map_err(|e| e.to_string())?;
+    let mut connection = client.get_multiplexed_tokio_connection().await.map_err(|e| e.to_string())?;
+    let pong: String = redis::cmd("PING").query_async(&mut connection).await.map_err(|e| e.to_string())?;
+    match pong == "PONG" {
+        true => Ok(()),
+        false => Err(format!("unexpected reply to PING: {pong}")),
+    }
+}
@@ -0,0 +1,33 @@
+use super::connect;
+use crate::guard::Guard;
+use hyper::Uri;
+use regex::Regex;
+use tokio::io::AsyncReadExt;
+
+/// Connects to a TCP port and, if a banner is expected, reads from the service until its output matches.
+///
+/// # Arguments
+/// * `url`: The `tcp://host:port` URL of the service.
+/// * `banner`: The regular expression the output of the service must match, e.g. `^SSH-2\.0-`.
+/// * `guard`: The guard applied to every resolved address, if any.
+pub(crate) async fn check(url: &Uri, banner: Option<&Regex>, guard: Option<&Guard>) -> Result<(), String> {
+    let port = url.port_u16().ok_or(format!("`{url}` has no port"))?;
+    let mut stream = connect(url, port, false, guard).await?;
+    let Some(banner) = banner else {
+        return Ok(());
+    };
+
+    let mut received = Vec::new();
+    let mut buf = [0u8; 1024];
+    loop {
+        let n = stream.read(&mut buf).await.map_err(|e| e.to_string())?;
+        if n == 0 {
+            let received = String::from_utf8_lossy(&received);
+            return Err(format!("banner `{}` doesn't match `{banner}`", received.trim_end()));
+        }
+        received.extend_from_slice(&buf[..n]);
+        if banner.is_match(&String::from_utf8_lossy(&received)) {
+            return Ok(());
+        }
+    }
+}
@@ -0,0 +1,91 @@
+use crate::incident::State;
+use crate::Score;
+use std::time::{Duration, SystemTime};
+
+/// A monitored endpoint, as ranked by `Service::ranking`.
+#[derive(serde::Deserialize, serde::Serialize, Clone, Debug)]
+pub struct RankedEndpoint {
+    /// The URL of the endpoint.
+    pub url: String,
+    /// The score of the endpoint.
+    pub score: Score,
+    /// The state of the endpoint, according to its latest probe; `None` if it's unknown to this replica.
+    pub state: Option<State>,
+    /// The average response time of the endpoint.
+    pub latency: Duration,
+    /// When the endpoint was last scored by this replica, if it was.
+    pub last_checked: Option<SystemTime>,
+    /// The short-term trend of the score, over its latest values scored by this replica; `None` until there are
+    /// enough of them.
+    #[serde(default)]
+    pub trend: Option<Trend>,
+}
+
+/// The number of latest scores of an endpoint its trend is computed over.
+pub(crate) const TREND_SCORES: usize = 20;
+
+/// The slope, in score per hour, under which an endpoint is considered stable.
+const STABLE_SLOPE: f64 = 0.05;
+
+/// The short-term direction of the score of an endpoint, so dashboards can show where it's heading rather than only
+/// where it is.
+#[derive(serde::Deserialize, serde::Serialize, Clone, Copy, Debug, PartialEq)]
+pub struct Trend {
+    /// Whether the score is improving, stable or degrading.
+    pub direction: Direction,
+    /// The change of the score per hour, fitted over its latest values by least squares.
+    pub slope: f64,
+}
+
+/// The direction of a `Trend`.
+#[derive(serde::Deserialize, serde::Serialize, Clone, Copy, Debug, PartialEq, Eq)]
+#[serde(rename_all = "snake_case")]
+pub enum Direction {
+    Improving,
+    Stable,
+    Degrading,
+}
+
+impl Trend {
+    /// Computes the trend of the latest scores of an endpoint.
+    ///
+    /// # Arguments
+    /// * `scores`: The scores, along with when they were computed.
+    ///
+    /// # Returns
+    /// The trend, or `None` if there are fewer than 3 scores, or they were all computed at once.
+    pub fn from_scores(scores: &[(SystemTime, f32)]) -> Option<Self> {
+        if scores.len() < 3 {
+            return None;
+        }
+        let start = scores.iter().map(|(at, _)| *at).min()?;
+        let points = scores
+            .iter()
+            .map(|(at, score)| (at.duration_since(start).unwrap_or_default().as_secs_f64() / 3600.0, *score as f64));
+        let n = scores.len() as f64;
+        let (sum_x, sum_y, sum_xx, sum_xy) =
+            points.fold((0.0, 0.0, 0.0, 0.0), |(x, y, xx, xy), (px, py)| (x + px, y + py, xx + px * px, xy + px * py));
+        let variance = n * sum_xx - sum_x * sum_x;
+        if variance <= f64::EPSILON {
+            return None;
+        }
+        let slope = (n * sum_xy - sum_x * sum_y) / variance;
+        let direction = match slope {
+            s if s > STABLE_SLOPE => Direction::Improving,
+            s if s < -STABLE_SLOPE => Direction::Degrading,
+            _ => Direction::Stable,
+        };
+        Some(Self { direction, slope })
+    }
+}
+
+impl Direction {
+    /// The value of the direction, as exported to Prometheus: `1` improving, `0` stable and `-1` degrading.
+    pub fn value(&self) -> i8 {
+        match self {
+            Direction::Improving => 1,
+            Direction::Stable => 0,
+            Direction::Degrading => -1,
+        }
+    }
+}
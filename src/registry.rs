@@ -0,0 +1,155 @@
+use crate::{Config, Service};
+use std::collections::BTreeMap;
+use std::error::Error;
+use std::sync::Arc;
+use std::time::Duration;
+use tokio::task::JoinHandle;
+
+/// A service managed by a `Registry`, along with the interval it runs at.
+struct Monitor {
+    service: Arc<Service>,
+    interval: Duration,
+    /// The update loop of the service, while it's running.
+    task: Option<JoinHandle<()>>,
+}
+
+/// Owns several named services, e.g. monitoring distinct pools of endpoints with different strategies,
+/// and starts or stops their update loops together.
+///
+/// Every service is namespaced after its name, unless it has a namespace already, so services sharing a store
+/// don't collide, and they can be hosted by a single embedded server through `Server::insert`.
+#[derive(Default)]
+pub struct Registry {
+    monitors: BTreeMap<String, Monitor>,
+}
+
+impl Registry {
+    /// Creates an empty registry.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Initializes a registry from the configuration of every service, named after its key.
+    ///
+    /// # Arguments
+    /// * `configs`: The name and configuration of every service, whose `interval` is required.
+    ///
+    /// # Returns
+    /// A registry of the services, which aren't started yet.
+    ///
+    /// # Errors
+    /// Returns an error if a configuration has no interval, or its service can't be initialized.
+    pub fn from_configs<I: Into<String>>(
+        configs: impl IntoIterator<Item = (I, Config)>,
+    ) -> Result<Self, Box<dyn Error + Send + Sync>> {
+        let mut registry = Self::new();
+        for (name, mut config) in configs {
+            let name = name.into();
+            let interval = config.interval().map_err(|e| format!("{e} for `{name}`"))?;
+            // Isolate the keys of the service in the store, unless namespaced otherwise
+            config.namespace.get_or_insert_with(|| name.clone());
+            let service = Service::from_config(config).map_err(|e| format!("invalid service `{name}`: {e}"))?;
+            registry.insert(name, service, interval)?;
+        }
+        Ok(registry)
+    }
+
+    /// Adds a service to the registry, namespaced after its name unless it has a namespace already.
+    /// The service is started along with the next call to `start`.
+    ///
+    /// # Arguments
+    /// * `name`: The name the service is queried by.
+    /// * `service`: The service to be managed.
+    /// * `interval`: The interval it runs at.
+    ///
+    /// # Errors
+    /// Returns an error if a service is already registered under the same name.
+    pub fn insert<I: Into<String>>(
+        &mut self,
+        name: I,
+        service: Service,
+        interval: Duration,
+    ) -> Result<(), Box<dyn Error + Send + Sync>> {
+        let name = name.into();
+        if self.monitors.contains_key(&name) {
+            return Err(format!("duplicate service `{name}`").into());
+        }
+        let service = match service.namespace() {
+            Some(_) => service,
+            None => service.use_namespace(name.as_str()),
+        };
+        self.monitors.insert(name, Monitor { service: Arc::new(service), interval, task: None });
+        Ok(())
+    }
+
+    /// Removes a service from the registry, stopping it if it's running.
+    ///
+    /// # Arguments
+    /// * `name`: The name of the service.
+    ///
+    /// # Returns
+    /// The removed service, if any was registered under the name.
+    pub fn remove(&mut self, name: &str) -> Option<Arc<Service>> {
+        let monitor = self.monitors.remove(name)?;
+        if let Some(task) = monitor.task {
+            task.abort();
+        }
+        Some(monitor.service)
+    }
+
+    /// Retrieves a service by its name.
+    pub fn get(&self, name: &str) -> Option<&Arc<Service>> {
+        self.monitors.get(name).map(|m| &m.service)
+    }
+
+    /// Retrieves the names of the registered services, in order.
+    pub fn names(&self) -> Vec<String> {
+        self.monitors.keys().cloned().collect()
+    }
+
+    /// Retrieves the best scoring URL of a service.
+    ///
+    /// # Arguments
+    /// * `name`: The name of the service.
+    ///
+    /// # Errors
+    /// Returns an error if no service is registered under the name, or its store fails.
+    pub async fn best_url(&self, name: &str) -> Result<Option<String>, Box<dyn Error + Send + Sync>> {
+        let service = self.get(name).ok_or(format!("unknown service `{name}`"))?;
+        service.best_url().await
+    }
+
+    /// Starts the update loop of every service that isn't running yet, at its own interval.
+    pub async fn start(&mut self) {
+        for monitor in self.monitors.values_mut() {
+            if monitor.task.as_ref().is_none_or(|task| task.is_finished()) {
+                monitor.task = Some(monitor.service.clone().run(monitor.interval).await);
+            }
+        }
+    }
+
+    /// Stops the update loop of every running service. The services keep their state, and can be started again.
+    pub fn stop(&mut self) {
+        for monitor in self.monitors.values_mut() {
+            if let Some(task) = monitor.task.take() {
+                task.abort();
+            }
+        }
+    }
+
+    /// Returns `true` if the update loop of the service is running.
+    ///
+    /// # Arguments
+    /// * `name`: The name of the service.
+    pub fn is_running(&self, name: &str) -> bool {
+        let task = self.monitors.get(name).and_then(|m| m.task.as_ref());
+        task.is_some_and(|task| !task.is_finished())
+    }
+}
+
+impl Drop for Registry {
+    /// Stops the services along with the registry.
+    fn drop(&mut self) {
+        self.stop();
+    }
+}
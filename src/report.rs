@@ -0,0 +1,463 @@
+use crate::config::ConfigDuration;
+use crate::history::Sample;
+use crate::incident::Incident;
+use crate::notify::Notification;
+use crate::ProbeOutcome;
+use std::collections::HashMap;
+use std::sync::Mutex;
+use std::time::{Duration, SystemTime};
+
+/// The number of points the score trajectory of an endpoint is averaged into, in a comparison.
+const TRAJECTORY_POINTS: u32 = 24;
+
+/// The maximum number of response times kept per endpoint to compute the percentiles of a report.
+/// Past that, the kept samples are a uniform sample of the ones received during the period.
+const MAX_SAMPLES: usize = 10_000;
+
+/// Summary report configuration
+///
+/// - `period`: how often a report is delivered, e.g. `1day` or `1week` (default: `1day`)
+/// - `format`: the format reports are rendered in, either `json`, `markdown` or `html` (default: `markdown`)
+///
+/// Every report summarizes the probes scored since the previous one, and is delivered through the notifiers of the
+/// service. The first period starts along with the service.
+#[derive(serde::Deserialize, serde::Serialize, Clone, Debug, Default, PartialEq, Eq)]
+pub struct Config {
+    #[serde(default)]
+    pub period: Option<ConfigDuration>,
+    #[serde(default)]
+    pub format: Format,
+}
+
+impl Config {
+    /// Sets how often a report is delivered.
+    pub fn set_period(mut self, period: Duration) -> Self {
+        self.period = Some(period.into());
+        self
+    }
+
+    /// Sets the format reports are rendered in.
+    pub fn set_format(mut self, format: Format) -> Self {
+        self.format = format;
+        self
+    }
+}
+
+/// The formats a report can be rendered in.
+#[derive(serde::Deserialize, serde::Serialize, Clone, Copy, Debug, Default, PartialEq, Eq)]
+#[serde(rename_all = "snake_case")]
+pub enum Format {
+    Json,
+    #[default]
+    Markdown,
+    Html,
+}
+
+impl Format {
+    /// The media type of the reports rendered in this format.
+    pub fn content_type(&self) -> &'static str {
+        match self {
+            Format::Json => "application/json",
+            Format::Markdown => "text/markdown",
+            Format::Html => "text/html",
+        }
+    }
+}
+
+/// A summary of the endpoints over a period.
+#[derive(serde::Deserialize, serde::Serialize, Clone, Debug, PartialEq)]
+pub struct Report {
+    /// The start of the period.
+    pub from: SystemTime,
+    /// The end of the period.
+    pub to: SystemTime,
+    /// The summary of every endpoint scored during the period, ordered by URL.
+    pub endpoints: Vec<Summary>,
+}
+
+/// The summary of an endpoint over the period of a report.
+#[derive(serde::Deserialize, serde::Serialize, Clone, Debug, PartialEq)]
+pub struct Summary {
+    /// The URL of the endpoint.
+    pub url: String,
+    /// The number of probes scored.
+    pub probes: u64,
+    /// The percentage of successful probes.
+    pub uptime: f64,
+    /// The 95th percentile of the response times of the successful probes; `None` if none succeeded.
+    pub p95: Option<Duration>,
+    /// The number of incidents opened.
+    pub incidents: usize,
+    /// The number of successful probes exceeding the latency budget of the endpoint.
+    #[serde(default)]
+    pub budget_violations: u64,
+}
+
+/// The latency and availability of an endpoint over a window, computed from its history, so reports can be
+/// generated without exporting the raw samples.
+///
+/// The rollups of a history with a retention policy are counted as a single sample each.
+#[derive(serde::Deserialize, serde::Serialize, Clone, Debug, PartialEq)]
+pub struct LatencyReport {
+    /// The URL of the endpoint.
+    pub url: String,
+    /// The start of the window.
+    pub from: SystemTime,
+    /// The end of the window.
+    pub to: SystemTime,
+    /// The number of samples recorded during the window.
+    pub samples: usize,
+    /// The percentage of successful samples; `None` if none were recorded.
+    pub availability: Option<f64>,
+    /// The response times of the successful samples; `None` if none succeeded.
+    pub min: Option<Duration>,
+    pub avg: Option<Duration>,
+    pub p50: Option<Duration>,
+    pub p95: Option<Duration>,
+    pub p99: Option<Duration>,
+    pub max: Option<Duration>,
+}
+
+impl LatencyReport {
+    /// Computes the report of an endpoint from its samples, the percentiles by the nearest-rank method.
+    ///
+    /// # Arguments
+    /// * `url`: The URL of the endpoint.
+    /// * `from`: The start of the window.
+    /// * `to`: The end of the window.
+    /// * `samples`: The samples of the endpoint recorded during the window.
+    pub fn new(url: impl Into<String>, from: SystemTime, to: SystemTime, samples: &[Sample]) -> Self {
+        let mut latencies =
+            samples.iter().filter(|s| (100..400).contains(&s.status)).map(|s| s.elapsed).collect::<Vec<_>>();
+        latencies.sort();
+        let avg = match latencies.len() {
+            0 => None,
+            n => Some(latencies.iter().sum::<Duration>() / n as u32),
+        };
+        Self {
+            url: url.into(),
+            from,
+            to,
+            samples: samples.len(),
+            availability: match samples.len() {
+                0 => None,
+                n => Some(latencies.len() as f64 * 100.0 / n as f64),
+            },
+            min: latencies.first().copied(),
+            avg,
+            p50: percentile(&latencies, 50),
+            p95: percentile(&latencies, 95),
+            p99: percentile(&latencies, 99),
+            max: latencies.last().copied(),
+        }
+    }
+}
+
+/// A side-by-side comparison of endpoints over a window, computed from their history, e.g. to tell which CDN or
+/// provider has actually been better over the last week.
+#[derive(serde::Deserialize, serde::Serialize, Clone, Debug, PartialEq)]
+pub struct Comparison {
+    /// The start of the window.
+    pub from: SystemTime,
+    /// The end of the window.
+    pub to: SystemTime,
+    /// The compared endpoints, in the order they were given.
+    pub endpoints: Vec<Compared>,
+    /// The URL of the endpoint with the best mean score over the window; `None` if none was sampled.
+    pub best: Option<String>,
+}
+
+/// An endpoint of a `Comparison`.
+#[derive(serde::Deserialize, serde::Serialize, Clone, Debug, PartialEq)]
+pub struct Compared {
+    /// The latency and availability of the endpoint over the window.
+    pub report: LatencyReport,
+    /// The mean score of the endpoint over the window; `None` if it wasn't sampled.
+    pub mean_score: Option<f32>,
+    /// The score of the endpoint over the window, averaged over consecutive slices of it, oldest first.
+    /// The slices without samples are left out.
+    pub trajectory: Vec<ScorePoint>,
+}
+
+/// The mean score of an endpoint over a slice of a window.
+#[derive(serde::Deserialize, serde::Serialize, Clone, Debug, PartialEq)]
+pub struct ScorePoint {
+    /// The start of the slice.
+    pub at: SystemTime,
+    /// The mean score of the samples of the slice.
+    pub score: f32,
+}
+
+impl Comparison {
+    /// Compares endpoints from their samples.
+    ///
+    /// # Arguments
+    /// * `from`: The start of the window.
+    /// * `to`: The end of the window.
+    /// * `endpoints`: The URL of every endpoint, along with its samples recorded during the window.
+    pub fn new(from: SystemTime, to: SystemTime, endpoints: Vec<(String, Vec<Sample>)>) -> Self {
+        let endpoints = endpoints
+            .into_iter()
+            .map(|(url, samples)| Compared {
+                mean_score: mean_score(samples.iter()),
+                trajectory: trajectory(from, to, &samples),
+                report: LatencyReport::new(url, from, to, &samples),
+            })
+            .collect::<Vec<Compared>>();
+        let best = endpoints
+            .iter()
+            .filter_map(|e| Some((e, e.mean_score?)))
+            .max_by(|(_, a), (_, b)| a.total_cmp(b))
+            .map(|(e, _)| e.report.url.clone());
+        Self { from, to, endpoints, best }
+    }
+}
+
+/// Returns the mean score of samples, or `None` if there are none.
+fn mean_score<'a>(samples: impl ExactSizeIterator<Item = &'a Sample>) -> Option<f32> {
+    let count = samples.len();
+    let sum = samples.map(|s| s.score).sum::<f32>();
+    (count > 0).then(|| sum / count as f32)
+}
+
+/// Averages the scores of the samples of a window over `TRAJECTORY_POINTS` consecutive slices of it.
+fn trajectory(from: SystemTime, to: SystemTime, samples: &[Sample]) -> Vec<ScorePoint> {
+    let slice = (to.duration_since(from).unwrap_or_default() / TRAJECTORY_POINTS).max(Duration::from_millis(1));
+    let mut slices = std::collections::BTreeMap::<u32, Vec<&Sample>>::new();
+    for sample in samples {
+        let index = sample.at.duration_since(from).unwrap_or_default().as_nanos() / slice.as_nanos();
+        slices.entry((index as u32).min(TRAJECTORY_POINTS - 1)).or_default().push(sample);
+    }
+    slices
+        .into_iter()
+        .filter_map(|(index, samples)| {
+            Some(ScorePoint { at: from + slice * index, score: mean_score(samples.into_iter())? })
+        })
+        .collect()
+}
+
+/// Returns a percentile of sorted response times, by the nearest-rank method.
+fn percentile(latencies: &[Duration], percentile: usize) -> Option<Duration> {
+    let rank = (latencies.len() * percentile).div_ceil(100);
+    latencies.get(rank.checked_sub(1)?).copied()
+}
+
+impl Report {
+    /// Renders the report in the given format.
+    pub fn render(&self, format: Format) -> String {
+        match format {
+            Format::Json => serde_json::to_string_pretty(self).expect("failed to serialize report"),
+            Format::Markdown => {
+                let mut out = format!("# {}\n\n", self.subject());
+                out.push_str(
+                    "| Endpoint | Probes | Uptime | p95 | Incidents | Over budget |\n|---|---|---|---|---|---|\n",
+                );
+                for s in &self.endpoints {
+                    let row = [
+                        s.url.clone(),
+                        s.probes.to_string(),
+                        format!("{:.2}%", s.uptime),
+                        p95(s),
+                        s.incidents.to_string(),
+                        s.budget_violations.to_string(),
+                    ];
+                    out.push_str(&format!("| {} |\n", row.join(" | ")));
+                }
+                out
+            }
+            Format::Html => {
+                let mut out = format!("<h1>{}</h1>\n<table>\n", escape(&self.subject()));
+                out.push_str(
+                    "<tr><th>Endpoint</th><th>Probes</th><th>Uptime</th><th>p95</th><th>Incidents</th><th>Over budget</th></tr>\n",
+                );
+                for s in &self.endpoints {
+                    let row = [
+                        escape(&s.url),
+                        s.probes.to_string(),
+                        format!("{:.2}%", s.uptime),
+                        p95(s),
+                        s.incidents.to_string(),
+                        s.budget_violations.to_string(),
+                    ];
+                    out.push_str(&format!("<tr><td>{}</td></tr>\n", row.join("</td><td>")));
+                }
+                out.push_str("</table>\n");
+                out
+            }
+        }
+    }
+
+    /// A title describing the period of the report.
+    pub fn subject(&self) -> String {
+        let (from, to) = (humantime::format_rfc3339_seconds(self.from), humantime::format_rfc3339_seconds(self.to));
+        format!("isup report from {from} to {to}")
+    }
+
+    /// Renders the report into a notification.
+    pub(crate) fn notification(&self, format: Format) -> Notification {
+        Notification { subject: self.subject(), content_type: format.content_type().into(), body: self.render(format) }
+    }
+}
+
+/// Formats the 95th percentile of an endpoint in milliseconds, or `-` if unknown.
+fn p95(summary: &Summary) -> String {
+    summary.p95.map_or("-".into(), |p95| format!("{}ms", p95.as_millis()))
+}
+
+/// Escapes the characters with a special meaning in HTML.
+fn escape(text: &str) -> String {
+    text.replace('&', "&amp;").replace('<', "&lt;").replace('>', "&gt;").replace('"', "&quot;")
+}
+
+/// The probes of an endpoint scored during the current period.
+#[derive(Clone, Debug, Default)]
+struct Tally {
+    probes: u64,
+    successes: u64,
+    budget_violations: u64,
+    /// A uniform sample of the response times of the successful probes.
+    latencies: Vec<Duration>,
+}
+
+impl Tally {
+    fn record(&mut self, outcome: &ProbeOutcome) {
+        self.probes += 1;
+        if !outcome.is_success() {
+            return;
+        }
+        self.successes += 1;
+        if outcome.exceeds_budget() {
+            self.budget_violations += 1;
+        }
+        // Reservoir sampling, keeping every response time with the same probability
+        if self.latencies.len() < MAX_SAMPLES {
+            self.latencies.push(outcome.elapsed);
+        } else {
+            let index = crate::request::splitmix64(self.successes) % self.successes;
+            if let Some(latency) = self.latencies.get_mut(index as usize) {
+                *latency = outcome.elapsed;
+            }
+        }
+    }
+
+    /// Returns the 95th percentile of the sampled response times, by the nearest-rank method.
+    fn p95(&self) -> Option<Duration> {
+        let mut latencies = self.latencies.clone();
+        latencies.sort();
+        percentile(&latencies, 95)
+    }
+}
+
+/// The probes scored since the start of the current period.
+#[derive(Clone, Debug)]
+struct Window {
+    started_at: SystemTime,
+    tallies: HashMap<String, Tally>,
+}
+
+impl Window {
+    fn new() -> Self {
+        Self { started_at: SystemTime::now(), tallies: HashMap::new() }
+    }
+}
+
+/// Summarizes the probes of a `Service` over periods of time.
+pub(crate) struct Reporter {
+    config: Config,
+    window: Mutex<Window>,
+}
+
+impl Reporter {
+    pub(crate) fn new(config: Config) -> Self {
+        Self { config, window: Mutex::new(Window::new()) }
+    }
+
+    /// The format reports are rendered in.
+    pub(crate) fn format(&self) -> Format {
+        self.config.format
+    }
+
+    /// Records the outcome of a scored probe.
+    pub(crate) fn record(&self, outcome: &ProbeOutcome) {
+        let mut window = self.window.lock().expect("failed to lock report window");
+        window.tallies.entry(outcome.url.clone()).or_default().record(outcome);
+    }
+
+    /// Summarizes the current period so far.
+    ///
+    /// # Arguments
+    /// * `incidents`: The incidents of the endpoints, of which the ones opened during the period are counted.
+    pub(crate) fn report(&self, incidents: &[Incident]) -> Report {
+        let window = self.window.lock().expect("failed to lock report window").clone();
+        summarize(window, SystemTime::now(), incidents)
+    }
+
+    /// Closes the current period once it's over, and starts the next one.
+    ///
+    /// # Returns
+    /// The report of the closed period, or `None` if it isn't over yet.
+    pub(crate) fn close(&self, incidents: &[Incident]) -> Option<Report> {
+        let period = self.config.period.map_or(Duration::from_secs(24 * 60 * 60), ConfigDuration::get);
+        let now = SystemTime::now();
+        let window = {
+            let mut window = self.window.lock().expect("failed to lock report window");
+            if now.duration_since(window.started_at).unwrap_or_default() < period {
+                return None;
+            }
+            std::mem::replace(&mut *window, Window::new())
+        };
+        Some(summarize(window, now, incidents))
+    }
+}
+
+/// Summarizes the probes of a period.
+fn summarize(window: Window, to: SystemTime, incidents: &[Incident]) -> Report {
+    let mut endpoints = window
+        .tallies
+        .into_iter()
+        .map(|(url, tally)| Summary {
+            incidents: incidents
+                .iter()
+                .filter(|i| i.url == url && i.started_at >= window.started_at && i.started_at < to)
+                .count(),
+            uptime: tally.successes as f64 * 100.0 / tally.probes.max(1) as f64,
+            probes: tally.probes,
+            p95: tally.p95(),
+            budget_violations: tally.budget_violations,
+            url,
+        })
+        .collect::<Vec<_>>();
+    endpoints.sort_by(|a, b| a.url.cmp(&b.url));
+    Report { from: window.started_at, to, endpoints }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_summary() {
+        let mut window = Window::new();
+        let url = "https://example.com/";
+        for ms in 1..=100 {
+            let status = if ms % 10 == 0 { 503 } else { 200 };
+            let mut outcome = ProbeOutcome::new(url, Duration::from_millis(ms), status);
+            outcome.budget = Some(Duration::from_millis(80));
+            window.tallies.entry(url.into()).or_default().record(&outcome);
+        }
+
+        let report = summarize(window, SystemTime::now(), &[]);
+        let summary = &report.endpoints[0];
+        assert_eq!(summary.probes, 100);
+        assert_eq!(summary.uptime, 90.0);
+        // The 95th percentile of the 90 successful probes
+        assert_eq!(summary.p95, Some(Duration::from_millis(95)));
+        // The successful probes over 80ms, the 100th having failed
+        assert_eq!(summary.budget_violations, 18);
+
+        let markdown = report.render(Format::Markdown);
+        assert!(markdown.contains("| https://example.com/ | 100 | 90.00% | 95ms | 0 | 18 |"));
+        let html = report.render(Format::Html);
+        assert!(html.contains("<td>https://example.com/</td><td>100</td>"));
+    }
+}
@@ -1,10 +1,15 @@
 use serde::{Deserialize, Serialize};
+use std::error::Error;
 use std::time::Duration;
 
 /// Represents a scoring system for evaluating the performance of a web service.
 /// It incorporates various metrics such as response time and reliability
 /// to produce a comprehensive performance score.
-#[derive(Clone, Debug, Default, Deserialize, Serialize)]
+///
+/// Its canonical encoding, shared by the stores, the embedded server and the exports, is the JSON produced by
+/// `Score::encode` and described by `Score::schema`. The persisted records are tagged with the version of the
+/// encoding they were written with, and upgraded by `Score::decode` when read back.
+#[derive(Clone, Debug, Default, PartialEq, Deserialize, Serialize)]
 pub struct Score {
     /// The average response time of the service.
     /// This value plays a key role in determining the service's responsiveness and efficiency.
@@ -15,9 +20,31 @@ pub struct Score {
     /// A measure of the service's reliability, typically based on its success rate of responses.
     /// It is a factor in the overall performance score, with higher reliability leading to a higher score.
     pub reliability: f32,
+    /// Whether the endpoint is paused, e.g. during a planned maintenance; its score is frozen until it's resumed.
+    pub paused: bool,
+    /// Why the latest probe of the endpoint failed, e.g. a timeout, a TLS failure or an error status;
+    /// `None` if it succeeded.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub last_error: Option<String>,
+    /// The rate of failed probes among the latest ones of the endpoint, within the error window of the service.
+    /// Unlike the reliability, it recovers as soon as the failures leave the window.
+    pub error_rate: f32,
+    /// The standard deviation of the latency of the successful probes among the latest ones of the endpoint,
+    /// within the error window of the service.
+    pub jitter: Duration,
 }
 
 impl Score {
+    /// The version of the canonical encoding of the scores, bumped whenever a field changes incompatibly.
+    ///
+    /// Version 2 tags the records with their version, and requires the `paused`, `error_rate` and `jitter` fields
+    /// the earliest records of the first version omitted. Every bump comes with an upgrade of the records of the
+    /// previous version in `Score::upgrade`.
+    pub const SCHEMA_VERSION: u32 = 2;
+
+    /// The default number of latest probes the error rate and jitter are computed over.
+    pub const DEFAULT_ERROR_WINDOW: usize = 100;
+
     /// Creates a new `Score` instance with specified initial values.
     ///
     /// # Arguments
@@ -29,6 +56,138 @@ impl Score {
     /// # Returns
     /// A new `Score` instance with the provided values.
     pub fn new(score: f32, reliability: f32, response_avg: Duration) -> Self {
-        Self { response_avg, score, reliability }
+        Self {
+            response_avg,
+            score,
+            reliability,
+            paused: false,
+            last_error: None,
+            error_rate: 0.0,
+            jitter: Duration::ZERO,
+        }
+    }
+
+    /// Scales the score relative to the best one of its cohort, which scores `1.0`.
+    ///
+    /// # Arguments
+    /// * `best`: The score of the best endpoint of the cohort.
+    ///
+    /// # Returns
+    /// The relative score, `0.0` if none of the cohort has a positive score.
+    pub fn relative_to(&self, best: f32) -> Self {
+        let score = if best > 0.0 { self.score / best } else { 0.0 };
+        Self { score, ..self.clone() }
+    }
+    /// Encodes the score in its canonical JSON representation, tagged with its version.
+    ///
+    /// # Returns
+    /// The JSON document, as described by `Score::schema`.
+    pub fn encode(&self) -> Result<String, Box<dyn Error + Send + Sync>> {
+        let mut record = serde_json::to_value(self)?;
+        record["version"] = Self::SCHEMA_VERSION.into();
+        Ok(record.to_string())
+    }
+
+    /// Decodes a score from its canonical JSON representation, or from the YAML one written by earlier versions.
+    ///
+    /// Records of earlier versions are upgraded to the current one. Records of later versions, written by newer
+    /// replicas sharing the store, are decoded from the fields known to this version, ignoring the others.
+    ///
+    /// # Arguments
+    /// * `encoded`: The encoded score.
+    ///
+    /// # Returns
+    /// The decoded score, with the fields missing from earlier versions set to their default.
+    pub fn decode(encoded: &str) -> Result<Self, Box<dyn Error + Send + Sync>> {
+        let mut record = match serde_json::from_str::<serde_json::Value>(encoded) {
+            Ok(record) => record,
+            Err(_) => serde_yaml::from_str(encoded)?,
+        };
+        let fields = record.as_object_mut().ok_or("the score record isn't an object")?;
+        // Records written before they were tagged are of the first version
+        let version = fields.remove("version").and_then(|v| v.as_u64()).unwrap_or(1);
+        for version in version..Self::SCHEMA_VERSION as u64 {
+            Self::upgrade(fields, version);
+        }
+        Ok(serde_json::from_value(record)?)
+    }
+
+    /// Upgrades the fields of a record from the given version to the next one.
+    fn upgrade(fields: &mut serde_json::Map<String, serde_json::Value>, version: u64) {
+        // The fields required by the second version were omitted by the earliest records of the first one
+        if version == 1 {
+            fields.entry("paused").or_insert(false.into());
+            fields.entry("error_rate").or_insert(0.0.into());
+            fields.entry("jitter").or_insert(serde_json::json!({ "secs": 0, "nanos": 0 }));
+        }
+    }
+
+    /// Builds the JSON Schema of the canonical representation of the scores, identified by its version.
+    pub fn schema() -> serde_json::Value {
+        serde_json::json!({
+            "$schema": "https://json-schema.org/draft/2020-12/schema",
+            "$id": format!("urn:isup:score:v{}", Self::SCHEMA_VERSION),
+            "title": "Score",
+            "type": "object",
+            "required": ["response_avg", "score", "reliability", "paused", "error_rate", "jitter"],
+            "properties": {
+                "response_avg": {
+                    "type": "object",
+                    "description": "The average response time of the endpoint",
+                    "required": ["secs", "nanos"],
+                    "properties": {
+                        "secs": { "type": "integer", "format": "int64" },
+                        "nanos": { "type": "integer", "format": "int32" },
+                    },
+                },
+                "score": { "type": "number", "description": "The performance score of the endpoint" },
+                "reliability": { "type": "number", "description": "The success rate of the endpoint" },
+                "paused": { "type": "boolean", "description": "Whether the endpoint is paused" },
+                "error_rate": {
+                    "type": "number",
+                    "description": "The rate of failed probes among the latest ones of the endpoint",
+                },
+                "jitter": {
+                    "type": "object",
+                    "description": "The standard deviation of the latency of the latest successful probes of the endpoint",
+                    "required": ["secs", "nanos"],
+                    "properties": {
+                        "secs": { "type": "integer", "format": "int64" },
+                        "nanos": { "type": "integer", "format": "int32" },
+                    },
+                },
+                "last_error": {
+                    "type": "string",
+                    "description": "Why the latest probe of the endpoint failed, absent if it succeeded",
+                },
+                "version": {
+                    "type": "integer",
+                    "description": "The version of the encoding the record was written with, absent before version 2",
+                },
+            },
+        })
+    }
+}
+
+/// The score of an endpoint as exposed by the APIs, along with its URL and the version of its encoding.
+#[derive(Clone, Debug, PartialEq, Deserialize, Serialize)]
+pub struct ScoreView {
+    /// The version of the encoding of the score, `Score::SCHEMA_VERSION` when it was built.
+    pub version: u32,
+    /// The URL of the endpoint.
+    pub url: String,
+    /// The score of the endpoint, whose fields are flattened into the view.
+    #[serde(flatten)]
+    pub score: Score,
+}
+
+impl ScoreView {
+    /// Creates the view of the score of an endpoint.
+    ///
+    /// # Arguments
+    /// * `url`: The URL of the endpoint.
+    /// * `score`: The score of the endpoint.
+    pub fn new<I: Into<String>>(url: I, score: Score) -> Self {
+        Self { version: Score::SCHEMA_VERSION, url: url.into(), score }
     }
 }
@@ -0,0 +1,134 @@
+use serde_yaml::Value;
+use std::error::Error;
+
+/// Resolves the references to secrets written in the configuration, e.g. `{ from_env: REDIS_URL }`.
+///
+/// Any value of the configuration can be a reference: a mapping with a single key, naming the resolver, and the
+/// reference as its value. References are resolved when the configuration is loaded, before it's deserialized, so
+/// implementations backed by a remote secret manager, such as Vault, may block.
+pub trait Resolver {
+    /// The key of the references handled by the resolver, e.g. `from_vault`.
+    fn key(&self) -> &str;
+
+    /// Resolves a reference into the secret it points to.
+    ///
+    /// # Arguments
+    /// * `reference`: The value of the reference, e.g. the name of an environment variable.
+    ///
+    /// # Returns
+    /// The secret.
+    fn resolve(&self, reference: &str) -> Result<String, Box<dyn Error + Send + Sync>>;
+}
+
+/// Resolves `{ from_env: NAME }` references into the value of an environment variable.
+#[derive(Clone, Copy, Debug, Default)]
+pub struct Env;
+
+impl Resolver for Env {
+    fn key(&self) -> &str {
+        "from_env"
+    }
+
+    fn resolve(&self, reference: &str) -> Result<String, Box<dyn Error + Send + Sync>> {
+        std::env::var(reference).map_err(|e| format!("environment variable `{reference}`: {e}").into())
+    }
+}
+
+/// Resolves `{ from_file: path }` references into the content of a file, e.g. a Docker or Kubernetes secret.
+/// A trailing newline is removed.
+#[derive(Clone, Copy, Debug, Default)]
+pub struct File;
+
+impl Resolver for File {
+    fn key(&self) -> &str {
+        "from_file"
+    }
+
+    fn resolve(&self, reference: &str) -> Result<String, Box<dyn Error + Send + Sync>> {
+        let content = std::fs::read_to_string(reference).map_err(|e| format!("file `{reference}`: {e}"))?;
+        let content = content.strip_suffix('\n').unwrap_or(&content);
+        Ok(content.strip_suffix('\r').unwrap_or(content).to_string())
+    }
+}
+
+/// Replaces the references of a YAML configuration with the secrets they point to.
+///
+/// # Arguments
+/// * `value` - The configuration, or one of its values.
+/// * `resolvers` - The resolvers of the references.
+///
+/// # Returns
+/// An error if a reference can't be resolved.
+pub(crate) fn resolve(value: &mut Value, resolvers: &[Box<dyn Resolver>]) -> Result<(), Box<dyn Error + Send + Sync>> {
+    match value {
+        Value::Mapping(map) if map.len() == 1 => {
+            let (key, reference) = map.iter().next().expect("a mapping of one entry");
+            match (key.as_str().and_then(|key| resolvers.iter().find(|r| r.key() == key)), reference.as_str()) {
+                (Some(resolver), Some(reference)) => *value = Value::String(resolver.resolve(reference)?),
+                _ => map.values_mut().try_for_each(|v| resolve(v, resolvers))?,
+            }
+        }
+        Value::Mapping(map) => map.values_mut().try_for_each(|v| resolve(v, resolvers))?,
+        Value::Sequence(items) => items.iter_mut().try_for_each(|v| resolve(v, resolvers))?,
+        _ => {}
+    }
+    Ok(())
+}
+
+/// The resolvers of the `from_env` and `from_file` references, used by `Config::from_file`.
+pub fn defaults() -> Vec<Box<dyn Resolver>> {
+    vec![Box::new(Env), Box::new(File)]
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// A resolver standing in for a secret manager.
+    struct Vault;
+
+    impl Resolver for Vault {
+        fn key(&self) -> &str {
+            "from_vault"
+        }
+
+        fn resolve(&self, reference: &str) -> Result<String, Box<dyn Error + Send + Sync>> {
+            match reference {
+                "secret/isup#token" => Ok("vault-token".into()),
+                _ => Err(format!("unknown secret `{reference}`").into()),
+            }
+        }
+    }
+
+    #[test]
+    fn test_resolve() {
+        let path = std::env::temp_dir().join(format!("isup-secret-{}", std::process::id()));
+        std::fs::write(&path, "redis://:password@redis:6379\n").unwrap();
+        std::env::set_var("SECRET_TEST_WEBHOOK_TOKEN", "Bearer env-token");
+
+        let mut value = serde_yaml::from_str::<Value>(&format!(
+            "
+            store: {{ type: redis, connection: {{ from_file: {} }} }}
+            agent: {{ coordinator: https://isup.example.com, token: {{ from_vault: 'secret/isup#token' }} }}
+            notifiers:
+              - type: webhook
+                url: https://hooks.example.com
+                headers: {{ authorization: {{ from_env: SECRET_TEST_WEBHOOK_TOKEN }} }}
+            ",
+            path.display()
+        ))
+        .unwrap();
+        let mut resolvers = defaults();
+        resolvers.push(Box::new(Vault));
+        resolve(&mut value, &resolvers).unwrap();
+        std::fs::remove_file(&path).unwrap();
+
+        assert_eq!(value["store"]["connection"], "redis://:password@redis:6379");
+        assert_eq!(value["agent"]["token"], "vault-token");
+        assert_eq!(value["notifiers"][0]["headers"]["authorization"], "Bearer env-token");
+
+        // Unresolvable references are rejected
+        let mut value = serde_yaml::from_str::<Value>("token: { from_env: SECRET_TEST_MISSING }").unwrap();
+        assert!(resolve(&mut value, &resolvers).is_err());
+    }
+}
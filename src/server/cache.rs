@@ -0,0 +1,86 @@
+use bytes::Bytes;
+use dashmap::DashMap;
+use http_body_util::{BodyExt, Full};
+use hyper::header::HeaderMap;
+use hyper::{Response, StatusCode};
+use std::time::{Duration, Instant};
+
+/// The maximum number of responses kept, so that queries for arbitrary URLs can't grow the cache unbounded.
+const MAX_ENTRIES: usize = 1024;
+
+/// Caches the responses of the query routes until the next update of their service, or until they expire.
+pub(super) struct Cache {
+    /// How long a response is served from the cache at most, e.g. to pick up an endpoint paused in the meantime.
+    ttl: Duration,
+    /// The cached responses, keyed by the path and query of their request.
+    entries: DashMap<String, Entry>,
+}
+
+/// A cached response.
+struct Entry {
+    /// The last update of the service when the response was cached.
+    updated_at: u64,
+    /// When the response was cached.
+    cached_at: Instant,
+    status: StatusCode,
+    headers: HeaderMap,
+    body: Bytes,
+}
+
+impl Entry {
+    /// Returns `true` if the response is still served, given the last update of its service.
+    fn is_fresh(&self, updated_at: u64, ttl: Duration) -> bool {
+        self.updated_at == updated_at && self.cached_at.elapsed() < ttl
+    }
+}
+
+impl Cache {
+    pub(super) fn new(ttl: Duration) -> Self {
+        Self { ttl, entries: DashMap::new() }
+    }
+
+    /// Retrieves the cached response of a request, unless its service was updated since.
+    ///
+    /// # Arguments
+    /// * `key`: The path and query of the request.
+    /// * `updated_at`: The Unix timestamp of the last update of the service.
+    pub(super) fn get(&self, key: &str, updated_at: u64) -> Option<Response<Full<Bytes>>> {
+        let entry = self.entries.get(key).filter(|entry| entry.is_fresh(updated_at, self.ttl))?;
+        let mut response = Response::new(Full::new(entry.body.clone()));
+        *response.status_mut() = entry.status;
+        *response.headers_mut() = entry.headers.clone();
+        Some(response)
+    }
+
+    /// Caches a successful response, then returns it.
+    ///
+    /// # Arguments
+    /// * `key`: The path and query of the request.
+    /// * `updated_at`: The Unix timestamp of the last update of the service the response was built from.
+    /// * `response`: The response of the request.
+    pub(super) async fn insert(
+        &self,
+        key: String,
+        updated_at: u64,
+        response: Response<Full<Bytes>>,
+    ) -> Response<Full<Bytes>> {
+        if !response.status().is_success() {
+            return response;
+        }
+        // Make room by evicting the stale responses, and skip caching if every other one is still fresh
+        if self.entries.len() >= MAX_ENTRIES {
+            self.entries.retain(|_, entry| entry.is_fresh(updated_at, self.ttl));
+        }
+        let (parts, body) = response.into_parts();
+        let body = match body.collect().await {
+            Ok(body) => body.to_bytes(),
+            Err(infallible) => match infallible {},
+        };
+        if self.entries.len() < MAX_ENTRIES {
+            let (status, headers) = (parts.status, parts.headers.clone());
+            let entry = Entry { updated_at, cached_at: Instant::now(), status, headers, body: body.clone() };
+            self.entries.insert(key, entry);
+        }
+        Response::from_parts(parts, Full::new(body))
+    }
+}
@@ -0,0 +1,71 @@
+use crate::history::Sample;
+use crate::Service;
+use std::error::Error;
+use std::time::{SystemTime, UNIX_EPOCH};
+
+/// The metrics of every endpoint, graphed as `{metric}:{url}` targets.
+const METRICS: [&str; 3] = ["latency", "score", "reliability"];
+
+/// A query of the Grafana JSON datasource.
+#[derive(serde::Deserialize, Debug)]
+#[serde(rename_all = "camelCase")]
+struct Query {
+    range: Range,
+    targets: Vec<Target>,
+    #[serde(default)]
+    max_data_points: Option<usize>,
+}
+
+/// The time range of a query, in RFC 3339 format.
+#[derive(serde::Deserialize, Debug)]
+struct Range {
+    from: String,
+    to: String,
+}
+
+/// A series requested by a query.
+#[derive(serde::Deserialize, Debug)]
+struct Target {
+    target: String,
+}
+
+/// A time series answered to a query, as `[value, unix timestamp in milliseconds]` points.
+#[derive(serde::Serialize, Debug)]
+pub(super) struct Series {
+    target: String,
+    datapoints: Vec<(f64, u64)>,
+}
+
+/// Lists the targets that can be queried.
+pub(super) fn search(service: &Service) -> Vec<String> {
+    service.urls().iter().flat_map(|url| METRICS.map(|metric| format!("{metric}:{url}"))).collect()
+}
+
+/// Answers a query with the series of its targets, drawn from the history of the endpoints.
+pub(super) async fn query(service: &Service, body: &[u8]) -> Result<Vec<Series>, Box<dyn Error + Send + Sync>> {
+    let query = serde_json::from_slice::<Query>(body)?;
+    let from = humantime::parse_rfc3339_weak(&query.range.from)?;
+    let to = humantime::parse_rfc3339_weak(&query.range.to)?;
+
+    let mut series = Vec::with_capacity(query.targets.len());
+    for target in query.targets {
+        let (metric, url) = target.target.split_once(':').ok_or(format!("invalid target `{}`", target.target))?;
+        let value = match metric {
+            "latency" => |s: &Sample| s.elapsed.as_secs_f64() * 1000.0,
+            "score" => |s: &Sample| f64::from(s.score),
+            "reliability" => |s: &Sample| f64::from(s.reliability),
+            _ => return Err(format!("unknown metric `{metric}`").into()),
+        };
+        let samples = service.history(url, from, to).await.map_err(|e| e.to_string())?;
+        // Skip samples evenly, so no more points than requested are returned
+        let step = query.max_data_points.map_or(1, |max| samples.len().div_ceil(max.max(1)).max(1));
+        let datapoints = samples.iter().step_by(step).map(|s| (value(s), timestamp(s.at))).collect();
+        series.push(Series { target: target.target, datapoints });
+    }
+    Ok(series)
+}
+
+/// Converts a date into a Unix timestamp in milliseconds.
+fn timestamp(at: SystemTime) -> u64 {
+    at.duration_since(UNIX_EPOCH).unwrap_or_default().as_millis() as u64
+}
@@ -0,0 +1,83 @@
+use dashmap::DashMap;
+use std::net::IpAddr;
+use std::time::{Duration, Instant};
+
+/// The maximum number of clients tracked; beyond it, the ones whose bucket refilled are forgotten first, then the
+/// least recently seen ones.
+const MAX_CLIENTS: usize = 10_000;
+
+/// Limits the rate of the requests of every client, with a token bucket per IP address.
+///
+/// A client may burst up to `limit` requests, then the bucket refills at `limit` requests per `period`.
+pub(super) struct RateLimiter {
+    limit: u32,
+    period: Duration,
+    max_clients: usize,
+    /// The tokens left to each client, as of when they were last counted.
+    buckets: DashMap<IpAddr, (f64, Instant)>,
+}
+
+impl RateLimiter {
+    pub(super) fn new(limit: u32, period: Duration) -> Self {
+        Self { limit, period, max_clients: MAX_CLIENTS, buckets: DashMap::new() }
+    }
+
+    /// Counts a request of a client against its bucket.
+    ///
+    /// # Errors
+    /// Returns how long the client should wait before retrying, if its bucket is empty.
+    pub(super) fn check(&self, client: IpAddr) -> Result<(), Duration> {
+        let now = Instant::now();
+        let (limit, rate) = (self.limit as f64, self.limit as f64 / self.period.as_secs_f64());
+        if self.buckets.len() >= self.max_clients && !self.buckets.contains_key(&client) {
+            self.evict(now, limit, rate);
+        }
+
+        let mut bucket = self.buckets.entry(client).or_insert((limit, now));
+        let (tokens, at) = *bucket;
+        let tokens = (tokens + now.duration_since(at).as_secs_f64() * rate).min(limit);
+        if tokens < 1.0 {
+            *bucket = (tokens, now);
+            return Err(Duration::from_secs_f64((1.0 - tokens) / rate));
+        }
+        *bucket = (tokens - 1.0, now);
+        Ok(())
+    }
+
+    /// Makes room for new clients, forgetting the ones whose bucket refilled, then the least recently seen ones
+    /// down to three quarters of the capacity, so a flood of distinct clients only scans the buckets once in a while.
+    fn evict(&self, now: Instant, limit: f64, rate: f64) {
+        self.buckets.retain(|_, (tokens, at)| *tokens + now.duration_since(*at).as_secs_f64() * rate < limit);
+        let target = self.max_clients / 4 * 3;
+        if self.buckets.len() <= target {
+            return;
+        }
+        let mut seen = self.buckets.iter().map(|bucket| bucket.1).collect::<Vec<_>>();
+        let evicted = seen.len() - target;
+        let (_, cutoff, _) = seen.select_nth_unstable(evicted - 1);
+        let cutoff = *cutoff;
+        self.buckets.retain(|_, (_, at)| *at > cutoff);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::net::Ipv4Addr;
+
+    #[test]
+    fn test_caps_the_clients_tracked() {
+        // Buckets that never refill during the test, so none of them is forgotten for being refilled
+        let limiter = RateLimiter { max_clients: 100, ..RateLimiter::new(1, Duration::from_secs(3600)) };
+        let client = |i: u32| IpAddr::V4(Ipv4Addr::from(i));
+
+        for i in 0..1_000 {
+            assert!(limiter.check(client(i)).is_ok());
+            assert!(limiter.buckets.len() <= 100);
+        }
+
+        // The least recently seen clients are forgotten, while the latest ones are still limited
+        assert!(limiter.check(client(999)).is_err());
+        assert!(!limiter.buckets.contains_key(&client(0)));
+    }
+}
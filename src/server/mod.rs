@@ -0,0 +1,615 @@
+use crate::agent::constant_time_eq;
+use crate::config::ConfigDuration;
+use crate::{strategy, RankedEndpoint, ScoreView, Service};
+use bytes::Bytes;
+use http_body_util::{BodyExt, Full, Limited};
+use hyper::body::Incoming;
+use hyper::header::{HeaderMap, HeaderValue, ACCEPT, AUTHORIZATION, CONTENT_TYPE, ETAG, IF_NONE_MATCH, ORIGIN};
+use hyper::header::{
+    ACCESS_CONTROL_ALLOW_HEADERS, ACCESS_CONTROL_ALLOW_METHODS, ACCESS_CONTROL_ALLOW_ORIGIN, ACCESS_CONTROL_MAX_AGE,
+};
+use hyper::header::{RETRY_AFTER, VARY};
+use hyper::{Method, Response, StatusCode};
+use hyper_util::rt::TokioIo;
+use std::collections::BTreeMap;
+use std::convert::Infallible;
+use std::hash::{DefaultHasher, Hash, Hasher};
+use std::net::IpAddr;
+use std::sync::atomic::Ordering::SeqCst;
+use std::sync::Arc;
+use std::time::Duration;
+use tokio::net::TcpListener;
+
+mod cache;
+mod grafana;
+mod limit;
+mod openapi;
+
+use cache::Cache;
+use limit::RateLimiter;
+
+/// The maximum size of a request body accepted by the server.
+const MAX_BODY_SIZE: usize = 1024 * 1024;
+
+/// An HTTP server exposing the state of a `Service`, to be embedded in the monitoring process.
+///
+/// Routes:
+/// - `GET /best`: the best scoring URL and the timestamp of the last update
+/// - `GET /ranking`: the monitored endpoints, from the best score down, along with their state and latency
+/// - `GET /score?url=...`: the score of an endpoint, in its canonical encoding
+/// - `GET /grafana`, `POST /grafana/search`, `POST /grafana/query`: a Grafana JSON datasource over the history of
+///   the endpoints, graphing their `latency` (in milliseconds), `score` and `reliability`
+/// - `GET /metrics`: the metrics of the service, in the Prometheus text exposition format
+/// - `GET /health`: the health of the service itself, answered with `503 Service Unavailable` when unhealthy
+/// - `GET /openapi.json`: the OpenAPI document describing the routes, as returned by `Server::openapi`
+/// - `POST /pause`, `POST /resume`: pauses or resumes the endpoint of the URL posted as `{ "url": "..." }`,
+///   e.g. during a planned maintenance
+/// - `POST /suppress`: suppresses the failures of the endpoint posted as `{ "url": "...", "duration": "10m" }`,
+///   e.g. by a CI/CD pipeline while it's deployed
+/// - `POST /admin/interval`: changes the interval of the update loop to the one posted as `{ "interval": "30s" }`
+/// - `POST /admin/strategy`: swaps the strategy of the service for the one posted, e.g. `{ "type": "linear",
+///   "max_latency": "1s" }`
+/// - `POST /admin/flush`: flushes the scores from the store, so the endpoints are scored from scratch
+///
+/// The clients authenticate with the bearer tokens inserted into the server, each granting a `Role`. Once the server
+/// has a token, every route but `/health` requires one: `read_only` tokens are allowed on the routes reading the
+/// state of the services, while `admin` tokens are also allowed on the ones changing it, i.e. `/pause`, `/resume`,
+/// `/suppress` and the `/admin` routes. Requests without a valid token are rejected with `401 Unauthorized`, and
+/// the ones whose token lacks the role of the route with `403 Forbidden`. A server without any token is open for
+/// reading only: the routes changing the state of the services, which require an `admin` token, are closed.
+///
+/// The `GET /best` and `/ranking` queries are answered in JSON, or with the URLs alone, one per line, to the clients
+/// accepting `text/plain` only, e.g. shell scripts; the `/metrics` are always exposed in the Prometheus text format.
+/// Browser dashboards hosted on other origins are allowed to query the server once their origin is allowed with
+/// `Server::set_cors_origins`.
+///
+/// The `GET /best`, `/ranking` and `/score` queries can be cached until the next update of their service, with
+/// `Server::set_cache`, and rate limited per client, with `Server::set_rate_limit`. Their responses are tagged
+/// with an `ETag` derived from the last update of their service and the revision of its state, bumped whenever an
+/// endpoint is paused, resumed or suppressed, or the scores are flushed, so polling clients sending it back as
+/// `If-None-Match` are answered `304 Not Modified` until either changes. The ranking is tagged by its hash as well.
+///
+/// The services inserted under their namespace are served the same routes, prefixed with
+/// `/namespaces/{namespace}`, e.g. `GET /namespaces/payments/best`.
+#[derive(Clone)]
+pub struct Server {
+    service: Arc<Service>,
+    namespaces: Arc<BTreeMap<String, Arc<Service>>>,
+    tokens: Arc<Vec<(String, Role)>>,
+    cache: Option<Arc<Cache>>,
+    rate_limiter: Option<Arc<RateLimiter>>,
+    cors_origins: Arc<Vec<String>>,
+}
+
+/// The representation of a response, negotiated from the `Accept` header of its request.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+enum Format {
+    Json,
+    /// The URLs of the endpoints alone, one per line.
+    Text,
+}
+
+impl Format {
+    /// Negotiates the representation preferred by a client, JSON if it has no preference.
+    ///
+    /// # Returns
+    /// The format of the media range with the highest quality, `None` if neither is acceptable.
+    fn negotiate(headers: &HeaderMap) -> Option<Self> {
+        let Some(accept) = headers.get(ACCEPT).and_then(|v| v.to_str().ok()) else {
+            return Some(Self::Json);
+        };
+        let mut preferred: Option<(Self, f32)> = None;
+        for range in accept.split(',') {
+            let mut params = range.split(';').map(str::trim);
+            let format = match params.next().unwrap_or_default().to_ascii_lowercase().as_str() {
+                "application/json" | "application/*" | "*/*" => Self::Json,
+                "text/plain" | "text/*" => Self::Text,
+                _ => continue,
+            };
+            let quality = params.find_map(|p| p.strip_prefix("q=")?.parse().ok()).unwrap_or(1.0);
+            if quality > 0.0 && preferred.is_none_or(|(_, preferred)| quality > preferred) {
+                preferred = Some((format, quality));
+            }
+        }
+        preferred.map(|(format, _)| format)
+    }
+}
+
+/// The role granted to a token of the server.
+#[derive(serde::Deserialize, serde::Serialize, Clone, Copy, Debug, PartialEq, Eq, PartialOrd, Ord, Hash)]
+#[serde(rename_all = "snake_case")]
+pub enum Role {
+    /// Reads the state of the services, e.g. a dashboard.
+    ReadOnly,
+    /// Reads and changes the state of the services, e.g. an operator pausing an endpoint or flushing the store.
+    Admin,
+}
+
+impl Role {
+    /// Returns the role required by a route, `None` if it's public.
+    fn required_by(path: &str) -> Option<Self> {
+        match path {
+            // Kept public for the liveness probes of orchestrators, which can't carry a token
+            "/health" => None,
+            "/pause" | "/resume" | "/suppress" => Some(Self::Admin),
+            path if path.starts_with("/admin/") => Some(Self::Admin),
+            _ => Some(Self::ReadOnly),
+        }
+    }
+}
+
+/// The response of the `/best` route.
+#[derive(serde::Deserialize, serde::Serialize, Clone, Debug, PartialEq, Eq)]
+pub struct Best {
+    /// The best scoring URL, if any endpoint was scored.
+    pub url: Option<String>,
+    /// The Unix timestamp of the last update.
+    pub updated_at: u64,
+}
+
+/// The body of the `/pause`, `/resume` and `/suppress` routes.
+#[derive(serde::Deserialize, serde::Serialize, Clone, Debug, PartialEq, Eq)]
+pub struct Endpoint {
+    /// The URL of the endpoint.
+    pub url: String,
+    /// How long the failures of the endpoint are suppressed, required by the `/suppress` route.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub duration: Option<ConfigDuration>,
+}
+
+/// The body of the `/admin/interval` route.
+#[derive(serde::Deserialize, serde::Serialize, Clone, Debug, PartialEq, Eq)]
+pub struct Interval {
+    /// The new interval of the update loop, e.g. `30s`.
+    pub interval: ConfigDuration,
+}
+
+impl Server {
+    /// Creates a new `Server`.
+    ///
+    /// # Arguments
+    /// * `service`: The service whose state is exposed.
+    pub fn new(service: Arc<Service>) -> Self {
+        Self {
+            service,
+            namespaces: Arc::default(),
+            tokens: Arc::default(),
+            cache: None,
+            rate_limiter: None,
+            cors_origins: Arc::default(),
+        }
+    }
+
+    /// Allows the browsers of other origins to query the server, e.g. a dashboard hosted on another domain,
+    /// answering their preflight requests and exposing the responses to them.
+    ///
+    /// # Arguments
+    /// * `origins`: The allowed origins, e.g. `https://status.example.com`, or `*` to allow any.
+    pub fn set_cors_origins(mut self, origins: Vec<String>) -> Self {
+        self.cors_origins = Arc::new(origins);
+        self
+    }
+
+    /// Caches the responses of `GET /best`, `/ranking` and `/score` until the next update of their service,
+    /// so frequent polling doesn't query the store on every request.
+    ///
+    /// # Arguments
+    /// * `ttl`: How long a response is cached at most, bounding how long a change made between two updates, e.g.
+    ///   an endpoint paused, takes to be served.
+    pub fn set_cache(mut self, ttl: Duration) -> Self {
+        self.cache = Some(Arc::new(Cache::new(ttl)));
+        self
+    }
+
+    /// Limits the rate of the `GET /best`, `/ranking` and `/score` requests of every client, by IP address.
+    /// The requests beyond the limit are rejected with `429 Too Many Requests`, along with a `Retry-After` header.
+    ///
+    /// # Arguments
+    /// * `limit`: The number of requests a client is allowed per period, which it may burst.
+    /// * `period`: The period the limit applies to, e.g. `limit` requests per second.
+    ///
+    /// # Panics
+    /// Panics if the limit or the period is zero.
+    pub fn set_rate_limit(mut self, limit: u32, period: Duration) -> Self {
+        assert!(limit > 0 && !period.is_zero(), "the rate limit must be positive");
+        self.rate_limiter = Some(Arc::new(RateLimiter::new(limit, period)));
+        self
+    }
+
+    /// Inserts a bearer token clients can authenticate with, after which every route but `/health` requires one.
+    ///
+    /// # Arguments
+    /// * `token`: The secret token, sent as `Authorization: Bearer {token}`.
+    /// * `role`: The role granted to the clients authenticated with it.
+    pub fn insert_token<I: Into<String>>(mut self, token: I, role: Role) -> Self {
+        Arc::make_mut(&mut self.tokens).push((token.into(), role));
+        self
+    }
+
+    /// Hosts another service, under its namespace.
+    ///
+    /// # Arguments
+    /// * `service`: The service whose state is exposed under `/namespaces/{namespace}`.
+    ///
+    /// # Panics
+    /// Panics if the service has no namespace.
+    pub fn insert(mut self, service: Arc<Service>) -> Self {
+        let namespace = service.namespace().expect("the service has no namespace").to_string();
+        Arc::make_mut(&mut self.namespaces).insert(namespace, service);
+        self
+    }
+
+    /// Builds the OpenAPI 3.0 document describing the routes of the server,
+    /// so clients and dashboards can be generated against it.
+    pub fn openapi() -> serde_json::Value {
+        openapi::document()
+    }
+
+    /// Serves the clients connecting to the listener, until accepting a connection fails.
+    ///
+    /// # Arguments
+    /// * `listener`: The listener clients connect to.
+    pub async fn serve(self, listener: TcpListener) -> std::io::Result<()> {
+        loop {
+            let (stream, peer) = listener.accept().await?;
+            let server = self.clone();
+            tokio::spawn(async move {
+                let handler = hyper::service::service_fn(move |request| {
+                    let server = server.clone();
+                    async move { Ok::<_, Infallible>(server.handle(request, peer.ip()).await) }
+                });
+                // Connection errors only affect the client on the other end
+                let _ =
+                    hyper::server::conn::http1::Builder::new().serve_connection(TokioIo::new(stream), handler).await;
+            });
+        }
+    }
+
+    /// Handles a request, exposing its response to its origin if it's allowed.
+    ///
+    /// # Arguments
+    /// * `request`: The request of the client.
+    /// * `client`: The IP address of the client, its requests are rate limited by.
+    async fn handle(&self, request: hyper::Request<Incoming>, client: IpAddr) -> Response<Full<Bytes>> {
+        let origin = request.headers().get(ORIGIN).cloned();
+        // Preflight requests don't carry the credentials of the client, so they're answered before authorization
+        let preflight = request.method() == Method::OPTIONS && !self.cors_origins.is_empty();
+        let mut response = match preflight {
+            true => {
+                let mut response = reply(StatusCode::NO_CONTENT, "");
+                let headers = response.headers_mut();
+                headers.insert(ACCESS_CONTROL_ALLOW_METHODS, HeaderValue::from_static("GET, POST"));
+                headers.insert(ACCESS_CONTROL_ALLOW_HEADERS, HeaderValue::from_static("authorization, content-type"));
+                headers.insert(ACCESS_CONTROL_MAX_AGE, HeaderValue::from_static("86400"));
+                response
+            }
+            false => self.dispatch(request, client).await,
+        };
+        if let Some(allowed) = origin.and_then(|origin| self.allowed_origin(origin)) {
+            response.headers_mut().insert(ACCESS_CONTROL_ALLOW_ORIGIN, allowed);
+            response.headers_mut().append(VARY, HeaderValue::from_static("origin"));
+        }
+        response
+    }
+
+    /// Returns the value of the `Access-Control-Allow-Origin` header answered to an origin, if it's allowed.
+    fn allowed_origin(&self, origin: HeaderValue) -> Option<HeaderValue> {
+        match self.cors_origins.iter().any(|allowed| allowed == "*") {
+            true => Some(HeaderValue::from_static("*")),
+            false => self.cors_origins.iter().any(|allowed| allowed.as_bytes() == origin.as_bytes()).then_some(origin),
+        }
+    }
+
+    /// Authorizes a request, then serves it from the cache or routes it to its handler.
+    ///
+    /// # Arguments
+    /// * `request`: The request of the client.
+    /// * `client`: The IP address of the client, its requests are rate limited by.
+    async fn dispatch(&self, request: hyper::Request<Incoming>, client: IpAddr) -> Response<Full<Bytes>> {
+        let (method, path) = (request.method().clone(), request.uri().path().to_string());
+        // Route the paths prefixed with a namespace to the service hosted under it
+        let (service, path) = match path.strip_prefix("/namespaces/").and_then(|p| p.split_once('/')) {
+            Some((namespace, path)) => match self.namespaces.get(namespace) {
+                Some(service) => (service, format!("/{path}")),
+                None => return reply(StatusCode::NOT_FOUND, "unknown namespace"),
+            },
+            None => (&self.service, path),
+        };
+        let path = path.trim_end_matches('/');
+        match self.authorize(path, request.headers()) {
+            Err(StatusCode::FORBIDDEN) => return reply(StatusCode::FORBIDDEN, "forbidden"),
+            Err(status) => return reply(status, "unauthorized"),
+            Ok(()) => {}
+        }
+
+        // Only the queries of the state of the services are rate limited, cached and tagged
+        let format = Format::negotiate(request.headers());
+        if method != Method::GET || !matches!(path, "/best" | "/ranking" | "/score") {
+            return self.route(service, method, path, format, request).await;
+        }
+        if let Some(Err(retry_after)) = self.rate_limiter.as_ref().map(|limiter| limiter.check(client)) {
+            let mut response = reply(StatusCode::TOO_MANY_REQUESTS, "too many requests");
+            let retry_after = retry_after.as_secs_f64().ceil().to_string().parse().expect("invalid retry after");
+            response.headers_mut().insert(RETRY_AFTER, retry_after);
+            return response;
+        }
+        let if_none_match = request.headers().get(IF_NONE_MATCH).cloned();
+        // The representations of a route are cached apart
+        let key = request.uri().path_and_query().map_or(path.to_string(), ToString::to_string);
+        let key = format!("{format:?} {key}");
+        // Read the last update before the response is built, so it's never cached as newer than it is
+        let updated_at = service.updated_at.load(SeqCst);
+
+        let response = match self.cache.as_ref().and_then(|cache| cache.get(&key, updated_at)) {
+            Some(response) => response,
+            None => {
+                // The ranking is read once, both to tag it and to answer it
+                let ranking = match (path, format) {
+                    ("/ranking", Some(_)) => Some(service.ranking().await),
+                    _ => None,
+                };
+                // The state is tagged before the response is built, so the client can be spared building it
+                let etag = etag(&key, updated_at, service.revision(), ranking.as_ref());
+                if let Some(etag) = etag.as_ref().filter(|etag| matches_etag(if_none_match.as_ref(), etag)) {
+                    return not_modified(etag.clone());
+                }
+                let mut response = match ranking {
+                    Some(ranking) => ranking_response(ranking, format),
+                    None => self.route(service, method, path, format, request).await,
+                };
+                if let Some(etag) = etag.filter(|_| response.status().is_success()) {
+                    response.headers_mut().insert(ETAG, etag);
+                }
+                match &self.cache {
+                    Some(cache) => cache.insert(key, updated_at, response).await,
+                    None => response,
+                }
+            }
+        };
+        match response.headers().get(ETAG) {
+            Some(etag) if matches_etag(if_none_match.as_ref(), etag) => not_modified(etag.clone()),
+            _ => response,
+        }
+    }
+
+    /// Routes a request to its handler.
+    ///
+    /// # Arguments
+    /// * `format`: The representation negotiated with the client, `None` if it accepts none of them.
+    async fn route(
+        &self,
+        service: &Service,
+        method: Method,
+        path: &str,
+        format: Option<Format>,
+        request: hyper::Request<Incoming>,
+    ) -> Response<Full<Bytes>> {
+        match (method, path) {
+            (Method::GET, "/best" | "/ranking") if format.is_none() => {
+                reply(StatusCode::NOT_ACCEPTABLE, "not acceptable, only application/json and text/plain are served")
+            }
+            (Method::GET, "/best") => {
+                let url = service.best_url().await.unwrap_or(None);
+                match format {
+                    Some(Format::Text) => text(&url.map(|url| url + "\n").unwrap_or_default()),
+                    _ => json(&Best { url, updated_at: service.updated_at.load(SeqCst) }),
+                }
+            }
+            (Method::GET, "/ranking") => ranking_response(service.ranking().await, format),
+            (Method::GET, "/score") => {
+                let Some(url) = query_param(request.uri(), "url") else {
+                    return reply(StatusCode::BAD_REQUEST, "missing url");
+                };
+                match service.score(&url).await {
+                    Ok(Some(score)) => json(&ScoreView::new(url, score)),
+                    Ok(None) => reply(StatusCode::NOT_FOUND, "unscored endpoint"),
+                    Err(e) => reply(StatusCode::BAD_REQUEST, &e.to_string()),
+                }
+            }
+            (Method::GET, "/metrics") => {
+                let mut response = reply(StatusCode::OK, &service.metrics().render());
+                let content_type = "text/plain; version=0.0.4".parse().expect("invalid content type");
+                response.headers_mut().insert(CONTENT_TYPE, content_type);
+                response
+            }
+            (Method::GET, "/health") => {
+                let health = service.self_health().await;
+                let mut response = json(&health);
+                if !health.healthy {
+                    *response.status_mut() = StatusCode::SERVICE_UNAVAILABLE;
+                }
+                response
+            }
+            (Method::GET, "/openapi.json") => json(&Self::openapi()),
+            (Method::GET, "/grafana") => reply(StatusCode::OK, "ok"),
+            (Method::POST, "/grafana/search") => json(&grafana::search(service)),
+            (Method::POST, "/grafana/query") => {
+                let body = match Limited::new(request.into_body(), MAX_BODY_SIZE).collect().await {
+                    Ok(body) => body.to_bytes(),
+                    Err(e) => return reply(StatusCode::BAD_REQUEST, &format!("failed to read query: {e}")),
+                };
+                match grafana::query(service, &body).await {
+                    Ok(series) => json(&series),
+                    Err(e) => reply(StatusCode::BAD_REQUEST, &format!("invalid query: {e}")),
+                }
+            }
+            (Method::POST, path @ ("/pause" | "/resume" | "/suppress")) => {
+                let body = match Limited::new(request.into_body(), MAX_BODY_SIZE).collect().await {
+                    Ok(body) => body.to_bytes(),
+                    Err(e) => return reply(StatusCode::BAD_REQUEST, &format!("failed to read endpoint: {e}")),
+                };
+                let endpoint = match serde_json::from_slice::<Endpoint>(&body) {
+                    Ok(endpoint) => endpoint,
+                    Err(e) => return reply(StatusCode::BAD_REQUEST, &format!("invalid endpoint: {e}")),
+                };
+                let result = match (path, endpoint.duration) {
+                    ("/pause", _) => service.pause(&endpoint.url).await,
+                    ("/resume", _) => service.resume(&endpoint.url).await,
+                    (_, Some(duration)) => service.suppress(&endpoint.url, duration.get()),
+                    (_, None) => return reply(StatusCode::BAD_REQUEST, "missing duration"),
+                };
+                match result {
+                    Ok(()) => reply(StatusCode::OK, "ok"),
+                    Err(e) => reply(StatusCode::BAD_REQUEST, &e.to_string()),
+                }
+            }
+            (Method::POST, "/admin/interval") => {
+                let body = match Limited::new(request.into_body(), MAX_BODY_SIZE).collect().await {
+                    Ok(body) => body.to_bytes(),
+                    Err(e) => return reply(StatusCode::BAD_REQUEST, &format!("failed to read interval: {e}")),
+                };
+                match serde_json::from_slice::<Interval>(&body) {
+                    Ok(Interval { interval }) if !interval.is_zero() => match service.set_interval(interval.get()) {
+                        Ok(()) => reply(StatusCode::OK, "ok"),
+                        Err(e) => reply(StatusCode::BAD_REQUEST, &format!("invalid interval: {e}")),
+                    },
+                    Ok(_) => reply(StatusCode::BAD_REQUEST, "invalid interval: zero"),
+                    Err(e) => reply(StatusCode::BAD_REQUEST, &format!("invalid interval: {e}")),
+                }
+            }
+            (Method::POST, "/admin/strategy") => {
+                let body = match Limited::new(request.into_body(), MAX_BODY_SIZE).collect().await {
+                    Ok(body) => body.to_bytes(),
+                    Err(e) => return reply(StatusCode::BAD_REQUEST, &format!("failed to read strategy: {e}")),
+                };
+                match serde_json::from_slice::<strategy::Config>(&body) {
+                    Ok(config) => match config.validate() {
+                        Ok(()) => {
+                            service.set_strategy(config);
+                            reply(StatusCode::OK, "ok")
+                        }
+                        Err(e) => reply(StatusCode::BAD_REQUEST, &format!("invalid strategy: {e}")),
+                    },
+                    Err(e) => reply(StatusCode::BAD_REQUEST, &format!("invalid strategy: {e}")),
+                }
+            }
+            (Method::POST, "/admin/flush") => match service.flush().await {
+                Ok(()) => reply(StatusCode::OK, "ok"),
+                Err(e) => reply(StatusCode::INTERNAL_SERVER_ERROR, &format!("failed to flush the store: {e}")),
+            },
+            (
+                _,
+                "/best" | "/ranking" | "/score" | "/metrics" | "/health" | "/openapi.json" | "/grafana"
+                | "/grafana/search" | "/grafana/query" | "/pause" | "/resume" | "/suppress" | "/admin/interval"
+                | "/admin/strategy" | "/admin/flush",
+            ) => reply(StatusCode::METHOD_NOT_ALLOWED, "method not allowed"),
+            _ => reply(StatusCode::NOT_FOUND, "not found"),
+        }
+    }
+
+    /// Checks that a request carries a token granting the role required by its route.
+    ///
+    /// # Errors
+    /// Returns `401 Unauthorized` if the request carries no valid token, or `403 Forbidden` if its token lacks
+    /// the role of the route.
+    fn authorize(&self, path: &str, headers: &HeaderMap) -> Result<(), StatusCode> {
+        let required = match (self.tokens.is_empty(), Role::required_by(path)) {
+            // Servers without tokens are open, except for the routes changing the state of the services
+            (true, Some(Role::Admin)) => Some(Role::Admin),
+            (true, _) => None,
+            (false, required) => required,
+        };
+        let token = headers.get(AUTHORIZATION).and_then(|v| v.to_str().ok()).and_then(|v| v.strip_prefix("Bearer "));
+        let role = token.and_then(|token| {
+            let mut tokens = self.tokens.iter();
+            tokens.find(|(t, _)| constant_time_eq(t.as_bytes(), token.as_bytes())).map(|(_, role)| *role)
+        });
+        match (required, role) {
+            (None, _) => Ok(()),
+            (Some(required), Some(role)) if role >= required => Ok(()),
+            (Some(_), Some(_)) => Err(StatusCode::FORBIDDEN),
+            (Some(_), None) => Err(StatusCode::UNAUTHORIZED),
+        }
+    }
+}
+
+/// Answers the ranking of a service in the given format.
+fn ranking_response(
+    ranking: Result<Vec<RankedEndpoint>, Box<dyn std::error::Error + Send + Sync>>,
+    format: Option<Format>,
+) -> Response<Full<Bytes>> {
+    match (ranking, format) {
+        (Ok(ranking), Some(Format::Text)) => text(&ranking.iter().map(|r| format!("{}\n", r.url)).collect::<String>()),
+        (Ok(ranking), _) => json(&ranking),
+        (Err(e), _) => reply(StatusCode::INTERNAL_SERVER_ERROR, &format!("failed to rank the endpoints: {e}")),
+    }
+}
+
+/// Computes the entity tag of a query of the state of a service, from its last update and the revision of its state,
+/// which changes when it's altered between two updates, e.g. when an endpoint is paused. The ranking is tagged by its
+/// hash as well, as it's read to be answered anyway.
+///
+/// # Arguments
+/// * `key`: The representation and the path of the query, so the representations of the routes are tagged apart.
+/// * `ranking`: The ranking answered to the query, if it queries the ranking.
+///
+/// # Returns
+/// The strong entity tag of the representation, `None` if the service can't be ranked.
+fn etag(
+    key: &str,
+    updated_at: u64,
+    revision: u64,
+    ranking: Option<&Result<Vec<RankedEndpoint>, Box<dyn std::error::Error + Send + Sync>>>,
+) -> Option<HeaderValue> {
+    let mut hasher = DefaultHasher::new();
+    key.hash(&mut hasher);
+    if let Some(ranking) = ranking {
+        serde_json::to_vec(ranking.as_ref().ok()?).ok()?.hash(&mut hasher);
+    }
+    HeaderValue::from_str(&format!("\"{updated_at:x}-{revision:x}-{:x}\"", hasher.finish())).ok()
+}
+
+/// Checks whether the `If-None-Match` header of a request matches an entity tag, weakly as specified for `GET`.
+fn matches_etag(if_none_match: Option<&HeaderValue>, etag: &HeaderValue) -> bool {
+    let Some(tags) = if_none_match.and_then(|v| v.to_str().ok()) else {
+        return false;
+    };
+    let etag = etag.to_str().unwrap_or_default();
+    tags.split(',').map(str::trim).any(|tag| tag == "*" || tag.trim_start_matches("W/") == etag)
+}
+
+/// Builds a `304 Not Modified` response, answered to the clients whose representation is still current.
+fn not_modified(etag: HeaderValue) -> Response<Full<Bytes>> {
+    let mut response = reply(StatusCode::NOT_MODIFIED, "");
+    response.headers_mut().insert(ETAG, etag);
+    response
+}
+
+/// Retrieves a parameter of the query string of a request, percent-decoded.
+fn query_param(uri: &hyper::Uri, name: &str) -> Option<String> {
+    let value = uri.query()?.split('&').find_map(|p| p.strip_prefix(name)?.strip_prefix('='))?;
+    let (mut bytes, mut decoded) = (value.bytes(), Vec::new());
+    while let Some(byte) = bytes.next() {
+        match byte {
+            b'+' => decoded.push(b' '),
+            b'%' => {
+                let hex = [bytes.next()?, bytes.next()?];
+                decoded.push(u8::from_str_radix(std::str::from_utf8(&hex).ok()?, 16).ok()?);
+            }
+            byte => decoded.push(byte),
+        }
+    }
+    String::from_utf8(decoded).ok()
+}
+
+/// Builds a plain-text response.
+fn reply(status: StatusCode, message: &str) -> Response<Full<Bytes>> {
+    let mut response = Response::new(Full::new(Bytes::from(message.to_string())));
+    *response.status_mut() = status;
+    response
+}
+
+/// Builds a plain-text response, served as such.
+fn text(body: &str) -> Response<Full<Bytes>> {
+    let mut response = reply(StatusCode::OK, body);
+    response.headers_mut().insert(CONTENT_TYPE, HeaderValue::from_static("text/plain; charset=utf-8"));
+    response
+}
+
+/// Builds a JSON response.
+fn json<T: serde::Serialize>(value: &T) -> Response<Full<Bytes>> {
+    let body = serde_json::to_vec(value).expect("failed to serialize response");
+    let mut response = Response::new(Full::new(Bytes::from(body)));
+    response.headers_mut().insert(CONTENT_TYPE, "application/json".parse().expect("invalid content type"));
+    response
+}
@@ -0,0 +1,466 @@
+use crate::Score;
+use serde_json::{json, Value};
+
+/// Builds the OpenAPI 3.0 document describing the routes of the embedded server.
+pub(super) fn document() -> Value {
+    json!({
+        "openapi": "3.0.3",
+        "info": {
+            "title": "isup",
+            "description": "The state of the endpoints monitored by an isup service. The services hosted under a namespace are served the same paths, prefixed with `/namespaces/{namespace}`.",
+            "version": env!("CARGO_PKG_VERSION"),
+        },
+        "security": [{ "bearer": [] }, {}],
+        "paths": {
+            "/best": {
+                "get": {
+                    "summary": "The best scoring endpoint",
+                    "operationId": "best",
+                    "responses": {
+                        "200": {
+                            "description": "The best scoring URL, alone to the clients accepting `text/plain` only",
+                            "content": {
+                                "application/json": { "schema": { "$ref": "#/components/schemas/Best" } },
+                                "text/plain": { "schema": { "type": "string" } },
+                            },
+                        },
+                        "304": { "description": "The representation tagged by the `If-None-Match` header is current" },
+                        "406": text_response("The client accepts neither JSON nor plain text"),
+                        "429": text_response("The client exceeded the rate limit of the server"),
+                    },
+                },
+            },
+            "/ranking": {
+                "get": {
+                    "summary": "The monitored endpoints, from the best score down",
+                    "operationId": "ranking",
+                    "responses": {
+                        "200": {
+                            "description": "The ranked endpoints, their URLs alone, one per line, to the clients accepting `text/plain` only",
+                            "content": {
+                                "application/json": {
+                                    "schema": { "type": "array", "items": { "$ref": "#/components/schemas/RankedEndpoint" } },
+                                },
+                                "text/plain": { "schema": { "type": "string" } },
+                            },
+                        },
+                        "304": { "description": "The representation tagged by the `If-None-Match` header is current" },
+                        "406": text_response("The client accepts neither JSON nor plain text"),
+                        "429": text_response("The client exceeded the rate limit of the server"),
+                        "500": text_response("The store can't be queried"),
+                    },
+                },
+            },
+            "/score": {
+                "get": {
+                    "summary": "The score of an endpoint",
+                    "operationId": "score",
+                    "parameters": [{
+                        "name": "url",
+                        "in": "query",
+                        "required": true,
+                        "description": "The URL of the endpoint, percent-encoded",
+                        "schema": { "type": "string" },
+                    }],
+                    "responses": {
+                        "200": json_response("The score of the endpoint", "#/components/schemas/ScoreView"),
+                        "304": { "description": "The representation tagged by the `If-None-Match` header is current" },
+                        "400": text_response("The URL is missing or invalid, or the store can't be queried"),
+                        "404": text_response("The endpoint hasn't been scored"),
+                        "429": text_response("The client exceeded the rate limit of the server"),
+                    },
+                },
+            },
+            "/metrics": {
+                "get": {
+                    "summary": "The metrics of the service, e.g. the duration of its update cycles and the latency of its store",
+                    "operationId": "metrics",
+                    "responses": {
+                        "200": text_response("The metrics, in the Prometheus text exposition format"),
+                    },
+                },
+            },
+            "/health": {
+                "get": {
+                    "summary": "The health of the monitor itself",
+                    "operationId": "health",
+                    "security": [],
+                    "responses": {
+                        "200": json_response("The monitor is healthy", "#/components/schemas/Health"),
+                        "503": json_response("The store is unreachable, or the update loop is stuck", "#/components/schemas/Health"),
+                    },
+                },
+            },
+            "/grafana": {
+                "get": {
+                    "summary": "Tests the connection of the Grafana datasource",
+                    "operationId": "grafanaTest",
+                    "responses": { "200": text_response("The datasource is available") },
+                },
+            },
+            "/grafana/search": {
+                "post": {
+                    "summary": "Lists the targets of the Grafana datasource, as `{metric}:{url}`",
+                    "operationId": "grafanaSearch",
+                    "responses": {
+                        "200": {
+                            "description": "The targets, for the `latency`, `score` and `reliability` of every endpoint",
+                            "content": {
+                                "application/json": {
+                                    "schema": { "type": "array", "items": { "type": "string" } },
+                                },
+                            },
+                        },
+                    },
+                },
+            },
+            "/grafana/query": {
+                "post": {
+                    "summary": "Queries the history of the endpoints through the Grafana datasource",
+                    "operationId": "grafanaQuery",
+                    "requestBody": {
+                        "required": true,
+                        "content": {
+                            "application/json": { "schema": { "$ref": "#/components/schemas/GrafanaQuery" } },
+                        },
+                    },
+                    "responses": {
+                        "200": {
+                            "description": "The series of the targets",
+                            "content": {
+                                "application/json": {
+                                    "schema": { "type": "array", "items": { "$ref": "#/components/schemas/Series" } },
+                                },
+                            },
+                        },
+                        "400": text_response("The query is invalid"),
+                    },
+                },
+            },
+            "/pause": {
+                "post": {
+                    "summary": "Pauses an endpoint, which is no longer probed nor routed to, freezing its score",
+                    "operationId": "pause",
+                    "requestBody": {
+                        "required": true,
+                        "content": {
+                            "application/json": { "schema": { "$ref": "#/components/schemas/Endpoint" } },
+                        },
+                    },
+                    "responses": {
+                        "200": text_response("The endpoint is paused"),
+                        "400": text_response("The endpoint is invalid or isn't monitored"),
+                        "401": text_response("The request doesn't carry a valid token"),
+                        "403": text_response("The token of the request isn't an admin one"),
+                    },
+                },
+            },
+            "/resume": {
+                "post": {
+                    "summary": "Resumes a paused endpoint",
+                    "operationId": "resume",
+                    "requestBody": {
+                        "required": true,
+                        "content": {
+                            "application/json": { "schema": { "$ref": "#/components/schemas/Endpoint" } },
+                        },
+                    },
+                    "responses": {
+                        "200": text_response("The endpoint is resumed"),
+                        "400": text_response("The endpoint is invalid or isn't monitored"),
+                        "401": text_response("The request doesn't carry a valid token"),
+                        "403": text_response("The token of the request isn't an admin one"),
+                    },
+                },
+            },
+            "/suppress": {
+                "post": {
+                    "summary": "Suppresses the failures of an endpoint for a while, e.g. while it's deployed",
+                    "operationId": "suppress",
+                    "requestBody": {
+                        "required": true,
+                        "content": {
+                            "application/json": { "schema": { "$ref": "#/components/schemas/Endpoint" } },
+                        },
+                    },
+                    "responses": {
+                        "200": text_response("The failures of the endpoint are suppressed"),
+                        "400": text_response("The endpoint is invalid or isn't monitored, or the duration is missing"),
+                        "401": text_response("The request doesn't carry a valid token"),
+                        "403": text_response("The token of the request isn't an admin one"),
+                    },
+                },
+            },
+            "/admin/interval": {
+                "post": {
+                    "summary": "Changes the interval of the update loop",
+                    "operationId": "setInterval",
+                                        "requestBody": {
+                        "required": true,
+                        "content": {
+                            "application/json": { "schema": { "$ref": "#/components/schemas/Interval" } },
+                        },
+                    },
+                    "responses": {
+                        "200": text_response("The interval is changed"),
+                        "400": text_response("The interval is missing, invalid or zero"),
+                        "401": text_response("The request doesn't carry a valid token"),
+                        "403": text_response("The token of the request isn't an admin one"),
+                    },
+                },
+            },
+            "/admin/strategy": {
+                "post": {
+                    "summary": "Swaps the strategy of the service",
+                    "operationId": "setStrategy",
+                                        "requestBody": {
+                        "required": true,
+                        "content": {
+                            "application/json": { "schema": { "$ref": "#/components/schemas/Strategy" } },
+                        },
+                    },
+                    "responses": {
+                        "200": text_response("The strategy is swapped"),
+                        "400": text_response("The strategy is invalid"),
+                        "401": text_response("The request doesn't carry a valid token"),
+                        "403": text_response("The token of the request isn't an admin one"),
+                    },
+                },
+            },
+            "/admin/flush": {
+                "post": {
+                    "summary": "Flushes the scores from the store, so the endpoints are scored from scratch",
+                    "operationId": "flush",
+                                        "responses": {
+                        "200": text_response("The store is flushed"),
+                        "401": text_response("The request doesn't carry a valid token"),
+                        "403": text_response("The token of the request isn't an admin one"),
+                        "500": text_response("The store can't be flushed"),
+                    },
+                },
+            },
+            "/openapi.json": {
+                "get": {
+                    "summary": "This document",
+                    "operationId": "openapi",
+                    "responses": {
+                        "200": {
+                            "description": "The OpenAPI document of the server",
+                            "content": { "application/json": { "schema": { "type": "object" } } },
+                        },
+                    },
+                },
+            },
+        },
+        "components": {
+            "schemas": {
+                "Best": {
+                    "type": "object",
+                    "required": ["url", "updated_at"],
+                    "properties": {
+                        "url": {
+                            "type": "string",
+                            "nullable": true,
+                            "description": "The best scoring URL, if any endpoint was scored",
+                        },
+                        "updated_at": {
+                            "type": "integer",
+                            "format": "int64",
+                            "description": "The Unix timestamp of the last update",
+                        },
+                    },
+                },
+                "RankedEndpoint": {
+                    "type": "object",
+                    "required": ["url", "score", "state", "latency", "last_checked"],
+                    "properties": {
+                        "url": { "type": "string" },
+                        "score": { "$ref": "#/components/schemas/Score" },
+                        "state": {
+                            "type": "string",
+                            "enum": ["up", "down"],
+                            "nullable": true,
+                            "description": "The state of the endpoint, according to its latest probe",
+                        },
+                        "latency": {
+                            "allOf": [{ "$ref": "#/components/schemas/Duration" }],
+                            "description": "The average response time of the endpoint",
+                        },
+                        "last_checked": {
+                            "allOf": [{ "$ref": "#/components/schemas/Time" }],
+                            "nullable": true,
+                            "description": "When the endpoint was last scored",
+                        },
+                        "trend": {
+                            "type": "object",
+                            "nullable": true,
+                            "description": "The short-term trend of the score, over its latest values",
+                            "required": ["direction", "slope"],
+                            "properties": {
+                                "direction": { "type": "string", "enum": ["improving", "stable", "degrading"] },
+                                "slope": { "type": "number", "description": "The change of the score per hour" },
+                            },
+                        },
+                    },
+                },
+                "Score": score_schema(),
+                "ScoreView": {
+                    "allOf": [
+                        {
+                            "type": "object",
+                            "required": ["version", "url"],
+                            "properties": {
+                                "version": { "type": "integer", "description": "The version of the encoding of the score" },
+                                "url": { "type": "string" },
+                            },
+                        },
+                        { "$ref": "#/components/schemas/Score" },
+                    ],
+                },
+                "Endpoint": {
+                    "type": "object",
+                    "required": ["url"],
+                    "properties": {
+                        "url": { "type": "string", "description": "The URL of the endpoint" },
+                        "duration": {
+                            "type": "string",
+                            "description": "How long the failures are suppressed, e.g. `10m`; required by `/suppress`",
+                        },
+                    },
+                },
+                "Interval": {
+                    "type": "object",
+                    "required": ["interval"],
+                    "properties": {
+                        "interval": { "type": "string", "description": "The interval of the update loop, e.g. `30s`" },
+                    },
+                },
+                "Strategy": {
+                    "type": "object",
+                    "required": ["type"],
+                    "description": "The configuration of a strategy, along with the parameters of its type",
+                    "properties": {
+                        "type": { "type": "string", "enum": ["weighted_log", "linear", "step"] },
+                    },
+                    "additionalProperties": true,
+                },
+                "Health": {
+                    "type": "object",
+                    "required": ["healthy", "queue_depth"],
+                    "properties": {
+                        "healthy": { "type": "boolean" },
+                        "last_update": {
+                            "allOf": [{ "$ref": "#/components/schemas/Time" }],
+                            "nullable": true,
+                            "description": "When the last update completed",
+                        },
+                        "since_last_update": {
+                            "allOf": [{ "$ref": "#/components/schemas/Duration" }],
+                            "nullable": true,
+                            "description": "The time elapsed since the last update completed, or the update loop started",
+                        },
+                        "stale_after": {
+                            "allOf": [{ "$ref": "#/components/schemas/Duration" }],
+                            "nullable": true,
+                            "description": "The time without a completed update after which the monitor is unhealthy",
+                        },
+                        "store_error": { "type": "string", "nullable": true },
+                        "store_latency": {
+                            "allOf": [{ "$ref": "#/components/schemas/Duration" }],
+                            "description": "How long the store took to answer the health check",
+                        },
+                        "queue_depth": {
+                            "type": "integer",
+                            "description": "The number of samples waiting to be delivered to the coordinator",
+                        },
+                    },
+                },
+                "Time": {
+                    "type": "object",
+                    "description": "A date, as the time elapsed since the Unix epoch",
+                    "required": ["secs_since_epoch", "nanos_since_epoch"],
+                    "properties": {
+                        "secs_since_epoch": { "type": "integer", "format": "int64" },
+                        "nanos_since_epoch": { "type": "integer", "format": "int32" },
+                    },
+                },
+                "Duration": {
+                    "type": "object",
+                    "required": ["secs", "nanos"],
+                    "properties": {
+                        "secs": { "type": "integer", "format": "int64" },
+                        "nanos": { "type": "integer", "format": "int32" },
+                    },
+                },
+                "GrafanaQuery": {
+                    "type": "object",
+                    "required": ["range", "targets"],
+                    "properties": {
+                        "range": {
+                            "type": "object",
+                            "required": ["from", "to"],
+                            "properties": {
+                                "from": { "type": "string", "format": "date-time" },
+                                "to": { "type": "string", "format": "date-time" },
+                            },
+                        },
+                        "targets": {
+                            "type": "array",
+                            "items": {
+                                "type": "object",
+                                "required": ["target"],
+                                "properties": { "target": { "type": "string" } },
+                            },
+                        },
+                        "maxDataPoints": { "type": "integer", "minimum": 1 },
+                    },
+                },
+                "Series": {
+                    "type": "object",
+                    "required": ["target", "datapoints"],
+                    "properties": {
+                        "target": { "type": "string" },
+                        "datapoints": {
+                            "type": "array",
+                            "description": "The points of the series, as `[value, unix timestamp in milliseconds]`",
+                            "items": { "type": "array", "items": { "type": "number" }, "minItems": 2, "maxItems": 2 },
+                        },
+                    },
+                },
+            },
+            "securitySchemes": {
+                "bearer": {
+                    "type": "http",
+                    "scheme": "bearer",
+                    "description": "A token of the server, granting the `read_only` or `admin` role; required by every route but `/health` once the server has one",
+                },
+            },
+        },
+    })
+}
+
+/// Describes the scores with their canonical JSON Schema, without the keywords OpenAPI 3.0 doesn't support.
+fn score_schema() -> Value {
+    let mut schema = Score::schema();
+    if let Some(schema) = schema.as_object_mut() {
+        schema.remove("$schema");
+        schema.remove("$id");
+    }
+    schema
+}
+
+/// Describes a JSON response of the given schema.
+fn json_response(description: &str, schema: &str) -> Value {
+    json!({
+        "description": description,
+        "content": { "application/json": { "schema": { "$ref": schema } } },
+    })
+}
+
+/// Describes a plain-text response.
+fn text_response(description: &str) -> Value {
+    json!({
+        "description": description,
+        "content": { "text/plain": { "schema": { "type": "string" } } },
+    })
+}
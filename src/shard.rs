@@ -0,0 +1,204 @@
+use crate::config::ConfigDuration;
+use std::error::Error;
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, Instant};
+
+/// Sharding configuration
+///
+/// - `id`: identifies the replica among the others (default: a random ID)
+/// - `ttl`: how long a replica stays registered without a heartbeat, which should exceed the interval (default: 30s)
+/// - `vnodes`: the number of points of every replica on the hash ring, evening out the shards (default: 64)
+///
+/// The replicas register themselves in the configured store on every update, so replicas sharing a Redis store split
+/// the endpoints among themselves, and rebalance them once a replica stops renewing its registration.
+#[derive(serde::Deserialize, serde::Serialize, Clone, Debug, Default, PartialEq, Eq)]
+pub struct Config {
+    #[serde(default)]
+    pub id: Option<String>,
+    #[serde(default)]
+    pub ttl: Option<ConfigDuration>,
+    #[serde(default)]
+    pub vnodes: Option<usize>,
+}
+
+impl Config {
+    /// Sets the ID of the replica.
+    pub fn set_id<I: Into<String>>(mut self, id: I) -> Self {
+        self.id = Some(id.into());
+        self
+    }
+
+    /// Sets how long the replica stays registered without a heartbeat.
+    pub fn set_ttl(mut self, ttl: Duration) -> Self {
+        self.ttl = Some(ttl.into());
+        self
+    }
+
+    /// Sets the number of points of every replica on the hash ring.
+    pub fn set_vnodes(mut self, vnodes: usize) -> Self {
+        self.vnodes = Some(vnodes);
+        self
+    }
+}
+
+/// A registry of the live replicas, shared by the replicas splitting the endpoints.
+#[async_trait::async_trait]
+pub trait Registry {
+    /// Registers a replica, or renews its registration, and lists the live replicas.
+    ///
+    /// # Arguments
+    /// * `replica`: The ID of the replica.
+    /// * `ttl`: How long the replica stays registered without renewing its registration.
+    ///
+    /// # Returns
+    /// The IDs of the replicas whose registration hasn't expired, including the given one.
+    ///
+    /// # Errors
+    /// Returns an error if the registry can't be reached, or the expiration of the registration can't be represented.
+    async fn heartbeat(&self, replica: &str, ttl: Duration) -> Result<Vec<String>, Box<dyn Error + Send + Sync>>;
+}
+
+#[async_trait::async_trait]
+impl<T: Registry + Sync + Send + ?Sized> Registry for Arc<T> {
+    async fn heartbeat(&self, replica: &str, ttl: Duration) -> Result<Vec<String>, Box<dyn Error + Send + Sync>> {
+        (**self).heartbeat(replica, ttl).await
+    }
+}
+
+/// An in-process registry, shared by the replicas running within the same process.
+#[derive(Debug, Default)]
+pub struct Memory {
+    /// The registered replicas and the expiration of their registration.
+    inner: Mutex<Vec<(String, Instant)>>,
+}
+
+impl Memory {
+    /// Creates a new in-process registry.
+    pub fn new() -> Self {
+        Self::default()
+    }
+}
+
+#[async_trait::async_trait]
+impl Registry for Memory {
+    async fn heartbeat(&self, replica: &str, ttl: Duration) -> Result<Vec<String>, Box<dyn Error + Send + Sync>> {
+        let mut inner = self.inner.lock().map_err(|e| e.to_string())?;
+        let now = Instant::now();
+        let expires_at = now.checked_add(ttl).ok_or("the TTL of the registration overflows the clock")?;
+        inner.retain(|(id, expires_at)| id != replica && *expires_at > now);
+        inner.push((replica.to_string(), expires_at));
+        Ok(inner.iter().map(|(id, _)| id.clone()).collect())
+    }
+}
+
+/// Assigns the endpoints of a `Service` to the live replicas, through consistent hashing.
+pub(crate) struct Sharding {
+    registry: Box<dyn Registry + Sync + Send + 'static>,
+    id: String,
+    ttl: Duration,
+    vnodes: usize,
+}
+
+impl Sharding {
+    pub(crate) fn new(registry: Box<dyn Registry + Sync + Send + 'static>, config: Config) -> Self {
+        Self {
+            registry,
+            id: config.id.unwrap_or_else(crate::request::generate_id),
+            ttl: config.ttl.map_or(Duration::from_secs(30), ConfigDuration::get),
+            vnodes: config.vnodes.unwrap_or(64).max(1),
+        }
+    }
+
+    /// Renews the registration of the replica, and builds the hash ring of the live replicas.
+    pub(crate) async fn ring(&self) -> Result<Ring, Box<dyn Error + Send + Sync>> {
+        let replicas = self.registry.heartbeat(&self.id, self.ttl).await?;
+        Ok(Ring::new(&replicas, self.vnodes))
+    }
+
+    /// Returns `true` if the endpoint is assigned to this replica.
+    pub(crate) fn owns(&self, ring: &Ring, url: &str) -> bool {
+        ring.owner(url).is_none_or(|owner| owner == self.id)
+    }
+}
+
+/// A consistent hash ring, mapping every endpoint to a replica.
+///
+/// Every replica is placed on the ring at several points, and owns the endpoints hashed between its points and the
+/// previous ones. When a replica leaves, only its endpoints are reassigned, to the replicas following its points.
+pub(crate) struct Ring {
+    points: Vec<(u64, String)>,
+}
+
+impl Ring {
+    pub(crate) fn new(replicas: &[String], vnodes: usize) -> Self {
+        let mut points = replicas
+            .iter()
+            .flat_map(|replica| (0..vnodes).map(move |i| (hash(&format!("{replica}#{i}")), replica.clone())))
+            .collect::<Vec<_>>();
+        points.sort();
+        Self { points }
+    }
+
+    /// Returns the replica owning a key, or `None` if no replica is registered.
+    pub(crate) fn owner(&self, key: &str) -> Option<&str> {
+        let hash = hash(key);
+        let index = self.points.partition_point(|(point, _)| *point < hash);
+        // Keys past the last point wrap around to the first one
+        self.points.get(index).or(self.points.first()).map(|(_, replica)| replica.as_str())
+    }
+}
+
+/// Hashes a key with FNV-1a, followed by a splitmix64 finalizer to spread similar keys across the ring.
+///
+/// The hash has to be stable across processes and builds, so every replica computes the same ring.
+fn hash(key: &str) -> u64 {
+    let mut x = key.bytes().fold(0xcbf29ce484222325u64, |h, b| (h ^ u64::from(b)).wrapping_mul(0x100000001b3));
+    x = (x ^ (x >> 30)).wrapping_mul(0xbf58476d1ce4e5b9);
+    x = (x ^ (x >> 27)).wrapping_mul(0x94d049bb133111eb);
+    x ^ (x >> 31)
+}
+
+/// Creates the registry held in the store of the given configuration.
+///
+/// # Arguments
+/// * `config` - Storage configuration.
+///
+/// # Returns
+/// A boxed registry, shared by the replicas using the same store.
+pub fn from_config(config: &crate::store::Config) -> Box<dyn Registry + Sync + Send + 'static> {
+    match config {
+        #[cfg(feature = "redis")]
+        crate::store::Config::Redis(config) => Box::new(crate::store::Redis::from_config(config)),
+        crate::store::Config::Memory => Box::new(Memory::new()),
+        // The registry is held in the primary store only, as the replicas coordinate through it
+        crate::store::Config::Fallback(fallback) => from_config(&fallback.primary),
+        crate::store::Config::Buffered(buffered) => from_config(&buffered.store),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_ring_rebalance() {
+        let replicas = ["a", "b", "c"].map(String::from);
+        let keys = (0..300).map(|i| format!("https://{i}.example.com/")).collect::<Vec<_>>();
+
+        let ring = Ring::new(&replicas, 64);
+        let owners = keys.iter().map(|k| ring.owner(k).unwrap().to_string()).collect::<Vec<_>>();
+        // Every replica owns a fair share of the keys
+        for replica in &replicas {
+            assert!(owners.iter().filter(|o| *o == replica).count() > 50);
+        }
+
+        // Only the keys of a leaving replica are reassigned
+        let ring = Ring::new(&replicas[..2], 64);
+        for (key, owner) in keys.iter().zip(&owners) {
+            match owner.as_str() {
+                "c" => assert_ne!(ring.owner(key), Some("c")),
+                owner => assert_eq!(ring.owner(key), Some(owner)),
+            }
+        }
+    }
+}
@@ -0,0 +1,248 @@
+use super::{Predicate, Store, StoreHealth};
+use crate::incident::Incident;
+use crate::request::Request;
+use crate::score::Score;
+use dashmap::DashMap;
+use std::collections::VecDeque;
+use std::error::Error;
+use std::sync::atomic::{AtomicU64, Ordering::SeqCst};
+use std::sync::{Arc, Mutex};
+
+/// The default number of scores buffered while the store is unavailable.
+pub const DEFAULT_CAPACITY: usize = 10_000;
+
+/// Buffered store configuration
+///
+/// - `store`: the store the scores are written to, e.g. Redis
+/// - `capacity`: the number of endpoints whose latest score is buffered while the store is unavailable (default: 10000)
+/// - `drop_policy`: which scores are dropped once the buffer is full, `oldest` (default) or `newest`
+#[derive(serde::Deserialize, serde::Serialize, Clone, Debug)]
+pub struct Config {
+    pub store: Box<super::Config>,
+    #[serde(default)]
+    pub capacity: Option<usize>,
+    #[serde(default)]
+    pub drop_policy: DropPolicy,
+}
+
+/// Which scores are dropped once the buffer of a `Buffered` store is full.
+#[derive(serde::Deserialize, serde::Serialize, Clone, Copy, Debug, Default, PartialEq, Eq)]
+#[serde(rename_all = "snake_case")]
+pub enum DropPolicy {
+    /// The score buffered the longest ago is dropped, in favor of the new one.
+    #[default]
+    Oldest,
+    /// The new score is dropped, keeping the ones already buffered.
+    Newest,
+}
+
+/// A store buffering the scores it fails to write, e.g. during a brief Redis outage, and flushing them once the
+/// store is reachable again, so the reliability accumulated meanwhile isn't lost.
+///
+/// Only the latest score of every endpoint is buffered, and served by `get` until it's flushed. The last score read
+/// from or written to the store is served as well while it's unavailable, so the endpoints keep being scored from
+/// their previous score rather than from scratch. The buffer is flushed before every other operation.
+pub struct Buffered<S> {
+    inner: S,
+    capacity: usize,
+    drop_policy: DropPolicy,
+    buffer: Mutex<VecDeque<(String, Score)>>,
+    /// The last score of every endpoint read from or written to the store.
+    known: DashMap<String, Score>,
+    dropped: AtomicU64,
+}
+
+impl<S: Store + Sync + Send> Buffered<S> {
+    /// Wraps a store, buffering up to `DEFAULT_CAPACITY` scores and dropping the oldest ones first.
+    pub fn new(store: S) -> Self {
+        Self {
+            inner: store,
+            capacity: DEFAULT_CAPACITY,
+            drop_policy: DropPolicy::default(),
+            buffer: Mutex::new(VecDeque::new()),
+            known: DashMap::new(),
+            dropped: AtomicU64::new(0),
+        }
+    }
+
+    /// Sets the number of endpoints whose latest score is buffered.
+    pub fn set_capacity(mut self, capacity: usize) -> Self {
+        self.capacity = capacity;
+        self
+    }
+
+    /// Sets which scores are dropped once the buffer is full.
+    pub fn set_drop_policy(mut self, drop_policy: DropPolicy) -> Self {
+        self.drop_policy = drop_policy;
+        self
+    }
+
+    /// Returns the number of scores waiting to be written to the store.
+    pub fn pending(&self) -> usize {
+        self.buffer.lock().expect("poisoned buffer").len()
+    }
+
+    /// Returns the number of scores dropped because the buffer was full.
+    pub fn dropped(&self) -> u64 {
+        self.dropped.load(SeqCst)
+    }
+
+    /// Writes the buffered scores to the store.
+    ///
+    /// # Errors
+    /// Returns the error of the store, in which case the scores stay buffered.
+    pub async fn flush(&self) -> Result<(), Box<dyn Error + Send + Sync>> {
+        let scores = std::mem::take(&mut *self.buffer.lock().expect("poisoned buffer"));
+        if scores.is_empty() {
+            return Ok(());
+        }
+        match self.inner.set_many(scores.iter().cloned().collect()).await {
+            Ok(()) => {
+                self.remember(scores);
+                Ok(())
+            }
+            Err(e) => {
+                // Buffer the scores again, unless they were superseded meanwhile
+                for (key, score) in scores.into_iter().rev() {
+                    self.push(key, score, true);
+                }
+                Err(e)
+            }
+        }
+    }
+
+    /// Buffers the latest score of an endpoint, replacing the previous one, and applies the drop policy.
+    ///
+    /// # Arguments
+    /// * `front`: Whether the score is older than the ones buffered, e.g. when it failed to be flushed.
+    fn push(&self, key: String, score: Score, front: bool) {
+        let mut buffer = self.buffer.lock().expect("poisoned buffer");
+        match buffer.iter().position(|(k, _)| *k == key) {
+            Some(_) if front => return,
+            Some(i) => drop(buffer.remove(i)),
+            None => {}
+        }
+        if buffer.len() >= self.capacity {
+            self.dropped.fetch_add(1, SeqCst);
+            match (self.drop_policy, front) {
+                (DropPolicy::Oldest, true) | (DropPolicy::Newest, false) => return,
+                (DropPolicy::Oldest, false) => drop(buffer.pop_front()),
+                (DropPolicy::Newest, true) => drop(buffer.pop_back()),
+            }
+        }
+        match front {
+            true => buffer.push_front((key, score)),
+            false => buffer.push_back((key, score)),
+        }
+    }
+
+    /// Remembers the scores written to the store, to be served while it's unavailable.
+    fn remember(&self, scores: impl IntoIterator<Item = (String, Score)>) {
+        for (key, score) in scores {
+            self.known.insert(key, score);
+        }
+    }
+
+    /// Returns the buffered score of an endpoint, if any.
+    fn buffered(&self, key: &str) -> Option<Score> {
+        let buffer = self.buffer.lock().expect("poisoned buffer");
+        buffer.iter().find(|(k, _)| k == key).map(|(_, score)| score.clone())
+    }
+}
+
+impl Buffered<Arc<dyn Store + Sync + Send>> {
+    /// Constructs a buffered store from its configuration.
+    pub(crate) fn from_config(config: Config) -> Self {
+        Self::new(Arc::from(super::from_config(*config.store)))
+            .set_capacity(config.capacity.unwrap_or(DEFAULT_CAPACITY))
+            .set_drop_policy(config.drop_policy)
+    }
+}
+
+#[async_trait::async_trait]
+impl<S: Store + Sync + Send> Store for Buffered<S> {
+    async fn set(&self, key: String, value: Score) -> Result<(), Box<dyn Error + Send + Sync>> {
+        self.set_many(vec![(key, value)]).await
+    }
+
+    /// Sets the scores, buffering them if the store fails to write them.
+    async fn set_many(&self, scores: Vec<(String, Score)>) -> Result<(), Box<dyn Error + Send + Sync>> {
+        if self.flush().await.is_ok() && self.inner.set_many(scores.clone()).await.is_ok() {
+            self.remember(scores);
+            return Ok(());
+        }
+        for (key, score) in scores {
+            self.push(key, score, false);
+        }
+        Ok(())
+    }
+
+    /// Retrieves the score of an endpoint, the buffered one first, then the stored one, or the last one known while
+    /// the store is unavailable.
+    async fn get(&self, key: &str) -> Result<Option<Score>, Box<dyn Error + Send + Sync>> {
+        if let Some(score) = self.buffered(key) {
+            return Ok(Some(score));
+        }
+        match self.inner.get(key).await {
+            Ok(Some(score)) => {
+                self.known.insert(key.to_string(), score.clone());
+                Ok(Some(score))
+            }
+            Ok(None) => {
+                self.known.remove(key);
+                Ok(None)
+            }
+            Err(e) => self.known.get(key).map(|score| Some(score.clone())).ok_or(e),
+        }
+    }
+
+    async fn best_url(&self) -> Result<Option<String>, Box<dyn Error + Send + Sync>> {
+        self.flush().await?;
+        self.inner.best_url().await
+    }
+
+    async fn best_url_where(&self, predicate: &Predicate<'_>) -> Result<Option<String>, Box<dyn Error + Send + Sync>> {
+        self.flush().await?;
+        self.inner.best_url_where(predicate).await
+    }
+
+    async fn ranking(&self) -> Result<Vec<(String, Score)>, Box<dyn Error + Send + Sync>> {
+        self.flush().await?;
+        self.inner.ranking().await
+    }
+
+    async fn set_incident(&self, incident: Incident) -> Result<(), Box<dyn Error + Send + Sync>> {
+        self.inner.set_incident(incident).await
+    }
+
+    async fn get_incident(&self, id: &str) -> Result<Option<Incident>, Box<dyn Error + Send + Sync>> {
+        self.inner.get_incident(id).await
+    }
+
+    async fn incidents(&self) -> Result<Vec<Incident>, Box<dyn Error + Send + Sync>> {
+        self.inner.incidents().await
+    }
+
+    /// Removes every score from the store, along with the buffered ones.
+    async fn clear(&self) -> Result<(), Box<dyn Error + Send + Sync>> {
+        self.buffer.lock().expect("poisoned buffer").clear();
+        self.known.clear();
+        self.inner.clear().await
+    }
+
+    async fn ping(&self) -> Result<(), Box<dyn Error + Send + Sync>> {
+        self.inner.ping().await
+    }
+
+    async fn health(&self) -> StoreHealth {
+        self.inner.health().await
+    }
+
+    async fn set_requests(&self, requests: &[Request]) -> Result<(), Box<dyn Error + Send + Sync>> {
+        self.inner.set_requests(requests).await
+    }
+
+    async fn requests(&self) -> Result<Vec<Request>, Box<dyn Error + Send + Sync>> {
+        self.inner.requests().await
+    }
+}
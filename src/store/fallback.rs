@@ -0,0 +1,284 @@
+use super::{Predicate, Store, StoreHealth};
+use crate::config::ConfigDuration;
+use crate::incident::Incident;
+use crate::request::Request;
+use crate::score::Score;
+use dashmap::DashSet;
+use std::error::Error;
+use std::sync::atomic::{AtomicBool, Ordering::SeqCst};
+use std::sync::Mutex;
+use std::time::{Duration, Instant};
+
+/// The default time between the attempts to reach the primary store, while it's unavailable.
+const DEFAULT_RETRY_INTERVAL: Duration = Duration::from_secs(10);
+
+/// Fallback store configuration
+///
+/// - `primary`: the store the scores are kept in, e.g. Redis
+/// - `fallback`: the store the service falls back on while the primary one is unavailable, e.g. memory.
+///   It can itself be a fallback store, chaining several of them.
+/// - `retry_interval`: the time between the attempts to reach the primary store while it's unavailable (default: 10s)
+#[derive(serde::Deserialize, serde::Serialize, Clone, Debug)]
+pub struct Config {
+    pub primary: Box<super::Config>,
+    pub fallback: Box<super::Config>,
+    #[serde(default)]
+    pub retry_interval: Option<ConfigDuration>,
+}
+
+/// A store falling back on another one while it's unavailable, so that the endpoints are still probed and ranked
+/// locally, e.g. while the shared Redis server restarts.
+///
+/// Every write goes to both stores, keeping the fallback one up to date, and the reads are served by the primary one
+/// while it's available. The writes it missed are replayed from the fallback store once it recovers.
+pub struct Fallback {
+    primary: Box<dyn Store + Sync + Send>,
+    fallback: Box<dyn Store + Sync + Send>,
+    retry_interval: Duration,
+    /// When the primary store last failed, while it's unavailable.
+    failed_at: Mutex<Option<Instant>>,
+    /// The keys of the scores missed by the primary store.
+    pending: DashSet<String>,
+    /// The IDs of the incidents missed by the primary store.
+    pending_incidents: DashSet<String>,
+    /// Whether the primary store missed a change of the persisted requests.
+    pending_requests: AtomicBool,
+}
+
+impl Fallback {
+    /// Creates a store falling back on `fallback` while `primary` is unavailable.
+    pub fn new<P, F>(primary: P, fallback: F) -> Self
+    where
+        P: Store + Sync + Send + 'static,
+        F: Store + Sync + Send + 'static,
+    {
+        Self::from_boxes(Box::new(primary), Box::new(fallback))
+    }
+
+    fn from_boxes(primary: Box<dyn Store + Sync + Send>, fallback: Box<dyn Store + Sync + Send>) -> Self {
+        Self {
+            primary,
+            fallback,
+            retry_interval: DEFAULT_RETRY_INTERVAL,
+            failed_at: Mutex::new(None),
+            pending: DashSet::new(),
+            pending_incidents: DashSet::new(),
+            pending_requests: AtomicBool::new(false),
+        }
+    }
+
+    /// Sets the time between the attempts to reach the primary store, while it's unavailable.
+    pub fn set_retry_interval(mut self, interval: Duration) -> Self {
+        self.retry_interval = interval;
+        self
+    }
+
+    /// Constructs a fallback store from its configuration.
+    pub(crate) fn from_config(config: Config) -> Self {
+        let store = Self::from_boxes(super::from_config(*config.primary), super::from_config(*config.fallback));
+        store.set_retry_interval(config.retry_interval.map_or(DEFAULT_RETRY_INTERVAL, ConfigDuration::get))
+    }
+
+    /// Returns `true` while the primary store is unavailable, and the fallback one serves the reads.
+    pub fn is_degraded(&self) -> bool {
+        self.failed_at.lock().expect("poisoned fallback state").is_some()
+    }
+
+    /// Checks whether the primary store is available, attempting to reach it again once the retry interval elapsed.
+    /// The writes it missed are replayed first, so it isn't read until it's up to date.
+    async fn primary_available(&self) -> bool {
+        match *self.failed_at.lock().expect("poisoned fallback state") {
+            Some(failed_at) if failed_at.elapsed() < self.retry_interval => return false,
+            Some(_) => {}
+            None => return true,
+        }
+        match self.resync().await {
+            Ok(()) => {
+                *self.failed_at.lock().expect("poisoned fallback state") = None;
+                true
+            }
+            Err(_) => {
+                self.fail();
+                false
+            }
+        }
+    }
+
+    /// Marks the primary store as unavailable.
+    fn fail(&self) {
+        *self.failed_at.lock().expect("poisoned fallback state") = Some(Instant::now());
+    }
+
+    /// Replays the writes missed by the primary store, from the fallback one.
+    async fn resync(&self) -> Result<(), Box<dyn Error + Send + Sync>> {
+        let keys = self.pending.iter().map(|key| key.clone()).collect::<Vec<_>>();
+        let mut scores = Vec::with_capacity(keys.len());
+        for key in &keys {
+            if let Some(score) = self.fallback.get(key).await? {
+                scores.push((key.clone(), score));
+            }
+        }
+        self.primary.set_many(scores).await?;
+        for key in &keys {
+            self.pending.remove(key);
+        }
+
+        let ids = self.pending_incidents.iter().map(|id| id.clone()).collect::<Vec<_>>();
+        for id in ids {
+            if let Some(incident) = self.fallback.get_incident(&id).await? {
+                self.primary.set_incident(incident).await?;
+            }
+            self.pending_incidents.remove(&id);
+        }
+
+        if self.pending_requests.swap(false, SeqCst) {
+            if let Err(e) = self.primary.set_requests(&self.fallback.requests().await?).await {
+                self.pending_requests.store(true, SeqCst);
+                return Err(e);
+            }
+        }
+        Ok(())
+    }
+}
+
+#[async_trait::async_trait]
+impl Store for Fallback {
+    async fn set(&self, key: String, value: Score) -> Result<(), Box<dyn Error + Send + Sync>> {
+        self.set_many(vec![(key, value)]).await
+    }
+
+    async fn set_many(&self, scores: Vec<(String, Score)>) -> Result<(), Box<dyn Error + Send + Sync>> {
+        self.fallback.set_many(scores.clone()).await?;
+        let keys = scores.iter().map(|(key, _)| key.clone()).collect::<Vec<_>>();
+        if !self.primary_available().await || self.primary.set_many(scores).await.is_err() {
+            self.fail();
+            for key in keys {
+                self.pending.insert(key);
+            }
+        }
+        Ok(())
+    }
+
+    async fn get(&self, key: &str) -> Result<Option<Score>, Box<dyn Error + Send + Sync>> {
+        if self.primary_available().await {
+            match self.primary.get(key).await {
+                Ok(score) => return Ok(score),
+                Err(_) => self.fail(),
+            }
+        }
+        self.fallback.get(key).await
+    }
+
+    async fn best_url(&self) -> Result<Option<String>, Box<dyn Error + Send + Sync>> {
+        if self.primary_available().await {
+            match self.primary.best_url().await {
+                Ok(url) => return Ok(url),
+                Err(_) => self.fail(),
+            }
+        }
+        self.fallback.best_url().await
+    }
+
+    async fn best_url_where(&self, predicate: &Predicate<'_>) -> Result<Option<String>, Box<dyn Error + Send + Sync>> {
+        if self.primary_available().await {
+            match self.primary.best_url_where(predicate).await {
+                Ok(url) => return Ok(url),
+                Err(_) => self.fail(),
+            }
+        }
+        self.fallback.best_url_where(predicate).await
+    }
+
+    async fn ranking(&self) -> Result<Vec<(String, Score)>, Box<dyn Error + Send + Sync>> {
+        if self.primary_available().await {
+            match self.primary.ranking().await {
+                Ok(ranking) => return Ok(ranking),
+                Err(_) => self.fail(),
+            }
+        }
+        self.fallback.ranking().await
+    }
+
+    async fn set_incident(&self, incident: Incident) -> Result<(), Box<dyn Error + Send + Sync>> {
+        self.fallback.set_incident(incident.clone()).await?;
+        let id = incident.id.clone();
+        if !self.primary_available().await || self.primary.set_incident(incident).await.is_err() {
+            self.fail();
+            self.pending_incidents.insert(id);
+        }
+        Ok(())
+    }
+
+    async fn get_incident(&self, id: &str) -> Result<Option<Incident>, Box<dyn Error + Send + Sync>> {
+        if self.primary_available().await {
+            match self.primary.get_incident(id).await {
+                Ok(incident) => return Ok(incident),
+                Err(_) => self.fail(),
+            }
+        }
+        self.fallback.get_incident(id).await
+    }
+
+    async fn incidents(&self) -> Result<Vec<Incident>, Box<dyn Error + Send + Sync>> {
+        if self.primary_available().await {
+            match self.primary.incidents().await {
+                Ok(incidents) => return Ok(incidents),
+                Err(_) => self.fail(),
+            }
+        }
+        self.fallback.incidents().await
+    }
+
+    /// Removes every score from both stores, failing if the primary one can't be flushed, so the scores don't
+    /// resurface once it recovers.
+    async fn clear(&self) -> Result<(), Box<dyn Error + Send + Sync>> {
+        self.fallback.clear().await?;
+        self.pending.clear();
+        self.primary.clear().await
+    }
+
+    /// Checks that either store can be reached, the service running on the fallback one otherwise.
+    async fn ping(&self) -> Result<(), Box<dyn Error + Send + Sync>> {
+        match self.primary.ping().await {
+            Ok(()) => Ok(()),
+            Err(_) => self.fallback.ping().await,
+        }
+    }
+
+    /// Checks the health of the primary store, healthy while the fallback one can be reached in its place.
+    async fn health(&self) -> StoreHealth {
+        let primary = self.primary.health().await;
+        if primary.healthy {
+            return primary;
+        }
+        let fallback = self.fallback.health().await;
+        let error = primary.error.unwrap_or_default();
+        StoreHealth {
+            healthy: fallback.healthy,
+            error: Some(match fallback.error {
+                None => format!("primary store unavailable, running on the fallback one: {error}"),
+                Some(fallback) => format!("primary store unavailable: {error}, fallback store unavailable: {fallback}"),
+            }),
+            ..fallback
+        }
+    }
+
+    async fn set_requests(&self, requests: &[Request]) -> Result<(), Box<dyn Error + Send + Sync>> {
+        self.fallback.set_requests(requests).await?;
+        if !self.primary_available().await || self.primary.set_requests(requests).await.is_err() {
+            self.fail();
+            self.pending_requests.store(true, SeqCst);
+        }
+        Ok(())
+    }
+
+    async fn requests(&self) -> Result<Vec<Request>, Box<dyn Error + Send + Sync>> {
+        if self.primary_available().await {
+            match self.primary.requests().await {
+                Ok(requests) => return Ok(requests),
+                Err(_) => self.fail(),
+            }
+        }
+        self.fallback.requests().await
+    }
+}
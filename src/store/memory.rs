@@ -1,4 +1,6 @@
-use super::Store;
+use super::{Predicate, Store};
+use crate::incident::Incident;
+use crate::request::Request;
 use crate::score::Score;
 use std::error::Error;
 
@@ -10,6 +12,10 @@ pub struct Memory {
     /// The inner data structure for storing scores.
     /// Maps a `String` (representing a URL) to a `Score`.
     pub inner: dashmap::DashMap<String, Score>,
+    /// The incident log, keyed by the ID of the incidents.
+    pub incidents: dashmap::DashMap<String, Incident>,
+    /// The persisted requests, keyed by their URL.
+    pub requests: dashmap::DashMap<String, Request>,
 }
 
 impl Default for Memory {
@@ -18,7 +24,7 @@ impl Default for Memory {
     /// ## Returns
     /// A new `Memory` instance with an initialized `DashMap`.
     fn default() -> Self {
-        Self { inner: dashmap::DashMap::new() }
+        Self { inner: dashmap::DashMap::new(), incidents: dashmap::DashMap::new(), requests: dashmap::DashMap::new() }
     }
 }
 
@@ -42,7 +48,7 @@ impl Store for Memory {
     ///
     /// ## Returns
     /// A result indicating success or an error.
-    async fn set(&self, key: String, value: Score) -> Result<(), Box<dyn Error>> {
+    async fn set(&self, key: String, value: Score) -> Result<(), Box<dyn Error + Send + Sync>> {
         self.inner.insert(key, value);
         Ok(())
     }
@@ -53,18 +59,103 @@ impl Store for Memory {
     ///
     /// ## Returns
     /// An option containing the score if it exists, or None otherwise.
-    async fn get(&self, key: &str) -> Result<Option<Score>, Box<dyn Error>> {
+    async fn get(&self, key: &str) -> Result<Option<Score>, Box<dyn Error + Send + Sync>> {
         Ok(self.inner.get(key).map(|v| v.value().clone()))
     }
     /// Identifies the key associated with the best score (highest value).
     ///
     /// ## Returns
     /// An option containing the key of the best score if it exists, or None otherwise.
-    async fn best_url(&self) -> Result<Option<String>, Box<dyn Error>> {
+    async fn best_url(&self) -> Result<Option<String>, Box<dyn Error + Send + Sync>> {
         Ok(self
             .inner
             .iter()
             .max_by(|a, b| a.value().score.partial_cmp(&b.value().score).expect("failed to compare scores"))
             .map(|v| v.key().clone()))
     }
+    /// Ranks the keys from the best score down.
+    ///
+    /// ## Returns
+    /// A vector of keys along with their score, ordered by their score.
+    async fn ranking(&self) -> Result<Vec<(String, Score)>, Box<dyn Error + Send + Sync>> {
+        let mut ranked: Vec<_> = self.inner.iter().map(|v| (v.key().clone(), v.value().clone())).collect();
+        ranked.sort_by(|a, b| b.1.score.partial_cmp(&a.1.score).expect("failed to compare scores"));
+        Ok(ranked)
+    }
+    /// Identifies the key associated with the best score, among the ones meeting a constraint.
+    ///
+    /// ## Arguments
+    /// * `predicate`: &Predicate - The constraint on the URL and score of the endpoints.
+    ///
+    /// ## Returns
+    /// An option containing the key of the best score meeting the constraint, or None if there's none.
+    async fn best_url_where(&self, predicate: &Predicate<'_>) -> Result<Option<String>, Box<dyn Error + Send + Sync>> {
+        Ok(self
+            .inner
+            .iter()
+            .filter(|v| predicate(v.key(), v.value()))
+            .max_by(|a, b| a.value().score.partial_cmp(&b.value().score).expect("failed to compare scores"))
+            .map(|v| v.key().clone()))
+    }
+    /// Inserts or replaces an incident, identified by its `id`.
+    ///
+    /// ## Arguments
+    /// * `incident`: Incident - The incident to store.
+    ///
+    /// ## Returns
+    /// A result indicating success or an error.
+    async fn set_incident(&self, incident: Incident) -> Result<(), Box<dyn Error + Send + Sync>> {
+        self.incidents.insert(incident.id.clone(), incident);
+        Ok(())
+    }
+    /// Retrieves an incident by its `id`.
+    ///
+    /// ## Arguments
+    /// * `id`: &str - The ID of the incident.
+    ///
+    /// ## Returns
+    /// An option containing the incident if it exists, or None otherwise.
+    async fn get_incident(&self, id: &str) -> Result<Option<Incident>, Box<dyn Error + Send + Sync>> {
+        Ok(self.incidents.get(id).map(|v| v.value().clone()))
+    }
+    /// Lists the recorded incidents, ordered by their start.
+    ///
+    /// ## Returns
+    /// A vector of incidents.
+    async fn incidents(&self) -> Result<Vec<Incident>, Box<dyn Error + Send + Sync>> {
+        let mut incidents = self.incidents.iter().map(|v| v.value().clone()).collect::<Vec<_>>();
+        incidents.sort_by_key(|i| i.started_at);
+        Ok(incidents)
+    }
+    /// Removes every score from the store.
+    ///
+    /// ## Returns
+    /// A result indicating success or an error.
+    async fn clear(&self) -> Result<(), Box<dyn Error + Send + Sync>> {
+        self.inner.clear();
+        Ok(())
+    }
+    /// Replaces the persisted set of monitored requests.
+    ///
+    /// ## Arguments
+    /// * `requests`: &[Request] - The requests monitored by the service.
+    ///
+    /// ## Returns
+    /// A result indicating success or an error.
+    async fn set_requests(&self, requests: &[Request]) -> Result<(), Box<dyn Error + Send + Sync>> {
+        self.requests.retain(|key, _| requests.iter().any(|r| r.key() == *key));
+        for request in requests {
+            self.requests.insert(request.key(), request.clone());
+        }
+        Ok(())
+    }
+    /// Retrieves the persisted set of monitored requests, ordered by their key.
+    ///
+    /// ## Returns
+    /// A vector of requests.
+    async fn requests(&self) -> Result<Vec<Request>, Box<dyn Error + Send + Sync>> {
+        let mut requests = self.requests.iter().map(|v| v.value().clone()).collect::<Vec<_>>();
+        requests.sort_by_key(Request::key);
+        Ok(requests)
+    }
 }
@@ -1,5 +1,9 @@
+use crate::incident::Incident;
+use crate::request::Request;
 use crate::score::Score;
 use std::error::Error;
+use std::sync::Arc;
+use std::time::{Duration, Instant};
 
 // Feature-gated Redis module. Included only if the "redis" feature is enabled.
 #[cfg(feature = "redis")]
@@ -12,12 +16,21 @@ pub use redis::Redis;
 mod memory;
 pub use memory::Memory;
 
+mod read_only;
+pub use read_only::ReadOnlyStore;
+
+pub mod fallback;
+pub use fallback::Fallback;
+
+pub mod buffered;
+pub use buffered::Buffered;
+
 /// Configuration options for different storage types.
 ///
 /// The configuration is defined as an enum to represent various storage types.
 /// Feature gates are used to conditionally compile code for specific storage,
 /// like Redis, based on the compilation features provided.
-#[derive(serde::Deserialize, Debug)]
+#[derive(serde::Deserialize, serde::Serialize, Clone, Debug)]
 #[serde(tag = "type")]
 #[serde(rename_all = "snake_case")]
 pub enum Config {
@@ -27,6 +40,12 @@ pub enum Config {
 
     // Memory storage configuration.
     Memory,
+
+    // A primary store along with the one it falls back on while it's unavailable.
+    Fallback(fallback::Config),
+
+    // A store buffering the scores it fails to write, until it's reachable again.
+    Buffered(buffered::Config),
 }
 
 impl Default for Config {
@@ -36,6 +55,33 @@ impl Default for Config {
     }
 }
 
+impl Config {
+    /// Isolates the data of the service under a namespace, in the stores shared with other services.
+    ///
+    /// # Arguments
+    /// * `namespace` - The namespace of the service, e.g. the name of a team or tenant.
+    pub fn set_namespace<I: Into<String>>(self, namespace: I) -> Self {
+        let namespace = namespace.into();
+        match self {
+            #[cfg(feature = "redis")]
+            Config::Redis(config) => Config::Redis(redis::Config { namespace: Some(namespace), ..config }),
+            // Every in-memory store is already private to its service
+            Config::Memory => {
+                let _ = namespace;
+                Config::Memory
+            }
+            Config::Fallback(config) => Config::Fallback(fallback::Config {
+                primary: Box::new(config.primary.set_namespace(&namespace)),
+                fallback: Box::new(config.fallback.set_namespace(&namespace)),
+                ..config
+            }),
+            Config::Buffered(config) => {
+                Config::Buffered(buffered::Config { store: Box::new(config.store.set_namespace(namespace)), ..config })
+            }
+        }
+    }
+}
+
 /// Constructs a storage instance from the provided configuration.
 ///
 /// Based on the provided `Config`, this function initializes the appropriate
@@ -50,13 +96,22 @@ pub fn from_config(config: Config) -> Box<dyn Store + Sync + Send + 'static> {
     match config {
         // Initialize Redis storage if the "redis" feature is enabled and selected.
         #[cfg(feature = "redis")]
-        Config::Redis(config) => Box::new(Redis::from_url(config.connection)),
+        Config::Redis(config) => Box::new(Redis::from_config(&config)),
 
         // Initialize in-memory storage by default.
         Config::Memory => Box::new(Memory::new()),
+
+        // Initialize both stores of a fallback chain.
+        Config::Fallback(config) => Box::new(Fallback::from_config(config)),
+
+        // Initialize the buffered store along with the one it writes to.
+        Config::Buffered(config) => Box::new(Buffered::from_config(config)),
     }
 }
 
+/// A constraint on the endpoints, given their URL and score, e.g. `|_, score| score.reliability > 0.9`.
+pub type Predicate<'a> = dyn Fn(&str, &Score) -> bool + Send + Sync + 'a;
+
 /// Trait defining the key-value store functionality.
 /// This trait abstracts the store layer, allowing various implementations such as memory-based or database-backed stores.
 #[async_trait::async_trait]
@@ -69,7 +124,20 @@ pub trait Store {
     ///
     /// ## Returns
     /// A result indicating success or an error.
-    async fn set(&self, key: String, value: Score) -> Result<(), Box<dyn Error>>;
+    async fn set(&self, key: String, value: Score) -> Result<(), Box<dyn Error + Send + Sync>>;
+    /// Sets the scores of several keys at once, e.g. the ones of a batch of probes.
+    ///
+    /// ## Arguments
+    /// * `scores`: Vec<(String, Score)> - The keys along with their score, set in order.
+    ///
+    /// ## Returns
+    /// A result indicating success or an error. By default, the scores are set one by one.
+    async fn set_many(&self, scores: Vec<(String, Score)>) -> Result<(), Box<dyn Error + Send + Sync>> {
+        for (key, value) in scores {
+            self.set(key, value).await?;
+        }
+        Ok(())
+    }
     /// Retrieves the score associated with a given key.
     ///
     /// ## Arguments
@@ -77,10 +145,195 @@ pub trait Store {
     ///
     /// ## Returns
     /// An optional score if found, or None otherwise.
-    async fn get(&self, key: &str) -> Result<Option<Score>, Box<dyn Error>>;
+    async fn get(&self, key: &str) -> Result<Option<Score>, Box<dyn Error + Send + Sync>>;
     /// Retrieves the key associated with the highest score.
     ///
     /// ## Returns
     /// An optional string representing the key of the highest score, or None if the store is empty.
-    async fn best_url(&self) -> Result<Option<String>, Box<dyn Error>>;
+    async fn best_url(&self) -> Result<Option<String>, Box<dyn Error + Send + Sync>>;
+    /// Retrieves the key associated with the highest score, among the ones meeting a constraint.
+    ///
+    /// ## Arguments
+    /// * `predicate`: &Predicate - The constraint on the URL and score of the endpoints.
+    ///
+    /// ## Returns
+    /// The key of the highest score meeting the constraint, or None if there's none.
+    /// Stores that can't filter their scores return an error by default.
+    async fn best_url_where(&self, _predicate: &Predicate<'_>) -> Result<Option<String>, Box<dyn Error + Send + Sync>> {
+        Err("the store can't filter its scores".into())
+    }
+    /// Ranks the keys from the highest score down.
+    ///
+    /// ## Returns
+    /// A vector of keys along with their score, ordered by their score.
+    /// Stores that can't rank their scores return an error by default.
+    async fn ranking(&self) -> Result<Vec<(String, Score)>, Box<dyn Error + Send + Sync>> {
+        Err("the store can't rank its scores".into())
+    }
+    /// Inserts or replaces an incident, identified by its `id`.
+    ///
+    /// ## Arguments
+    /// * `incident`: Incident - The incident to store.
+    ///
+    /// ## Returns
+    /// A result indicating success or an error. Stores without an incident log return an error by default.
+    async fn set_incident(&self, incident: Incident) -> Result<(), Box<dyn Error + Send + Sync>> {
+        Err(format!("the store can't record incident `{}`", incident.id).into())
+    }
+    /// Retrieves an incident by its `id`.
+    ///
+    /// ## Arguments
+    /// * `id`: &str - The ID of the incident.
+    ///
+    /// ## Returns
+    /// The incident if found, or None otherwise.
+    async fn get_incident(&self, _id: &str) -> Result<Option<Incident>, Box<dyn Error + Send + Sync>> {
+        Ok(None)
+    }
+    /// Lists the recorded incidents, ordered by their start.
+    ///
+    /// ## Returns
+    /// A vector of incidents, empty for stores without an incident log.
+    async fn incidents(&self) -> Result<Vec<Incident>, Box<dyn Error + Send + Sync>> {
+        Ok(Vec::new())
+    }
+    /// Removes every score from the store, e.g. to score the endpoints from scratch after changing the strategy.
+    /// The incidents and persisted requests are kept.
+    ///
+    /// ## Returns
+    /// A result indicating success or an error. Stores that can't be flushed return an error by default.
+    async fn clear(&self) -> Result<(), Box<dyn Error + Send + Sync>> {
+        Err("the store can't be flushed".into())
+    }
+    /// Checks that the store can be reached.
+    ///
+    /// ## Returns
+    /// A result indicating whether the store is reachable, by default through a `best_url` lookup.
+    async fn ping(&self) -> Result<(), Box<dyn Error + Send + Sync>> {
+        self.best_url().await.map(|_| ())
+    }
+    /// Checks the health of the store: whether it can be reached, and how long it takes to answer.
+    ///
+    /// ## Returns
+    /// The health of the store, by default measured through a `ping`.
+    async fn health(&self) -> StoreHealth {
+        let started = Instant::now();
+        let error = self.ping().await.err().map(|e| e.to_string());
+        StoreHealth { healthy: error.is_none(), latency: started.elapsed(), error, connections: None }
+    }
+    /// Replaces the persisted set of monitored requests.
+    ///
+    /// ## Arguments
+    /// * `requests`: &[Request] - The requests monitored by the service.
+    ///
+    /// ## Returns
+    /// A result indicating success or an error. Stores that can't persist requests return an error by default.
+    async fn set_requests(&self, _requests: &[Request]) -> Result<(), Box<dyn Error + Send + Sync>> {
+        Err("the store can't persist requests".into())
+    }
+    /// Retrieves the persisted set of monitored requests.
+    ///
+    /// ## Returns
+    /// A vector of requests, empty if none were persisted.
+    async fn requests(&self) -> Result<Vec<Request>, Box<dyn Error + Send + Sync>> {
+        Ok(Vec::new())
+    }
+}
+
+/// Shares a store between the `Service` and the application, e.g. a web handler reading the scores directly,
+/// without opening a second connection to it.
+#[async_trait::async_trait]
+impl<T: Store + Sync + Send + ?Sized> Store for Arc<T> {
+    async fn set(&self, key: String, value: Score) -> Result<(), Box<dyn Error + Send + Sync>> {
+        (**self).set(key, value).await
+    }
+
+    async fn set_many(&self, scores: Vec<(String, Score)>) -> Result<(), Box<dyn Error + Send + Sync>> {
+        (**self).set_many(scores).await
+    }
+
+    async fn get(&self, key: &str) -> Result<Option<Score>, Box<dyn Error + Send + Sync>> {
+        (**self).get(key).await
+    }
+
+    async fn best_url(&self) -> Result<Option<String>, Box<dyn Error + Send + Sync>> {
+        (**self).best_url().await
+    }
+
+    async fn best_url_where(&self, predicate: &Predicate<'_>) -> Result<Option<String>, Box<dyn Error + Send + Sync>> {
+        (**self).best_url_where(predicate).await
+    }
+
+    async fn ranking(&self) -> Result<Vec<(String, Score)>, Box<dyn Error + Send + Sync>> {
+        (**self).ranking().await
+    }
+
+    async fn set_incident(&self, incident: Incident) -> Result<(), Box<dyn Error + Send + Sync>> {
+        (**self).set_incident(incident).await
+    }
+
+    async fn get_incident(&self, id: &str) -> Result<Option<Incident>, Box<dyn Error + Send + Sync>> {
+        (**self).get_incident(id).await
+    }
+
+    async fn incidents(&self) -> Result<Vec<Incident>, Box<dyn Error + Send + Sync>> {
+        (**self).incidents().await
+    }
+
+    async fn clear(&self) -> Result<(), Box<dyn Error + Send + Sync>> {
+        (**self).clear().await
+    }
+
+    async fn ping(&self) -> Result<(), Box<dyn Error + Send + Sync>> {
+        (**self).ping().await
+    }
+
+    async fn health(&self) -> StoreHealth {
+        (**self).health().await
+    }
+
+    async fn set_requests(&self, requests: &[Request]) -> Result<(), Box<dyn Error + Send + Sync>> {
+        (**self).set_requests(requests).await
+    }
+
+    async fn requests(&self) -> Result<Vec<Request>, Box<dyn Error + Send + Sync>> {
+        (**self).requests().await
+    }
+}
+
+/// The health of a store, as checked by `Store::health`.
+#[derive(serde::Deserialize, serde::Serialize, Clone, Debug, PartialEq, Eq)]
+pub struct StoreHealth {
+    /// Whether the store can be reached.
+    pub healthy: bool,
+    /// How long the store took to answer, or to fail.
+    pub latency: Duration,
+    /// The error encountered while reaching the store, if any.
+    pub error: Option<String>,
+    /// The state of the pool of connections to the store, for the stores keeping one.
+    pub connections: Option<Connections>,
+}
+
+/// The state of the pool of connections to a store.
+#[derive(serde::Deserialize, serde::Serialize, Clone, Copy, Debug, PartialEq, Eq)]
+pub struct Connections {
+    /// The number of open connections.
+    pub open: usize,
+    /// The number of open connections, idle in the pool.
+    pub idle: usize,
+    /// The maximum number of open connections.
+    pub max: usize,
+}
+
+/// The summary of a migration between two stores, returned by `Service::migrate`.
+#[derive(serde::Deserialize, serde::Serialize, Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub struct Migration {
+    /// The number of scores copied.
+    pub scores: usize,
+    /// The number of incidents copied.
+    pub incidents: usize,
+    /// The number of persisted requests copied.
+    pub requests: usize,
+    /// The number of history samples copied, by `Service::migrate_history`.
+    pub samples: usize,
 }
@@ -0,0 +1,75 @@
+use super::{Config, Predicate, Store, StoreHealth};
+use crate::incident::Incident;
+use crate::request::Request;
+use crate::score::Score;
+use std::error::Error;
+use std::sync::Arc;
+
+/// A view of a store without any write path, for consumers such as API servers that only query the scores
+/// written by the probing service.
+///
+/// Unlike the stores it wraps, it doesn't implement `Store`, so it can neither be written to nor handed to a `Service`.
+#[derive(Clone)]
+pub struct ReadOnlyStore {
+    inner: Arc<dyn Store + Sync + Send>,
+}
+
+impl ReadOnlyStore {
+    /// Wraps a store, e.g. one shared with a `Service` through an `Arc`.
+    pub fn new<T: Store + Sync + Send + 'static>(store: T) -> Self {
+        Self { inner: Arc::new(store) }
+    }
+
+    /// Connects to the store of the given configuration, e.g. `Config::effective_store` of a `read_only` one.
+    pub fn from_config(config: Config) -> Self {
+        Self { inner: Arc::from(super::from_config(config)) }
+    }
+
+    /// Retrieves the score of an endpoint, as stored.
+    pub async fn get(&self, key: &str) -> Result<Option<Score>, Box<dyn Error + Send + Sync>> {
+        self.inner.get(key).await
+    }
+
+    /// Retrieves the URL with the best score.
+    pub async fn best_url(&self) -> Result<Option<String>, Box<dyn Error + Send + Sync>> {
+        self.inner.best_url().await
+    }
+
+    /// Retrieves the URL with the best score, among the endpoints meeting a constraint.
+    pub async fn best_url_where(
+        &self,
+        predicate: &Predicate<'_>,
+    ) -> Result<Option<String>, Box<dyn Error + Send + Sync>> {
+        self.inner.best_url_where(predicate).await
+    }
+
+    /// Ranks the endpoints from the best score down.
+    pub async fn ranking(&self) -> Result<Vec<(String, Score)>, Box<dyn Error + Send + Sync>> {
+        self.inner.ranking().await
+    }
+
+    /// Retrieves an incident by its `id`.
+    pub async fn get_incident(&self, id: &str) -> Result<Option<Incident>, Box<dyn Error + Send + Sync>> {
+        self.inner.get_incident(id).await
+    }
+
+    /// Lists the recorded incidents, ordered by their start.
+    pub async fn incidents(&self) -> Result<Vec<Incident>, Box<dyn Error + Send + Sync>> {
+        self.inner.incidents().await
+    }
+
+    /// Retrieves the persisted set of monitored requests.
+    pub async fn requests(&self) -> Result<Vec<Request>, Box<dyn Error + Send + Sync>> {
+        self.inner.requests().await
+    }
+
+    /// Checks that the store can be reached.
+    pub async fn ping(&self) -> Result<(), Box<dyn Error + Send + Sync>> {
+        self.inner.ping().await
+    }
+
+    /// Checks the health of the store.
+    pub async fn health(&self) -> StoreHealth {
+        self.inner.health().await
+    }
+}
@@ -1,12 +1,35 @@
-use super::Store; // Import the KVStore trait from the parent module
+use super::{Connections, Predicate, Store, StoreHealth}; // Import the KVStore trait and the types it uses from the parent module
+use crate::config::ConfigDuration;
+use crate::election::Lease; // Import the Lease trait, for leader election over the store
+use crate::history::{Compactions, History, Retention, Sample}; // Import the History trait, for the time series of the samples
+use crate::incident::Incident; // Import the Incident struct, recorded in the incident log
+use crate::request::Request; // Import the Request struct, persisted along with the scores
 use crate::score::Score; // Import the Score struct from the crate root
-use deadpool_redis::Pool; // Deadpool pool for managing Redis connections
+use crate::shard::Registry; // Import the Registry trait, for sharding the endpoints over the store
+use deadpool_redis::{Connection, Pool, PoolError}; // Deadpool pool for managing Redis connections
 use redis::AsyncCommands; // Import Redis async commands
 use std::error::Error;
+use std::sync::Arc;
+use std::time::{Duration, Instant, SystemTime, UNIX_EPOCH};
 
-#[derive(serde::Deserialize, Debug)]
+/// The default number of attempts to reconnect to the server, before an operation fails.
+const DEFAULT_RECONNECT_ATTEMPTS: u32 = 3;
+
+/// The default delay before the first attempt to reconnect, doubled after every failed attempt.
+const DEFAULT_RECONNECT_BACKOFF: Duration = Duration::from_millis(100);
+
+#[derive(serde::Deserialize, serde::Serialize, Clone, Debug)]
 pub struct Config {
     pub connection: String,
+    /// Isolates the keys of the service from the ones of other namespaces sharing the server.
+    #[serde(default)]
+    pub namespace: Option<String>,
+    /// The number of attempts to reconnect to the server, before an operation fails (default: 3).
+    #[serde(default)]
+    pub reconnect_attempts: Option<u32>,
+    /// The delay before the first attempt to reconnect, doubled after every failed attempt (default: 100ms).
+    #[serde(default)]
+    pub reconnect_backoff: Option<ConfigDuration>,
 }
 
 /// Represents a store system using Redis.
@@ -21,6 +44,16 @@ pub struct Redis {
     sorted_set_name: String,
     // Prefix for keys to avoid collisions
     key_prefix: String,
+    // Number of samples kept in the history of every endpoint
+    history_capacity: usize,
+    // Retention policy of the history, compacting it in the background if set
+    history_retention: Option<Retention>,
+    // When the history of every endpoint was last compacted
+    compactions: Arc<Compactions>,
+    // Number of attempts to reconnect to the server, before an operation fails
+    reconnect_attempts: u32,
+    // Delay before the first attempt to reconnect, doubled after every failed attempt
+    reconnect_backoff: Duration,
 }
 
 impl Default for Redis {
@@ -51,7 +84,74 @@ impl Redis {
     {
         let inner = deadpool_redis::Config::from_url(url).create_pool(None).expect("failed to create pool");
 
-        Self { inner, sorted_set_name: sorted_set_name.into(), key_prefix: key_prefix.into() }
+        Self {
+            inner,
+            sorted_set_name: sorted_set_name.into(),
+            key_prefix: key_prefix.into(),
+            history_capacity: crate::history::DEFAULT_CAPACITY,
+            history_retention: None,
+            compactions: Arc::default(),
+            reconnect_attempts: DEFAULT_RECONNECT_ATTEMPTS,
+            reconnect_backoff: DEFAULT_RECONNECT_BACKOFF,
+        }
+    }
+
+    /// Sets how the store reconnects to the server once the connection is lost, e.g. while Redis restarts.
+    ///
+    /// ## Arguments
+    /// * `attempts`: u32 - The number of attempts to reconnect, before an operation fails.
+    /// * `backoff`: Duration - The delay before the first attempt, doubled after every failed attempt.
+    ///
+    /// ## Returns
+    /// The updated Redis instance.
+    pub fn set_reconnect(mut self, attempts: u32, backoff: Duration) -> Self {
+        self.reconnect_attempts = attempts;
+        self.reconnect_backoff = backoff;
+        self
+    }
+
+    /// Retrieves a connection from the pool, reconnecting with an exponential backoff while the server can't be
+    /// reached. The pool checks its idle connections before handing them out, dropping the broken ones, so a lost
+    /// connection is replaced by a new one rather than failing every operation.
+    ///
+    /// ## Returns
+    /// A connection, or the error of the last attempt.
+    async fn connection(&self) -> Result<Connection, PoolError> {
+        let mut backoff = self.reconnect_backoff;
+        for _ in 0..self.reconnect_attempts {
+            match self.inner.get().await {
+                Ok(connection) => return Ok(connection),
+                Err(_) => {
+                    tokio::time::sleep(backoff).await;
+                    backoff *= 2;
+                }
+            }
+        }
+        self.inner.get().await
+    }
+
+    /// Sets the number of samples kept in the history of every endpoint.
+    ///
+    /// ## Arguments
+    /// * `capacity`: usize - The number of samples kept per endpoint; the oldest are dropped first.
+    ///
+    /// ## Returns
+    /// The updated Redis instance.
+    pub fn set_history_capacity(mut self, capacity: usize) -> Self {
+        self.history_capacity = capacity;
+        self
+    }
+
+    /// Sets the retention policy of the history, rolling up the older samples of every endpoint in the background.
+    ///
+    /// ## Arguments
+    /// * `retention`: Retention - How long the samples and their rollups are kept.
+    ///
+    /// ## Returns
+    /// The updated Redis instance.
+    pub fn set_history_retention(mut self, retention: Retention) -> Self {
+        self.history_retention = Some(retention);
+        self
     }
 
     /// Constructs a Redis store instance from a URL with default prefix `isup:` and sorted set name `isup:scores`.
@@ -64,6 +164,38 @@ impl Redis {
     pub fn from_url<I: Into<String>>(url: I) -> Self {
         Self::new(url, "isup:scores", "isup:")
     }
+
+    /// Constructs a Redis store instance from its configuration, namespaced if a namespace is set.
+    ///
+    /// ## Arguments
+    /// * `config`: &Config - Redis configuration.
+    ///
+    /// ## Returns
+    /// The new Redis instance.
+    pub(crate) fn from_config(config: &Config) -> Self {
+        let store = Self::from_url(config.connection.clone()).set_reconnect(
+            config.reconnect_attempts.unwrap_or(DEFAULT_RECONNECT_ATTEMPTS),
+            config.reconnect_backoff.map_or(DEFAULT_RECONNECT_BACKOFF, ConfigDuration::get),
+        );
+        match &config.namespace {
+            Some(namespace) => store.set_namespace(namespace),
+            None => store,
+        }
+    }
+
+    /// Isolates the keys of the store under a namespace, with prefix `isup:{namespace}:` and sorted set name
+    /// `isup:{namespace}:scores`, so that several services can share a Redis server without their scores colliding.
+    ///
+    /// ## Arguments
+    /// * `namespace`: &str - The namespace of the service, e.g. the name of a team or tenant.
+    ///
+    /// ## Returns
+    /// The updated Redis instance.
+    pub fn set_namespace<I: AsRef<str>>(mut self, namespace: I) -> Self {
+        self.sorted_set_name = format!("isup:{}:scores", namespace.as_ref());
+        self.key_prefix = format!("isup:{}:", namespace.as_ref());
+        self
+    }
 }
 
 #[async_trait::async_trait]
@@ -78,16 +210,16 @@ impl Store for Redis {
     /// A `Result` indicating success or an error.
     ///
     /// Utilizes Redis pipeline to efficiently set data and update the sorted set.
-    async fn set(&self, key: String, value: Score) -> Result<(), Box<dyn Error>> {
+    async fn set(&self, key: String, value: Score) -> Result<(), Box<dyn Error + Send + Sync>> {
         // Retrieve a connection from the pool.
-        let mut connection = self.inner.get().await?;
+        let mut connection = self.connection().await?;
         let prefixed_key = format!("{}{}", self.key_prefix, key);
         // Create a new Redis pipeline. Pipelines allow for multiple commands
         // to be sent to the server without waiting for individual replies,
         // thus improving performance.
         let mut pipe = redis::pipe();
-        // Serialize the `Score` object to a JSON string.
-        let json = serde_yaml::to_string(&value)?;
+        // Serialize the `Score` object to its canonical JSON representation.
+        let json = value.encode()?;
         // Add a command to the pipeline to set the key-value pair in Redis.
         // The `ignore` method is used since we're not interested in the command's result.
         pipe.set(&prefixed_key, json).ignore();
@@ -100,6 +232,25 @@ impl Store for Redis {
         Ok(pipe.query_async(&mut connection).await?)
     }
 
+    /// Sets the scores of several keys at once.
+    ///
+    /// ## Arguments
+    /// * `scores` - Vec<(String, Score)>: The keys along with their score, set in order.
+    ///
+    /// ## Returns
+    /// A `Result` indicating success or an error.
+    ///
+    /// Sends the commands of every score through a single pipeline, i.e. a single round trip to Redis.
+    async fn set_many(&self, scores: Vec<(String, Score)>) -> Result<(), Box<dyn Error + Send + Sync>> {
+        let mut connection = self.connection().await?;
+        let mut pipe = redis::pipe();
+        for (key, value) in scores {
+            pipe.set(format!("{}{}", self.key_prefix, key), value.encode()?).ignore();
+            pipe.zadd(&self.sorted_set_name, &key, value.score).ignore();
+        }
+        Ok(pipe.query_async(&mut connection).await?)
+    }
+
     // Retrieves a score for a given key.
     ///
     /// ## Arguments
@@ -109,12 +260,12 @@ impl Store for Redis {
     /// A `Result` containing the score or None if not found.
     ///
     /// Retrieves the score from Redis, handling serialization and key prefixing.
-    async fn get(&self, key: &str) -> Result<Option<Score>, Box<dyn Error>> {
-        let mut connection = self.inner.get().await?;
+    async fn get(&self, key: &str) -> Result<Option<Score>, Box<dyn Error + Send + Sync>> {
+        let mut connection = self.connection().await?;
         let prefixed_key = format!("{}{}", self.key_prefix, key);
 
         Ok(match connection.get::<_, String>(prefixed_key).await {
-            Ok(r) => serde_yaml::from_str(&r).ok(),
+            Ok(r) => Score::decode(&r).ok(),
             Err(_) => None,
         })
     }
@@ -125,9 +276,310 @@ impl Store for Redis {
     /// A `Result` containing the key with the highest score or None if the store is empty.
     ///
     /// Uses a Redis sorted set to efficiently find the highest score.
-    async fn best_url(&self) -> Result<Option<String>, Box<dyn Error>> {
-        let mut connection = self.inner.get().await?;
+    async fn best_url(&self) -> Result<Option<String>, Box<dyn Error + Send + Sync>> {
+        let mut connection = self.connection().await?;
         let best: Vec<String> = connection.zrevrange(&self.sorted_set_name, 0, 0).await?;
         Ok(best.first().cloned())
     }
+
+    /// Ranks the keys from the highest score down.
+    ///
+    /// ## Returns
+    /// A `Result` containing the keys along with their score, ordered by their score.
+    ///
+    /// Reads the ranking from the sorted set (`ZREVRANGE`), then the scores of its keys at once (`MGET`).
+    async fn ranking(&self) -> Result<Vec<(String, Score)>, Box<dyn Error + Send + Sync>> {
+        let mut connection = self.connection().await?;
+        let keys: Vec<String> = connection.zrevrange(&self.sorted_set_name, 0, -1).await?;
+        if keys.is_empty() {
+            return Ok(Vec::new());
+        }
+        let prefixed: Vec<String> = keys.iter().map(|key| format!("{}{}", self.key_prefix, key)).collect();
+        let scores: Vec<Option<String>> = redis::cmd("MGET").arg(&prefixed).query_async(&mut connection).await?;
+        Ok(keys.into_iter().zip(scores).filter_map(|(key, score)| Some((key, Score::decode(&score?).ok()?))).collect())
+    }
+
+    /// Retrieves the key with the highest score, among the ones meeting a constraint.
+    ///
+    /// ## Arguments
+    /// * `predicate`: &Predicate - The constraint on the URL and score of the endpoints.
+    ///
+    /// ## Returns
+    /// A `Result` containing the key with the highest score meeting the constraint, or None if there's none.
+    ///
+    /// Walks the sorted set from the highest score down, stopping at the first key meeting the constraint.
+    async fn best_url_where(&self, predicate: &Predicate<'_>) -> Result<Option<String>, Box<dyn Error + Send + Sync>> {
+        let mut connection = self.connection().await?;
+        let ranked: Vec<String> = connection.zrevrange(&self.sorted_set_name, 0, -1).await?;
+        for key in ranked {
+            let score: Option<String> = connection.get(format!("{}{}", self.key_prefix, key)).await?;
+            if score.and_then(|s| Score::decode(&s).ok()).is_some_and(|s| predicate(&key, &s)) {
+                return Ok(Some(key));
+            }
+        }
+        Ok(None)
+    }
+
+    /// Inserts or replaces an incident, identified by its `id`.
+    ///
+    /// ## Arguments
+    /// * `incident` - Incident: The incident to be stored.
+    ///
+    /// ## Returns
+    /// A `Result` indicating success or an error.
+    ///
+    /// The incidents are kept in a single hash, keyed by their ID.
+    async fn set_incident(&self, incident: Incident) -> Result<(), Box<dyn Error + Send + Sync>> {
+        let mut connection = self.connection().await?;
+        let yaml = serde_yaml::to_string(&incident)?;
+        Ok(connection.hset(format!("{}incidents", self.key_prefix), &incident.id, yaml).await?)
+    }
+
+    /// Retrieves an incident by its `id`.
+    ///
+    /// ## Arguments
+    /// * `id` - &str: The ID of the incident.
+    ///
+    /// ## Returns
+    /// A `Result` containing the incident or None if not found.
+    async fn get_incident(&self, id: &str) -> Result<Option<Incident>, Box<dyn Error + Send + Sync>> {
+        let mut connection = self.connection().await?;
+        let yaml: Option<String> = connection.hget(format!("{}incidents", self.key_prefix), id).await?;
+        Ok(yaml.map(|yaml| serde_yaml::from_str(&yaml)).transpose()?)
+    }
+
+    /// Lists the recorded incidents, ordered by their start.
+    ///
+    /// ## Returns
+    /// A `Result` containing the incidents.
+    async fn incidents(&self) -> Result<Vec<Incident>, Box<dyn Error + Send + Sync>> {
+        let mut connection = self.connection().await?;
+        let values: Vec<String> = connection.hvals(format!("{}incidents", self.key_prefix)).await?;
+        let mut incidents =
+            values.iter().map(|yaml| serde_yaml::from_str(yaml)).collect::<Result<Vec<Incident>, _>>()?;
+        incidents.sort_by_key(|i| i.started_at);
+        Ok(incidents)
+    }
+
+    /// Removes every score from the store.
+    ///
+    /// ## Returns
+    /// A `Result` indicating success or an error.
+    ///
+    /// Deletes the keys of the sorted set along with the set itself, in a single transaction.
+    async fn clear(&self) -> Result<(), Box<dyn Error + Send + Sync>> {
+        let mut connection = self.connection().await?;
+        let keys: Vec<String> = connection.zrange(&self.sorted_set_name, 0, -1).await?;
+        let mut pipe = redis::pipe();
+        pipe.atomic();
+        for key in keys {
+            pipe.del(format!("{}{}", self.key_prefix, key)).ignore();
+        }
+        pipe.del(&self.sorted_set_name).ignore();
+        Ok(pipe.query_async(&mut connection).await?)
+    }
+
+    /// Checks that the Redis server can be reached.
+    ///
+    /// ## Returns
+    /// A `Result` indicating whether the server answered a `PING`.
+    async fn ping(&self) -> Result<(), Box<dyn Error + Send + Sync>> {
+        let mut connection = self.connection().await?;
+        Ok(redis::cmd("PING").query_async(&mut connection).await?)
+    }
+
+    /// Checks the health of the Redis server, along with the pool of connections to it.
+    ///
+    /// ## Returns
+    /// The health of the store, whose latency includes the attempts to reconnect.
+    async fn health(&self) -> StoreHealth {
+        let started = Instant::now();
+        let error = self.ping().await.err().map(|e| e.to_string());
+        let status = self.inner.status();
+        let connections = Connections { open: status.size, idle: status.available, max: status.max_size };
+        StoreHealth { healthy: error.is_none(), latency: started.elapsed(), error, connections: Some(connections) }
+    }
+
+    /// Replaces the persisted set of monitored requests.
+    ///
+    /// ## Arguments
+    /// * `requests` - &[Request]: The requests monitored by the service.
+    ///
+    /// ## Returns
+    /// A `Result` indicating success or an error.
+    ///
+    /// The requests are kept as a single YAML list, so they're replaced atomically and keep their order.
+    async fn set_requests(&self, requests: &[Request]) -> Result<(), Box<dyn Error + Send + Sync>> {
+        let mut connection = self.connection().await?;
+        let yaml = serde_yaml::to_string(requests)?;
+        Ok(connection.set(format!("{}requests", self.key_prefix), yaml).await?)
+    }
+
+    /// Retrieves the persisted set of monitored requests.
+    ///
+    /// ## Returns
+    /// A `Result` containing the requests, empty if none were persisted.
+    async fn requests(&self) -> Result<Vec<Request>, Box<dyn Error + Send + Sync>> {
+        let mut connection = self.connection().await?;
+        let yaml: Option<String> = connection.get(format!("{}requests", self.key_prefix)).await?;
+        Ok(yaml.map(|yaml| serde_yaml::from_str(&yaml)).transpose()?.unwrap_or_default())
+    }
+}
+
+#[async_trait::async_trait]
+impl Lease for Redis {
+    /// Acquires the leadership lease, or renews it if it's already held by the given replica.
+    ///
+    /// ## Arguments
+    /// * `holder` - &str: The ID of the replica.
+    /// * `ttl` - Duration: How long the lease lasts without being renewed.
+    ///
+    /// ## Returns
+    /// A `Result` containing whether the replica holds the lease.
+    ///
+    /// Uses a Lua script, so that checking the holder and setting the expiration happen atomically.
+    async fn acquire(&self, holder: &str, ttl: Duration) -> Result<bool, Box<dyn Error + Send + Sync>> {
+        let ttl = u64::try_from(ttl.as_millis()).map_err(|_| "the TTL of the lease overflows the clock")?;
+        let mut connection = self.connection().await?;
+        let script = redis::Script::new(
+            r"
+            local current = redis.call('GET', KEYS[1])
+            if not current then
+                redis.call('SET', KEYS[1], ARGV[1], 'PX', ARGV[2])
+                return 1
+            elseif current == ARGV[1] then
+                redis.call('PEXPIRE', KEYS[1], ARGV[2])
+                return 1
+            end
+            return 0
+            ",
+        );
+        let acquired: i32 =
+            script.key(format!("{}leader", self.key_prefix)).arg(holder).arg(ttl).invoke_async(&mut connection).await?;
+        Ok(acquired == 1)
+    }
+}
+
+#[async_trait::async_trait]
+impl Registry for Redis {
+    /// Registers a replica, or renews its registration, and lists the live replicas.
+    ///
+    /// ## Arguments
+    /// * `replica` - &str: The ID of the replica.
+    /// * `ttl` - Duration: How long the replica stays registered without renewing its registration.
+    ///
+    /// ## Returns
+    /// A `Result` containing the IDs of the live replicas.
+    ///
+    /// Uses a Redis sorted set scored by the expiration of every registration, pruning the expired ones.
+    async fn heartbeat(&self, replica: &str, ttl: Duration) -> Result<Vec<String>, Box<dyn Error + Send + Sync>> {
+        let now = u64::try_from(SystemTime::now().duration_since(UNIX_EPOCH)?.as_millis())?;
+        let expires_at = u64::try_from(ttl.as_millis())
+            .ok()
+            .and_then(|ttl| now.checked_add(ttl))
+            .ok_or("the TTL of the registration overflows the clock")?;
+        let mut connection = self.connection().await?;
+        let key = format!("{}replicas", self.key_prefix);
+
+        let mut pipe = redis::pipe();
+        pipe.zadd(&key, replica, expires_at).ignore();
+        pipe.zrembyscore(&key, "-inf", now).ignore();
+        pipe.zrange(&key, 0, -1);
+        let (replicas,): (Vec<String>,) = pipe.query_async(&mut connection).await?;
+        Ok(replicas)
+    }
+}
+
+#[async_trait::async_trait]
+impl History for Redis {
+    /// Records a sample of an endpoint.
+    ///
+    /// ## Arguments
+    /// * `url` - &str: The URL of the endpoint.
+    /// * `sample` - Sample: The sample to be recorded.
+    ///
+    /// ## Returns
+    /// A `Result` indicating success or an error.
+    ///
+    /// Every endpoint has a sorted set scored by the date of its samples, trimmed to the capacity of the history.
+    /// With a retention policy, the history of the endpoint is compacted in the background once it's due.
+    async fn record(&self, url: &str, sample: Sample) -> Result<(), Box<dyn Error + Send + Sync>> {
+        let mut connection = self.connection().await?;
+        let key = format!("{}history:{}", self.key_prefix, url);
+        let at = sample.at.duration_since(UNIX_EPOCH)?.as_millis() as u64;
+
+        let mut pipe = redis::pipe();
+        pipe.zadd(&key, serde_json::to_string(&sample)?, at).ignore();
+        pipe.zremrangebyrank(&key, 0, -(self.history_capacity as isize) - 1).ignore();
+        pipe.query_async::<_, ()>(&mut connection).await?;
+
+        if self.history_retention.as_ref().is_some_and(|retention| self.compactions.due(url, retention)) {
+            let (store, url) = (self.clone(), url.to_string());
+            // The compaction is best-effort, it's attempted again once the next one is due
+            tokio::spawn(async move { store.compact(&url).await });
+        }
+        Ok(())
+    }
+
+    /// Retrieves the samples of an endpoint recorded within a time range.
+    ///
+    /// ## Arguments
+    /// * `url` - &str: The URL of the endpoint.
+    /// * `from` - SystemTime: The start of the range, inclusive.
+    /// * `to` - SystemTime: The end of the range, inclusive.
+    ///
+    /// ## Returns
+    /// A `Result` containing the samples, ordered by their date, the rollups of the older ones first.
+    async fn query(
+        &self,
+        url: &str,
+        from: SystemTime,
+        to: SystemTime,
+    ) -> Result<Vec<Sample>, Box<dyn Error + Send + Sync>> {
+        let mut connection = self.connection().await?;
+        let key = format!("{}history:{}", self.key_prefix, url);
+        let rollups_key = format!("{}rollups:{}", self.key_prefix, url);
+        let (from, to) =
+            (from.duration_since(UNIX_EPOCH)?.as_millis() as u64, to.duration_since(UNIX_EPOCH)?.as_millis() as u64);
+
+        let mut pipe = redis::pipe();
+        pipe.zrangebyscore(&rollups_key, from, to);
+        pipe.zrangebyscore(&key, from, to);
+        let (rollups, values): (Vec<String>, Vec<String>) = pipe.query_async(&mut connection).await?;
+        Ok(rollups.iter().chain(&values).map(|json| serde_json::from_str(json)).collect::<Result<_, _>>()?)
+    }
+
+    /// Compacts the history of an endpoint, moving the rollups of the samples past the raw retention to a sorted set
+    /// of their own, and dropping the expired rollups.
+    ///
+    /// ## Arguments
+    /// * `url` - &str: The URL of the endpoint.
+    ///
+    /// ## Returns
+    /// A `Result` indicating success or an error.
+    async fn compact(&self, url: &str) -> Result<(), Box<dyn Error + Send + Sync>> {
+        let Some(retention) = &self.history_retention else {
+            return Ok(());
+        };
+        let mut connection = self.connection().await?;
+        let key = format!("{}history:{}", self.key_prefix, url);
+        let rollups_key = format!("{}rollups:{}", self.key_prefix, url);
+        let now = SystemTime::now();
+        let cutoff = retention.raw_cutoff(now).duration_since(UNIX_EPOCH)?.as_millis() as u64;
+        let rollup_cutoff = retention.rollup_cutoff(now).duration_since(UNIX_EPOCH)?.as_millis() as u64;
+
+        // The cutoffs are exclusive, the samples dated at the cutoff are kept as is
+        let values: Vec<String> = connection.zrangebyscore(&key, "-inf", format!("({cutoff}")).await?;
+        let samples = values.iter().map(|json| serde_json::from_str(json)).collect::<Result<Vec<Sample>, _>>()?;
+
+        let mut pipe = redis::pipe();
+        pipe.atomic();
+        for rollup in retention.rollup(&samples) {
+            let at = rollup.at.duration_since(UNIX_EPOCH)?.as_millis() as u64;
+            pipe.zadd(&rollups_key, serde_json::to_string(&rollup)?, at).ignore();
+        }
+        pipe.zrembyscore(&key, "-inf", format!("({cutoff}")).ignore();
+        pipe.zrembyscore(&rollups_key, "-inf", format!("({rollup_cutoff}")).ignore();
+        Ok(pipe.query_async(&mut connection).await?)
+    }
 }
@@ -0,0 +1,45 @@
+use super::Strategy;
+use crate::config::ConfigDuration;
+use crate::score::Score;
+use std::time::Duration;
+
+/// A strategy scoring the latency linearly, from `1.0` for an immediate response down to `0.0` at the
+/// maximum latency and beyond, i.e. `clamp(1 - latency / max_latency) * success`.
+///
+/// Failed probes score `0.0`. The reliability of the score is the success rate over the error window
+/// of the service, and its response time the latency of the latest probe.
+#[derive(Clone, Debug, serde::Deserialize, serde::Serialize)]
+pub struct Linear {
+    /// The latency scoring `0.0`, e.g. `1s`.
+    pub max_latency: ConfigDuration,
+}
+
+impl Default for Linear {
+    /// Provides a maximum latency of one second.
+    fn default() -> Self {
+        Self { max_latency: Duration::from_secs(1).into() }
+    }
+}
+
+impl Linear {
+    /// Constructs a new `Linear` instance scoring `0.0` from the given latency.
+    pub fn new(max_latency: Duration) -> Self {
+        Self { max_latency: max_latency.into() }
+    }
+}
+
+impl Strategy for Linear {
+    /// Implementation of `calculate` for `Linear`.
+    ///
+    /// Informational, successful and redirect responses succeed, any other status fails.
+    fn calculate(&self, score: Score, new_response: Duration, status_code: u16) -> Score {
+        let success = matches!(status_code, 100..=399);
+        // A zero maximum latency leaves no room for any response to score.
+        let value = match success && !self.max_latency.is_zero() {
+            true => (1.0 - new_response.as_secs_f32() / self.max_latency.as_secs_f32()).clamp(0.0, 1.0),
+            false => 0.0,
+        };
+        let reliability = 1.0 - score.error_rate;
+        Score { error_rate: score.error_rate, jitter: score.jitter, ..Score::new(value, reliability, new_response) }
+    }
+}
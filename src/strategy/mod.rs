@@ -1,20 +1,30 @@
 use crate::score::Score;
+use crate::{ProbeOutcome, Request};
 use std::time::Duration;
 
+mod linear;
+mod step;
 mod weighted_log;
-pub use weighted_log::WeightedLog;
+pub use linear::Linear;
+pub use step::{Band, Step};
+pub use weighted_log::{LatencyCurve, WeightedLog};
 
 /// Defines the configuration options for different scoring strategies.
 ///
 /// The `Config` enum allows the selection of different scoring strategies through configuration.
-/// Currently, it supports the `WeightedLog` strategy, which can be expanded to include more strategies in the future.
-#[derive(serde::Deserialize, Debug)]
+/// It supports the `WeightedLog` strategy, along with the `Linear` and `Step` strategies, whose scores are simpler
+/// to reason about.
+#[derive(serde::Deserialize, serde::Serialize, Clone, Debug)]
 #[serde(rename_all = "snake_case")]
 #[serde(tag = "type")]
 pub enum Config {
     /// Configuration for the Weighted Logarithmic strategy.
     /// It is designed to provide a score based on weighted response times.
     WeightedLog(weighted_log::WeightedLog),
+    /// Configuration for the Linear strategy, scoring the latency linearly up to a maximum.
+    Linear(linear::Linear),
+    /// Configuration for the Step strategy, mapping bands of latency to fixed scores.
+    Step(step::Step),
 }
 
 impl Default for Config {
@@ -24,6 +34,16 @@ impl Default for Config {
     }
 }
 
+impl Config {
+    /// Checks the parameters of the strategy, e.g. the log offset of the `WeightedLog` strategy.
+    pub(crate) fn validate(&self) -> Result<(), String> {
+        match self {
+            Config::WeightedLog(config) => config.validate(),
+            Config::Linear(_) | Config::Step(_) => Ok(()),
+        }
+    }
+}
+
 /// Creates a scoring strategy instance from the given configuration.
 ///
 /// This function is responsible for interpreting the configuration and initializing
@@ -38,6 +58,34 @@ pub fn from_config(config: Config) -> Box<dyn Strategy + Sync + Send + 'static>
     match config {
         // Constructs a `WeightedLog` strategy based on the provided configuration.
         Config::WeightedLog(config) => Box::new(config),
+        Config::Linear(config) => Box::new(config),
+        Config::Step(config) => Box::new(config),
+    }
+}
+
+/// Selects the endpoints scored by a strategy other than the one of the service.
+///
+/// When several keys match an endpoint, its URL takes precedence over its tags, which are matched in order of name.
+#[derive(Clone, Debug, PartialEq, Eq, Hash)]
+pub enum Key {
+    /// The endpoint of the given normalized URL.
+    Url(String),
+    /// The endpoints whose request is tagged with the given name and value, e.g. `tier: health`.
+    Tag(String, String),
+}
+
+impl Key {
+    /// Creates a key selecting the endpoint of a URL, normalized the same way as the URL of a `Request`.
+    ///
+    /// # Panics
+    /// Panics if the URL cannot be parsed.
+    pub fn url<I: Into<String>>(url: I) -> Self {
+        Self::Url(Request::normalize(url.into().parse().expect("Invalid URL")).to_string())
+    }
+
+    /// Creates a key selecting the endpoints whose request is tagged with the given name and value.
+    pub fn tag<I: Into<String>>(name: I, value: I) -> Self {
+        Self::Tag(name.into(), value.into())
     }
 }
 
@@ -53,4 +101,29 @@ pub trait Strategy {
     /// # Returns
     /// A new `Score` instance representing the updated score after applying the strategy.
     fn calculate(&self, score: Score, new_response: Duration, status_code: u16) -> Score;
+
+    /// Calculates a new `Score` based on the previous score and the whole outcome of a probe.
+    ///
+    /// The default implementation scores failed probes as if no response was received, and otherwise only
+    /// factors in the response time and status code. Strategies can override it to weigh the other metrics
+    /// of the outcome, such as the download or upload throughput and the processing time of the server.
+    ///
+    /// # Arguments
+    /// * `score`: The current score before this calculation.
+    /// * `outcome`: The outcome of the most recent probe.
+    ///
+    /// # Returns
+    /// A new `Score` instance representing the updated score after applying the strategy.
+    fn calculate_outcome(&self, score: Score, outcome: &ProbeOutcome) -> Score {
+        let status = if outcome.error.is_some() { 0 } else { outcome.status };
+        self.calculate(score, outcome.elapsed, status)
+    }
+
+    /// Provides the score an endpoint starts from, before its first probe is scored.
+    ///
+    /// # Returns
+    /// The default `Score` by default, i.e. without any reliability.
+    fn initial_score(&self) -> Score {
+        Score::default()
+    }
 }
@@ -0,0 +1,52 @@
+use super::Strategy;
+use crate::config::ConfigDuration;
+use crate::score::Score;
+use std::time::Duration;
+
+/// A strategy mapping bands of latency to fixed scores, e.g. `1.0` up to 100ms and `0.5` up to 500ms.
+///
+/// A probe is given the score of the narrowest band its latency fits in. Probes slower than every band,
+/// as well as failed probes, score `0.0`. The reliability of the score is the success rate over the error
+/// window of the service, and its response time the latency of the latest probe.
+#[derive(Clone, Debug, Default, serde::Deserialize, serde::Serialize)]
+pub struct Step {
+    /// The bands of latency, in any order.
+    pub bands: Vec<Band>,
+}
+
+/// A band of latency of the `Step` strategy.
+#[derive(Clone, Debug, serde::Deserialize, serde::Serialize)]
+pub struct Band {
+    /// The highest latency within the band, inclusive.
+    pub up_to: ConfigDuration,
+    /// The score of the probes within the band.
+    pub score: f32,
+}
+
+impl Step {
+    /// Constructs a new `Step` instance without any band, scoring every probe `0.0`.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Adds a band scoring the probes up to the given latency, unless a narrower band contains them.
+    pub fn insert(mut self, up_to: Duration, score: f32) -> Self {
+        self.bands.push(Band { up_to: up_to.into(), score });
+        self
+    }
+}
+
+impl Strategy for Step {
+    /// Implementation of `calculate` for `Step`.
+    ///
+    /// Informational, successful and redirect responses succeed, any other status fails.
+    fn calculate(&self, score: Score, new_response: Duration, status_code: u16) -> Score {
+        let band = self.bands.iter().filter(|band| new_response <= *band.up_to).min_by_key(|band| band.up_to);
+        let value = match band {
+            Some(band) if matches!(status_code, 100..=399) => band.score,
+            _ => 0.0,
+        };
+        let reliability = 1.0 - score.error_rate;
+        Score { error_rate: score.error_rate, jitter: score.jitter, ..Score::new(value, reliability, new_response) }
+    }
+}
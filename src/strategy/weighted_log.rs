@@ -1,11 +1,12 @@
 use super::Strategy;
 use crate::score::Score;
+use crate::ProbeOutcome;
 use std::time::Duration;
 
 /// A struct for creating a score utilizing HTTP response metrics and a weighted response average.
 /// It returns a natural logarithmic score based on the weighted average, response reliability,
 /// and HTTP status codes.
-#[derive(Debug, serde::Deserialize)]
+#[derive(Clone, Debug, serde::Deserialize, serde::Serialize)]
 pub struct WeightedLog {
     /// The weight given to new responses. A value closer to 1.0 gives
     /// more weight to newer responses, whereas a value closer to 0.0
@@ -14,12 +15,101 @@ pub struct WeightedLog {
     /// A factor that determines the amount of effort a service will require
     // to recover back to it's current score after a failure.
     pub effort: f32,
+    /// The reliability gained by every successful probe, and lost `effort` times over by every failed one.
+    #[serde(default = "default_reliability_factor")]
+    pub reliability_factor: f32,
+    /// The reliability new endpoints start from, e.g. `0.5` so a healthy new endpoint isn't ranked below a failing
+    /// old one until it accumulated hundreds of successful probes.
+    #[serde(default)]
+    pub initial_reliability: f32,
+    /// When enabled, the probes are scored by their deviation from the baseline of their endpoint, i.e. the latency
+    /// exceeding its usual one at the same hour of the week, rather than their absolute latency.
+    /// Requires the baselines of the history; probes without a baseline are scored by their absolute latency.
+    #[serde(default)]
+    pub relative_to_baseline: bool,
+    /// When enabled, the reliability is the success rate over the error window of the service, i.e.
+    /// `1 - error_rate`, rather than an accumulator taking a thousand successful probes to recover from a failure.
+    #[serde(default)]
+    pub windowed_reliability: bool,
+    /// The multiple of the jitter of the endpoint added to the latency of its probes when scoring them, e.g. `2.0`
+    /// scores a probe as if it took twice the standard deviation of the latency longer. Disabled at `0.0`.
+    #[serde(default)]
+    pub jitter_penalty: f32,
+    /// When enabled, the probes are scored by the weighted average of the response time, rather than their own
+    /// latency, so a single slow probe doesn't make the score jump.
+    #[serde(default)]
+    pub smoothed: bool,
+    /// The influence of the response time on the score of a successful probe: the higher it is, the faster the
+    /// score falls as the latency grows.
+    #[serde(default = "default_response_influence")]
+    pub response_influence: f32,
+    /// The status weight the influence of the response time is the lowest at. The further the status weight of a
+    /// probe is from it, the more its response time influences its score.
+    #[serde(default = "default_status_pivot")]
+    pub status_pivot: f32,
+    /// The influence added to the one of the response time per unit of distance from the `status_pivot`.
+    #[serde(default = "default_status_influence")]
+    pub status_influence: f32,
+    /// The offset added to the product of the reliability, the status weight and the latency factor before its
+    /// logarithm is taken. At `1.0`, a perfect probe scores `ln(2)` and the worst one `0.0`; lower offsets spread
+    /// the scores wider, and turn them negative below `1.0`. Must be positive.
+    #[serde(default = "default_log_offset")]
+    pub log_offset: f32,
+    /// How the latency factor decays as the response time grows.
+    #[serde(default)]
+    pub latency_curve: LatencyCurve,
+}
+
+/// The decay of the latency factor of `WeightedLog`, from `1.0` for an immediate response down to `0.0`, given the
+/// response time `t` in seconds and its influence `k`.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, serde::Deserialize, serde::Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum LatencyCurve {
+    /// `1 / (1 + t * k)`, a long tail keeping slow endpoints apart from each other.
+    #[default]
+    Hyperbolic,
+    /// `e^(-t * k)`, falling faster, so slow endpoints score close to each other, far below the fast ones.
+    Exponential,
+}
+
+fn default_reliability_factor() -> f32 {
+    0.001
+}
+
+fn default_response_influence() -> f32 {
+    0.1
+}
+
+fn default_status_pivot() -> f32 {
+    0.5
+}
+
+fn default_status_influence() -> f32 {
+    0.15
+}
+
+fn default_log_offset() -> f32 {
+    1.0
 }
 
 impl Default for WeightedLog {
     /// Provides default values for the `WeightLog` struct.
     fn default() -> Self {
-        Self { weight: 0.5, effort: 10.0 }
+        Self {
+            weight: 0.5,
+            effort: 10.0,
+            reliability_factor: default_reliability_factor(),
+            initial_reliability: 0.0,
+            relative_to_baseline: false,
+            windowed_reliability: false,
+            jitter_penalty: 0.0,
+            smoothed: false,
+            response_influence: default_response_influence(),
+            status_pivot: default_status_pivot(),
+            status_influence: default_status_influence(),
+            log_offset: default_log_offset(),
+            latency_curve: LatencyCurve::default(),
+        }
     }
 }
 
@@ -45,14 +135,112 @@ impl WeightedLog {
     /// These represent significant issues on the client-side and are likely to have the most impact on
     /// the service score, as they often require client-side intervention to resolve.
     const STATUS_NON_RECOVERABLE: f32 = 0.2;
-    /// A constant factor used in the calculation of the reliability score.
-    /// The reliability factor determines the magnitude of adjustment to the reliability score
-    /// based on the outcome of each HTTP request.
-    const RELIABILITY_FACTOR: f32 = 0.001;
-
     /// Constructs a new `WeightLog` instance with specified weight and effort values.
     pub fn new(weight: f32, effort: f32) -> Self {
-        Self { weight, effort }
+        Self { weight, effort, ..Self::default() }
+    }
+
+    /// Scores the probes by their deviation from the baseline of their endpoint, rather than their absolute latency.
+    pub fn set_relative_to_baseline(mut self, enabled: bool) -> Self {
+        self.relative_to_baseline = enabled;
+        self
+    }
+
+    /// Derives the reliability from the error rate over the error window of the service, rather than accumulating it.
+    pub fn set_windowed_reliability(mut self, enabled: bool) -> Self {
+        self.windowed_reliability = enabled;
+        self
+    }
+
+    /// Penalizes the endpoints whose latency oscillates, scoring their probes as if they took the given multiple
+    /// of their jitter longer.
+    pub fn set_jitter_penalty(mut self, penalty: f32) -> Self {
+        self.jitter_penalty = penalty;
+        self
+    }
+
+    /// Sets the reliability gained by every successful probe, the failed ones losing `effort` times more.
+    pub fn set_reliability_factor(mut self, factor: f32) -> Self {
+        self.reliability_factor = factor;
+        self
+    }
+
+    /// Sets the reliability new endpoints start from, between `0.0` and `1.0`.
+    pub fn set_initial_reliability(mut self, reliability: f32) -> Self {
+        self.initial_reliability = reliability;
+        self
+    }
+
+    /// Scores the probes by the weighted average of the response time, rather than their own latency.
+    pub fn set_smoothed(mut self, enabled: bool) -> Self {
+        self.smoothed = enabled;
+        self
+    }
+
+    /// Sets the influence of the response time on the score, and how it grows with the distance of the status weight
+    /// of the probes from a pivot, i.e. `influence + |pivot - status_weight| * status_influence`.
+    ///
+    /// # Panics
+    /// Panics if the influence is negative, which would raise the score of the slow responses.
+    pub fn set_response_influence(mut self, influence: f32, pivot: f32, status_influence: f32) -> Self {
+        assert!(influence >= 0.0, "the response influence must not be negative");
+        (self.response_influence, self.status_pivot, self.status_influence) = (influence, pivot, status_influence);
+        self
+    }
+
+    /// Sets the offset added before the logarithm of the score is taken.
+    ///
+    /// # Panics
+    /// Panics if the offset isn't positive, as the logarithm of the worst probes would be undefined.
+    pub fn set_log_offset(mut self, offset: f32) -> Self {
+        assert!(offset > 0.0, "the log offset must be positive");
+        self.log_offset = offset;
+        self
+    }
+
+    /// Checks the parameters the scores are undefined or inverted with, i.e. a log offset that isn't positive, or a
+    /// negative response influence.
+    pub(crate) fn validate(&self) -> Result<(), String> {
+        if self.log_offset.is_nan() || self.log_offset <= 0.0 {
+            return Err(format!("the log offset must be positive, got {}", self.log_offset));
+        }
+        if self.response_influence.is_nan() || self.response_influence < 0.0 {
+            return Err(format!("the response influence must not be negative, got {}", self.response_influence));
+        }
+        Ok(())
+    }
+
+    /// Sets how the latency factor decays as the response time grows.
+    pub fn set_latency_curve(mut self, curve: LatencyCurve) -> Self {
+        self.latency_curve = curve;
+        self
+    }
+
+    /// Scores a probe by the given latency, e.g. its own one or the average response time.
+    ///
+    /// ## Arguments
+    /// * `score`: Score - The current score before this calculation.
+    /// * `response`: Duration - The updated average response time, kept along with the score.
+    /// * `latency`: Duration - The latency the probe is scored by.
+    /// * `status_code`: u16 - The HTTP status code of the probe.
+    ///
+    /// ## Returns
+    /// The updated score.
+    fn score_latency(&self, score: Score, response: Duration, latency: Duration, status_code: u16) -> Score {
+        // Determine the weight associated with the given status code.
+        let status_weight = self.get_status_weight(status_code);
+        // Adjust the reliability based on the status code, or take it from the error rate if windowed.
+        let reliability = match self.windowed_reliability {
+            true => (1.0 - score.error_rate).clamp(0.0, 1.0),
+            false => self.adjust_reliability(score.reliability, status_code),
+        };
+        // Penalize the response time by the jitter of the endpoint.
+        let scored = latency + score.jitter.mul_f32(self.jitter_penalty.max(0.0));
+        // Calculate the new score using the updated parameters.
+        let (error_rate, jitter) = (score.error_rate, score.jitter);
+        let score = self.calculate_logarithmic_score(reliability, status_weight, scored);
+        // Return a new Score instance with the updated values.
+        Score { error_rate, jitter, ..Score::new(score, reliability, response) }
     }
 
     /// Determines the status weight based on the HTTP status code.
@@ -88,11 +276,11 @@ impl WeightedLog {
     pub(crate) fn adjust_reliability(&self, reliability: f32, status_code: u16) -> f32 {
         let increment = match status_code {
             // Increase reliability for successful operations.
-            200..=299 => Self::RELIABILITY_FACTOR,
+            200..=299 => self.reliability_factor,
             // Keep reliability neutral for info or redirect responses.
             100..=199 | 300..=399 => 0.0,
             // Decrease reliability for failures.
-            _ => -(self.effort * Self::RELIABILITY_FACTOR),
+            _ => -(self.effort * self.reliability_factor),
         };
 
         // Ensure the reliability score stays within the bounds of 0.0 to 1.0.
@@ -134,11 +322,15 @@ impl WeightedLog {
     /// The calculated logarithmic score as a floating-point number.
     pub(crate) fn calculate_logarithmic_score(&self, reliability: f32, status_weight: f32, response: Duration) -> f32 {
         // Influence of response time on score, adjusted by status weight.
-        let response_influence = 0.1 + (0.5 - status_weight).abs() * 0.15;
+        let response_influence =
+            self.response_influence + (self.status_pivot - status_weight).abs() * self.status_influence;
         // Calculate the response time factor.
-        let response_factor = 1.0 / (1.0 + response.as_secs_f32() * response_influence);
+        let response_factor = match self.latency_curve {
+            LatencyCurve::Hyperbolic => 1.0 / (1.0 + response.as_secs_f32() * response_influence),
+            LatencyCurve::Exponential => (-response.as_secs_f32() * response_influence).exp(),
+        };
         // Base score combining reliability, status weight, and response time factor.
-        let base_score = reliability * status_weight * response_factor + 1.0;
+        let base_score = reliability * status_weight * response_factor + self.log_offset;
         // Apply logarithm to base score for final total score.
         base_score.ln()
     }
@@ -157,15 +349,43 @@ impl Strategy for WeightedLog {
     /// # Returns
     /// A new `Score` instance representing the updated score.
     fn calculate(&self, score: Score, new_response: Duration, status_code: u16) -> Score {
-        // Determine the weight associated with the given status code.
-        let status_weight = self.get_status_weight(status_code);
         // Calculate the weighted average of the response time.
         let response = self.weighted_response_average(score.response_avg, new_response);
-        // Adjust the reliability based on the status code.
-        let reliability = self.adjust_reliability(score.reliability, status_code);
-        // Calculate the new score using the updated parameters.
-        let score = self.calculate_logarithmic_score(reliability, status_weight, new_response);
-        // Return a new Score instance with the updated values.
-        Score::new(score, reliability, response)
+        // Score the average rather than the latency of the probe, if smoothed.
+        let scored = if self.smoothed { response } else { new_response };
+        self.score_latency(score, response, scored, status_code)
+    }
+
+    /// Implementation of `calculate_outcome` for `WeightLog`.
+    ///
+    /// When relative to the baseline, the probe is scored by the latency exceeding the baseline of its endpoint.
+    /// When its endpoint has a latency budget, the latency is scored in budgets: a response taking the whole budget
+    /// scores like a one-second response would without a budget. Either way, the average response time keeps
+    /// tracking the absolute latency, and is scored instead of the latency of the probe when smoothed.
+    fn calculate_outcome(&self, score: Score, outcome: &ProbeOutcome) -> Score {
+        let status = if outcome.error.is_some() { 0 } else { outcome.status };
+        if !self.relative_to_baseline && outcome.budget.is_none() {
+            return self.calculate(score, outcome.elapsed, status);
+        }
+
+        let (response, jitter) = (self.weighted_response_average(score.response_avg, outcome.elapsed), score.jitter);
+        let latency = if self.smoothed { response } else { outcome.elapsed };
+        let mut scored = match outcome.baseline.filter(|_| self.relative_to_baseline) {
+            Some(baseline) => latency.saturating_sub(baseline),
+            None => latency,
+        };
+        // The jitter is scored in budgets as well
+        let mut scored_jitter = jitter;
+        if let Some(budget) = outcome.budget.filter(|budget| !budget.is_zero()) {
+            scored = Duration::from_secs_f64(scored.as_secs_f64() / budget.as_secs_f64());
+            scored_jitter = Duration::from_secs_f64(jitter.as_secs_f64() / budget.as_secs_f64());
+        }
+        let score = Score { jitter: scored_jitter, ..score };
+        Score { jitter, ..self.score_latency(score, response, scored, status) }
+    }
+
+    /// Implementation of `initial_score` for `WeightLog`, starting from the initial reliability.
+    fn initial_score(&self) -> Score {
+        Score::new(0.0, self.initial_reliability.clamp(0.0, 1.0), Duration::ZERO)
     }
 }
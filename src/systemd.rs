@@ -0,0 +1,52 @@
+use std::time::Duration;
+
+/// Notifies the service manager of a change of state, following the `sd_notify` protocol, e.g. `READY=1`.
+///
+/// # Arguments
+/// * `state`: The newline-separated assignments describing the state.
+///
+/// # Returns
+/// `true` if the notification was sent, or `false` if the process isn't supervised by systemd (`NOTIFY_SOCKET` unset).
+pub fn notify(state: &str) -> std::io::Result<bool> {
+    #[cfg(unix)]
+    {
+        use std::os::unix::ffi::OsStrExt;
+        use std::os::unix::net::UnixDatagram;
+
+        let Some(path) = std::env::var_os("NOTIFY_SOCKET") else {
+            return Ok(false);
+        };
+        let socket = UnixDatagram::unbound()?;
+        match path.as_bytes().strip_prefix(b"@") {
+            // Sockets prefixed with `@` live in the abstract namespace
+            #[cfg(any(target_os = "linux", target_os = "android"))]
+            Some(name) => {
+                use std::os::linux::net::SocketAddrExt;
+                let addr = std::os::unix::net::SocketAddr::from_abstract_name(name)?;
+                socket.send_to_addr(state.as_bytes(), &addr)?;
+            }
+            _ => {
+                socket.send_to(state.as_bytes(), path)?;
+            }
+        }
+        Ok(true)
+    }
+    #[cfg(not(unix))]
+    {
+        let _ = state;
+        Ok(false)
+    }
+}
+
+/// Returns how often the service manager expects a `WATCHDOG=1` notification, before considering the process hung.
+///
+/// # Returns
+/// The watchdog timeout, or `None` if the watchdog isn't enabled for this process.
+pub fn watchdog() -> Option<Duration> {
+    let usec = std::env::var("WATCHDOG_USEC").ok()?.parse::<u64>().ok()?;
+    // The watchdog may be meant for another process, e.g. the parent of a forked one
+    match std::env::var("WATCHDOG_PID") {
+        Ok(pid) if pid.parse::<u32>().ok()? != std::process::id() => None,
+        _ => Some(Duration::from_micros(usec)).filter(|timeout| !timeout.is_zero()),
+    }
+}
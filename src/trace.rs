@@ -0,0 +1,91 @@
+use crate::request::generate_id;
+use tracing::field::Empty;
+
+/// The name of the header propagating the trace context, as specified by W3C Trace Context.
+pub const TRACEPARENT: &str = "traceparent";
+
+/// Tracing configuration
+///
+/// - `sample_rate`: the fraction of the probes traced, between `0.0` and `1.0` (default: 1.0)
+/// - `propagate`: whether the W3C `traceparent` header is sent along with every probe, sampled or not,
+///   so the spans of the endpoint can be correlated with the probe (default: true)
+///
+/// Every sampled probe is given a `probe` span carrying its `url`, `trace_id`, `status`, `latency_ms` and
+/// `score_delta`, emitted through the `tracing` crate to whichever subscriber the application installed.
+#[derive(serde::Deserialize, serde::Serialize, Clone, Debug, PartialEq)]
+pub struct Config {
+    #[serde(default = "default_sample_rate")]
+    pub sample_rate: f64,
+    #[serde(default = "default_propagate")]
+    pub propagate: bool,
+}
+
+impl Default for Config {
+    fn default() -> Self {
+        Self { sample_rate: default_sample_rate(), propagate: default_propagate() }
+    }
+}
+
+fn default_sample_rate() -> f64 {
+    1.0
+}
+
+fn default_propagate() -> bool {
+    true
+}
+
+impl Config {
+    /// Sets the fraction of the probes traced.
+    pub fn set_sample_rate(mut self, sample_rate: f64) -> Self {
+        self.sample_rate = sample_rate;
+        self
+    }
+
+    /// Sets whether the `traceparent` header is sent along with every probe.
+    pub fn set_propagate(mut self, propagate: bool) -> Self {
+        self.propagate = propagate;
+        self
+    }
+}
+
+/// The trace context of a probe.
+pub(crate) struct Context {
+    trace_id: String,
+    span_id: String,
+    sampled: bool,
+}
+
+impl Context {
+    /// Creates the context of a new trace, sampled according to the configuration.
+    pub(crate) fn new(config: &Config) -> Self {
+        let trace_id = generate_id();
+        let span_id = generate_id()[16..].to_string();
+        // The trace IDs are uniformly distributed, so their lower half doubles as the sampling draw
+        let draw = u64::from_str_radix(&trace_id[16..], 16).unwrap_or_default() as f64 / u64::MAX as f64;
+        let sampled = config.sample_rate >= 1.0 || draw < config.sample_rate;
+        Self { trace_id, span_id, sampled }
+    }
+
+    /// Returns the value of the `traceparent` header of the probe.
+    pub(crate) fn traceparent(&self) -> String {
+        format!("00-{}-{}-{:02x}", self.trace_id, self.span_id, u8::from(self.sampled))
+    }
+
+    /// Creates the span of the probe, disabled unless it's sampled.
+    ///
+    /// # Arguments
+    /// * `url`: The URL of the probed endpoint.
+    pub(crate) fn span(&self, url: &str) -> tracing::Span {
+        match self.sampled {
+            true => tracing::info_span!(
+                "probe",
+                url,
+                trace_id = self.trace_id.as_str(),
+                status = Empty,
+                latency_ms = Empty,
+                score_delta = Empty
+            ),
+            false => tracing::Span::none(),
+        }
+    }
+}
@@ -0,0 +1,240 @@
+use crate::config::ConfigDuration;
+use crate::guard::Guard;
+use hyper::Uri;
+use socket2::{Domain, Protocol, SockAddr, Socket, Type};
+use std::io::{ErrorKind, Read};
+use std::net::{IpAddr, Ipv4Addr, SocketAddr};
+use std::sync::atomic::{AtomicU16, Ordering::SeqCst};
+use std::time::{Duration, Instant};
+
+/// Traceroute configuration
+///
+/// - `max_hops`: the maximum number of hops traced toward the endpoint (default: 30)
+/// - `timeout`: how long to wait for the reply of every hop (default: 1s)
+///
+/// Tracing sends ICMP echo requests over a raw socket, which requires elevated privileges
+/// (e.g. `CAP_NET_RAW` on Linux). Only IPv4 endpoints are supported.
+#[derive(serde::Deserialize, serde::Serialize, Clone, Debug, PartialEq, Eq)]
+pub struct Config {
+    #[serde(default = "default_max_hops")]
+    pub max_hops: u8,
+    #[serde(default)]
+    pub timeout: Option<ConfigDuration>,
+}
+
+impl Default for Config {
+    fn default() -> Self {
+        Self { max_hops: default_max_hops(), timeout: None }
+    }
+}
+
+fn default_max_hops() -> u8 {
+    30
+}
+
+impl Config {
+    /// Sets the maximum number of hops traced toward the endpoint.
+    pub fn set_max_hops(mut self, max_hops: u8) -> Self {
+        self.max_hops = max_hops;
+        self
+    }
+
+    /// Sets how long to wait for the reply of every hop.
+    pub fn set_timeout(mut self, timeout: Duration) -> Self {
+        self.timeout = Some(timeout.into());
+        self
+    }
+}
+
+/// A router on the path toward an endpoint.
+#[derive(serde::Deserialize, serde::Serialize, Clone, Debug, PartialEq, Eq)]
+pub struct Hop {
+    /// The time-to-live the hop was probed with, i.e. its distance from the host.
+    pub ttl: u8,
+    /// The address of the router, or `None` if it didn't reply in time.
+    pub address: Option<IpAddr>,
+    /// The round-trip time to the router, or `None` if it didn't reply in time.
+    pub rtt: Option<Duration>,
+}
+
+/// The network path toward an endpoint.
+///
+/// A path reaching the endpoint points to an application failure, while one ending before it points to the network.
+#[derive(serde::Deserialize, serde::Serialize, Clone, Debug, Default, PartialEq, Eq)]
+pub struct Trace {
+    /// The address that was traced.
+    pub target: Option<IpAddr>,
+    /// The hops toward the target, in order.
+    pub hops: Vec<Hop>,
+    /// Whether the target itself replied.
+    pub reached: bool,
+    /// The reason the trace couldn't be completed, if any.
+    pub error: Option<String>,
+}
+
+/// Traces the network path toward the host of a URL.
+///
+/// # Arguments
+/// * `url`: The URL of the endpoint.
+/// * `config`: The number of hops and the timeout of the trace.
+/// * `guard`: Restricts the addresses that can be traced, if set.
+///
+/// # Returns
+/// The hops toward the endpoint; a trace that failed records its error along with the hops traced so far.
+pub(crate) async fn trace(url: &Uri, config: &Config, guard: Option<&Guard>) -> Trace {
+    let host = url.host().unwrap_or_default().trim_start_matches('[').trim_end_matches(']');
+    let addresses = match tokio::net::lookup_host((host, url.port_u16().unwrap_or(0))).await {
+        Ok(addresses) => addresses.map(|a| a.ip()).collect::<Vec<_>>(),
+        Err(e) => return Trace { error: Some(format!("failed to resolve `{host}`: {e}")), ..Default::default() },
+    };
+    let mut allowed = addresses.into_iter().filter(|ip| guard.is_none_or(|g| g.check_ip(*ip).is_ok()));
+    let Some(target) = allowed.find_map(|ip| match ip {
+        IpAddr::V4(ip) => Some(ip),
+        IpAddr::V6(_) => None,
+    }) else {
+        return Trace { error: Some(format!("no IPv4 address allowed for `{host}`")), ..Default::default() };
+    };
+
+    let config = config.clone();
+    let mut trace = Trace { target: Some(target.into()), ..Default::default() };
+    // Raw sockets are blocking, so the trace runs on a dedicated thread
+    match tokio::task::spawn_blocking(move || run(target, &config)).await {
+        Ok((hops, result)) => {
+            trace.reached = hops.last().is_some_and(|hop| hop.address == Some(target.into()));
+            trace.hops = hops;
+            trace.error = result.err().map(|e| format!("failed to trace `{target}`: {e}"));
+        }
+        Err(e) => trace.error = Some(e.to_string()),
+    }
+    trace
+}
+
+/// Sends ICMP echo requests with an increasing time-to-live, until the target replies or the hops run out.
+///
+/// # Returns
+/// The hops traced, along with the error that interrupted the trace, if any.
+fn run(target: Ipv4Addr, config: &Config) -> (Vec<Hop>, std::io::Result<()>) {
+    // Concurrent traces are told apart by the identifier of their echo requests
+    static ID: AtomicU16 = AtomicU16::new(0);
+    let id = (std::process::id() as u16).wrapping_add(ID.fetch_add(1, SeqCst));
+    let timeout = config.timeout.map_or(Duration::from_secs(1), ConfigDuration::get);
+
+    let mut hops = Vec::new();
+    let result = (|| {
+        let socket = Socket::new(Domain::IPV4, Type::RAW, Some(Protocol::ICMPV4))?;
+        let destination = SockAddr::from(SocketAddr::new(target.into(), 0));
+        for ttl in 1..=config.max_hops {
+            socket.set_ttl(ttl.into())?;
+            let sent = Instant::now();
+            socket.send_to(&echo_request(id, ttl.into()), &destination)?;
+
+            let reply = receive(&socket, id, ttl.into(), sent + timeout)?;
+            hops.push(Hop { ttl, address: reply.map(|(ip, _)| ip.into()), rtt: reply.map(|_| sent.elapsed()) });
+            if let Some((_, true)) = reply {
+                break;
+            }
+        }
+        Ok(())
+    })();
+    (hops, result)
+}
+
+/// Waits for the reply to an echo request, ignoring the ICMP traffic of others.
+///
+/// # Returns
+/// The address of the replying host and whether it's final, or `None` if the deadline elapsed.
+fn receive(socket: &Socket, id: u16, seq: u16, deadline: Instant) -> std::io::Result<Option<(Ipv4Addr, bool)>> {
+    let mut buf = [0u8; 1500];
+    loop {
+        let remaining = deadline.saturating_duration_since(Instant::now());
+        if remaining.is_zero() {
+            return Ok(None);
+        }
+        socket.set_read_timeout(Some(remaining))?;
+        match (&*socket).read(&mut buf) {
+            Ok(n) => {
+                if let Some(reply) = parse(&buf[..n], id, seq) {
+                    return Ok(Some(reply));
+                }
+            }
+            Err(e) if matches!(e.kind(), ErrorKind::WouldBlock | ErrorKind::TimedOut | ErrorKind::Interrupted) => {}
+            Err(e) => return Err(e),
+        }
+    }
+}
+
+/// Builds an ICMP echo request.
+fn echo_request(id: u16, seq: u16) -> Vec<u8> {
+    let mut packet = vec![8, 0, 0, 0];
+    packet.extend_from_slice(&id.to_be_bytes());
+    packet.extend_from_slice(&seq.to_be_bytes());
+    packet.extend_from_slice(b"isup-traceroute");
+    let checksum = checksum(&packet);
+    packet[2..4].copy_from_slice(&checksum.to_be_bytes());
+    packet
+}
+
+/// Computes the Internet checksum (RFC 1071) of a packet.
+fn checksum(packet: &[u8]) -> u16 {
+    let mut sum = packet.chunks(2).map(|w| u32::from(u16::from_be_bytes([w[0], *w.get(1).unwrap_or(&0)]))).sum::<u32>();
+    while sum > 0xffff {
+        sum = (sum & 0xffff) + (sum >> 16);
+    }
+    !(sum as u16)
+}
+
+/// Parses an ICMP packet received on a raw socket, including its IPv4 header.
+///
+/// # Returns
+/// The source of the packet and whether it's final, i.e. sent by the target, if it answers the given echo request.
+fn parse(packet: &[u8], id: u16, seq: u16) -> Option<(Ipv4Addr, bool)> {
+    let header_len = usize::from(packet.first()? & 0x0f) * 4;
+    let source: [u8; 4] = packet.get(12..16)?.try_into().ok()?;
+    let icmp = packet.get(header_len..)?;
+    let ids = [id.to_be_bytes(), seq.to_be_bytes()].concat();
+
+    match *icmp.first()? {
+        // An echo reply from the target
+        0 => (icmp.get(4..8)? == ids).then_some((source.into(), true)),
+        // Destination unreachable and time exceeded quote the header of the echo request
+        kind @ (3 | 11) => {
+            let inner = icmp.get(8..)?;
+            let inner_len = usize::from(inner.first()? & 0x0f) * 4;
+            let quoted = inner.get(inner_len..inner_len + 8)?;
+            (quoted[0] == 8 && quoted[4..8] == ids).then_some((source.into(), kind == 3))
+        }
+        _ => None,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Wraps an ICMP message in a minimal IPv4 header sent by `source`.
+    fn ipv4(source: [u8; 4], icmp: &[u8]) -> Vec<u8> {
+        let mut packet = vec![0x45, 0, 0, 0, 0, 0, 0, 0, 64, 1, 0, 0];
+        packet.extend_from_slice(&source);
+        packet.extend_from_slice(&[192, 0, 2, 1]);
+        packet.extend_from_slice(icmp);
+        packet
+    }
+
+    #[test]
+    fn test_parse() {
+        let request = echo_request(7, 3);
+        assert_eq!(checksum(&request), 0);
+
+        // A router on the path, quoting the request whose time-to-live exceeded
+        let mut exceeded = vec![11, 0, 0, 0, 0, 0, 0, 0];
+        exceeded.extend_from_slice(&ipv4([192, 0, 2, 1], &request));
+        assert_eq!(parse(&ipv4([10, 0, 0, 1], &exceeded), 7, 3), Some((Ipv4Addr::new(10, 0, 0, 1), false)));
+        assert_eq!(parse(&ipv4([10, 0, 0, 1], &exceeded), 7, 4), None);
+
+        // The target itself
+        let mut reply = request.clone();
+        reply[0] = 0;
+        assert_eq!(parse(&ipv4([198, 51, 100, 7], &reply), 7, 3), Some((Ipv4Addr::new(198, 51, 100, 7), true)));
+        assert_eq!(parse(&ipv4([198, 51, 100, 7], &reply), 8, 3), None);
+    }
+}
@@ -0,0 +1,90 @@
+mod common;
+
+#[cfg(test)]
+mod agent_tests {
+    use super::common;
+    use bytes::Bytes;
+    use http_body_util::{BodyExt, Full};
+    use isup::agent::{self, Coordinator, SAMPLES_PATH};
+    use isup::incident::State;
+    use isup::{Client, ProbeOutcome, Request, Sample, Service};
+    use std::net::SocketAddr;
+    use std::sync::Arc;
+    use std::time::Duration;
+    use tokio::net::TcpListener;
+
+    /// Starts a coordinator scoring the samples of the given endpoint, returning its address and service.
+    async fn coordinator(url: &str) -> (SocketAddr, Arc<Service>) {
+        let mut service = Service::default();
+        service.insert_request(Request::new("GET", url)).unwrap();
+        let service = Arc::new(service);
+
+        let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let addr = listener.local_addr().unwrap();
+        tokio::spawn(Coordinator::new(service.clone(), vec!["secret".into()]).serve(listener));
+        (addr, service)
+    }
+
+    #[tokio::test]
+    async fn it_pushes_samples_to_the_coordinator() {
+        let url = format!("http://{}/", common::serve(common::OK).await);
+        let (addr, coordinator) = coordinator(&url).await;
+
+        // The agent probes the endpoint and pushes the outcome
+        let config = agent::Config::new(format!("http://{addr}{SAMPLES_PATH}"), "secret".into()).set_name("eu-west");
+        let mut service = Service::default().use_agent(config);
+        service.insert_request(Request::new("GET", url.as_str())).unwrap();
+        service.update().await.unwrap();
+
+        // Verify that the coordinator scored the sample without probing the endpoint itself
+        assert!(coordinator.store.get(&url).await.unwrap().is_some());
+        assert_eq!(coordinator.state(&url), Some(State::Up));
+    }
+
+    #[tokio::test]
+    async fn it_rejects_unauthorized_agents() {
+        let url = format!("http://{}/", common::serve(common::OK).await);
+        let (addr, coordinator) = coordinator(&url).await;
+
+        let request = hyper::Request::post(format!("http://{addr}{SAMPLES_PATH}"))
+            .header("authorization", "Bearer guess")
+            .body(Full::new(Bytes::from(r#"{"agent":"rogue","samples":[]}"#)))
+            .unwrap();
+        let response = Client::default().request(request).await.unwrap();
+        assert_eq!(response.status(), 401);
+
+        // Samples for endpoints the coordinator doesn't monitor are rejected
+        let sample = ProbeOutcome::new(format!("{url}other"), Duration::from_millis(10), 200);
+        let batch = serde_json::to_vec(&agent::Batch { agent: "eu-west".into(), samples: vec![sample] }).unwrap();
+        let request = hyper::Request::post(format!("http://{addr}{SAMPLES_PATH}"))
+            .header("authorization", "Bearer secret")
+            .body(Full::new(Bytes::from(batch)))
+            .unwrap();
+        let response = Client::default().request(request).await.unwrap();
+        let receipt: agent::Receipt = serde_json::from_slice(&response.collect().await.unwrap().to_bytes()).unwrap();
+        assert_eq!(receipt.accepted, 0);
+        assert_eq!(receipt.rejected, vec![format!("unknown endpoint `{url}other`")]);
+        assert!(coordinator.store.get(&format!("{url}other")).await.unwrap().is_none());
+    }
+
+    #[tokio::test]
+    async fn it_ingests_external_samples() {
+        const URL: &str = "http://api.example/";
+        let mut service = Service::default();
+        service.insert_request(Request::new("GET", URL)).unwrap();
+
+        // The URL of the samples is normalized, e.g. as reported by the real-user monitoring of a browser
+        service.ingest("http://API.example:80", Sample::new(Duration::from_millis(80), 200)).await.unwrap();
+        assert_eq!(service.score(URL).await.unwrap().unwrap().response_avg, Duration::from_millis(40));
+        assert_eq!(service.state(URL), Some(State::Up));
+
+        let sample = Sample::new(Duration::from_millis(80), 200).set_error("unexpected body");
+        service.ingest(URL, sample).await.unwrap();
+        assert_eq!(service.score(URL).await.unwrap().unwrap().last_error.as_deref(), Some("unexpected body"));
+        assert_eq!(service.state(URL), Some(State::Down));
+
+        let sample = Sample::new(Duration::from_millis(80), 200);
+        assert!(service.ingest("http://other.example/", sample.clone()).await.is_err());
+        assert!(service.ingest("not a url", sample).await.is_err());
+    }
+}
@@ -0,0 +1,127 @@
+mod common;
+
+#[cfg(test)]
+mod alert_tests {
+    use super::common;
+    use isup::alert::{Alert, Config};
+    use isup::chaos::{Chaos, Fault};
+    use isup::forecast;
+    use isup::incident::State;
+    use isup::strategy::Linear;
+    use isup::{Request, Service};
+    use std::sync::atomic::{AtomicBool, Ordering::SeqCst};
+    use std::sync::Arc;
+    use std::time::Duration;
+
+    #[tokio::test]
+    async fn it_raises_alerts_in_alertmanager() {
+        // An endpoint that can be taken down and brought back up
+        let down = Arc::new(AtomicBool::new(true));
+        let toggle = down.clone();
+        let addr = common::serve_with(move |_| match toggle.load(SeqCst) {
+            true => "HTTP/1.1 503 Service Unavailable\r\ncontent-length: 0\r\n\r\n".into(),
+            false => common::OK.into(),
+        })
+        .await;
+        let url = format!("http://{addr}/");
+        let (alertmanager, received) = common::record().await;
+
+        let config = Config::new(format!("http://{alertmanager}/")).set_label("env", "test");
+        let mut service = Service::default().use_alertmanager(config);
+        service.insert_request(Request::new("GET", url.as_str()).set_tag("team", "payments")).unwrap();
+
+        // Going down fires an alert, labeled with the tags of the endpoint
+        service.update().await.unwrap();
+        let (head, body) = received.lock().unwrap()[0].clone();
+        assert!(head.starts_with("post /api/v2/alerts http/1.1"));
+        let alerts = serde_json::from_slice::<Vec<Alert>>(&body).unwrap();
+        assert_eq!(alerts[0].labels["alertname"], "EndpointDown");
+        assert_eq!(alerts[0].labels["url"], url);
+        assert_eq!(alerts[0].labels["team"], "payments");
+        assert_eq!(alerts[0].labels["env"], "test");
+        assert_eq!(alerts[0].annotations["status"], "503");
+        assert_eq!(alerts[0].ends_at, None);
+
+        // Recovering resolves it
+        down.store(false, SeqCst);
+        service.update().await.unwrap();
+        let (_, body) = received.lock().unwrap()[1].clone();
+        let alerts = serde_json::from_slice::<Vec<Alert>>(&body).unwrap();
+        assert!(alerts[0].ends_at.is_some());
+        assert_eq!(received.lock().unwrap().len(), 2);
+    }
+
+    #[tokio::test]
+    async fn it_raises_budget_alerts() {
+        // An endpoint exceeding its budget twice, then fitting it again
+        const URL: &str = "http://simulated.example/";
+        let latencies = [120, 80, 10].map(Duration::from_millis).to_vec();
+        let chaos = Chaos::new(42).insert(Fault::new(URL).set_latencies(latencies));
+        let (alertmanager, received) = common::record().await;
+
+        let config = Config::new(format!("http://{alertmanager}/"));
+        let mut service = Service::default().use_chaos(chaos).use_alertmanager(config);
+        service.insert_request(Request::new("GET", URL).set_budget(Duration::from_millis(50))).unwrap();
+        for _ in 0..3 {
+            service.update().await.unwrap();
+        }
+
+        let alerts = received
+            .lock()
+            .unwrap()
+            .iter()
+            .map(|(_, body)| serde_json::from_slice::<Vec<Alert>>(body).unwrap()[0].clone())
+            .collect::<Vec<_>>();
+        assert_eq!(alerts.len(), 3);
+        assert!(alerts.iter().all(|a| a.labels["alertname"] == "BudgetExceeded"));
+        assert_eq!(alerts[1].annotations["violations"], "2");
+        assert_eq!(alerts[1].annotations["latency"], "80ms");
+        assert_eq!(alerts[1].ends_at, None);
+        assert!(alerts[2].ends_at.is_some());
+        // The endpoint never went down
+        assert_eq!(service.state(URL), Some(State::Up));
+    }
+
+    #[tokio::test]
+    async fn it_raises_alerts_before_the_score_crosses_the_threshold() {
+        // An endpoint slowing down on every probe, scored linearly from its latency
+        const URL: &str = "http://simulated.example/";
+        let latencies = [10, 30, 50, 70, 150].map(Duration::from_millis).to_vec();
+        let chaos = Chaos::new(42).insert(Fault::new(URL).set_latencies(latencies));
+        let (alertmanager, received) = common::record().await;
+
+        let mut service = Service::default()
+            .use_chaos(chaos)
+            .use_strategy(Linear::new(Duration::from_millis(200)))
+            .use_forecast(forecast::Config::default().set_threshold(0.5))
+            .use_alertmanager(Config::new(format!("http://{alertmanager}/")));
+        service.insert_request(Request::new("GET", URL)).unwrap();
+
+        // The endpoint is at risk once its trend is known, while its score is still above the threshold
+        for _ in 0..3 {
+            service.update().await.unwrap();
+        }
+        let risks = service.risks();
+        assert_eq!(risks.len(), 1);
+        assert!(risks[0].score >= 0.5 && risks[0].forecast < 0.5);
+
+        // The risk is resolved once the score crosses the threshold
+        service.update().await.unwrap();
+        service.update().await.unwrap();
+        assert!(service.risks().is_empty());
+
+        let alerts = received
+            .lock()
+            .unwrap()
+            .iter()
+            .map(|(_, body)| serde_json::from_slice::<Vec<Alert>>(body).unwrap()[0].clone())
+            .collect::<Vec<_>>();
+        assert_eq!(alerts.len(), 3);
+        assert!(alerts.iter().all(|a| a.labels["alertname"] == "EndpointAtRisk"));
+        assert_eq!(alerts[0].labels["url"], URL);
+        assert_eq!(alerts[1].ends_at, None);
+        assert!(alerts[2].ends_at.is_some());
+        // The endpoint never went down
+        assert_eq!(service.state(URL), Some(State::Up));
+    }
+}
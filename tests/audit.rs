@@ -0,0 +1,40 @@
+mod common;
+
+#[cfg(test)]
+mod audit_tests {
+    use super::common;
+    use isup::{audit, Request, Service};
+
+    const RESPONSE: &str = "HTTP/1.1 200 OK\r\nx-content-type-options: nosniff\r\ncontent-length: 2\r\n\r\nok";
+
+    #[tokio::test]
+    async fn it_reports_missing_headers() {
+        let addr = common::serve(RESPONSE).await;
+        let url = format!("http://{addr}/");
+        let mut service = Service::default();
+        service.insert_request(Request::new("GET", &url).set_audit(audit::Config::default())).unwrap();
+
+        service.update().await.unwrap();
+        let reports = service.audit_reports();
+        // Verify that the present and missing headers were reported
+        assert_eq!(reports.len(), 1);
+        assert_eq!(reports[0].1.present, vec!["x-content-type-options"]);
+        assert!(reports[0].1.missing.contains(&"strict-transport-security".to_string()));
+    }
+
+    #[tokio::test]
+    async fn it_penalizes_missing_headers() {
+        let addr = common::serve(RESPONSE).await;
+        let (reported, penalized) = (format!("http://{addr}/reported"), format!("http://{addr}/penalized"));
+        let audit = audit::Config::default().set_required(vec!["X-Frame-Options"]);
+        let mut service = Service::default();
+        service.insert_request(Request::new("GET", &reported).set_audit(audit.clone())).unwrap();
+        service.insert_request(Request::new("GET", &penalized).set_audit(audit.set_penalize(true))).unwrap();
+
+        service.update().await.unwrap();
+        // Verify that the missing header only lowered the reliability of the penalized endpoint
+        let reported = service.store.get(&reported).await.unwrap().unwrap();
+        let penalized = service.store.get(&penalized).await.unwrap().unwrap();
+        assert!(penalized.reliability < reported.reliability);
+    }
+}
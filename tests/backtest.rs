@@ -0,0 +1,40 @@
+#[cfg(test)]
+mod backtest_tests {
+    use isup::{backtest, strategy::WeightedLog, ProbeOutcome};
+    use std::time::Duration;
+
+    const FAST: &str = "http://fast.example/";
+    const SLOW: &str = "http://slow.example/";
+
+    // Creates a cycle where both endpoints respond successfully with the given latencies
+    fn cycle(fast: u64, slow: u64) -> Vec<ProbeOutcome> {
+        vec![
+            ProbeOutcome::new(FAST, Duration::from_millis(fast), 200),
+            ProbeOutcome::new(SLOW, Duration::from_millis(slow), 200),
+        ]
+    }
+
+    #[test]
+    fn it_ranks_replayed_cycles() {
+        let strategy = WeightedLog::default();
+        // The fast endpoint is faster in every cycle
+        let report = backtest::run(&strategy, vec![cycle(100, 900), cycle(100, 900), cycle(100, 900)]);
+
+        // Verify that every cycle produced a round
+        assert_eq!(report.rounds.len(), 3);
+        // Verify that the fast endpoint was ranked first in all of them
+        assert_eq!(report.best_counts().get(FAST), Some(&3));
+        assert_eq!(report.switches(), 0);
+    }
+
+    #[test]
+    fn it_counts_switches() {
+        let strategy = WeightedLog::default();
+        // The endpoints swap places in the second cycle
+        let report = backtest::run(&strategy, vec![cycle(100, 900), cycle(5000, 100)]);
+
+        assert_eq!(report.rounds[0].best(), Some(FAST));
+        assert_eq!(report.last().and_then(|r| r.best()), Some(SLOW));
+        assert_eq!(report.switches(), 1);
+    }
+}
@@ -0,0 +1,63 @@
+mod common;
+
+#[cfg(test)]
+mod cache_tests {
+    use super::common;
+    use bytes::Bytes;
+    use isup::{ProbeOutcome, Request};
+    use std::time::{Duration, SystemTime};
+
+    const FULL: &str = "HTTP/1.1 200 OK\r\netag: \"v1\"\r\ncontent-length: 2\r\n\r\nok";
+    const NOT_MODIFIED: &str = "HTTP/1.1 304 Not Modified\r\netag: \"v1\"\r\n\r\n";
+
+    #[tokio::test]
+    async fn it_validates_conditional_requests() {
+        // A server honoring conditional requests
+        let addr = common::serve_with(|head| match head.contains("if-none-match: \"v1\"") {
+            true => Bytes::from(NOT_MODIFIED),
+            false => Bytes::from(FULL),
+        })
+        .await;
+        let request = Request::new("GET", &format!("http://{addr}/")).set_cache_validation(true);
+
+        let outcomes = common::probe(request, 2).await;
+        // The first probe receives the full response, the second one is revalidated
+        assert_eq!(outcomes[0].revalidated, Some(false));
+        assert_eq!(outcomes[1].revalidated, Some(true));
+        assert!(outcomes.iter().all(ProbeOutcome::is_success));
+    }
+
+    #[tokio::test]
+    async fn it_fails_when_conditional_requests_are_ignored() {
+        // A server always answering with the full response
+        let addr = common::serve(FULL).await;
+        let request = Request::new("GET", &format!("http://{addr}/")).set_cache_validation(true);
+
+        let outcomes = common::probe(request, 2).await;
+        // The second probe should have been answered with a 304
+        assert!(outcomes[0].is_success());
+        assert!(!outcomes[1].is_success());
+        assert!(outcomes[1].error.as_ref().unwrap().contains("304"));
+    }
+
+    #[tokio::test]
+    async fn it_fails_on_stale_responses() {
+        // A server dating its responses, as if they were cached for the given time
+        let dated = |age: u64| async move {
+            let addr = common::serve_with(move |_| {
+                let date = httpdate::fmt_http_date(SystemTime::now() - Duration::from_secs(age));
+                Bytes::from(format!("HTTP/1.1 200 OK\r\ndate: {date}\r\ncontent-length: 2\r\n\r\nok"))
+            })
+            .await;
+            Request::new("GET", &format!("http://{addr}/")).set_max_staleness(Duration::from_secs(60))
+        };
+
+        let fresh = common::probe(dated(0).await, 1).await;
+        assert!(fresh[0].is_success());
+        assert!(fresh[0].staleness.unwrap() < Duration::from_secs(60));
+
+        let stale = common::probe(dated(3600).await, 1).await;
+        assert!(stale[0].staleness.unwrap() >= Duration::from_secs(3600));
+        assert!(stale[0].error.as_ref().unwrap().starts_with("stale response"));
+    }
+}
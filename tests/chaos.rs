@@ -0,0 +1,101 @@
+#[cfg(test)]
+mod chaos_tests {
+    use isup::{
+        chaos::{Chaos, Fault},
+        store::Memory,
+        strategy::WeightedLog,
+        Client, Request, Service,
+    };
+    use std::sync::{Arc, Mutex};
+    use std::time::Duration;
+
+    const URL: &str = "http://simulated.example/";
+
+    #[tokio::test]
+    async fn it_cycles_scripted_latencies() {
+        // Script two latencies that are alternated on each probe
+        let latencies = vec![Duration::from_millis(10), Duration::from_millis(20)];
+        let chaos = Chaos::new(42).insert(Fault::new(URL).set_latencies(latencies.clone()));
+
+        // Collect the elapsed time of every outcome
+        let elapsed = Arc::new(Mutex::new(vec![]));
+        let observed = elapsed.clone();
+        let mut service = Service::default()
+            .use_chaos(chaos)
+            .on_result(move |o| observed.lock().unwrap().push((o.elapsed, o.status)));
+        service.insert_request(Request::new("GET", URL)).unwrap();
+
+        for _ in 0..3 {
+            service.update().await.unwrap();
+        }
+
+        // Verify that the latencies were cycled and every probe succeeded
+        let expected = vec![(latencies[0], 200), (latencies[1], 200), (latencies[0], 200)];
+        assert_eq!(*elapsed.lock().unwrap(), expected);
+    }
+
+    #[tokio::test]
+    async fn it_injects_failures() {
+        // Every probe of the endpoint fails with a `503` status
+        let chaos = Chaos::new(42).insert(Fault::new(URL).set_failure_rate(1.0).set_failure_status(503));
+
+        let statuses = Arc::new(Mutex::new(vec![]));
+        let observed = statuses.clone();
+        let mut service =
+            Service::default().use_chaos(chaos).on_result(move |o| observed.lock().unwrap().push(o.status));
+        service.insert_request(Request::new("GET", URL)).unwrap();
+
+        service.update().await.unwrap();
+        service.update().await.unwrap();
+
+        assert_eq!(*statuses.lock().unwrap(), vec![503, 503]);
+    }
+
+    #[tokio::test]
+    async fn it_times_out_slow_latencies() {
+        // The scripted latency exceeds the request timeout of the client
+        let chaos = Chaos::new(42).insert(Fault::new(URL).set_latencies(vec![Duration::from_secs(60)]));
+        let client = Client::default().set_timeout(Some(Duration::from_millis(10)));
+
+        let statuses = Arc::new(Mutex::new(vec![]));
+        let observed = statuses.clone();
+        let mut service = Service::new(WeightedLog::default(), Memory::new(), client, vec![])
+            .use_chaos(chaos)
+            .on_result(move |o| observed.lock().unwrap().push((o.elapsed, o.status)));
+        service.insert_request(Request::new("GET", URL)).unwrap();
+
+        service.update().await.unwrap();
+
+        // Verify that the probe was reported as a timeout
+        assert_eq!(*statuses.lock().unwrap(), vec![(Duration::from_millis(10), 0)]);
+    }
+
+    #[tokio::test]
+    async fn it_probes_down_and_priority_endpoints_first() {
+        const DOWN: &str = "http://down.example/";
+        const PRIORITY: &str = "http://priority.example/";
+        let chaos = Chaos::new(42)
+            .insert(Fault::new(URL))
+            .insert(Fault::new(PRIORITY))
+            .insert(Fault::new(DOWN).set_failure_rate(1.0).set_failure_status(503));
+
+        let probed = Arc::new(Mutex::new(vec![]));
+        let observed = probed.clone();
+        let mut service = Service::default()
+            .use_chaos(chaos)
+            .use_concurrency(1)
+            .on_result(move |o| observed.lock().unwrap().push(o.url.clone()));
+        service.insert_request(Request::new("GET", URL)).unwrap();
+        service.insert_request(Request::new("GET", DOWN)).unwrap();
+        service.insert_request(Request::new("GET", PRIORITY).set_priority(10)).unwrap();
+
+        // The endpoints of higher priority are probed first, then in their order of insertion
+        service.update().await.unwrap();
+        assert_eq!(*probed.lock().unwrap(), [PRIORITY, URL, DOWN]);
+
+        // Once down, an endpoint is probed before any other
+        probed.lock().unwrap().clear();
+        service.update().await.unwrap();
+        assert_eq!(*probed.lock().unwrap(), [DOWN, PRIORITY, URL]);
+    }
+}
@@ -0,0 +1,252 @@
+mod common;
+
+#[cfg(test)]
+mod client_tests {
+    use super::common;
+    use bytes::Bytes;
+    use http_body_util::Full;
+    use isup::{capture, AddressFamily, Client, PoolStats, ProbeOutcome, Request, Service, Tls};
+    use std::sync::{Arc, Mutex};
+    use std::time::{Duration, Instant};
+
+    #[tokio::test]
+    async fn it_tracks_connection_reuse() {
+        let addr = common::serve(common::OK).await;
+        let url = format!("http://{addr}/");
+        let client = Client::default();
+
+        // The first request establishes a new connection
+        let request = hyper::Request::get(&url).body(Full::new(Bytes::new())).unwrap();
+        let response = client.request(request).await.unwrap();
+        assert_eq!(Client::is_reused(&response), Some(false));
+        // Drop the response, returning the connection to the pool
+        drop(response);
+
+        // The second request is sent over the pooled connection
+        let request = hyper::Request::get(&url).body(Full::new(Bytes::new())).unwrap();
+        let response = client.request(request).await.unwrap();
+        assert_eq!(Client::is_reused(&response), Some(true));
+
+        // Verify that the statistics were recorded for the host
+        assert_eq!(client.pool_stats(), vec![(addr.to_string(), PoolStats { new: 1, reused: 1 })]);
+    }
+
+    #[tokio::test]
+    async fn it_bypasses_the_pool_for_fresh_connections() {
+        let addr = common::serve(common::OK).await;
+        let client = Client::default();
+        let request = Request::new("GET", &format!("http://{addr}/")).set_fresh_connection(true);
+
+        for _ in 0..2 {
+            // Every request establishes a new connection
+            let response = client.request(request.clone().into()).await.unwrap();
+            assert_eq!(Client::is_reused(&response), Some(false));
+        }
+
+        assert_eq!(client.pool_stats(), vec![(addr.to_string(), PoolStats { new: 2, reused: 0 })]);
+    }
+
+    #[tokio::test]
+    async fn it_records_the_address_family() {
+        // The server only listens on the IPv4 loopback
+        let addr = common::serve(common::OK).await;
+        let client = Client::default();
+        let request = Request::new("GET", &format!("http://localhost:{}/", addr.port()));
+
+        // Connecting over IPv4 reaches the server
+        let response = client.request(request.clone().set_address_family(AddressFamily::V4Only).into()).await.unwrap();
+        assert!(Client::remote_addr(&response).unwrap().is_ipv4());
+
+        // Connecting over IPv6 fails, either to resolve or to connect
+        assert!(client.request(request.set_address_family(AddressFamily::V6Only).into()).await.is_err());
+    }
+
+    #[tokio::test]
+    async fn it_binds_to_the_local_address() {
+        let addr = common::serve(common::OK).await;
+        let client = Client::default();
+        // The whole 127.0.0.0/8 range is routed through the loopback interface
+        let local = "127.0.0.2".parse().unwrap();
+        let request = Request::new("GET", &format!("http://{addr}/")).set_local_address(local);
+
+        let response = client.request(request.into()).await.unwrap();
+        assert_eq!(Client::local_addr(&response).unwrap().ip(), local);
+    }
+
+    #[tokio::test]
+    async fn it_overrides_the_timeout_per_request() {
+        // The server accepts connections, but never answers
+        let listener = tokio::net::TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let addr = listener.local_addr().unwrap();
+        tokio::spawn(async move {
+            let mut streams = vec![];
+            while let Ok((stream, _)) = listener.accept().await {
+                streams.push(stream);
+            }
+        });
+
+        let client = Client::new(Some(Duration::from_secs(10)), None);
+        let request = Request::new("GET", &format!("http://{addr}/")).set_timeout(Duration::from_millis(50));
+        let start = Instant::now();
+        assert!(client.request(request.into()).await.is_err());
+        assert!(start.elapsed() < Duration::from_secs(1));
+    }
+
+    #[tokio::test]
+    async fn it_aborts_the_probes_at_the_cycle_deadline() {
+        // The server accepts connections, but never answers
+        let listener = tokio::net::TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let hanging = format!("http://{}/", listener.local_addr().unwrap());
+        tokio::spawn(async move {
+            let mut streams = vec![];
+            while let Ok((stream, _)) = listener.accept().await {
+                streams.push(stream);
+            }
+        });
+        let responsive = format!("http://{}/", common::serve(common::OK).await);
+
+        let outcomes = Arc::new(Mutex::new(Vec::<ProbeOutcome>::new()));
+        let recorded = outcomes.clone();
+        let mut service = Service::default()
+            .use_cycle_deadline(Duration::from_millis(100))
+            .on_result(move |outcome| recorded.lock().unwrap().push(outcome.clone()));
+        service.insert_request(Request::new("GET", hanging.as_str())).unwrap();
+        service.insert_request(Request::new("GET", responsive.as_str())).unwrap();
+
+        let start = Instant::now();
+        service.update().await.unwrap();
+        assert!(start.elapsed() < Duration::from_secs(1));
+
+        // The straggler is scored as a timeout, while the other endpoint is scored as usual
+        let outcomes = outcomes.lock().unwrap();
+        let aborted = outcomes.iter().find(|o| o.url == hanging).unwrap();
+        assert_eq!(aborted.status, 0);
+        assert!(aborted.error.as_deref().unwrap().contains("deadline"));
+        assert!(outcomes.iter().find(|o| o.url == responsive).unwrap().is_success());
+    }
+
+    #[tokio::test]
+    async fn it_ignores_cycle_deadlines_overflowing_the_clock() {
+        let url = format!("http://{}/", common::serve(common::OK).await);
+        let mut service = Service::default().use_cycle_deadline(Duration::MAX);
+        service.insert_request(Request::new("GET", url.as_str())).unwrap();
+
+        // The probes run as if there was no deadline, rather than panicking
+        service.update().await.unwrap();
+        assert!(service.score(&url).await.unwrap().unwrap().score > 0.0);
+    }
+
+    #[tokio::test]
+    async fn it_follows_redirects() {
+        let addr = common::serve_with(|head| match head.starts_with("GET /old ") {
+            true => Bytes::from("HTTP/1.1 301 Moved Permanently\r\nlocation: /new\r\ncontent-length: 0\r\n\r\n"),
+            false => Bytes::from(common::OK),
+        })
+        .await;
+        let client = Client::default();
+        let request = Request::new("GET", &format!("http://{addr}/old"));
+
+        // Redirects aren't followed by default, so they can be scored as such
+        let response = client.request(request.clone().into()).await.unwrap();
+        assert_eq!(response.status(), 301);
+
+        let response = client.request(request.set_follow_redirects(1).into()).await.unwrap();
+        assert_eq!(response.status(), 200);
+    }
+
+    #[tokio::test]
+    async fn it_overrides_the_host_of_the_url() {
+        let (addr, recorded) = common::record().await;
+        let client = Client::default();
+
+        // The probe connects to the address of the URL, while presenting the overriding host
+        let request = Request::new("GET", &format!("http://{addr}/health")).set_host("api.example.invalid");
+        let response = client.request(request.into()).await.unwrap();
+        assert_eq!(Client::remote_addr(&response), Some(addr));
+        let (head, _) = recorded.lock().unwrap()[0].clone();
+        assert!(head.contains(&format!("host: api.example.invalid:{}", addr.port())), "{head}");
+
+        // Hosts carrying credentials or a port are rejected
+        let mut service = Service::default();
+        for host in ["user@api.example.com", "api.example.com:443", ""] {
+            assert!(service.insert_request(Request::new("GET", "http://127.0.0.1/").set_host(host)).is_err());
+        }
+    }
+
+    #[tokio::test]
+    async fn it_verifies_the_pinned_certificates() {
+        let addr = common::serve_tls(common::OK).await;
+        let client = Client::default();
+        let tls = Tls { accept_invalid_certs: true, ..Default::default() };
+        let request = Request::new("GET", &format!("https://{addr}/")).set_tls(tls);
+
+        // A certificate matching one of the pins is accepted
+        let pins = vec!["sha256/AAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAA=".into(), common::PIN.into()];
+        let response = client.request(request.clone().set_pins(pins).into()).await.unwrap();
+        assert!(response.status().is_success());
+
+        // Another certificate fails the connection, before any request is sent
+        let pins = vec!["sha256/AAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAA=".into()];
+        let error = client.request(request.set_pins(pins.clone()).into()).await.unwrap_err().to_string();
+        assert_eq!(error, format!("certificate pin mismatch: the server presented {}", common::PIN));
+
+        // Plain-text endpoints have no certificate to match, and their mismatches are counted apart from other failures
+        let addr = common::serve(common::OK).await;
+        let url = format!("http://{addr}/");
+        let mut service = Service::default();
+        service.insert_request(Request::new("GET", &url).set_pins(pins)).unwrap();
+        service.update().await.unwrap();
+        let statuses = service.statuses(&url).unwrap();
+        assert_eq!((statuses.pin_mismatch, statuses.no_response), (1, 0));
+    }
+
+    #[tokio::test]
+    async fn it_sends_requests_through_a_proxy() {
+        let (addr, recorded) = common::record().await;
+        let client = Client::default();
+        let request = Request::new("GET", "http://isup.invalid/status").set_proxy(format!("http://user:pass@{addr}"));
+
+        // The request is forwarded by the proxy, in absolute form and along with its credentials
+        let response = client.request(request.into()).await.unwrap();
+        assert_eq!(response.status(), 200);
+        let head = recorded.lock().unwrap()[0].0.clone();
+        assert!(head.starts_with("get http://isup.invalid/status http/1.1"));
+        assert!(head.contains("proxy-authorization: basic dxnlcjpwyxnz"));
+    }
+
+    #[tokio::test]
+    async fn it_captures_the_failed_responses() {
+        let addr = common::serve(
+            "HTTP/1.1 500 Internal Server Error\r\ncontent-type: application/json\r\nset-cookie: session=secret\r\n\
+             content-length: 32\r\n\r\n{\"error\":\"database unreachable\"}",
+        )
+        .await;
+        let url = format!("http://{addr}/");
+        let mut service = Service::default().use_capture(capture::Config::default().set_max_body(20));
+        service.insert_request(Request::new("GET", url.as_str())).unwrap();
+        service.update().await.unwrap();
+
+        // The error payload is kept, truncated, along with the headers whose values aren't sensitive
+        let failure = service.last_failure(&url).expect("missing failure");
+        assert_eq!(failure.status, 500);
+        assert_eq!(failure.body.as_deref(), Some("{\"error\":\"database u"));
+        assert!(failure.truncated);
+        assert_eq!(failure.headers["content-type"], "application/json");
+        assert_eq!(failure.headers["set-cookie"], "<redacted>");
+
+        // Failures without a response are kept with their error
+        let url = "http://127.0.0.1:1/";
+        service.insert_request(Request::new("GET", url)).unwrap();
+        service.update().await.unwrap();
+        let failure = service.last_failure(url).expect("missing failure");
+        assert_eq!((failure.status, failure.body), (0, None));
+        assert!(failure.error.is_some());
+
+        // Successful probes aren't captured
+        let addr = common::serve(common::OK).await;
+        let url = format!("http://{addr}/");
+        service.insert_request(Request::new("GET", url.as_str())).unwrap();
+        service.update().await.unwrap();
+        assert!(service.last_failure(&url).is_none());
+    }
+}
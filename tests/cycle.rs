@@ -0,0 +1,84 @@
+#[cfg(test)]
+mod cycle_tests {
+    use isup::{
+        chaos::{Chaos, Fault},
+        Direction, Request, Service, SkipReason, Skipped,
+    };
+    use std::time::Duration;
+
+    const UP: &str = "http://up.example/";
+    const PAUSED: &str = "http://paused.example/";
+
+    #[tokio::test]
+    async fn it_reports_on_the_update_cycles() {
+        let chaos = Chaos::new(42)
+            .insert(Fault::new(UP).set_latencies(vec![Duration::from_millis(10)]))
+            .insert(Fault::new(PAUSED));
+        let mut service = Service::default().use_chaos(chaos);
+        service.insert_request(Request::new("GET", UP)).unwrap();
+        service.insert_request(Request::new("GET", PAUSED)).unwrap();
+        service.pause(PAUSED).await.unwrap();
+        assert!(service.last_cycle().is_none());
+
+        // The probed endpoints are reported along with their score, and the other ones along with the reason
+        let report = service.update_report().await.unwrap().unwrap();
+        assert_eq!(report.outcomes.len(), 1);
+        assert_eq!(report.outcomes[0].outcome.url, UP);
+        assert_eq!(report.outcomes[0].outcome.elapsed, Duration::from_millis(10));
+        assert_eq!(report.skipped, vec![Skipped { url: PAUSED.into(), reason: SkipReason::Paused }]);
+        assert!(report.store_errors.is_empty());
+
+        // The report is kept until the next cycle, and can be published as JSON
+        let value = serde_json::to_value(service.last_cycle().unwrap()).unwrap();
+        assert_eq!(value["skipped"][0]["reason"], "paused");
+        assert_eq!(value["outcomes"][0]["outcome"]["url"], UP);
+        assert!(value["started_at"].is_object() && value["duration"].is_object());
+    }
+
+    #[tokio::test]
+    async fn it_reports_the_misconfigured_endpoints_of_a_dry_run() {
+        const UNAUTHORIZED: &str = "http://unauthorized.example/";
+        let chaos = Chaos::new(42)
+            .insert(Fault::new(UP))
+            .insert(Fault::new(UNAUTHORIZED).set_failure_rate(1.0).set_failure_status(401));
+        let mut service = Service::default().use_chaos(chaos);
+        service.insert_request(Request::new("GET", UP)).unwrap();
+        service.insert_request(Request::new("GET", UNAUTHORIZED)).unwrap();
+
+        // Every endpoint is probed once, and the failing ones are reported
+        let report = service.dry_run().await;
+        assert_eq!(report.outcomes.len(), 2);
+        assert!(!report.is_valid());
+        assert_eq!(report.misconfigured.len(), 1);
+        assert_eq!(report.misconfigured[0].url, UNAUTHORIZED);
+        assert!(report.misconfigured[0].reason.contains("401"));
+
+        // Nothing is scored nor reported as an update cycle
+        assert!(service.best_url().await.unwrap().is_none());
+        assert!(service.last_cycle().is_none());
+    }
+
+    #[tokio::test]
+    async fn it_exposes_the_trend_of_the_scores() {
+        let latencies = [160, 120, 80, 40, 2].map(Duration::from_millis).to_vec();
+        let chaos = Chaos::new(42).insert(Fault::new(UP).set_latencies(latencies));
+        let mut service = Service::default().use_chaos(chaos);
+        service.insert_request(Request::new("GET", UP)).unwrap();
+
+        // Until the endpoint was scored a few times, there's no trend
+        service.update().await.unwrap();
+        assert_eq!(service.ranking().await.unwrap()[0].trend, None);
+
+        // The score rises as the endpoint speeds up
+        for _ in 0..4 {
+            service.update().await.unwrap();
+        }
+        let trend = service.ranking().await.unwrap()[0].trend.unwrap();
+        assert_eq!(trend.direction, Direction::Improving);
+        assert!(trend.slope > 0.0);
+
+        let text = service.metrics().render();
+        assert!(text.contains(&format!("isup_score_trend{{url=\"{UP}\"}} 1")));
+        assert!(text.contains(&format!("isup_score_trend_slope{{url=\"{UP}\"}} {}", trend.slope)));
+    }
+}
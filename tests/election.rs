@@ -0,0 +1,47 @@
+mod common;
+
+#[cfg(test)]
+mod election_tests {
+    use super::common;
+    use isup::election::{self, Lease, Memory};
+    use isup::{Request, Service};
+    use std::sync::Arc;
+    use std::time::Duration;
+
+    /// Creates a replica monitoring the endpoint, sharing the lease with the others.
+    fn replica(id: &str, lease: Arc<Memory>, url: &str) -> Service {
+        let config = election::Config::default().set_id(id).set_ttl(Duration::from_millis(100));
+        let mut service = Service::default().use_election(lease, config);
+        service.insert_request(Request::new("GET", url)).unwrap();
+        service
+    }
+
+    #[tokio::test]
+    async fn it_probes_from_the_leader_only() {
+        let url = format!("http://{}/", common::serve(common::OK).await);
+        let lease = Arc::new(Memory::new());
+        let (a, b) = (replica("a", lease.clone(), &url), replica("b", lease, &url));
+
+        a.update().await.unwrap();
+        b.update().await.unwrap();
+        assert!(a.is_leader() && !b.is_leader());
+        // Verify that the follower didn't probe the endpoint
+        assert!(a.store.get(&url).await.unwrap().is_some());
+        assert!(b.store.get(&url).await.unwrap().is_none());
+
+        // The follower takes over once the leadership expires
+        tokio::time::sleep(Duration::from_millis(150)).await;
+        b.update().await.unwrap();
+        a.update().await.unwrap();
+        assert!(b.is_leader() && !a.is_leader());
+        assert!(b.store.get(&url).await.unwrap().is_some());
+    }
+
+    #[tokio::test]
+    async fn it_rejects_leases_overflowing_the_clock() {
+        let lease = Memory::new();
+        assert!(lease.acquire("a", Duration::MAX).await.is_err());
+        // The lease is left to the other replicas, rather than held forever
+        assert!(lease.acquire("b", Duration::from_secs(30)).await.unwrap());
+    }
+}
@@ -0,0 +1,58 @@
+mod common;
+
+#[cfg(test)]
+mod encoding_tests {
+    use super::common;
+    use isup::{Encoding, Request};
+    use std::io::Write;
+
+    /// Builds a raw response with a gzip-compressed body.
+    fn gzip_response(body: &str) -> Vec<u8> {
+        let mut encoder = flate2::write::GzEncoder::new(Vec::new(), flate2::Compression::default());
+        encoder.write_all(body.as_bytes()).unwrap();
+        let body = encoder.finish().unwrap();
+
+        let head = format!("HTTP/1.1 200 OK\r\ncontent-encoding: gzip\r\ncontent-length: {}\r\n\r\n", body.len());
+        [head.into_bytes(), body].concat()
+    }
+
+    #[tokio::test]
+    async fn it_decodes_compressed_bodies() {
+        let body = "isup ".repeat(100);
+        let addr = common::serve(gzip_response(&body)).await;
+        let request = Request::new("GET", &format!("http://{addr}/")).set_accept_encoding(vec![Encoding::Gzip]);
+
+        let outcome = common::probe(request, 1).await.remove(0);
+        // Verify that both the compressed and decoded sizes were recorded
+        assert!(outcome.is_success());
+        assert!(outcome.body_size.unwrap() < body.len());
+        assert_eq!(outcome.decoded_size, Some(body.len()));
+        assert!(outcome.decode_time.is_some());
+    }
+
+    #[tokio::test]
+    async fn it_checks_decoded_bodies() {
+        let addr = common::serve(gzip_response("status: healthy")).await;
+        let request = Request::new("GET", &format!("http://{addr}/")).set_accept_encoding(vec![Encoding::Gzip]);
+
+        // The expected text is found in the decoded body
+        let outcome = common::probe(request.clone().set_expect_body("healthy"), 1).await.remove(0);
+        assert!(outcome.is_success());
+
+        // A missing text fails the probe, although the status code is successful
+        let outcome = common::probe(request.set_expect_body("degraded"), 1).await.remove(0);
+        assert_eq!(outcome.status, 200);
+        assert!(!outcome.is_success());
+        assert!(outcome.error.unwrap().contains("degraded"));
+    }
+
+    #[tokio::test]
+    async fn it_skips_the_body_by_default() {
+        let addr = common::serve(common::OK).await;
+
+        let outcome = common::probe(Request::new("GET", &format!("http://{addr}/")), 1).await.remove(0);
+        // Verify that the body wasn't read without encodings or expectations
+        assert_eq!(outcome.body_size, None);
+        assert_eq!(outcome.decoded_size, None);
+    }
+}
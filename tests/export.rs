@@ -0,0 +1,77 @@
+mod common;
+
+#[cfg(test)]
+mod export_tests {
+    use super::common;
+    use isup::chaos::{Chaos, Fault};
+    use isup::export::{Exporter, File, Influx, Record, Transition};
+    use isup::incident::State;
+    use isup::{Request, Service};
+    use std::error::Error;
+    use std::sync::{Arc, Mutex};
+    use std::time::Duration;
+
+    #[tokio::test]
+    async fn it_exports_the_scored_probes_to_every_sink() {
+        const URL: &str = "http://api.example/";
+        let path = std::env::temp_dir().join(format!("isup-export-{}.jsonl", std::process::id()));
+        let (influx, received) = common::record().await;
+
+        let chaos = Chaos::new(42).insert(Fault::new(URL).set_latencies(vec![Duration::from_millis(20)]));
+        let mut service = Service::default()
+            .use_chaos(chaos)
+            .use_exporter(File::new(&path))
+            .use_exporter(Influx::new(format!("http://{influx}/api/v2/write?bucket=isup")));
+        service.insert_request(Request::new("GET", URL)).unwrap();
+        service.update().await.unwrap();
+        service.update().await.unwrap();
+
+        // The records of every cycle are appended to the file, one per line
+        let content = std::fs::read_to_string(&path).unwrap();
+        std::fs::remove_file(&path).unwrap();
+        let records: Vec<Record> = content.lines().map(|line| serde_json::from_str(line).unwrap()).collect();
+        assert_eq!(records.len(), 2);
+        assert_eq!(records[1].outcome.url, URL);
+        assert_eq!(records[1].score, service.score(URL).await.unwrap().unwrap());
+
+        // And written to InfluxDB in its line protocol
+        let (head, body) = received.lock().unwrap()[0].clone();
+        assert!(head.starts_with("post /api/v2/write?bucket=isup http/1.1"), "{head}");
+        let line = String::from_utf8(body.to_vec()).unwrap();
+        assert!(line.starts_with("isup,url=http://api.example/ latency_ms=20,status=200i,score="), "{line}");
+    }
+
+    /// An exporter recording the transitions it's given.
+    #[derive(Default)]
+    struct Transitions(Mutex<Vec<Transition>>);
+
+    #[async_trait::async_trait]
+    impl Exporter for Transitions {
+        async fn export(&self, _records: &[Record]) -> Result<(), Box<dyn Error + Send + Sync>> {
+            Ok(())
+        }
+        async fn transition(&self, transition: &Transition) -> Result<(), Box<dyn Error + Send + Sync>> {
+            self.0.lock().unwrap().push(transition.clone());
+            Ok(())
+        }
+    }
+
+    #[tokio::test]
+    async fn it_exports_the_state_transitions() {
+        const URL: &str = "http://down.example/";
+        let chaos = Chaos::new(42).insert(Fault::new(URL).set_failure_rate(1.0).set_failure_status(503));
+        let transitions = Arc::new(Transitions::default());
+        let mut service = Service::default().use_chaos(chaos).use_exporter(transitions.clone());
+        service.insert_request(Request::new("GET", URL)).unwrap();
+        service.update().await.unwrap();
+        service.update().await.unwrap();
+
+        // Only the changes of state are exported
+        let transitions = transitions.0.lock().unwrap().clone();
+        assert_eq!(transitions.len(), 1);
+        assert_eq!(
+            (transitions[0].url.as_str(), transitions[0].previous, transitions[0].state),
+            (URL, None, State::Down)
+        );
+    }
+}
@@ -0,0 +1,35 @@
+#[cfg(test)]
+mod grace_tests {
+    use isup::{
+        chaos::{Chaos, Fault},
+        Request, Service,
+    };
+    use std::time::Duration;
+
+    const URL: &str = "http://simulated.example/";
+    const NEW_URL: &str = "http://new.example/";
+
+    #[tokio::test]
+    async fn it_excludes_new_endpoints_during_their_grace_period() {
+        // The new endpoint responds faster, so it would be the best one right away
+        let chaos = Chaos::new(42)
+            .insert(Fault::new(URL).set_latencies(vec![Duration::from_millis(100)]))
+            .insert(Fault::new(NEW_URL).set_latencies(vec![Duration::from_millis(1)]));
+        let mut service = Service::default().use_chaos(chaos);
+        service.insert_request(Request::new("GET", URL)).unwrap();
+
+        // Insert the new endpoint at runtime, once the grace period is configured
+        let mut service = service.use_grace_period(Duration::from_millis(200));
+        service.insert_request(Request::new("GET", NEW_URL)).unwrap();
+        service.update().await.unwrap();
+
+        // It's scored, but not routed to until its grace period is over
+        assert!(service.is_warming_up(NEW_URL));
+        assert!(service.store.get(NEW_URL).await.unwrap().is_some());
+        assert_eq!(service.best_url().await.unwrap().as_deref(), Some(URL));
+
+        tokio::time::sleep(Duration::from_millis(250)).await;
+        assert!(!service.is_warming_up(NEW_URL));
+        assert_eq!(service.best_url().await.unwrap().as_deref(), Some(NEW_URL));
+    }
+}
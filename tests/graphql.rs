@@ -0,0 +1,41 @@
+mod common;
+
+#[cfg(test)]
+mod graphql_tests {
+    use super::common;
+    use bytes::Bytes;
+    use isup::{GraphQl, Request};
+
+    /// Builds a raw `200` response with the given JSON body.
+    fn response(body: &str) -> Bytes {
+        format!("HTTP/1.1 200 OK\r\ncontent-type: application/json\r\ncontent-length: {}\r\n\r\n{body}", body.len())
+            .into()
+    }
+
+    #[tokio::test]
+    async fn it_fails_on_graphql_errors() {
+        // A server answering with an error, unless the query is posted as JSON
+        let addr = common::serve_with(|head| match head.starts_with("POST") && head.contains("application/json") {
+            true => response(r#"{"data":null,"errors":[{"message":"database unavailable"}]}"#),
+            false => response(r#"{"data":{"__typename":"Query"}}"#),
+        })
+        .await;
+        let request =
+            Request::new("GET", &format!("http://{addr}/graphql")).set_graphql(GraphQl::new("{ __typename }"));
+
+        let outcome = common::probe(request, 1).await.remove(0);
+        // Verify that the error failed the probe, despite the `200` status
+        assert_eq!(outcome.status, 200);
+        assert!(!outcome.is_success());
+        assert!(outcome.error.unwrap().contains("database unavailable"));
+    }
+
+    #[tokio::test]
+    async fn it_accepts_graphql_data() {
+        let addr = common::serve(response(r#"{"data":{"__typename":"Query"}}"#)).await;
+        let request =
+            Request::new("GET", &format!("http://{addr}/graphql")).set_graphql(GraphQl::new("{ __typename }"));
+
+        assert!(common::probe(request, 1).await.remove(0).is_success());
+    }
+}
@@ -0,0 +1,53 @@
+mod common;
+
+#[cfg(test)]
+mod guard_tests {
+    use super::common;
+    use isup::{
+        guard::{Config, Guard},
+        Client, Request, Service,
+    };
+
+    #[test]
+    fn it_rejects_disallowed_urls() {
+        // Default rules: public http(s) endpoints only
+        let mut service = Service::default().use_guard(Guard::default());
+
+        // Verify that non-public addresses and other schemes are rejected
+        assert!(service.insert_request(Request::new("GET", "http://127.0.0.1/")).is_err());
+        assert!(service.insert_request(Request::new("GET", "http://[::1]/")).is_err());
+        assert!(service.insert_request(Request::new("GET", "http://169.254.169.254/latest/meta-data")).is_err());
+        assert!(service.insert_request(Request::new("GET", "ftp://example.com/")).is_err());
+        // Verify that public endpoints are accepted
+        assert!(service.insert_request(Request::new("GET", "https://example.com/")).is_ok());
+        assert_eq!(service.urls(), vec!["https://example.com/"]);
+    }
+
+    #[test]
+    fn it_applies_host_and_network_rules() {
+        let config = Config {
+            allow_hosts: vec!["*.example.com".into()],
+            deny_hosts: vec!["internal.example.com".into()],
+            deny_networks: vec!["203.0.113.0/24".parse().unwrap()],
+            ..Default::default()
+        };
+        let guard = Guard::new(config);
+
+        assert!(guard.check_url(&"https://api.example.com/".parse().unwrap()).is_ok());
+        assert!(guard.check_url(&"https://internal.example.com/".parse().unwrap()).is_err());
+        assert!(guard.check_url(&"https://example.org/".parse().unwrap()).is_err());
+        assert!(guard.check_ip("203.0.113.7".parse().unwrap()).is_err());
+        assert!(guard.check_ip("198.51.100.7".parse().unwrap()).is_err());
+    }
+
+    #[tokio::test]
+    async fn it_blocks_hostnames_resolving_to_denied_addresses() {
+        let addr = common::serve(common::OK).await;
+        // `localhost` passes the hostname rules, but resolves to a loopback address
+        let client = Client::default().set_guard(Guard::default());
+        let request = Request::new("GET", &format!("http://localhost:{}/", addr.port()));
+
+        let error = client.request(request.into()).await.unwrap_err();
+        assert!(format!("{error:?}").contains("blocked by guard"));
+    }
+}
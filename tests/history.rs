@@ -0,0 +1,139 @@
+mod common;
+
+#[cfg(test)]
+mod history_tests {
+    use super::common;
+    use isup::{
+        history::{Baseline, History, Memory, RemoteWrite, Retention, Sample},
+        strategy::{Strategy, WeightedLog},
+        ProbeOutcome, Score,
+    };
+    use std::time::{Duration, SystemTime, UNIX_EPOCH};
+
+    const URL: &str = "http://simulated.example/";
+    const WEEK: Duration = Duration::from_secs(7 * 24 * 3600);
+
+    /// A sample of the given latency and status, recorded at the given time.
+    fn sample(at: SystemTime, elapsed: Duration, status: u16) -> Sample {
+        Sample { at, elapsed, status, score: 0.0, reliability: 0.0 }
+    }
+
+    #[tokio::test]
+    async fn it_computes_hourly_baselines() {
+        let now = SystemTime::now();
+        let history = Memory::default();
+        // The endpoint is slower at this hour of the week, as observed over the previous weeks
+        for (weeks, elapsed) in [(3, 400), (2, 500), (1, 600)] {
+            history.record(URL, sample(now - WEEK * weeks, Duration::from_millis(elapsed), 200)).await.unwrap();
+            let earlier = now - WEEK * weeks - Duration::from_secs(3 * 3600);
+            history.record(URL, sample(earlier, Duration::from_millis(elapsed / 10), 200)).await.unwrap();
+        }
+        // Failed probes don't count toward the baseline
+        history.record(URL, sample(now - WEEK, Duration::from_secs(10), 0)).await.unwrap();
+
+        let baseline = history.baseline(URL, WEEK * 4).await.unwrap();
+        assert_eq!(baseline.at(now), Some(Duration::from_millis(500)));
+        assert_eq!(baseline.at(now - Duration::from_secs(3 * 3600)), Some(Duration::from_millis(50)));
+        assert_eq!(baseline.at(now - Duration::from_secs(3600)), None);
+
+        // Samples out of the window are left out
+        assert_eq!(history.baseline(URL, WEEK / 2).await.unwrap(), Baseline::new(&[]));
+    }
+
+    #[test]
+    fn it_scores_deviations_from_the_baseline() {
+        let strategy = WeightedLog::default().set_relative_to_baseline(true);
+        let score = Score::new(0.0, 0.5, Duration::from_millis(500));
+
+        // A usual nightly slowdown scores as well as a fast response
+        let mut slow = ProbeOutcome::new(URL, Duration::from_millis(800), 200);
+        slow.baseline = Some(Duration::from_millis(800));
+        let fast = ProbeOutcome::new(URL, Duration::ZERO, 200);
+        let (slow, fast) =
+            (strategy.calculate_outcome(score.clone(), &slow), strategy.calculate_outcome(score.clone(), &fast));
+        assert_eq!(slow.score, fast.score);
+        // The average response time keeps tracking the absolute latency
+        assert_eq!(slow.response_avg, Duration::from_millis(650));
+
+        // Without a baseline, the absolute latency is scored
+        let unknown = strategy.calculate_outcome(score, &ProbeOutcome::new(URL, Duration::from_millis(800), 200));
+        assert!(unknown.score < fast.score);
+    }
+
+    #[tokio::test]
+    async fn it_rolls_up_the_expired_samples() {
+        const HOUR: Duration = Duration::from_secs(3600);
+        let retention = Retention::default().set_raw(HOUR).set_rollups(WEEK);
+        let history = Memory::default().set_retention(retention.clone());
+        let now = SystemTime::now();
+        // The samples of an interval past the raw retention, along with an expired one and a recent one
+        let start = retention.rollup(&[sample(now - 3 * HOUR, Duration::ZERO, 200)])[0].at;
+        let minute = Duration::from_secs(60);
+        for (at, elapsed, status) in [
+            (now - 2 * WEEK, 100, 200),
+            (start + minute, 100, 200),
+            (start + 2 * minute, 300, 200),
+            (start + 3 * minute, 10_000, 0),
+            (now, 50, 200),
+        ] {
+            history.record(URL, sample(at, Duration::from_millis(elapsed), status)).await.unwrap();
+        }
+        history.compact(URL).await.unwrap();
+
+        // The interval is rolled up into a single sample, and the expired one is dropped
+        let samples = history.query(URL, now - 4 * WEEK, now).await.unwrap();
+        assert_eq!(samples.len(), 2);
+        assert_eq!(samples[0].at, start);
+        // Averaging the latency of the successful probes
+        assert_eq!((samples[0].elapsed, samples[0].status), (Duration::from_millis(200), 200));
+        assert_eq!(samples[1].at, now);
+
+        // Compacting again leaves the rollups as they are
+        history.compact(URL).await.unwrap();
+        assert_eq!(history.query(URL, now - 4 * WEEK, now).await.unwrap(), samples);
+    }
+
+    #[tokio::test]
+    async fn it_ships_the_history_through_remote_write() {
+        let (receiver, received) = common::record().await;
+        let prometheus = common::serve_with(|head| {
+            // The series of the endpoint are selected over the queried range
+            assert!(head.contains("query=%7B__name__%3D~%22isup_probe_duration_seconds%7C"));
+            assert!(head.contains("url%3D%22http%3A%2F%2Fsimulated.example%2F%22%7D%5B60000ms%5D&time=1060.000"));
+            let body = r#"{"status":"success","data":{"resultType":"matrix","result":[
+                {"metric":{"__name__":"isup_probe_duration_seconds"},"values":[[1000.5,"0.2"],[1030,"0.3"]]},
+                {"metric":{"__name__":"isup_probe_status"},"values":[[1000.5,"200"],[1030,"503"]]},
+                {"metric":{"__name__":"isup_score"},"values":[[1000.5,"0.9"],[1030,"0.4"]]},
+                {"metric":{"__name__":"isup_reliability"},"values":[[1000.5,"1"],[1030,"0.5"]]}
+            ]}}"#;
+            format!("HTTP/1.1 200 OK\r\ncontent-length: {}\r\n\r\n{body}", body.len()).into()
+        })
+        .await;
+        let history = RemoteWrite::new(format!("http://{receiver}/api/v1/write"))
+            .set_query_url(format!("http://{prometheus}"))
+            .set_label("job", "isup");
+
+        // The samples are written as snappy framed protobuf messages
+        history.record(URL, sample(SystemTime::now(), Duration::from_millis(200), 200)).await.unwrap();
+        let (head, body) = received.lock().unwrap()[0].clone();
+        assert!(head.starts_with("post /api/v1/write"));
+        assert!(head.contains("content-encoding: snappy"));
+        assert!(head.contains("content-type: application/x-protobuf"));
+        assert!(head.contains("x-prometheus-remote-write-version: 0.1.0"));
+        for label in ["isup_probe_duration_seconds", "isup_reliability", "job", URL] {
+            assert!(body.windows(label.len()).any(|w| w == label.as_bytes()));
+        }
+
+        // And read back from the Prometheus API, joined by their timestamp
+        let at = |millis| UNIX_EPOCH + Duration::from_millis(millis);
+        let samples = history.query(URL, at(1_000_000), at(1_060_000)).await.unwrap();
+        assert_eq!(samples.len(), 2);
+        assert_eq!(samples[0].at, at(1_000_500));
+        assert_eq!((samples[0].elapsed, samples[0].status), (Duration::from_millis(200), 200));
+        assert_eq!((samples[1].status, samples[1].score, samples[1].reliability), (503, 0.4, 0.5));
+
+        // Without a query URL, the history can only be written
+        let history = RemoteWrite::new(format!("http://{receiver}/api/v1/write"));
+        assert!(history.query(URL, at(0), at(1)).await.is_err());
+    }
+}
@@ -0,0 +1,215 @@
+mod common;
+
+#[cfg(test)]
+mod incident_tests {
+    use super::common;
+    use bytes::Bytes;
+    use isup::incident::{Annotation, Backoff, Quorum, State};
+    use isup::{Request, Service};
+    use std::net::SocketAddr;
+    use std::sync::atomic::{AtomicBool, AtomicUsize, Ordering::SeqCst};
+    use std::sync::Arc;
+
+    const UNAVAILABLE: &str = "HTTP/1.1 503 Service Unavailable\r\ncontent-length: 0\r\n\r\n";
+
+    /// Starts a server failing the given number of requests, before answering successfully.
+    async fn flaky(failures: Arc<AtomicUsize>) -> SocketAddr {
+        common::serve_with(move |_| match failures.fetch_update(SeqCst, SeqCst, |n| n.checked_sub(1)) {
+            Ok(_) => Bytes::from(UNAVAILABLE),
+            Err(_) => Bytes::from(common::OK),
+        })
+        .await
+    }
+
+    #[tokio::test]
+    async fn it_tracks_incidents() {
+        // A server that can be taken down and brought back up
+        let down = Arc::new(AtomicBool::new(false));
+        let toggle = down.clone();
+        let addr = common::serve_with(move |_| match toggle.load(SeqCst) {
+            true => Bytes::from(UNAVAILABLE),
+            false => Bytes::from(common::OK),
+        })
+        .await;
+        let url = format!("http://{addr}/");
+
+        let mut service = Service::default();
+        service.insert_request(Request::new("GET", url.as_str())).unwrap();
+        assert_eq!(service.state(&url), None);
+
+        service.update().await.unwrap();
+        assert_eq!(service.state(&url), Some(State::Up));
+        assert!(service.incidents().await.unwrap().is_empty());
+
+        // Going down opens an incident
+        down.store(true, SeqCst);
+        service.update().await.unwrap();
+        service.update().await.unwrap();
+        assert_eq!(service.state(&url), Some(State::Down));
+        let incidents = service.incidents().await.unwrap();
+        assert_eq!(incidents.len(), 1);
+        assert_eq!(incidents[0].status, 503);
+        assert!(incidents[0].is_ongoing());
+
+        // Recovering resolves it
+        down.store(false, SeqCst);
+        service.update().await.unwrap();
+        assert_eq!(service.state(&url), Some(State::Up));
+        let incidents = service.incidents().await.unwrap();
+        assert_eq!(incidents.len(), 1);
+        assert!(!incidents[0].is_ongoing());
+        assert!(incidents[0].duration.is_some());
+        // The evidence holds the failed probes, along with the one that resolved the incident
+        assert_eq!(incidents[0].evidence.iter().map(|o| o.status).collect::<Vec<_>>(), [503, 503, 200]);
+    }
+
+    #[tokio::test]
+    async fn it_annotates_incidents() {
+        let failures = Arc::new(AtomicUsize::new(1));
+        let url = format!("http://{}/", flaky(failures.clone()).await);
+
+        let mut service = Service::default();
+        service.insert_request(Request::new("GET", url.as_str())).unwrap();
+
+        // Annotate an ongoing incident
+        service.update().await.unwrap();
+        let id = service.incidents().await.unwrap()[0].id.clone();
+        service.annotate(&id, Annotation::new("ops", "upstream outage")).await.unwrap();
+
+        // The annotation survives the resolution, and resolved incidents can be annotated as well
+        service.update().await.unwrap();
+        let incident = service.annotate(&id, Annotation::new("ops", "resolved by the provider")).await.unwrap();
+        assert!(!incident.is_ongoing());
+        let texts = incident.annotations.iter().map(|a| a.text.as_str()).collect::<Vec<_>>();
+        assert_eq!(texts, ["upstream outage", "resolved by the provider"]);
+        assert_eq!(service.incidents().await.unwrap()[0].annotations.len(), 2);
+
+        // Unknown incidents can't be annotated
+        assert!(service.annotate("unknown", Annotation::new("ops", "note")).await.is_err());
+    }
+
+    #[tokio::test]
+    async fn it_requires_a_quorum_of_failures() {
+        let failures = Arc::new(AtomicUsize::new(0));
+        let url = format!("http://{}/", flaky(failures.clone()).await);
+
+        let mut service = Service::default().use_quorum(Quorum::new(2, 3));
+        service.insert_request(Request::new("GET", url.as_str())).unwrap();
+
+        // A single failure isn't enough
+        failures.store(1, SeqCst);
+        service.update().await.unwrap();
+        service.update().await.unwrap();
+        assert_eq!(service.state(&url), Some(State::Up));
+
+        // The second failure within the window is
+        failures.store(1, SeqCst);
+        service.update().await.unwrap();
+        assert_eq!(service.state(&url), Some(State::Down));
+        assert_eq!(service.incidents().await.unwrap().len(), 1);
+    }
+
+    #[tokio::test]
+    async fn it_confirms_failures_concurrently() {
+        let failures = Arc::new(AtomicUsize::new(1));
+        let url = format!("http://{}/", flaky(failures.clone()).await);
+
+        let mut service = Service::default().use_quorum(Quorum::new(2, 3).set_concurrent(true));
+        service.insert_request(Request::new("GET", url.as_str())).unwrap();
+
+        // The failure isn't confirmed by the additional probes
+        service.update().await.unwrap();
+        assert_eq!(service.state(&url), Some(State::Up));
+        assert_eq!(failures.load(SeqCst), 0);
+
+        // A persistent failure is
+        failures.store(usize::MAX, SeqCst);
+        service.update().await.unwrap();
+        assert_eq!(service.state(&url), Some(State::Down));
+    }
+
+    #[tokio::test]
+    async fn it_backs_off_down_endpoints() {
+        let probes = Arc::new(AtomicUsize::new(0));
+        let down = Arc::new(AtomicBool::new(true));
+        let (counter, toggle) = (probes.clone(), down.clone());
+        let addr = common::serve_with(move |_| {
+            counter.fetch_add(1, SeqCst);
+            match toggle.load(SeqCst) {
+                true => Bytes::from(UNAVAILABLE),
+                false => Bytes::from(common::OK),
+            }
+        })
+        .await;
+        let url = format!("http://{addr}/");
+
+        let mut service = Service::default().use_backoff(Backoff::new(2, 4));
+        service.insert_request(Request::new("GET", url.as_str())).unwrap();
+
+        // Probed on the first 2 cycles, then after 1 skipped cycle, then after 3 of them (capped by `max_cycles`)
+        for _ in 0..8 {
+            service.update().await.unwrap();
+        }
+        assert_eq!(probes.load(SeqCst), 4);
+        assert_eq!(service.state(&url), Some(State::Down));
+
+        // Found up on its next probe, the endpoint is probed on every cycle again
+        down.store(false, SeqCst);
+        for _ in 0..4 {
+            service.update().await.unwrap();
+        }
+        assert_eq!(service.state(&url), Some(State::Up));
+        assert_eq!(probes.load(SeqCst), 5);
+        service.update().await.unwrap();
+        service.update().await.unwrap();
+        assert_eq!(probes.load(SeqCst), 7);
+    }
+
+    #[tokio::test]
+    async fn it_invokes_the_event_hooks() {
+        let down = Arc::new(AtomicBool::new(false));
+        let toggle = down.clone();
+        let addr = common::serve_with(move |_| match toggle.load(SeqCst) {
+            true => Bytes::from(UNAVAILABLE),
+            false => Bytes::from(common::OK),
+        })
+        .await;
+        let url = format!("http://{addr}/");
+
+        let (successes, failures) = (Arc::new(AtomicUsize::new(0)), Arc::new(AtomicUsize::new(0)));
+        let transitions = Arc::new(std::sync::Mutex::new(vec![]));
+        let mut service = Service::default()
+            .on_success({
+                let successes = successes.clone();
+                move |_| {
+                    successes.fetch_add(1, SeqCst);
+                }
+            })
+            .on_failure({
+                let failures = failures.clone();
+                move |outcome| {
+                    assert_eq!(outcome.status, 503);
+                    failures.fetch_add(1, SeqCst);
+                }
+            })
+            .on_transition({
+                let transitions = transitions.clone();
+                move |_, previous, state| transitions.lock().unwrap().push((previous, state))
+            });
+        service.insert_request(Request::new("GET", url.as_str())).unwrap();
+
+        service.update().await.unwrap();
+        down.store(true, SeqCst);
+        service.update().await.unwrap();
+        service.update().await.unwrap();
+        down.store(false, SeqCst);
+        service.update().await.unwrap();
+
+        assert_eq!((successes.load(SeqCst), failures.load(SeqCst)), (2, 2));
+        // Staying down isn't a transition
+        assert_eq!(
+            *transitions.lock().unwrap(),
+            [(None, State::Up), (Some(State::Up), State::Down), (Some(State::Down), State::Up)]
+        );
+    }
+}
@@ -0,0 +1,99 @@
+#[cfg(test)]
+mod lint_tests {
+    use isup::lint::{self, Rule, Severity};
+    use std::process::Command;
+
+    /// Writes a configuration to a temporary file, returning its path.
+    fn write_config(name: &str, yaml: &str) -> String {
+        let path = std::env::temp_dir().join(format!("isup-lint-{name}-{}.yml", std::process::id()));
+        std::fs::write(&path, yaml).unwrap();
+        path.to_str().unwrap().to_string()
+    }
+
+    #[tokio::test]
+    async fn it_lints_the_configurations() {
+        let path = write_config(
+            "invalid",
+            "
+            interval: 5s
+            client: { request_timeout: 10s, pool_idle_timeout: null }
+            guard: { allow_hosts: ['*.example.com'] }
+            requests:
+              - { url: https://a.example.com, method: GET }
+              - { url: https://a.example.com, method: HEAD }
+              - { url: b.example.com, method: GET }
+              - { url: https://c.example.org, method: GET, host: 'c.example.com:443' }
+            ",
+        );
+        let findings = lint::lint_file(&path, false).await;
+        let rules = findings.iter().map(|f| (f.rule, f.path.as_str())).collect::<Vec<_>>();
+        assert_eq!(
+            rules,
+            vec![
+                (Rule::TimeoutExceedsInterval, "client.request_timeout"),
+                (Rule::DuplicateUrl, "requests[1].url"),
+                (Rule::MissingScheme, "requests[2].url"),
+                (Rule::Guarded, "requests[2].url"),
+                (Rule::InvalidHost, "requests[3].host"),
+                (Rule::Guarded, "requests[3].url"),
+            ]
+        );
+        assert!(lint::has_errors(&findings));
+
+        // The command prints the findings as JSON, and fails on errors
+        let output = Command::new(env!("CARGO_BIN_EXE_isup")).args(["lint", &path]).output().unwrap();
+        std::fs::remove_file(&path).unwrap();
+        assert_eq!(output.status.code(), Some(1));
+        let value = serde_json::from_slice::<serde_json::Value>(&output.stdout).unwrap();
+        assert_eq!(value.as_array().unwrap().len(), 6);
+        assert_eq!(value[1]["rule"], "duplicate_url");
+        assert_eq!(value[1]["severity"], "error");
+
+        // Warnings alone don't fail the command
+        let path = write_config("warning", "requests: [{ url: 'https://a.example.com', method: GET }]");
+        let output = Command::new(env!("CARGO_BIN_EXE_isup")).args(["lint", &path]).output().unwrap();
+        std::fs::remove_file(&path).unwrap();
+        assert_eq!(output.status.code(), Some(0));
+        let value = serde_json::from_slice::<serde_json::Value>(&output.stdout).unwrap();
+        assert_eq!(value[0]["rule"], "missing_interval");
+        assert_eq!(value[0]["severity"], "warning");
+
+        // A zero interval is pointed at, rather than at the request timeout it can't exceed
+        let config = serde_yaml::from_str::<isup::Config>("interval: 0s\nrequests: []").unwrap();
+        let findings = lint::lint(&config);
+        assert_eq!(
+            findings.iter().map(|f| (f.rule, f.path.as_str())).collect::<Vec<_>>(),
+            [(Rule::ZeroInterval, "interval")]
+        );
+
+        // Unparsable files and invalid arguments are reported as well
+        let output = Command::new(env!("CARGO_BIN_EXE_isup")).args(["lint", "/nonexistent.yml"]).output().unwrap();
+        assert_eq!(output.status.code(), Some(1));
+        let output = Command::new(env!("CARGO_BIN_EXE_isup")).args(["check"]).output().unwrap();
+        assert_eq!(output.status.code(), Some(2));
+    }
+
+    #[tokio::test]
+    async fn it_checks_the_hosts_are_reachable() {
+        let listener = tokio::net::TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let reachable = listener.local_addr().unwrap();
+        // Bind a port, and release it so nothing listens on it
+        let closed = tokio::net::TcpListener::bind("127.0.0.1:0").await.unwrap().local_addr().unwrap();
+
+        let config = serde_yaml::from_str::<isup::Config>(&format!(
+            "
+            interval: 5s
+            requests:
+              - {{ url: 'http://{reachable}', method: GET }}
+              - {{ url: 'http://{closed}', method: GET }}
+            "
+        ))
+        .unwrap();
+        assert!(lint::lint(&config).is_empty());
+
+        let findings = lint::check_hosts(&config).await;
+        assert_eq!(findings.len(), 1);
+        assert_eq!((findings[0].severity, findings[0].rule), (Severity::Error, Rule::UnreachableHost));
+        assert_eq!(findings[0].path, "requests[1].url");
+    }
+}
@@ -0,0 +1,92 @@
+#[cfg(test)]
+mod middleware_tests {
+    use bytes::Bytes;
+    use http_body_util::Full;
+    use isup::{
+        middleware::{Action, Middleware},
+        ProbeOutcome, Request, Service,
+    };
+    use std::sync::{
+        atomic::{AtomicUsize, Ordering::SeqCst},
+        Arc, Mutex,
+    };
+
+    // Nothing listens on port 1, so the request fails immediately without reaching the network.
+    const UNREACHABLE: &str = "http://127.0.0.1:1/";
+
+    struct Veto;
+
+    impl Middleware for Veto {
+        fn after(&self, _outcome: &mut ProbeOutcome) -> Action {
+            Action::Veto
+        }
+    }
+
+    /// Records the request IDs seen in outgoing requests and in outcomes.
+    #[derive(Clone, Default)]
+    struct Ids {
+        sent: Arc<Mutex<Vec<String>>>,
+        received: Arc<Mutex<Vec<String>>>,
+    }
+
+    impl Middleware for Ids {
+        fn before(&self, request: &mut hyper::Request<Full<Bytes>>) {
+            let id = request.headers().get("x-request-id").map(|v| v.to_str().unwrap().to_string());
+            self.sent.lock().unwrap().extend(id);
+        }
+
+        fn after(&self, outcome: &mut ProbeOutcome) -> Action {
+            self.received.lock().unwrap().extend(outcome.request_id.clone());
+            Action::Continue
+        }
+    }
+
+    #[tokio::test]
+    async fn it_observes_outcomes() {
+        // Count the outcomes received by the callback
+        let counter = Arc::new(AtomicUsize::new(0));
+        let observed = counter.clone();
+
+        let mut service = Service::default().on_result(move |outcome| {
+            // The failed request is reported with a `0` status
+            assert_eq!(outcome.status, 0);
+            observed.fetch_add(1, SeqCst);
+        });
+        service.insert_request(Request::new("GET", UNREACHABLE)).unwrap();
+
+        service.update().await.unwrap();
+        // Verify that the callback was invoked once
+        assert_eq!(counter.load(SeqCst), 1);
+        // Verify that the outcome was still scored
+        assert!(service.store.get(UNREACHABLE).await.unwrap().is_some());
+    }
+
+    #[tokio::test]
+    async fn it_vetoes_outcomes() {
+        let mut service = Service::default().use_middleware(Veto);
+        service.insert_request(Request::new("GET", UNREACHABLE)).unwrap();
+
+        service.update().await.unwrap();
+        // Verify that the vetoed outcome was never scored
+        assert!(service.store.get(UNREACHABLE).await.unwrap().is_none());
+    }
+
+    #[tokio::test]
+    async fn it_tags_probes_with_request_ids() {
+        let ids = Ids::default();
+        let header = hyper::header::HeaderName::from_static("x-request-id");
+        let mut service = Service::default().use_request_id(header).use_middleware(ids.clone());
+        service.insert_request(Request::new("GET", UNREACHABLE)).unwrap();
+
+        service.update().await.unwrap();
+        service.update().await.unwrap();
+
+        let sent = ids.sent.lock().unwrap().clone();
+        // Verify that the header was sent with every probe and recorded in its outcome
+        assert_eq!(sent.len(), 2);
+        assert_eq!(sent, *ids.received.lock().unwrap());
+        // Verify that every probe was tagged with a distinct, 128-bit ID
+        assert_ne!(sent[0], sent[1]);
+        assert!(sent.iter().all(|id| id.len() == 32));
+    }
+}
@@ -0,0 +1,41 @@
+#[cfg(test)]
+mod persistence_tests {
+    use isup::{
+        chaos::{Chaos, Fault},
+        store::{Memory, Store},
+        Request, Service,
+    };
+
+    const URL: &str = "http://simulated.example/";
+    const RUNTIME_URL: &str = "http://runtime.example/";
+
+    #[tokio::test]
+    async fn it_restores_persisted_requests() {
+        let chaos = Chaos::new(42).insert(Fault::new(URL)).insert(Fault::new(RUNTIME_URL));
+        let mut service = Service::default().use_chaos(chaos).use_request_persistence();
+        service.insert_request(Request::new("GET", URL)).unwrap();
+
+        // Insert an endpoint at runtime, persisted on the next update
+        service.insert_request(Request::new("POST", RUNTIME_URL).set_tag("team", "platform")).unwrap();
+        service.update().await.unwrap();
+        let persisted = service.store.requests().await.unwrap();
+        assert_eq!(persisted.iter().map(|r| r.url.to_string()).collect::<Vec<_>>(), vec![RUNTIME_URL, URL]);
+
+        // A restarted service, configured with the first endpoint only, gets the runtime one back
+        let store = Memory::new();
+        store.set_requests(&persisted).await.unwrap();
+        let chaos = Chaos::new(42).insert(Fault::new(URL));
+        let mut restarted = Service::default().use_chaos(chaos).use_store(store).use_request_persistence();
+        restarted.insert_request(Request::new("GET", URL)).unwrap();
+        assert_eq!(restarted.restore_requests().await.unwrap(), 1);
+
+        let restored = restarted.requests.iter().find(|r| r.url == RUNTIME_URL).unwrap();
+        assert_eq!(restored.method, "POST");
+        assert_eq!(restored.tags["team"], "platform");
+
+        // Removed endpoints are no longer persisted
+        restarted.remove_request(RUNTIME_URL).unwrap();
+        restarted.update().await.unwrap();
+        assert_eq!(restarted.store.requests().await.unwrap().len(), 1);
+    }
+}
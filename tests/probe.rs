@@ -0,0 +1,115 @@
+mod common;
+
+#[cfg(test)]
+mod probe_tests {
+    use super::common;
+    use isup::{Exec, Request};
+    use regex::Regex;
+    use std::net::SocketAddr;
+    use tokio::io::{AsyncBufReadExt, AsyncWriteExt, BufReader};
+    use tokio::net::TcpListener;
+
+    /// Starts a local line-based server, which sends the greeting and answers every line received
+    /// with the reply of the first matching prefix. Without replies, the connection is closed after the greeting.
+    async fn serve(greeting: &'static str, replies: &'static [(&'static str, &'static str)]) -> SocketAddr {
+        let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let addr = listener.local_addr().unwrap();
+
+        tokio::spawn(async move {
+            while let Ok((stream, _)) = listener.accept().await {
+                tokio::spawn(async move {
+                    let mut stream = BufReader::new(stream);
+                    stream.write_all(greeting.as_bytes()).await.unwrap();
+                    if replies.is_empty() {
+                        return;
+                    }
+                    let mut line = String::new();
+                    while stream.read_line(&mut line).await.unwrap_or(0) > 0 {
+                        if let Some((_, reply)) = replies.iter().find(|(prefix, _)| line.starts_with(prefix)) {
+                            stream.write_all(reply.as_bytes()).await.unwrap();
+                        }
+                        line.clear();
+                    }
+                });
+            }
+        });
+
+        addr
+    }
+
+    #[tokio::test]
+    async fn it_probes_smtp_servers() {
+        let addr =
+            serve("220 mail.example.com ESMTP\r\n", &[("EHLO", "250-mail.example.com\r\n250 SIZE 1000\r\n")]).await;
+
+        let outcome = common::probe(Request::new("GET".to_string(), format!("smtp://{addr}")), 1).await.remove(0);
+        // Verify that the banner and the reply to `EHLO` were accepted
+        assert_eq!(outcome.status, 200);
+        assert!(outcome.is_success());
+    }
+
+    #[tokio::test]
+    async fn it_fails_on_rejected_smtp_connections() {
+        let addr = serve("554 no service\r\n", &[]).await;
+
+        let outcome = common::probe(Request::new("GET".to_string(), format!("smtp://{addr}")), 1).await.remove(0);
+        // Verify that the unexpected banner failed the probe
+        assert_eq!(outcome.status, 0);
+        assert!(outcome.error.unwrap().contains("554"));
+    }
+
+    #[tokio::test]
+    async fn it_requires_starttls_support() {
+        let addr = serve("220 mail.example.com ESMTP\r\n", &[("EHLO", "250 mail.example.com\r\n")]).await;
+
+        let outcome =
+            common::probe(Request::new("GET".to_string(), format!("smtp://{addr}?starttls")), 1).await.remove(0);
+        // Verify that the probe failed, as STARTTLS wasn't advertised
+        assert!(outcome.error.unwrap().contains("STARTTLS"));
+    }
+
+    #[tokio::test]
+    async fn it_probes_imap_servers() {
+        let addr = serve(
+            "* OK IMAP4rev1 ready\r\n",
+            &[
+                ("a1 CAPABILITY", "* CAPABILITY IMAP4rev1 STARTTLS\r\na1 OK done\r\n"),
+                ("a4 LOGOUT", "* BYE\r\na4 OK bye\r\n"),
+            ],
+        )
+        .await;
+
+        let outcome = common::probe(Request::new("GET".to_string(), format!("imap://{addr}")), 1).await.remove(0);
+        // Verify that the greeting and the capabilities were accepted
+        assert!(outcome.is_success());
+    }
+
+    #[tokio::test]
+    async fn it_matches_tcp_banners() {
+        let addr = serve("SSH-2.0-OpenSSH_9.6\r\n", &[]).await;
+        let url = format!("tcp://{addr}");
+        let request = |banner: &str| Request::new("GET", url.as_str()).set_expect_banner(Regex::new(banner).unwrap());
+
+        // Verify that a matching banner succeeds
+        assert!(common::probe(request(r"^SSH-2\.0-"), 1).await.remove(0).is_success());
+        // Verify that an unexpected banner fails, once the connection is closed
+        let outcome = common::probe(request(r"^220 "), 1).await.remove(0);
+        assert!(outcome.error.unwrap().contains("doesn't match"));
+    }
+
+    #[tokio::test]
+    async fn it_runs_commands() {
+        let request = |exec: Exec| Request::new("GET", "exec://check").set_exec(exec);
+
+        // Verify that a successful command with the expected output succeeds
+        let exec = Exec::new("echo").set_args(vec!["healthy"]).set_expect_stdout(Regex::new("^healthy").unwrap());
+        assert!(common::probe(request(exec), 1).await.remove(0).is_success());
+        // Verify that an unexpected output fails the probe
+        let exec = Exec::new("echo").set_args(vec!["degraded"]).set_expect_stdout(Regex::new("^healthy").unwrap());
+        assert!(common::probe(request(exec), 1).await.remove(0).error.unwrap().contains("doesn't match"));
+        // Verify that a non-zero exit status fails the probe
+        let outcome = common::probe(request(Exec::new("false")), 1).await.remove(0);
+        assert_eq!(outcome.status, 0);
+        assert!(outcome.error.unwrap().contains("exited"));
+    }
+}
@@ -0,0 +1,41 @@
+mod common;
+
+#[cfg(test)]
+mod registry_tests {
+    use super::common;
+    use isup::registry::Registry;
+    use isup::{Request, Service};
+    use std::sync::atomic::Ordering::SeqCst;
+    use std::time::Duration;
+
+    #[tokio::test]
+    async fn it_manages_named_services() {
+        let mut registry = Registry::new();
+        for name in ["payments", "search"] {
+            let url = format!("http://{}/", common::serve(common::OK).await);
+            let mut service = Service::default();
+            service.insert_request(Request::new("GET", url.as_str())).unwrap();
+            registry.insert(name, service, Duration::from_secs(60)).unwrap();
+        }
+        assert!(registry.insert("search", Service::default(), Duration::from_secs(60)).is_err());
+        assert_eq!(registry.names(), ["payments", "search"]);
+        assert_eq!(registry.get("search").unwrap().namespace(), Some("search"));
+
+        // Every service runs its first update once started
+        registry.start().await;
+        for name in registry.names() {
+            let service = registry.get(&name).unwrap();
+            while service.updated_at.load(SeqCst) == 0 {
+                tokio::time::sleep(Duration::from_millis(10)).await;
+            }
+            assert_eq!(registry.best_url(&name).await.unwrap(), Some(service.urls()[0].clone()));
+            assert!(registry.is_running(&name));
+        }
+        assert!(registry.best_url("unknown").await.is_err());
+
+        registry.stop();
+        assert!(!registry.is_running("payments"));
+        assert!(registry.remove("payments").is_some());
+        assert_eq!(registry.names(), ["search"]);
+    }
+}
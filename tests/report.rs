@@ -0,0 +1,120 @@
+mod common;
+
+#[cfg(test)]
+mod report_tests {
+    use super::common;
+    use isup::history::{History, Memory, Sample};
+    use isup::notify::{Notification, Notifier};
+    use isup::report::{Config, Format, Report};
+    use isup::{Request, Service};
+    use std::error::Error;
+    use std::sync::{Arc, Mutex};
+    use std::time::{Duration, SystemTime};
+
+    /// A notifier keeping the notifications it receives.
+    #[derive(Default)]
+    struct Inbox(Mutex<Vec<Notification>>);
+
+    #[async_trait::async_trait]
+    impl Notifier for Inbox {
+        async fn notify(&self, notification: &Notification) -> Result<(), Box<dyn Error + Send + Sync>> {
+            self.0.lock().unwrap().push(notification.clone());
+            Ok(())
+        }
+    }
+
+    #[tokio::test]
+    async fn it_delivers_summary_reports() {
+        let url = format!("http://{}/", common::serve(common::OK).await);
+        let inbox = Arc::new(Inbox::default());
+
+        let config = Config::default().set_period(Duration::from_millis(200)).set_format(Format::Json);
+        let mut service = Service::default().use_notifier(inbox.clone()).use_report(config);
+        service.insert_request(Request::new("GET", url.as_str())).unwrap();
+
+        // The report accumulates until its period is over
+        service.update().await.unwrap();
+        service.update().await.unwrap();
+        assert!(inbox.0.lock().unwrap().is_empty());
+        let report = service.report().await.unwrap().unwrap();
+        assert_eq!(report.endpoints[0].probes, 2);
+
+        // Then it's delivered, and the next period starts over
+        tokio::time::sleep(Duration::from_millis(200)).await;
+        service.update().await.unwrap();
+        let notifications = inbox.0.lock().unwrap().clone();
+        assert_eq!(notifications.len(), 1);
+        assert_eq!(notifications[0].content_type, "application/json");
+
+        let report = serde_json::from_str::<Report>(&notifications[0].body).unwrap();
+        assert_eq!(report.endpoints.len(), 1);
+        assert_eq!(report.endpoints[0].url, url);
+        assert_eq!(report.endpoints[0].probes, 3);
+        assert_eq!(report.endpoints[0].uptime, 100.0);
+        assert!(report.endpoints[0].p95.is_some());
+        assert!(service.report().await.unwrap().unwrap().endpoints.is_empty());
+    }
+
+    #[tokio::test]
+    async fn it_reports_the_latency_over_a_window() {
+        const URL: &str = "http://api.example/";
+        const HOUR: Duration = Duration::from_secs(3600);
+        let history = Arc::new(Memory::default());
+        let now = SystemTime::now();
+        // 100 samples over the last hour, every tenth failing, and an older one out of the window
+        for ms in 1..=100 {
+            let status = if ms % 10 == 0 { 0 } else { 200 };
+            let at = now - Duration::from_secs(ms);
+            let sample = Sample { at, elapsed: Duration::from_millis(ms), status, score: 0.0, reliability: 0.0 };
+            history.record(URL, sample).await.unwrap();
+        }
+        let old = Sample { at: now - 2 * HOUR, elapsed: HOUR, status: 200, score: 0.0, reliability: 0.0 };
+        history.record(URL, old).await.unwrap();
+
+        // Without a history, there's nothing to report
+        assert!(Service::default().latency_report(URL, HOUR).await.unwrap().is_none());
+
+        let service = Service::default().use_history(history);
+        let report = service.latency_report(URL, HOUR).await.unwrap().unwrap();
+        assert_eq!((report.samples, report.availability), (100, Some(90.0)));
+        // The percentiles of the 90 successful samples
+        assert_eq!((report.min, report.max), (Some(Duration::from_millis(1)), Some(Duration::from_millis(99))));
+        assert_eq!(report.avg, Some(Duration::from_millis(50)));
+        assert_eq!(report.p50, Some(Duration::from_millis(49)));
+        assert_eq!(report.p95, Some(Duration::from_millis(95)));
+        assert_eq!(report.p99, Some(Duration::from_millis(99)));
+    }
+
+    #[tokio::test]
+    async fn it_compares_endpoints_side_by_side() {
+        const FAST: &str = "http://fast.example/";
+        const FLAKY: &str = "http://flaky.example/";
+        const WEEK: Duration = Duration::from_secs(7 * 24 * 3600);
+        let history = Arc::new(Memory::default());
+        let now = SystemTime::now();
+        // Both endpoints are sampled every hour over the last week, the flaky one failing a third of the time
+        for hour in 1..=7 * 24 {
+            let at = now - Duration::from_secs(hour * 3600);
+            let fast = Sample { at, elapsed: Duration::from_millis(20), status: 200, score: 0.9, reliability: 1.0 };
+            history.record(FAST, fast).await.unwrap();
+            let (status, score) = if hour % 3 == 0 { (0, 0.0) } else { (200, 0.6) };
+            let flaky = Sample { at, elapsed: Duration::from_millis(10), status, score, reliability: 0.6 };
+            history.record(FLAKY, flaky).await.unwrap();
+        }
+
+        let service = Service::default().use_history(history);
+        let comparison = service.compare(&[FLAKY, FAST], WEEK).await.unwrap().unwrap();
+        // The endpoints are kept in order, the faster but flaky one losing on its score
+        let [flaky, fast] = &comparison.endpoints[..] else { panic!("expected two endpoints") };
+        assert_eq!((flaky.report.url.as_str(), fast.report.url.as_str()), (FLAKY, FAST));
+        assert_eq!(comparison.best.as_deref(), Some(FAST));
+        assert_eq!(fast.report.availability, Some(100.0));
+        assert!(flaky.report.availability.unwrap() < 70.0);
+        assert!(flaky.report.p95 < fast.report.p95);
+        assert!((fast.mean_score.unwrap() - 0.9).abs() < 1e-6);
+        // The score trajectory covers the week, a point per slice of it
+        assert_eq!(fast.trajectory.len(), 24);
+        assert!(fast.trajectory.iter().all(|p| (p.score - 0.9).abs() < 1e-6));
+        assert!(fast.trajectory.windows(2).all(|w| w[0].at < w[1].at));
+    }
+}
@@ -1,8 +1,11 @@
+mod common;
+
 #[cfg(test)]
 mod request_tests {
+    use super::common;
     use bytes::Bytes;
     use hyper::header::{HeaderMap, HeaderValue, CONTENT_TYPE};
-    use isup::Request;
+    use isup::{Request, Service};
 
     #[test]
     fn it_creates_a_new() {
@@ -62,4 +65,66 @@ mod request_tests {
         // Verify that the headers were set
         assert_eq!(request.headers, headers);
     }
+
+    #[test]
+    fn it_normalizes_urls() {
+        // The scheme and host are lowercased, the default port removed and the empty path replaced
+        let request = Request::new("GET", "HTTP://Example.COM:80");
+        assert_eq!(request.url.to_string(), "http://example.com/");
+        // Non-default ports, user info, paths and queries are preserved
+        let request = Request::new("GET", "https://user@Example.com:8443/Path?Query=1");
+        assert_eq!(request.url.to_string(), "https://user@example.com:8443/Path?Query=1");
+        let request = Request::new("GET", "https://EXAMPLE.com:443/health");
+        assert_eq!(request.url.to_string(), "https://example.com/health");
+    }
+
+    #[test]
+    fn it_rejects_duplicate_requests() {
+        let mut service = Service::default();
+        service.insert_request(Request::new("GET", "https://example.com/")).unwrap();
+
+        // Verify that an equivalent URL is detected as a duplicate
+        assert!(service.insert_request(Request::new("GET", "https://EXAMPLE.com:443")).is_err());
+        assert_eq!(service.urls().len(), 1);
+
+        // Verify that the request is removed regardless of the URL format
+        service.remove_request("https://example.com").unwrap();
+        assert!(service.urls().is_empty());
+    }
+
+    #[tokio::test]
+    async fn it_scores_named_requests_of_the_same_url_separately() {
+        // The endpoint fails every POST, while answering every GET
+        let addr = common::serve_with(|head| match head.starts_with("POST") {
+            true => Bytes::from_static(b"HTTP/1.1 500 Internal Server Error\r\ncontent-length: 0\r\n\r\n"),
+            false => Bytes::from_static(common::OK.as_bytes()),
+        })
+        .await;
+        let url = format!("http://{addr}/orders");
+        let mut service = Service::default();
+        service.insert_request(Request::new("GET", &url).set_name("orders-list")).unwrap();
+        service.insert_request(Request::new("POST", &url).set_name("orders-create")).unwrap();
+
+        // Verify that a third request of the same URL needs a name of its own
+        assert!(service.insert_request(Request::new("GET", &url).set_name("orders-list")).is_err());
+        service.insert_request(Request::new("GET", &url)).unwrap();
+        assert!(service.insert_request(Request::new("HEAD", &url)).is_err());
+        service.remove_request(&url).unwrap();
+        assert_eq!(service.urls(), vec!["orders-list", "orders-create"]);
+
+        // Verify that each request is scored under its name, without overwriting the other one
+        service.update().await.unwrap();
+        let list = service.score("orders-list").await.unwrap().unwrap();
+        let create = service.score("orders-create").await.unwrap().unwrap();
+        assert!(list.reliability > create.reliability && list.score > create.score);
+        assert!(service.score(&url).await.unwrap().is_none());
+
+        // Verify that the best endpoint is still given by its URL, to be routed to
+        assert_eq!(service.best_url().await.unwrap(), Some(url.clone()));
+        assert_eq!(service.best_url_where(|u, _| u == url).await.unwrap(), Some(url.clone()));
+
+        // Verify that a named request is removed by its name
+        service.remove_request("orders-create").unwrap();
+        assert_eq!(service.urls(), vec!["orders-list"]);
+    }
 }
@@ -0,0 +1,637 @@
+mod common;
+
+#[cfg(test)]
+mod server_tests {
+    use super::common;
+    use bytes::Bytes;
+    use http_body_util::{BodyExt, Full};
+    use isup::chaos::{Chaos, Fault};
+    use isup::history::Memory;
+    use isup::server::{Best, Endpoint, Role, Server};
+    use isup::store::Store;
+    use isup::strategy::WeightedLog;
+    use isup::{Client, Health, Request, Score, ScoreView, Service};
+    use std::error::Error;
+    use std::net::SocketAddr;
+    use std::sync::Arc;
+    use std::time::Duration;
+    use tokio::net::TcpListener;
+
+    /// Starts the embedded server of a service.
+    async fn start(service: Arc<Service>) -> SocketAddr {
+        let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let addr = listener.local_addr().unwrap();
+        tokio::spawn(Server::new(service).serve(listener));
+        addr
+    }
+
+    /// Starts the embedded server of a service, granting the `admin` token the admin role.
+    async fn start_as_admin(service: Arc<Service>) -> SocketAddr {
+        let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let addr = listener.local_addr().unwrap();
+        tokio::spawn(Server::new(service).insert_token("admin", Role::Admin).serve(listener));
+        addr
+    }
+
+    /// Sends a request to the server, returning the status and body of its response.
+    async fn send(request: hyper::Request<Full<Bytes>>) -> (u16, Bytes) {
+        let response = Client::default().request(request).await.unwrap();
+        let status = response.status().as_u16();
+        (status, response.into_body().collect().await.unwrap().to_bytes())
+    }
+
+    #[tokio::test]
+    async fn it_serves_the_best_url() {
+        let url = format!("http://{}/", common::serve(common::OK).await);
+        let mut service = Service::default();
+        service.insert_request(Request::new("GET", url.as_str())).unwrap();
+        service.update().await.unwrap();
+        let addr = start(Arc::new(service)).await;
+
+        let (status, body) =
+            send(hyper::Request::get(format!("http://{addr}/best")).body(Full::default()).unwrap()).await;
+        assert_eq!(status, 200);
+        assert_eq!(serde_json::from_slice::<Best>(&body).unwrap().url, Some(url));
+
+        let (status, _) =
+            send(hyper::Request::get(format!("http://{addr}/unknown")).body(Full::default()).unwrap()).await;
+        assert_eq!(status, 404);
+    }
+
+    #[tokio::test]
+    async fn it_serves_the_score_of_an_endpoint() {
+        let url = format!("http://{}/", common::serve(common::OK).await);
+        let mut service = Service::default();
+        service.insert_request(Request::new("GET", url.as_str())).unwrap();
+        service.update().await.unwrap();
+        let score = service.score(&url).await.unwrap().unwrap();
+        let addr = start(Arc::new(service)).await;
+
+        let encoded = url.replace(':', "%3A").replace('/', "%2F");
+        let request = hyper::Request::get(format!("http://{addr}/score?url={encoded}"));
+        let (status, body) = send(request.body(Full::default()).unwrap()).await;
+        assert_eq!(status, 200);
+        let view = serde_json::from_slice::<ScoreView>(&body).unwrap();
+        assert_eq!(view, ScoreView::new(url, score));
+        assert_eq!(view.version, Score::SCHEMA_VERSION);
+
+        let request = hyper::Request::get(format!("http://{addr}/score?url=http%3A%2F%2Funscored.example%2F"));
+        assert_eq!(send(request.body(Full::default()).unwrap()).await.0, 404);
+        let request = hyper::Request::get(format!("http://{addr}/score"));
+        assert_eq!(send(request.body(Full::default()).unwrap()).await.0, 400);
+    }
+
+    #[tokio::test]
+    async fn it_serves_namespaced_services() {
+        let (a, b) = (common::serve(common::OK).await, common::serve(common::OK).await);
+        let mut payments = Service::default().use_namespace("payments");
+        payments.insert_request(Request::new("GET", format!("http://{a}/").as_str())).unwrap();
+        let mut search = Service::default().use_namespace("search");
+        search.insert_request(Request::new("GET", format!("http://{b}/").as_str())).unwrap();
+        for service in [&payments, &search] {
+            service.update().await.unwrap();
+        }
+
+        let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let addr = listener.local_addr().unwrap();
+        let server = Server::new(Arc::new(Service::default())).insert(Arc::new(payments)).insert(Arc::new(search));
+        tokio::spawn(server.serve(listener));
+
+        // Every namespace is served its own state
+        for (namespace, url) in [("payments", a), ("search", b)] {
+            let request = hyper::Request::get(format!("http://{addr}/namespaces/{namespace}/best"));
+            let (status, body) = send(request.body(Full::default()).unwrap()).await;
+            assert_eq!(status, 200);
+            assert_eq!(serde_json::from_slice::<Best>(&body).unwrap().url, Some(format!("http://{url}/")));
+        }
+
+        let request = hyper::Request::get(format!("http://{addr}/namespaces/unknown/best"));
+        assert_eq!(send(request.body(Full::default()).unwrap()).await.0, 404);
+    }
+
+    #[tokio::test]
+    async fn it_serves_a_grafana_datasource() {
+        let url = format!("http://{}/", common::serve(common::OK).await);
+        let mut service = Service::default().use_history(Memory::default());
+        service.insert_request(Request::new("GET", url.as_str())).unwrap();
+        for _ in 0..3 {
+            service.update().await.unwrap();
+        }
+        let addr = start(Arc::new(service)).await;
+
+        // Grafana tests the connection, then lists the targets
+        let (status, _) =
+            send(hyper::Request::get(format!("http://{addr}/grafana")).body(Full::default()).unwrap()).await;
+        assert_eq!(status, 200);
+        let request = hyper::Request::post(format!("http://{addr}/grafana/search")).body(Full::default()).unwrap();
+        let targets = serde_json::from_slice::<Vec<String>>(&send(request).await.1).unwrap();
+        assert_eq!(targets, [format!("latency:{url}"), format!("score:{url}"), format!("reliability:{url}")]);
+
+        // Query the series of the endpoint
+        let query = serde_json::json!({
+            "range": { "from": "2000-01-01T00:00:00.000Z", "to": "2100-01-01T00:00:00.000Z" },
+            "targets": [{ "target": format!("score:{url}"), "refId": "A" }],
+            "maxDataPoints": 2,
+        });
+        let request = hyper::Request::post(format!("http://{addr}/grafana/query"))
+            .body(Full::new(Bytes::from(query.to_string())))
+            .unwrap();
+        let (status, body) = send(request).await;
+        assert_eq!(status, 200);
+        let series = serde_json::from_slice::<serde_json::Value>(&body).unwrap();
+        assert_eq!(series[0]["target"], format!("score:{url}"));
+        // The 3 samples are thinned to the requested number of points
+        let datapoints = series[0]["datapoints"].as_array().unwrap();
+        assert_eq!(datapoints.len(), 2);
+        assert!(datapoints[0][0].as_f64().unwrap() > 0.0);
+
+        // Unknown metrics are rejected
+        let query = query.to_string().replace("score:", "unknown:");
+        let request =
+            hyper::Request::post(format!("http://{addr}/grafana/query")).body(Full::new(Bytes::from(query))).unwrap();
+        assert_eq!(send(request).await.0, 400);
+    }
+
+    #[tokio::test]
+    async fn it_pauses_and_resumes_endpoints() {
+        let urls = [common::serve(common::OK).await, common::serve(common::OK).await].map(|a| format!("http://{a}/"));
+        let mut service = Service::default();
+        for url in &urls {
+            service.insert_request(Request::new("GET", url.as_str())).unwrap();
+        }
+        service.update().await.unwrap();
+        let service = Arc::new(service);
+        let addr = start_as_admin(service.clone()).await;
+
+        let best = || async {
+            let request = hyper::Request::get(format!("http://{addr}/best")).header("authorization", "Bearer admin");
+            let (_, body) = send(request.body(Full::default()).unwrap()).await;
+            serde_json::from_slice::<Best>(&body).unwrap().url.unwrap()
+        };
+        let post = |path: &str, url: &str| {
+            let body = serde_json::to_vec(&Endpoint { url: url.to_string(), duration: None }).unwrap();
+            let request = hyper::Request::post(format!("http://{addr}{path}")).header("authorization", "Bearer admin");
+            send(request.body(Full::new(Bytes::from(body))).unwrap())
+        };
+
+        // The paused endpoint is no longer routed to, and its score is frozen
+        let paused = best().await;
+        assert_eq!(post("/pause", &paused).await.0, 200);
+        assert!(service.is_paused(&paused));
+        assert_ne!(best().await, paused);
+        let frozen = service.store.get(&paused).await.unwrap().unwrap();
+        assert!(frozen.paused);
+        service.update().await.unwrap();
+        assert_eq!(service.store.get(&paused).await.unwrap().unwrap().response_avg, frozen.response_avg);
+
+        assert_eq!(post("/resume", &paused).await.0, 200);
+        assert!(!service.store.get(&paused).await.unwrap().unwrap().paused);
+
+        // Only monitored endpoints can be paused
+        assert_eq!(post("/pause", "http://unknown.example/").await.0, 400);
+    }
+
+    #[tokio::test]
+    async fn it_suppresses_failures() {
+        const URL: &str = "http://simulated.example/";
+        let chaos = Chaos::new(42).insert(Fault::new(URL).set_failure_rate(1.0).set_failure_status(503));
+        let mut service = Service::default().use_chaos(chaos);
+        service.insert_request(Request::new("GET", URL)).unwrap();
+        let service = Arc::new(service);
+        let addr = start_as_admin(service.clone()).await;
+
+        let suppress = |body: &str| {
+            let request =
+                hyper::Request::post(format!("http://{addr}/suppress")).header("authorization", "Bearer admin");
+            send(request.body(Full::new(Bytes::from(body.to_string()))).unwrap())
+        };
+        assert_eq!(suppress(&format!(r#"{{ "url": "{URL}" }}"#)).await.0, 400);
+        // A duration overflowing the clock is rejected, rather than crashing the server
+        assert_eq!(suppress(&format!(r#"{{ "url": "{URL}", "duration": {} }}"#, u64::MAX)).await.0, 400);
+        assert!(!service.is_suppressed(URL));
+        assert_eq!(suppress(&format!(r#"{{ "url": "{URL}", "duration": "1m" }}"#)).await.0, 200);
+
+        // The failures during the deployment are neither scored nor raise an incident
+        assert!(service.is_suppressed(URL));
+        service.update().await.unwrap();
+        assert!(service.store.get(URL).await.unwrap().is_none());
+        assert_eq!(service.state(URL), None);
+    }
+
+    #[tokio::test]
+    async fn it_administers_the_service() {
+        let url = format!("http://{}/", common::serve(common::OK).await);
+        let mut service = Service::default();
+        service.insert_request(Request::new("GET", url.as_str())).unwrap();
+        service.update().await.unwrap();
+        let service = Arc::new(service);
+        let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let addr = listener.local_addr().unwrap();
+        tokio::spawn(Server::new(service.clone()).insert_token("secret", Role::Admin).serve(listener));
+
+        let post = |path: &str, token: &str, body: &str| {
+            let request = hyper::Request::post(format!("http://{addr}{path}")).header("authorization", token);
+            send(request.body(Full::new(Bytes::from(body.to_string()))).unwrap())
+        };
+
+        // The admin routes require an admin token
+        assert_eq!(post("/admin/flush", "Bearer wrong", "").await.0, 401);
+        assert_eq!(post("/admin/flush", "", "").await.0, 401);
+
+        assert_eq!(post("/admin/interval", "Bearer secret", r#"{ "interval": "0s" }"#).await.0, 400);
+        // An interval overflowing its representation in nanoseconds is rejected rather than wrapped
+        assert_eq!(post("/admin/interval", "Bearer secret", r#"{ "interval": 18446744074 }"#).await.0, 400);
+        assert_eq!(service.interval(), Duration::ZERO);
+        assert_eq!(post("/admin/interval", "Bearer secret", r#"{ "interval": "30s" }"#).await.0, 200);
+        assert_eq!(service.interval(), Duration::from_secs(30));
+
+        // The new strategy scores the next probes
+        assert!(service.score(&url).await.unwrap().unwrap().score > 0.0);
+        assert_eq!(post("/admin/strategy", "Bearer secret", r#"{ "type": "unknown" }"#).await.0, 400);
+        // A strategy whose scores would be undefined is rejected
+        let undefined = r#"{ "type": "weighted_log", "weight": 0.5, "effort": 10.0, "log_offset": 0.0 }"#;
+        assert_eq!(post("/admin/strategy", "Bearer secret", undefined).await.0, 400);
+        assert_eq!(post("/admin/strategy", "Bearer secret", r#"{ "type": "step", "bands": [] }"#).await.0, 200);
+        service.update().await.unwrap();
+        assert_eq!(service.score(&url).await.unwrap().unwrap().score, 0.0);
+
+        assert_eq!(post("/admin/flush", "Bearer secret", "").await.0, 200);
+        assert!(service.score(&url).await.unwrap().is_none());
+    }
+
+    #[tokio::test]
+    async fn it_authorizes_the_roles_of_the_tokens() {
+        let url = format!("http://{}/", common::serve(common::OK).await);
+        let mut service = Service::default();
+        service.insert_request(Request::new("GET", url.as_str())).unwrap();
+        let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let addr = listener.local_addr().unwrap();
+        let server =
+            Server::new(Arc::new(service)).insert_token("reader", Role::ReadOnly).insert_token("admin", Role::Admin);
+        tokio::spawn(server.serve(listener));
+
+        let send_as = |method: &str, path: &str, token: Option<&str>| {
+            let mut request = hyper::Request::builder().method(method).uri(format!("http://{addr}{path}"));
+            if let Some(token) = token {
+                request = request.header("authorization", format!("Bearer {token}"));
+            }
+            let body = serde_json::to_vec(&Endpoint { url: url.clone(), duration: None }).unwrap();
+            send(request.body(Full::new(Bytes::from(body))).unwrap())
+        };
+
+        // The health is kept public, the other routes require a token
+        assert_eq!(send_as("GET", "/health", None).await.0, 200);
+        assert_eq!(send_as("GET", "/ranking", None).await.0, 401);
+        assert_eq!(send_as("GET", "/ranking", Some("unknown")).await.0, 401);
+        assert_eq!(send_as("GET", "/ranking", Some("reader")).await.0, 200);
+        assert_eq!(send_as("GET", "/ranking", Some("admin")).await.0, 200);
+
+        // Only admins can change the state of the service
+        assert_eq!(send_as("POST", "/pause", Some("reader")).await.0, 403);
+        assert_eq!(send_as("POST", "/admin/flush", Some("reader")).await.0, 403);
+        assert_eq!(send_as("POST", "/pause", Some("admin")).await.0, 200);
+        assert_eq!(send_as("POST", "/admin/flush", Some("admin")).await.0, 200);
+    }
+
+    #[tokio::test]
+    async fn it_closes_the_mutating_routes_of_servers_without_tokens() {
+        let url = format!("http://{}/", common::serve(common::OK).await);
+        let mut service = Service::default();
+        service.insert_request(Request::new("GET", url.as_str())).unwrap();
+        let service = Arc::new(service);
+        let addr = start(service.clone()).await;
+
+        let post = |path: &str, body: &Endpoint| {
+            let body = serde_json::to_vec(body).unwrap();
+            send(hyper::Request::post(format!("http://{addr}{path}")).body(Full::new(Bytes::from(body))).unwrap())
+        };
+
+        // The queries are open, but changing the state of the service requires an admin token
+        let ranking = send(hyper::Request::get(format!("http://{addr}/ranking")).body(Full::default()).unwrap());
+        assert_eq!(ranking.await.0, 200);
+        assert_eq!(post("/pause", &Endpoint { url: url.clone(), duration: None }).await.0, 401);
+        assert_eq!(post("/resume", &Endpoint { url: url.clone(), duration: None }).await.0, 401);
+        let suppress = Endpoint { url: url.clone(), duration: Some(Duration::from_secs(60).into()) };
+        assert_eq!(post("/suppress", &suppress).await.0, 401);
+        assert_eq!(post("/admin/flush", &Endpoint { url: url.clone(), duration: None }).await.0, 401);
+        assert!(!service.is_paused(&url) && !service.is_suppressed(&url));
+    }
+
+    #[tokio::test]
+    async fn it_caches_and_rate_limits_the_queries() {
+        let service = Arc::new(Service::default());
+        service.store.set("http://a.example/".into(), Score::new(0.5, 1.0, Duration::ZERO)).await.unwrap();
+        let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let addr = listener.local_addr().unwrap();
+        let server =
+            Server::new(service.clone()).set_cache(Duration::from_secs(60)).set_rate_limit(3, Duration::from_secs(60));
+        tokio::spawn(server.serve(listener));
+
+        let best = || async {
+            let (_, body) =
+                send(hyper::Request::get(format!("http://{addr}/best")).body(Full::default()).unwrap()).await;
+            serde_json::from_slice::<Best>(&body).unwrap().url.unwrap()
+        };
+        assert_eq!(best().await, "http://a.example/");
+
+        // The response is served from the cache until the next update
+        service.store.set("http://b.example/".into(), Score::new(1.0, 1.0, Duration::ZERO)).await.unwrap();
+        assert_eq!(best().await, "http://a.example/");
+        service.updated_at.store(1, std::sync::atomic::Ordering::SeqCst);
+        assert_eq!(best().await, "http://b.example/");
+
+        // The client exceeded its limit
+        let client = Client::default();
+        let response =
+            client.request(hyper::Request::get(format!("http://{addr}/best")).body(Full::default()).unwrap());
+        let response = response.await.unwrap();
+        assert_eq!(response.status(), 429);
+        assert!(response.headers().contains_key("retry-after"));
+    }
+
+    #[tokio::test]
+    async fn it_negotiates_the_content_and_allows_cross_origin_queries() {
+        let service = Arc::new(Service::default());
+        service.store.set("http://a.example/".into(), Score::new(0.5, 1.0, Duration::ZERO)).await.unwrap();
+        let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let addr = listener.local_addr().unwrap();
+        let server = Server::new(service).set_cors_origins(vec!["https://status.example".into()]);
+        tokio::spawn(server.serve(listener));
+
+        let client = Client::default();
+        let query = |method: &str, accept: &str, origin: &str| {
+            let request = hyper::Request::builder().method(method).uri(format!("http://{addr}/best"));
+            let request = request.header("accept", accept).header("origin", origin).body(Full::default()).unwrap();
+            client.request(request)
+        };
+
+        // Shell scripts get the URL alone
+        let response = query("GET", "text/plain", "https://status.example").await.unwrap();
+        assert_eq!(response.headers()["access-control-allow-origin"], "https://status.example");
+        assert_eq!(response.into_body().collect().await.unwrap().to_bytes(), "http://a.example/\n");
+        let response = query("GET", "text/html, application/json;q=0.9, text/plain;q=0.5", "").await.unwrap();
+        assert_eq!(response.headers()["content-type"], "application/json");
+        assert!(!response.headers().contains_key("access-control-allow-origin"));
+        assert_eq!(query("GET", "image/png", "").await.unwrap().status(), 406);
+
+        // The preflight requests of the allowed origins are answered
+        let response = query("OPTIONS", "*/*", "https://status.example").await.unwrap();
+        assert_eq!(response.status(), 204);
+        assert!(response.headers()["access-control-allow-headers"].to_str().unwrap().contains("authorization"));
+        let response = query("OPTIONS", "*/*", "https://unknown.example").await.unwrap();
+        assert!(!response.headers().contains_key("access-control-allow-origin"));
+    }
+
+    #[tokio::test]
+    async fn it_answers_not_modified_to_current_clients() {
+        let url = format!("http://{}/", common::serve(common::OK).await);
+        let mut service = Service::default();
+        service.insert_request(Request::new("GET", url.as_str())).unwrap();
+        service.update().await.unwrap();
+        let service = Arc::new(service);
+        let addr = start(service.clone()).await;
+
+        let client = Client::default();
+        let query = |path: &str, etag: Option<&str>| {
+            let request = hyper::Request::get(format!("http://{addr}{path}"));
+            let request = match etag {
+                Some(etag) => request.header("if-none-match", etag),
+                None => request,
+            };
+            client.request(request.body(Full::default()).unwrap())
+        };
+
+        let response = query("/ranking", None).await.unwrap();
+        assert_eq!(response.status(), 200);
+        let etag = response.headers()["etag"].to_str().unwrap().to_string();
+        let response = query("/ranking", Some(&etag)).await.unwrap();
+        assert_eq!(response.status(), 304);
+        assert_eq!(response.headers()["etag"], etag.as_str());
+
+        // Pausing the endpoint changes the ranking, without an update
+        service.pause(&url).await.unwrap();
+        let response = query("/ranking", Some(&etag)).await.unwrap();
+        assert_eq!(response.status(), 200);
+        assert_ne!(response.headers()["etag"], etag.as_str());
+
+        // The best endpoint is tagged apart from the ranking, and retagged once the state is altered
+        let response = query("/best", None).await.unwrap();
+        let best = response.headers()["etag"].to_str().unwrap().to_string();
+        assert_ne!(query("/ranking", None).await.unwrap().headers()["etag"], best.as_str());
+        assert_eq!(query("/best", Some(&best)).await.unwrap().status(), 304);
+        service.suppress(&url, Duration::from_secs(60)).unwrap();
+        assert_eq!(query("/best", Some(&best)).await.unwrap().status(), 200);
+        let response = query("/best", None).await.unwrap();
+        let best = response.headers()["etag"].to_str().unwrap().to_string();
+        service.flush().await.unwrap();
+        assert_eq!(query("/best", Some(&best)).await.unwrap().status(), 200);
+    }
+
+    #[tokio::test]
+    async fn it_serves_its_openapi_document() {
+        let addr = start(Arc::new(Service::default())).await;
+
+        let request = hyper::Request::get(format!("http://{addr}/openapi.json")).body(Full::default()).unwrap();
+        let (status, body) = send(request).await;
+        assert_eq!(status, 200);
+        let document = serde_json::from_slice::<serde_json::Value>(&body).unwrap();
+        assert_eq!(document, Server::openapi());
+
+        // Every documented route is served
+        for (path, operations) in document["paths"].as_object().unwrap() {
+            for method in operations.as_object().unwrap().keys() {
+                let request = hyper::Request::builder()
+                    .method(method.to_uppercase().as_str())
+                    .uri(format!("http://{addr}{path}"))
+                    .body(Full::default())
+                    .unwrap();
+                assert_ne!(send(request).await.0, 404, "{method} {path}");
+            }
+        }
+    }
+
+    /// A store that can't be reached.
+    struct Unreachable;
+
+    #[async_trait::async_trait]
+    impl Store for Unreachable {
+        async fn set(&self, _: String, _: Score) -> Result<(), Box<dyn Error + Send + Sync>> {
+            Err("connection refused".into())
+        }
+        async fn get(&self, _: &str) -> Result<Option<Score>, Box<dyn Error + Send + Sync>> {
+            Err("connection refused".into())
+        }
+        async fn best_url(&self) -> Result<Option<String>, Box<dyn Error + Send + Sync>> {
+            Err("connection refused".into())
+        }
+    }
+
+    #[tokio::test]
+    async fn it_serves_the_health_of_the_service() {
+        let url = format!("http://{}/", common::serve(common::OK).await);
+        let mut service = Service::default();
+        service.insert_request(Request::new("GET", url.as_str())).unwrap();
+        let service = Arc::new(service);
+        service.clone().run(Duration::from_secs(60)).await;
+        let addr = start(service.clone()).await;
+
+        // Healthy once the first update completed
+        while service.self_health().await.last_update.is_none() {
+            tokio::time::sleep(Duration::from_millis(10)).await;
+        }
+        let (status, body) =
+            send(hyper::Request::get(format!("http://{addr}/health")).body(Full::default()).unwrap()).await;
+        assert_eq!(status, 200);
+        let health = serde_json::from_slice::<Health>(&body).unwrap();
+        assert!(health.healthy);
+        assert_eq!(health.stale_after, Some(Duration::from_secs(181)));
+        assert_eq!(health.queue_depth, 0);
+
+        // Unhealthy while the store can't be reached
+        let service = Service::new(WeightedLog::default(), Unreachable, Client::default(), vec![]);
+        let addr = start(Arc::new(service)).await;
+        let (status, body) =
+            send(hyper::Request::get(format!("http://{addr}/health")).body(Full::default()).unwrap()).await;
+        assert_eq!(status, 503);
+        let health = serde_json::from_slice::<Health>(&body).unwrap();
+        assert_eq!(health.store_error.as_deref(), Some("connection refused"));
+    }
+
+    #[tokio::test]
+    async fn it_keeps_running_while_the_store_is_unreachable() {
+        let url = format!("http://{}/", common::serve(common::OK).await);
+        let mut service = Service::new(WeightedLog::default(), Unreachable, Client::default(), vec![]);
+        service.insert_request(Request::new("GET", url.as_str())).unwrap();
+        let service = Arc::new(service);
+        let handle = service.clone().run(Duration::from_millis(20)).await;
+
+        // The failed updates are counted, rather than stopping the update loop
+        while service.metrics().cycles().errors < 3 {
+            tokio::time::sleep(Duration::from_millis(10)).await;
+        }
+        assert!(!handle.is_finished());
+        handle.abort();
+
+        let health = Unreachable.health().await;
+        assert!(!health.healthy);
+        assert_eq!(health.error.as_deref(), Some("connection refused"));
+        assert_eq!(health.connections, None);
+    }
+
+    #[tokio::test]
+    async fn it_serves_the_metrics_of_the_store() {
+        let url = format!("http://{}/", common::serve(common::OK).await);
+        let mut service = Service::default();
+        service.insert_request(Request::new("GET", url.as_str())).unwrap();
+        service.update().await.unwrap();
+        service.best_url().await.unwrap();
+        let operations = service.metrics().store_operations();
+        assert_eq!((operations["set"].count, operations["set"].errors), (1, 0));
+        assert_eq!(operations["best_url"].count, 1);
+        assert!(operations["get"].max >= operations["get"].mean().unwrap());
+
+        // The failures of the store are counted as errors
+        let service = Service::new(WeightedLog::default(), Unreachable, Client::default(), vec![]);
+        let addr = start(Arc::new(service)).await;
+        send(hyper::Request::get(format!("http://{addr}/best")).body(Full::default()).unwrap()).await;
+        let (status, body) =
+            send(hyper::Request::get(format!("http://{addr}/metrics")).body(Full::default()).unwrap()).await;
+        assert_eq!(status, 200);
+        let text = String::from_utf8(body.to_vec()).unwrap();
+        assert!(text.contains("# TYPE isup_store_errors_total counter"), "{text}");
+        assert!(text.contains("isup_store_operations_total{operation=\"best_url\"} 1"), "{text}");
+        assert!(text.contains("isup_store_errors_total{operation=\"best_url\"} 1"), "{text}");
+    }
+
+    #[tokio::test]
+    async fn it_labels_the_metrics_of_namespaced_services() {
+        let url = format!("http://{}/", common::serve(common::OK).await);
+        let services = ["payments", "search"].map(|namespace| {
+            let mut service = Service::default().use_namespace(namespace);
+            service.insert_request(Request::new("GET", url.as_str())).unwrap();
+            service
+        });
+        for service in &services {
+            service.update().await.unwrap();
+        }
+
+        // The services monitoring the same URL export distinct series, every one of them labeled
+        for (service, namespace) in services.iter().zip(["payments", "search"]) {
+            let text = service.metrics().render();
+            assert!(text.contains(&format!("isup_update_cycles_total{{namespace=\"{namespace}\"}} 1")), "{text}");
+            let series =
+                format!("isup_probe_responses_total{{namespace=\"{namespace}\",url=\"{url}\",class=\"2xx\"}} 1");
+            assert!(text.contains(&series), "{text}");
+            let labeled = |line: &&str| line.contains(&format!("{{namespace=\"{namespace}\"")) || line.starts_with('#');
+            assert!(text.lines().all(|line| labeled(&line)), "{text}");
+        }
+
+        // Services without a namespace are left unlabeled
+        let text = Service::default().metrics().render();
+        assert!(text.contains("isup_update_cycles_total 0") && !text.contains("namespace"), "{text}");
+    }
+
+    #[tokio::test]
+    async fn it_counts_the_probes_by_status_class() {
+        let ok = format!("http://{}/", common::serve(common::OK).await);
+        let failing =
+            format!("http://{}/", common::serve("HTTP/1.1 503 Unavailable\r\ncontent-length: 0\r\n\r\n").await);
+        // A server that never answers
+        let silent = format!("http://{}/", common::serve_with(|_| Bytes::new()).await);
+        let unreachable = "http://127.0.0.1:1/".to_string();
+        let mut service = Service::default();
+        service.insert_request(Request::new("GET", ok.as_str())).unwrap();
+        service.insert_request(Request::new("GET", failing.as_str())).unwrap();
+        service.insert_request(Request::new("GET", silent.as_str()).set_timeout(Duration::from_millis(50))).unwrap();
+        service.insert_request(Request::new("GET", unreachable.as_str())).unwrap();
+        service.update().await.unwrap();
+        service.update().await.unwrap();
+
+        assert_eq!(service.statuses(&ok).map(|s| (s.success, s.total())), Some((2, 2)));
+        assert_eq!(service.statuses(&failing).map(|s| (s.server_error, s.total())), Some((2, 2)));
+        assert_eq!(service.statuses(&silent).map(|s| (s.timeout, s.total())), Some((2, 2)));
+        assert_eq!(service.statuses(&unreachable).map(|s| (s.no_response, s.total())), Some((2, 2)));
+
+        // The counts are exported along with the other metrics
+        let text = service.metrics().render();
+        assert!(text.contains("# TYPE isup_probe_responses_total counter"), "{text}");
+        assert!(text.contains(&format!("isup_probe_responses_total{{url=\"{failing}\",class=\"5xx\"}} 2")), "{text}");
+        assert!(text.contains(&format!("isup_probe_responses_total{{url=\"{ok}\",class=\"5xx\"}} 0")), "{text}");
+
+        // And forgotten along with their endpoint
+        service.remove_request(&failing).unwrap();
+        assert_eq!(service.statuses(&failing), None);
+    }
+
+    #[tokio::test(flavor = "multi_thread")]
+    async fn it_skips_overlapping_update_cycles() {
+        // A server answering slowly, so the first update is still running when the second one starts
+        let addr = common::serve_with(|_| {
+            std::thread::sleep(Duration::from_millis(200));
+            Bytes::from(common::OK)
+        })
+        .await;
+        let mut service = Service::default();
+        service.insert_request(Request::new("GET", format!("http://{addr}/").as_str())).unwrap();
+
+        let overlapping = async {
+            tokio::time::sleep(Duration::from_millis(50)).await;
+            service.update().await
+        };
+        let (first, second) = tokio::join!(service.update(), overlapping);
+        first.unwrap();
+        second.unwrap();
+
+        let metrics = service.metrics();
+        assert_eq!(metrics.cycles().count, 1);
+        assert_eq!(metrics.skipped_cycles(), 1);
+        assert!(metrics.last_cycle().unwrap() >= Duration::from_millis(200));
+
+        // Once the cycle completes, the next one runs
+        service.update().await.unwrap();
+        assert_eq!(metrics.cycles().count, 2);
+        let text = metrics.render();
+        assert!(text.contains("isup_update_cycles_total 2"), "{text}");
+        assert!(text.contains("isup_update_cycles_skipped_total 1"), "{text}");
+    }
+}
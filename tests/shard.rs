@@ -0,0 +1,60 @@
+mod common;
+
+#[cfg(test)]
+mod shard_tests {
+    use super::common;
+    use isup::shard::{self, Memory, Registry};
+    use isup::{Request, Service};
+    use std::sync::{Arc, Mutex};
+    use std::time::Duration;
+
+    /// Creates a replica monitoring the endpoints, sharing the registry with the others.
+    /// The URLs it probes are recorded in `probed`.
+    fn replica(id: &str, registry: Arc<Memory>, urls: &[String], probed: Arc<Mutex<Vec<String>>>) -> Service {
+        let config = shard::Config::default().set_id(id).set_ttl(Duration::from_millis(100));
+        let mut service = Service::default()
+            .use_sharding(registry, config)
+            .on_result(move |outcome| probed.lock().unwrap().push(outcome.url.clone()));
+        for url in urls {
+            service.insert_request(Request::new("GET", url.as_str())).unwrap();
+        }
+        service
+    }
+
+    #[tokio::test]
+    async fn it_splits_endpoints_among_replicas() {
+        let addr = common::serve(common::OK).await;
+        let urls = (0..20).map(|i| format!("http://{addr}/{i}")).collect::<Vec<_>>();
+        let registry = Arc::new(Memory::new());
+        let (probed_a, probed_b) = (Arc::new(Mutex::new(Vec::new())), Arc::new(Mutex::new(Vec::new())));
+        let a = replica("a", registry.clone(), &urls, probed_a.clone());
+        let b = replica("b", registry, &urls, probed_b.clone());
+
+        // Register both replicas, before comparing their shards
+        a.update().await.unwrap();
+        b.update().await.unwrap();
+        probed_a.lock().unwrap().clear();
+        probed_b.lock().unwrap().clear();
+
+        a.update().await.unwrap();
+        b.update().await.unwrap();
+        let (shard_a, shard_b) = (probed_a.lock().unwrap().clone(), probed_b.lock().unwrap().clone());
+        assert!(!shard_a.is_empty() && !shard_b.is_empty());
+        assert_eq!(shard_a.len() + shard_b.len(), urls.len());
+        assert!(shard_a.iter().all(|url| !shard_b.contains(url)));
+
+        // The remaining replica takes over once the other one stops renewing its registration
+        tokio::time::sleep(Duration::from_millis(150)).await;
+        probed_a.lock().unwrap().clear();
+        a.update().await.unwrap();
+        assert_eq!(probed_a.lock().unwrap().len(), urls.len());
+    }
+
+    #[tokio::test]
+    async fn it_rejects_registrations_overflowing_the_clock() {
+        let registry = Memory::new();
+        assert!(registry.heartbeat("a", Duration::MAX).await.is_err());
+        // The replica isn't registered, so the endpoints are split among the others
+        assert_eq!(registry.heartbeat("b", Duration::from_secs(30)).await.unwrap(), ["b"]);
+    }
+}
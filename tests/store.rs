@@ -0,0 +1,382 @@
+#[cfg(test)]
+mod store_tests {
+    use isup::{
+        chaos::{Chaos, Fault},
+        history::{self, History},
+        incident::State,
+        store::{Buffered, Fallback, Memory, ReadOnlyStore, Store},
+        Request, Score, Service,
+    };
+    use std::error::Error;
+    use std::sync::Arc;
+    use std::time::{Duration, SystemTime, UNIX_EPOCH};
+
+    #[tokio::test]
+    async fn it_filters_the_best_url() {
+        let store = Memory::new();
+        store.set("http://fast.example/".into(), Score::new(0.9, 0.5, Duration::from_millis(10))).await.unwrap();
+        store.set("http://reliable.example/".into(), Score::new(0.6, 0.95, Duration::from_millis(50))).await.unwrap();
+        store.set("http://slow.example/".into(), Score::new(0.3, 0.99, Duration::from_millis(90))).await.unwrap();
+
+        let best = store.best_url_where(&|_, score| score.reliability > 0.9).await.unwrap();
+        assert_eq!(best.as_deref(), Some("http://reliable.example/"));
+        let best = store.best_url_where(&|url, _| url.contains("slow")).await.unwrap();
+        assert_eq!(best.as_deref(), Some("http://slow.example/"));
+        assert_eq!(store.best_url_where(&|_, score| score.score > 1.0).await.unwrap(), None);
+    }
+
+    #[tokio::test]
+    async fn it_skips_paused_endpoints_in_filtered_queries() {
+        const FAST: &str = "http://fast.example/";
+        const SLOW: &str = "http://slow.example/";
+        let chaos = Chaos::new(42)
+            .insert(Fault::new(FAST).set_latencies(vec![Duration::from_millis(1)]))
+            .insert(Fault::new(SLOW).set_latencies(vec![Duration::from_millis(100)]));
+        let mut service = Service::default().use_chaos(chaos);
+        service.insert_request(Request::new("GET", FAST)).unwrap();
+        service.insert_request(Request::new("GET", SLOW)).unwrap();
+        service.update().await.unwrap();
+
+        assert_eq!(service.best_url_where(|_, _| true).await.unwrap().as_deref(), Some(FAST));
+        service.pause(FAST).await.unwrap();
+        assert_eq!(service.best_url_where(|_, _| true).await.unwrap().as_deref(), Some(SLOW));
+        assert_eq!(service.best_url_where(|url, _| url == FAST).await.unwrap(), None);
+    }
+
+    #[tokio::test]
+    async fn it_ranks_the_endpoints() {
+        const FAST: &str = "http://fast.example/";
+        const SLOW: &str = "http://slow.example/";
+        const DOWN: &str = "http://down.example/";
+        let chaos = Chaos::new(42)
+            .insert(Fault::new(FAST).set_latencies(vec![Duration::from_millis(1)]))
+            .insert(Fault::new(SLOW).set_latencies(vec![Duration::from_millis(100)]))
+            .insert(Fault::new(DOWN).set_failure_rate(1.0).set_failure_status(503));
+        let mut service = Service::default().use_chaos(chaos);
+        for url in [SLOW, DOWN, FAST] {
+            service.insert_request(Request::new("GET", url)).unwrap();
+        }
+        // Scores left by endpoints that are no longer monitored aren't ranked
+        service.store.set("http://removed.example/".into(), Score::new(1.0, 1.0, Duration::ZERO)).await.unwrap();
+        service.update().await.unwrap();
+
+        let ranking = service.ranking().await.unwrap();
+        assert_eq!(ranking.iter().map(|e| e.url.as_str()).collect::<Vec<_>>(), vec![FAST, SLOW, DOWN]);
+        assert_eq!(
+            ranking.iter().map(|e| e.state).collect::<Vec<_>>(),
+            vec![Some(State::Up), Some(State::Up), Some(State::Down)]
+        );
+        assert!(ranking.iter().all(|e| e.latency == e.score.response_avg));
+        assert!(ranking.iter().all(|e| e.last_checked.is_some()));
+    }
+
+    #[tokio::test]
+    async fn it_stores_the_last_error_with_the_score() {
+        const UP: &str = "http://up.example/";
+        const DOWN: &str = "http://down.example/";
+        const UNREACHABLE: &str = "http://127.0.0.1:1/";
+        let chaos = Chaos::new(42)
+            .insert(Fault::new(UP).set_latencies(vec![Duration::from_millis(1)]))
+            .insert(Fault::new(DOWN).set_failure_rate(1.0).set_failure_status(503));
+        let mut service = Service::default().use_chaos(chaos);
+        for url in [UP, DOWN, UNREACHABLE] {
+            service.insert_request(Request::new("GET", url)).unwrap();
+        }
+        service.update().await.unwrap();
+
+        // The reason of the failures is ranked along with their score
+        let ranking = service.ranking().await.unwrap();
+        let error = |url: &str| ranking.iter().find(|e| e.url == url).unwrap().score.last_error.clone();
+        assert_eq!(error(UP), None);
+        assert_eq!(error(DOWN).as_deref(), Some("responded with status 503"));
+        assert!(error(UNREACHABLE).is_some_and(|e| !e.is_empty()));
+
+        // And survives the canonical encoding
+        let score = service.score(DOWN).await.unwrap().unwrap();
+        assert_eq!(Score::decode(&score.encode().unwrap()).unwrap(), score);
+    }
+
+    #[tokio::test]
+    async fn it_scores_relative_to_the_best_endpoint() {
+        const FAST: &str = "http://fast.example/";
+        const SLOW: &str = "http://slow.example/";
+        let chaos = Chaos::new(42)
+            .insert(Fault::new(FAST).set_latencies(vec![Duration::from_millis(1)]))
+            .insert(Fault::new(SLOW).set_latencies(vec![Duration::from_millis(100)]));
+        let mut service = Service::default().use_chaos(chaos).use_relative_scoring();
+        service.insert_request(Request::new("GET", FAST)).unwrap();
+        service.insert_request(Request::new("GET", SLOW)).unwrap();
+        service.update().await.unwrap();
+
+        // The best endpoint scores 1.0, the other ones a fraction of it, while the store keeps the absolute scores
+        let ranking = service.ranking().await.unwrap();
+        assert_eq!(ranking[0].score.score, 1.0);
+        let (fast, slow) =
+            (service.store.get(FAST).await.unwrap().unwrap(), service.store.get(SLOW).await.unwrap().unwrap());
+        assert_eq!(ranking[1].score.score, slow.score / fast.score);
+
+        assert_eq!(service.best_url_where(|_, score| score.score < 1.0).await.unwrap().as_deref(), Some(SLOW));
+        assert_eq!(service.best_url_where(|_, score| score.score > 1.0).await.unwrap(), None);
+    }
+
+    #[tokio::test]
+    async fn it_propagates_errors_across_tasks() {
+        let service = std::sync::Arc::new(Service::default());
+
+        // The results of the service and its store can be awaited within a spawned task, and propagated out of it
+        let task = tokio::spawn({
+            let service = service.clone();
+            async move {
+                let best = service.best_url().await?;
+                service.pause("http://unmonitored.example/").await?;
+                Ok::<_, Box<dyn std::error::Error + Send + Sync>>(best)
+            }
+        });
+        let error = task.await.unwrap().unwrap_err();
+        assert!(error.to_string().contains("unknown endpoint"), "{error}");
+    }
+
+    #[tokio::test]
+    async fn it_shares_the_store_with_the_application() {
+        const URL: &str = "http://simulated.example/";
+        let store = std::sync::Arc::new(Memory::new());
+        let mut service = Service::default().use_chaos(Chaos::new(42).insert(Fault::new(URL))).use_store(store.clone());
+        service.insert_request(Request::new("GET", URL)).unwrap();
+        service.update().await.unwrap();
+
+        // The scores written by the service are read through the same store instance
+        assert!(store.get(URL).await.unwrap().is_some());
+        assert_eq!(store.best_url().await.unwrap().as_deref(), Some(URL));
+
+        // Or through the service itself
+        let score = service.score("http://simulated.example").await.unwrap().unwrap();
+        assert_eq!(score.score, store.get(URL).await.unwrap().unwrap().score);
+        assert_eq!(service.store().best_url().await.unwrap().as_deref(), Some(URL));
+        assert!(service.score("http://unscored.example/").await.unwrap().is_none());
+    }
+
+    /// A store that can be taken down, failing every operation meanwhile.
+    #[derive(Default)]
+    struct Flaky {
+        inner: Memory,
+        down: std::sync::atomic::AtomicBool,
+    }
+
+    impl Flaky {
+        fn check(&self) -> Result<(), Box<dyn Error + Send + Sync>> {
+            match self.down.load(std::sync::atomic::Ordering::SeqCst) {
+                true => Err("connection refused".into()),
+                false => Ok(()),
+            }
+        }
+    }
+
+    #[async_trait::async_trait]
+    impl Store for Flaky {
+        async fn set(&self, key: String, value: Score) -> Result<(), Box<dyn Error + Send + Sync>> {
+            self.check()?;
+            self.inner.set(key, value).await
+        }
+        async fn get(&self, key: &str) -> Result<Option<Score>, Box<dyn Error + Send + Sync>> {
+            self.check()?;
+            self.inner.get(key).await
+        }
+        async fn best_url(&self) -> Result<Option<String>, Box<dyn Error + Send + Sync>> {
+            self.check()?;
+            self.inner.best_url().await
+        }
+        async fn ranking(&self) -> Result<Vec<(String, Score)>, Box<dyn Error + Send + Sync>> {
+            self.check()?;
+            self.inner.ranking().await
+        }
+    }
+
+    #[tokio::test]
+    async fn it_falls_back_while_the_primary_store_is_down() {
+        const URL: &str = "http://a.example/";
+        let primary = Arc::new(Flaky::default());
+        let store = Arc::new(Fallback::new(primary.clone(), Memory::new()).set_retry_interval(Duration::ZERO));
+        let mut service = Service::default().use_store(store.clone()).use_chaos(Chaos::new(42).insert(Fault::new(URL)));
+        service.insert_request(Request::new("GET", URL)).unwrap();
+
+        // The endpoints are still scored and ranked while the primary store is down
+        primary.down.store(true, std::sync::atomic::Ordering::SeqCst);
+        service.update().await.unwrap();
+        assert!(store.is_degraded());
+        assert_eq!(service.best_url().await.unwrap().as_deref(), Some(URL));
+        assert!(store.health().await.healthy);
+        assert!(primary.inner.get(URL).await.unwrap().is_none());
+
+        // The scores missed by the primary store are replayed once it recovers
+        primary.down.store(false, std::sync::atomic::Ordering::SeqCst);
+        let score = store.get(URL).await.unwrap();
+        assert!(!store.is_degraded());
+        assert!(score.is_some());
+        assert_eq!(primary.inner.get(URL).await.unwrap(), score);
+
+        // Fallback chains are configured by nesting the stores
+        let config =
+            "store: { type: fallback, primary: { type: memory }, fallback: { type: memory }, retry_interval: 5s }";
+        let config = serde_yaml::from_str::<isup::Config>(&format!("{config}\nrequests: []")).unwrap();
+        assert!(Service::from_config(config).is_ok());
+    }
+
+    #[tokio::test]
+    async fn it_buffers_the_scores_during_store_outages() {
+        const URL: &str = "http://a.example/";
+        let primary = Arc::new(Flaky::default());
+        let store = Arc::new(Buffered::new(primary.clone()));
+        let mut service = Service::default().use_store(store.clone()).use_chaos(Chaos::new(42).insert(Fault::new(URL)));
+        service.insert_request(Request::new("GET", URL)).unwrap();
+        service.update().await.unwrap();
+        let before = primary.inner.get(URL).await.unwrap().unwrap();
+
+        // The scores are buffered during the outage, still accumulated from the previous ones
+        primary.down.store(true, std::sync::atomic::Ordering::SeqCst);
+        service.update().await.unwrap();
+        service.update().await.unwrap();
+        assert_eq!(store.pending(), 1);
+        assert_eq!(primary.inner.get(URL).await.unwrap(), Some(before.clone()));
+        let buffered = store.get(URL).await.unwrap().unwrap();
+        assert!(buffered.reliability > before.reliability);
+
+        // And flushed once the store is reachable again
+        primary.down.store(false, std::sync::atomic::Ordering::SeqCst);
+        assert_eq!(store.best_url().await.unwrap().as_deref(), Some(URL));
+        assert_eq!(store.pending(), 0);
+        assert_eq!(primary.inner.get(URL).await.unwrap(), Some(buffered));
+
+        // Once the buffer is full, the oldest scores are dropped first
+        primary.down.store(true, std::sync::atomic::Ordering::SeqCst);
+        let store = Buffered::new(primary.clone()).set_capacity(1);
+        store.set("http://b.example/".into(), Score::default()).await.unwrap();
+        store.set("http://c.example/".into(), Score::default()).await.unwrap();
+        assert_eq!((store.pending(), store.dropped()), (1, 1));
+        assert!(store.get("http://c.example/").await.unwrap().is_some());
+    }
+
+    /// A store recording the size of every batch of scores written to it.
+    #[derive(Default)]
+    struct Batches {
+        inner: Memory,
+        sizes: std::sync::Mutex<Vec<usize>>,
+    }
+
+    #[async_trait::async_trait]
+    impl Store for Batches {
+        async fn set(&self, key: String, value: Score) -> Result<(), Box<dyn Error + Send + Sync>> {
+            self.set_many(vec![(key, value)]).await
+        }
+        async fn set_many(&self, scores: Vec<(String, Score)>) -> Result<(), Box<dyn Error + Send + Sync>> {
+            self.sizes.lock().unwrap().push(scores.len());
+            self.inner.set_many(scores).await
+        }
+        async fn get(&self, key: &str) -> Result<Option<Score>, Box<dyn Error + Send + Sync>> {
+            self.inner.get(key).await
+        }
+        async fn best_url(&self) -> Result<Option<String>, Box<dyn Error + Send + Sync>> {
+            self.inner.best_url().await
+        }
+    }
+
+    #[tokio::test]
+    async fn it_writes_the_scores_in_batches() {
+        let urls: Vec<_> = (0..5).map(|i| format!("http://{i}.example/")).collect();
+        let chaos = urls.iter().fold(Chaos::new(42), |chaos, url| chaos.insert(Fault::new(url.as_str())));
+        let store = Arc::new(Batches::default());
+        let mut service = Service::default().use_chaos(chaos).use_store(store.clone()).use_scoring_batch(2);
+        for url in &urls {
+            service.insert_request(Request::new("GET", url.as_str())).unwrap();
+        }
+        service.update().await.unwrap();
+
+        let sizes = store.sizes.lock().unwrap().clone();
+        assert!(sizes.iter().all(|size| (1..=2).contains(size)), "{sizes:?}");
+        assert_eq!(sizes.iter().sum::<usize>(), urls.len());
+        for url in &urls {
+            assert!(service.score(url).await.unwrap().is_some());
+        }
+    }
+
+    #[tokio::test]
+    async fn it_migrates_between_stores() {
+        const URL: &str = "http://a.example/";
+        let (from, to) = (Memory::new(), Memory::new());
+        let score = Score::new(0.8, 0.95, Duration::from_millis(120));
+        from.set(URL.into(), score.clone()).await.unwrap();
+        from.set_requests(&[Request::new("GET", URL)]).await.unwrap();
+        to.set(URL.into(), Score::default()).await.unwrap();
+
+        // The scores overwrite the ones of the target store, and the source store is left untouched
+        let migration = Service::migrate(&from, &to).await.unwrap();
+        assert_eq!((migration.scores, migration.incidents, migration.requests), (1, 0, 1));
+        assert_eq!(to.get(URL).await.unwrap(), Some(score.clone()));
+        assert_eq!(to.requests().await.unwrap().len(), 1);
+        assert_eq!(from.get(URL).await.unwrap(), Some(score.clone()));
+
+        // The history is copied in the order it was recorded
+        let (from, to) = (history::Memory::new(10), history::Memory::new(10));
+        for secs in 1..=3 {
+            let at = UNIX_EPOCH + Duration::from_secs(secs);
+            let sample =
+                history::Sample { at, elapsed: score.response_avg, status: 200, score: 0.8, reliability: 0.95 };
+            from.record(URL, sample).await.unwrap();
+        }
+        assert_eq!(Service::migrate_history(&from, &to, &[URL.to_string()]).await.unwrap(), 3);
+        let (start, end) = (UNIX_EPOCH, SystemTime::now());
+        assert_eq!(to.query(URL, start, end).await.unwrap(), from.query(URL, start, end).await.unwrap());
+    }
+
+    #[tokio::test]
+    async fn it_reads_the_store_without_a_write_path() {
+        const URL: &str = "http://a.example/";
+        let store = Arc::new(Memory::new());
+        let chaos = Chaos::new(42).insert(Fault::new(URL));
+        let mut service = Service::default().use_store(store.clone()).use_chaos(chaos);
+        service.insert_request(Request::new("GET", URL)).unwrap();
+        service.update().await.unwrap();
+
+        // The scores written by the service are read through the view, which has no method to write them
+        let reader = ReadOnlyStore::new(store);
+        assert_eq!(reader.best_url().await.unwrap().as_deref(), Some(URL));
+        assert_eq!(reader.ranking().await.unwrap()[0].0, URL);
+        assert_eq!(reader.get(URL).await.unwrap(), service.score(URL).await.unwrap());
+
+        // No service probing and writing the scores is built from the configurations of the consumers
+        let config = serde_yaml::from_str::<isup::Config>("read_only: true\nrequests: []").unwrap();
+        assert!(ReadOnlyStore::from_config(config.effective_store()).ping().await.is_ok());
+        assert!(Service::from_config(config).is_err());
+    }
+
+    #[test]
+    fn it_encodes_scores_canonically() {
+        let score = Score { paused: true, ..Score::new(0.8, 0.95, Duration::from_millis(120)) };
+        let encoded = score.encode().unwrap();
+        assert_eq!(Score::decode(&encoded).unwrap(), score);
+        let record = serde_json::from_str::<serde_json::Value>(&encoded).unwrap();
+        assert_eq!(record["paused"], true);
+        assert_eq!(record["version"], Score::SCHEMA_VERSION);
+
+        // The untagged records of the first version are upgraded, and the fields of later versions ignored
+        let v1 = r#"{"response_avg":{"secs":0,"nanos":120000000},"score":0.8,"reliability":0.95}"#;
+        assert_eq!(Score::decode(v1).unwrap(), Score::new(0.8, 0.95, Duration::from_millis(120)));
+        // The same fields tagged with the second version aren't upgraded, lacking the fields it requires
+        let v2 = r#"{"response_avg":{"secs":0,"nanos":120000000},"score":0.8,"reliability":0.95,"version":2}"#;
+        assert!(Score::decode(v2).is_err());
+        let mut later = record.clone();
+        later["version"] = (Score::SCHEMA_VERSION + 1).into();
+        later["region"] = "eu-west".into();
+        assert_eq!(Score::decode(&later.to_string()).unwrap(), score);
+
+        // The YAML written by earlier versions, without the fields added since, is still decoded
+        let legacy = "response_avg:\n  secs: 0\n  nanos: 120000000\nscore: 0.8\nreliability: 0.95\n";
+        assert_eq!(Score::decode(legacy).unwrap(), Score::new(0.8, 0.95, Duration::from_millis(120)));
+
+        let schema = Score::schema();
+        assert_eq!(schema["$id"], format!("urn:isup:score:v{}", Score::SCHEMA_VERSION));
+        let fields = serde_json::to_value(&score).unwrap();
+        for field in fields.as_object().unwrap().keys() {
+            assert!(schema["properties"].get(field).is_some(), "{field} is missing from the schema");
+        }
+    }
+}
@@ -3,8 +3,9 @@ mod strategy_tests {
     use std::time::Duration;
 
     use isup::{
-        strategy::{Strategy, WeightedLog},
-        Score,
+        chaos::{Chaos, Fault},
+        strategy::{self, Key, LatencyCurve, Linear, Step, Strategy, WeightedLog},
+        ProbeOutcome, Request, Score, Service,
     };
 
     #[test]
@@ -30,4 +31,207 @@ mod strategy_tests {
         assert_eq!(weighted.reliability, 0.002);
         assert_eq!(weighted.score, 0.001898393);
     }
+
+    #[test]
+    fn it_tunes_the_weighted_log_curve() {
+        let score = || Score::new(0.0, 1.0, Duration::from_secs(1));
+        let second = Duration::from_secs(1);
+        // A successful probe is influenced by its latency at `0.1 + |0.5 - 1.0| * 0.15`
+        let influence = 0.175f32;
+
+        let hyperbolic = WeightedLog::default().calculate(score(), second, 200);
+        assert!((hyperbolic.score - (1.0 / (1.0 + influence) + 1.0).ln()).abs() < 1e-6);
+        let exponential = WeightedLog::default().set_latency_curve(LatencyCurve::Exponential);
+        assert!((exponential.calculate(score(), second, 200).score - ((-influence).exp() + 1.0).ln()).abs() < 1e-6);
+
+        // Tuning the parameters reshapes the curve
+        let tuned = WeightedLog::default().set_response_influence(1.0, 0.5, 0.0).set_log_offset(2.0);
+        assert!((tuned.calculate(score(), second, 200).score - (0.5f32 + 2.0).ln()).abs() < 1e-6);
+
+        // The parameters missing from the configuration keep their default
+        let config: strategy::Config =
+            serde_yaml::from_str("type: weighted_log\nweight: 0.5\neffort: 10.0\nlatency_curve: exponential\n")
+                .unwrap();
+        let strategy::Config::WeightedLog(config) = config else { panic!("unexpected strategy") };
+        assert_eq!(config.latency_curve, LatencyCurve::Exponential);
+        assert_eq!((config.response_influence, config.status_pivot, config.log_offset), (0.1, 0.5, 1.0));
+    }
+
+    #[test]
+    fn it_rejects_the_parameters_the_weighted_log_is_undefined_with() {
+        let service = |strategy: &str| {
+            let config = format!("strategy: {{ type: weighted_log, weight: 0.5, effort: 10.0, {strategy} }}");
+            Service::from_config(serde_yaml::from_str::<isup::Config>(&format!("{config}\nrequests: []")).unwrap())
+        };
+        assert!(service("log_offset: 0.5").is_ok());
+        assert!(service("log_offset: 0.0").is_err());
+        assert!(service("log_offset: -1.0").is_err());
+        assert!(service("response_influence: -0.1").is_err());
+
+        // The strategies of the requests are checked as well
+        let config = "requests: [{ method: GET, url: 'http://example.com/', strategy: { type: weighted_log, \
+            weight: 0.5, effort: 10.0, log_offset: 0.0 } }]";
+        assert!(Service::from_config(serde_yaml::from_str::<isup::Config>(config).unwrap()).is_err());
+    }
+
+    #[test]
+    #[should_panic]
+    fn it_panics_setting_a_non_positive_log_offset() {
+        let _ = WeightedLog::default().set_log_offset(0.0);
+    }
+
+    #[test]
+    fn it_smooths_the_scored_latency() {
+        let steady = Score::new(0.5, 1.0, Duration::from_millis(100));
+        let (raw, smoothed) = (WeightedLog::default(), WeightedLog::default().set_smoothed(true));
+
+        // A steady endpoint scores the same either way
+        let latency = Duration::from_millis(100);
+        assert_eq!(
+            raw.calculate(steady.clone(), latency, 200).score,
+            smoothed.calculate(steady.clone(), latency, 200).score
+        );
+
+        // A single spike is scored by its own latency, or by the average it moved halfway
+        let spike = Duration::from_secs(5);
+        let (raw, smoothed) = (raw.calculate(steady.clone(), spike, 200), smoothed.calculate(steady, spike, 200));
+        assert_eq!(raw.response_avg, smoothed.response_avg);
+        assert_eq!(
+            smoothed.score,
+            WeightedLog::default()
+                .calculate(Score::new(0.5, 1.0, smoothed.response_avg), smoothed.response_avg, 200)
+                .score
+        );
+        assert!(smoothed.score > raw.score);
+    }
+
+    #[tokio::test]
+    async fn it_warm_starts_the_reliability() {
+        // The reliability factor sets the reliability gained by every successful probe
+        let strategy = WeightedLog::new(0.5, 10.0).set_reliability_factor(0.01);
+        let score = strategy.calculate(strategy.initial_score(), Duration::from_millis(100), 200);
+        assert_eq!(score.reliability, 0.01);
+
+        // A new endpoint starts from the initial reliability, instead of ranking below a failing endpoint
+        const NEW: &str = "http://new.example/";
+        let strategy = WeightedLog::new(0.5, 10.0).set_initial_reliability(0.5);
+        assert_eq!(strategy.initial_score().reliability, 0.5);
+        let mut service = Service::default().use_chaos(Chaos::new(42).insert(Fault::new(NEW))).use_strategy(strategy);
+        service.insert_request(Request::new("GET", NEW)).unwrap();
+        service.update().await.unwrap();
+        let score = service.store.get(NEW).await.unwrap().unwrap();
+        assert!((score.reliability - 0.501).abs() < 1e-6);
+
+        let failing = Score::new(0.0, 0.1, Duration::from_millis(100));
+        assert!(score.score > WeightedLog::new(0.5, 10.0).calculate(failing, Duration::from_millis(100), 500).score);
+    }
+
+    #[test]
+    fn it_calculates_linear_and_step_scores() {
+        let score = || Score::new(0.0, 0.0, Duration::ZERO);
+
+        let linear = Linear::new(Duration::from_secs(1));
+        assert_eq!(linear.calculate(score(), Duration::from_millis(250), 200).score, 0.75);
+        assert_eq!(linear.calculate(score(), Duration::from_secs(2), 200).score, 0.0);
+        assert_eq!(linear.calculate(score(), Duration::from_millis(250), 503).score, 0.0);
+
+        // The bands can be listed in any order, the narrowest one containing the latency applies
+        let step = Step::new().insert(Duration::from_millis(500), 0.5).insert(Duration::from_millis(100), 1.0);
+        assert_eq!(step.calculate(score(), Duration::from_millis(100), 200).score, 1.0);
+        assert_eq!(step.calculate(score(), Duration::from_millis(300), 200).score, 0.5);
+        assert_eq!(step.calculate(score(), Duration::from_secs(1), 200).score, 0.0);
+        assert_eq!(step.calculate(score(), Duration::from_millis(50), 0).score, 0.0);
+
+        let config: strategy::Config =
+            serde_yaml::from_str("type: step\nbands:\n  - up_to: 100ms\n    score: 1.0\n").unwrap();
+        let step = strategy::from_config(config);
+        assert_eq!(step.calculate(score(), Duration::from_millis(80), 200).score, 1.0);
+    }
+
+    #[tokio::test]
+    async fn it_scores_the_error_rate_over_a_window() {
+        const URL: &str = "http://api.example/";
+        let strategy = WeightedLog::default().set_windowed_reliability(true);
+        let mut service = Service::default().use_strategy(strategy).use_error_window(4);
+        service.insert_request(Request::new("GET", URL)).unwrap();
+        let probe = |status| ProbeOutcome::new(URL, Duration::from_millis(100), status);
+
+        for _ in 0..4 {
+            service.ingest_outcome(probe(503)).await.unwrap();
+        }
+        let score = service.score(URL).await.unwrap().unwrap();
+        assert_eq!((score.error_rate, score.reliability), (1.0, 0.0));
+
+        // The failures leave the window as soon as enough probes succeed
+        for _ in 0..2 {
+            service.ingest_outcome(probe(200)).await.unwrap();
+        }
+        let score = service.score(URL).await.unwrap().unwrap();
+        assert_eq!((score.error_rate, score.reliability), (0.5, 0.5));
+        for _ in 0..2 {
+            service.ingest_outcome(probe(200)).await.unwrap();
+        }
+        let score = service.score(URL).await.unwrap().unwrap();
+        assert_eq!((score.error_rate, score.reliability), (0.0, 1.0));
+    }
+
+    #[tokio::test]
+    async fn it_penalizes_the_jitter() {
+        const STEADY: &str = "http://steady.example/";
+        const JITTERY: &str = "http://jittery.example/";
+        let strategy = WeightedLog::default().set_jitter_penalty(2.0);
+        let mut service = Service::default().use_strategy(strategy);
+        service.insert_request(Request::new("GET", STEADY)).unwrap();
+        service.insert_request(Request::new("GET", JITTERY)).unwrap();
+
+        for latency in [500, 50, 500, 50] {
+            service.ingest_outcome(ProbeOutcome::new(STEADY, Duration::from_millis(150), 200)).await.unwrap();
+            service.ingest_outcome(ProbeOutcome::new(JITTERY, Duration::from_millis(latency), 200)).await.unwrap();
+        }
+        let steady = service.score(STEADY).await.unwrap().unwrap();
+        let jittery = service.score(JITTERY).await.unwrap().unwrap();
+        assert_eq!((steady.jitter, jittery.jitter), (Duration::ZERO, Duration::from_millis(225)));
+        // The latest probe of the jittery endpoint was the fastest, yet scored as if it took 500ms
+        assert!(steady.score > jittery.score);
+    }
+
+    /// A strategy giving every endpoint the same score, whatever its outcome.
+    struct Fixed(f32);
+
+    impl Strategy for Fixed {
+        fn calculate(&self, _score: Score, new_response: Duration, _status_code: u16) -> Score {
+            Score::new(self.0, 1.0, new_response)
+        }
+    }
+
+    #[tokio::test]
+    async fn it_overrides_the_strategy_per_request() {
+        const API: &str = "http://api.example/";
+        const HEALTH: &str = "http://health.example/";
+        const TAGGED: &str = "http://tagged.example/";
+
+        let chaos = Chaos::new(42).insert(Fault::new(API)).insert(Fault::new(HEALTH)).insert(Fault::new(TAGGED));
+        let mut service = Service::default()
+            .use_chaos(chaos)
+            .use_strategy(Fixed(1.0))
+            .use_strategy_for(Key::url("http://HEALTH.example:80"), Fixed(2.0))
+            .use_strategy_for(Key::tag("tier", "health"), Fixed(3.0));
+        service.insert_request(Request::new("GET", API)).unwrap();
+        service.insert_request(Request::new("GET", HEALTH).set_tag("tier", "health")).unwrap();
+        service.insert_request(Request::new("GET", TAGGED).set_tag("tier", "health")).unwrap();
+        service.update().await.unwrap();
+
+        // The URL takes precedence over the tag, which takes precedence over the strategy of the service
+        for (url, expected) in [(API, 1.0), (HEALTH, 2.0), (TAGGED, 3.0)] {
+            assert_eq!(service.store.get(url).await.unwrap().unwrap().score, expected);
+        }
+
+        // A request naming its own strategy is scored by it
+        const OWN: &str = "http://own.example/";
+        let mut service = Service::default().use_chaos(Chaos::new(42).insert(Fault::new(OWN))).use_strategy(Fixed(1.0));
+        let request = Request::new("GET", OWN).set_strategy(strategy::Config::WeightedLog(WeightedLog::new(0.5, 10.0)));
+        service.insert_request(request).unwrap();
+        service.update().await.unwrap();
+        assert!(service.store.get(OWN).await.unwrap().unwrap().score < 1.0);
+    }
 }
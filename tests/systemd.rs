@@ -0,0 +1,35 @@
+mod common;
+
+#[cfg(test)]
+mod systemd_tests {
+    use super::common;
+    use isup::{Request, Service};
+    use std::sync::Arc;
+    use std::time::Duration;
+    use tokio::net::UnixDatagram;
+    use tokio::time::timeout;
+
+    #[tokio::test]
+    async fn it_notifies_systemd() {
+        // Act as the service manager, which sets the environment of the supervised process
+        let path = std::env::temp_dir().join(format!("isup-notify-{}.sock", std::process::id()));
+        let _ = std::fs::remove_file(&path);
+        let manager = UnixDatagram::bind(&path).unwrap();
+        std::env::set_var("NOTIFY_SOCKET", &path);
+        std::env::set_var("WATCHDOG_USEC", "100000");
+        std::env::set_var("WATCHDOG_PID", std::process::id().to_string());
+
+        let url = format!("http://{}/", common::serve(common::OK).await);
+        let mut service = Service::default();
+        service.insert_request(Request::new("GET", url.as_str())).unwrap();
+        Arc::new(service).run(Duration::from_secs(60)).await;
+
+        // Ready once the first update completes, then pinging the watchdog while waiting for the next one
+        let mut buf = [0u8; 64];
+        let n = timeout(Duration::from_secs(5), manager.recv(&mut buf)).await.unwrap().unwrap();
+        assert_eq!(&buf[..n], b"READY=1\nWATCHDOG=1");
+        let n = timeout(Duration::from_secs(5), manager.recv(&mut buf)).await.unwrap().unwrap();
+        assert_eq!(&buf[..n], b"WATCHDOG=1");
+        std::fs::remove_file(&path).unwrap();
+    }
+}
@@ -0,0 +1,77 @@
+mod common;
+
+#[cfg(test)]
+mod throughput_tests {
+    use super::common;
+    use isup::Request;
+    use std::sync::{Arc, Mutex};
+    use std::time::Duration;
+    use tokio::io::{AsyncReadExt, AsyncWriteExt};
+    use tokio::net::TcpListener;
+
+    #[tokio::test]
+    async fn it_measures_throughput() {
+        let body = "x".repeat(64 * 1024);
+        let addr = common::serve(format!("HTTP/1.1 200 OK\r\ncontent-length: {}\r\n\r\n{body}", body.len())).await;
+
+        // Download part of the object, as the server ignores the requested range
+        let outcome = common::probe(Request::new("GET", &format!("http://{addr}/")).set_download_size(32 * 1024), 1)
+            .await
+            .remove(0);
+        assert!(outcome.is_success());
+        assert!(outcome.body_size.unwrap() >= 32 * 1024);
+        assert!(outcome.throughput.unwrap() > 0.0);
+    }
+
+    #[tokio::test]
+    async fn it_fails_on_short_bodies() {
+        let addr = common::serve(common::OK).await;
+
+        let outcome =
+            common::probe(Request::new("GET", &format!("http://{addr}/")).set_download_size(1024), 1).await.remove(0);
+        // Verify that the download failed, as the body is smaller than the requested size
+        assert!(outcome.error.unwrap().contains("2 of 1024 bytes"));
+        assert_eq!(outcome.throughput, None);
+    }
+
+    #[tokio::test]
+    async fn it_measures_upload_throughput() {
+        let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let addr = listener.local_addr().unwrap();
+        let received = Arc::new(Mutex::new(String::new()));
+        let head = received.clone();
+
+        // Serve a single request, processing its whole body for 50ms before responding
+        tokio::spawn(async move {
+            let (mut stream, _) = listener.accept().await.unwrap();
+            let mut request = Vec::new();
+            let mut buf = vec![0u8; 8192];
+            let end = loop {
+                let n = stream.read(&mut buf).await.unwrap();
+                request.extend_from_slice(&buf[..n]);
+                if let Some(end) = request.windows(4).position(|w| w == b"\r\n\r\n") {
+                    break end + 4;
+                }
+            };
+            while request.len() < end + 256 * 1024 {
+                let n = stream.read(&mut buf).await.unwrap();
+                request.extend_from_slice(&buf[..n]);
+            }
+            *head.lock().unwrap() = String::from_utf8_lossy(&request[..end]).to_lowercase();
+            tokio::time::sleep(Duration::from_millis(50)).await;
+            stream.write_all(common::OK.as_bytes()).await.unwrap();
+        });
+
+        let outcome = common::probe(Request::new("GET", &format!("http://{addr}/")).set_upload_size(256 * 1024), 1)
+            .await
+            .remove(0);
+        assert!(outcome.is_success());
+        assert!(outcome.upload_throughput.unwrap() > 0.0);
+        assert!(outcome.processing_time.unwrap() >= Duration::from_millis(50));
+
+        // Verify that the payload was posted in full
+        let head = received.lock().unwrap().clone();
+        assert!(head.starts_with("post / http/1.1"));
+        assert!(head.contains("content-length: 262144"));
+    }
+}
@@ -0,0 +1,117 @@
+mod common;
+
+#[cfg(test)]
+mod trace_tests {
+    use super::common;
+    use isup::{trace, Request, Service};
+    use regex::Regex;
+    use std::collections::BTreeMap;
+    use std::sync::{Arc, Mutex};
+    use tracing::field::{Field, Visit};
+    use tracing::span::{Attributes, Id, Record};
+    use tracing::{Event, Metadata, Subscriber};
+    use tracing_core::span::Current;
+
+    /// The name, metadata and fields of a recorded span.
+    type Span = (&'static Metadata<'static>, BTreeMap<String, String>);
+
+    /// A subscriber recording the spans and their fields.
+    #[derive(Default)]
+    struct Recorder {
+        spans: Mutex<Vec<Span>>,
+        entered: Mutex<Vec<Id>>,
+    }
+
+    struct Fields<'a>(&'a mut BTreeMap<String, String>);
+
+    impl Visit for Fields<'_> {
+        fn record_debug(&mut self, field: &Field, value: &dyn std::fmt::Debug) {
+            self.0.insert(field.name().to_string(), format!("{value:?}"));
+        }
+    }
+
+    impl Subscriber for Recorder {
+        fn enabled(&self, _: &Metadata<'_>) -> bool {
+            true
+        }
+
+        fn new_span(&self, attributes: &Attributes<'_>) -> Id {
+            let mut fields = BTreeMap::new();
+            attributes.record(&mut Fields(&mut fields));
+            let mut spans = self.spans.lock().unwrap();
+            spans.push((attributes.metadata(), fields));
+            Id::from_u64(spans.len() as u64)
+        }
+
+        fn record(&self, span: &Id, values: &Record<'_>) {
+            values.record(&mut Fields(&mut self.spans.lock().unwrap()[span.into_u64() as usize - 1].1));
+        }
+
+        fn record_follows_from(&self, _: &Id, _: &Id) {}
+
+        fn event(&self, _: &Event<'_>) {}
+
+        fn enter(&self, span: &Id) {
+            self.entered.lock().unwrap().push(span.clone());
+        }
+
+        fn exit(&self, _: &Id) {
+            self.entered.lock().unwrap().pop();
+        }
+
+        fn current_span(&self) -> Current {
+            match self.entered.lock().unwrap().last() {
+                Some(id) => Current::new(id.clone(), self.spans.lock().unwrap()[id.into_u64() as usize - 1].0),
+                None => Current::none(),
+            }
+        }
+    }
+
+    #[tokio::test]
+    async fn it_traces_the_sampled_probes() {
+        let recorder = Arc::new(Recorder::default());
+        let _guard = tracing::subscriber::set_default(recorder.clone());
+        let (addr, recorded) = common::record().await;
+        let url = format!("http://{addr}/");
+
+        let mut service = Service::default().use_tracing(trace::Config::default());
+        service.insert_request(Request::new("GET", url.as_str())).unwrap();
+        service.update().await.unwrap();
+
+        // The probe carries its trace context, flagged as sampled
+        let traceparent = Regex::new(r"traceparent: 00-([0-9a-f]{32})-[0-9a-f]{16}-01").unwrap();
+        let head = recorded.lock().unwrap()[0].0.clone();
+        let trace_id = traceparent.captures(&head).expect("missing traceparent")[1].to_string();
+
+        let spans = recorder.spans.lock().unwrap();
+        let (metadata, fields) = spans.iter().find(|(m, _)| m.name() == "probe").unwrap();
+        assert_eq!(metadata.level(), &tracing::Level::INFO);
+        assert_eq!(fields["url"], format!("{url:?}"));
+        assert_eq!(fields["trace_id"], format!("{trace_id:?}"));
+        assert_eq!(fields["status"], "200");
+        assert!(fields.contains_key("latency_ms"));
+        assert!(fields["score_delta"].parse::<f32>().unwrap() > 0.0);
+    }
+
+    #[tokio::test]
+    async fn it_propagates_the_unsampled_probes() {
+        let recorder = Arc::new(Recorder::default());
+        let _guard = tracing::subscriber::set_default(recorder.clone());
+        let (addr, recorded) = common::record().await;
+
+        let mut service = Service::default().use_tracing(trace::Config::default().set_sample_rate(0.0));
+        service.insert_request(Request::new("GET", format!("http://{addr}/").as_str())).unwrap();
+        service.update().await.unwrap();
+
+        // The trace context is still propagated, flagged as not sampled, but no span is emitted
+        let traceparent = Regex::new(r"traceparent: 00-[0-9a-f]{32}-[0-9a-f]{16}-00").unwrap();
+        assert!(traceparent.is_match(&recorded.lock().unwrap()[0].0));
+        assert!(!recorder.spans.lock().unwrap().iter().any(|(m, _)| m.name() == "probe"));
+
+        // Unless disabled
+        let mut service = Service::default().use_tracing(trace::Config::default().set_propagate(false));
+        service.insert_request(Request::new("GET", format!("http://{addr}/").as_str())).unwrap();
+        service.update().await.unwrap();
+        assert!(!recorded.lock().unwrap()[1].0.contains("traceparent"));
+    }
+}